@@ -9,5 +9,110 @@ pub use public::PublicKey;
 mod signature;
 pub use signature::Signature;
 
+#[cfg(feature = "std")]
 mod cache;
+#[cfg(feature = "std")]
 pub use cache::PublicKeyCache;
+
+pub mod placement;
+
+use crate::{hash_to_curve::try_and_increment::COMPOSITE_HASH_TO_G1, BlsResult, Domain};
+
+/// Verifies a single validator's signature over the message, hashing it to G1 with the
+/// composite try-and-increment hasher in the `SIG_DOMAIN` and no extra data, i.e. exactly
+/// like the epoch SNARK does
+pub fn verify_signature(pubkey: &PublicKey, message: &[u8], sig: &Signature) -> BlsResult<()> {
+    verify_signature_with_domain(Domain::default(), pubkey, message, sig)
+}
+
+/// Same as [`verify_signature`](fn.verify_signature.html), but for signatures made in the
+/// provided domain, e.g. on a test network
+pub fn verify_signature_with_domain(
+    domain: Domain,
+    pubkey: &PublicKey,
+    message: &[u8],
+    sig: &Signature,
+) -> BlsResult<()> {
+    pubkey.verify_with_domain(domain, message, &[], sig, &*COMPOSITE_HASH_TO_G1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verifies_single_signature() {
+        let rng = &mut rand::thread_rng();
+        let key = PrivateKey::generate(rng);
+        let pubkey = key.to_public();
+        let sig = key.sign(b"hello", &[], &*COMPOSITE_HASH_TO_G1).unwrap();
+
+        verify_signature(&pubkey, b"hello", &sig).unwrap();
+        // wrong message
+        assert!(matches!(
+            verify_signature(&pubkey, b"goodbye", &sig).unwrap_err(),
+            crate::BLSError::VerificationFailed
+        ));
+        // wrong key
+        let other = PrivateKey::generate(rng).to_public();
+        verify_signature(&other, b"hello", &sig).unwrap_err();
+    }
+
+    #[test]
+    fn signatures_are_bound_to_the_domain() {
+        let rng = &mut rand::thread_rng();
+        let key = PrivateKey::generate(rng);
+        let pubkey = key.to_public();
+        let domain = Domain::new(b"testnet").unwrap();
+        let sig = key
+            .sign_with_domain(domain, b"hello", &[], &*COMPOSITE_HASH_TO_G1)
+            .unwrap();
+
+        verify_signature_with_domain(domain, &pubkey, b"hello", &sig).unwrap();
+        verify_signature(&pubkey, b"hello", &sig).unwrap_err();
+
+        // signing in the default domain is the same as signing in the SIG_DOMAIN
+        let sig = key
+            .sign_with_domain(Domain::default(), b"hello", &[], &*COMPOSITE_HASH_TO_G1)
+            .unwrap();
+        verify_signature(&pubkey, b"hello", &sig).unwrap();
+        verify_signature_with_domain(domain, &pubkey, b"hello", &sig).unwrap_err();
+    }
+
+    #[test]
+    fn aggregation_is_order_insensitive() {
+        use crate::hash_to_curve::try_and_increment::DIRECT_HASH_TO_G1;
+        use algebra::CanonicalSerialize;
+        use rand::seq::SliceRandom;
+
+        let rng = &mut rand::thread_rng();
+        let keys = (0..5)
+            .map(|_| PrivateKey::generate(rng))
+            .collect::<Vec<_>>();
+        let mut pairs = keys
+            .iter()
+            .map(|key| {
+                let sig = key.sign(b"hello", &[], &*DIRECT_HASH_TO_G1).unwrap();
+                (key.to_public(), sig)
+            })
+            .collect::<Vec<_>>();
+        let aggregate = |pairs: &[(PublicKey, Signature)]| {
+            let pubkey = PublicKey::aggregate(pairs.iter().map(|(pubkey, _)| pubkey));
+            let sig = Signature::aggregate(pairs.iter().map(|(_, sig)| sig));
+            let mut bytes = vec![];
+            pubkey.serialize(&mut bytes).unwrap();
+            sig.serialize(&mut bytes).unwrap();
+            (pubkey, sig, bytes)
+        };
+
+        let expected = aggregate(&pairs);
+        for _ in 0..5 {
+            pairs.shuffle(rng);
+            assert_eq!(aggregate(&pairs), expected);
+        }
+        expected
+            .0
+            .verify(b"hello", &[], &expected.1, &*DIRECT_HASH_TO_G1)
+            .unwrap();
+    }
+}
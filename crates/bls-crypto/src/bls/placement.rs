@@ -0,0 +1,148 @@
+//! The groups which BLS keys and signatures are placed on.
+//!
+//! [`PublicKey`](../struct.PublicKey.html) and [`Signature`](../struct.Signature.html) are
+//! generic over their placement. By default they place the keys on G2 and the signatures on
+//! G1, which keeps signatures small, and they can be instantiated with
+//! [`KeysOnG1`](struct.KeysOnG1.html) for smaller keys and signatures on G2 instead.
+//!
+//! Only [`KeysOnG2`](struct.KeysOnG2.html) signatures are supported by the epochs SNARK, and
+//! only they can be serialized, cached and batch verified.
+use algebra::{
+    bls12_377::{Bls12_377, Fq12, Fr, G1Affine, G1Projective, G2Affine, G2Projective},
+    AffineCurve, One, PairingEngine, ProjectiveCurve,
+};
+
+use core::{fmt::Debug, hash::Hash, iter::Sum, ops::Neg};
+
+/// The groups on which the public keys and the signatures of a BLS scheme are placed
+pub trait GroupPlacement: Clone + Debug + PartialEq + Eq + Hash {
+    /// The group of the public keys
+    type PublicKeyGroup: ProjectiveCurve<ScalarField = Fr> + Sum;
+    /// The group of the signatures, which messages are hashed to
+    type SignatureGroup: ProjectiveCurve<ScalarField = Fr> + Sum;
+
+    /// Returns `true` if `e(signature, g) == e(hash, public_key)`, where `g` is the
+    /// generator of the public keys' group
+    fn check_pairing(
+        public_key: &Self::PublicKeyGroup,
+        hash: &Self::SignatureGroup,
+        signature: &Self::SignatureGroup,
+    ) -> bool;
+}
+
+/// Public keys on G2 and signatures on G1, as used by the Celo protocol
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct KeysOnG2;
+
+impl GroupPlacement for KeysOnG2 {
+    type PublicKeyGroup = G2Projective;
+    type SignatureGroup = G1Projective;
+
+    fn check_pairing(
+        public_key: &G2Projective,
+        hash: &G1Projective,
+        signature: &G1Projective,
+    ) -> bool {
+        Bls12_377::product_of_pairings(&vec![
+            (
+                signature.into_affine().into(),
+                G2Affine::prime_subgroup_generator().neg().into(),
+            ),
+            (hash.into_affine().into(), public_key.into_affine().into()),
+        ]) == Fq12::one()
+    }
+}
+
+/// Public keys on G1 and signatures on G2
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct KeysOnG1;
+
+impl GroupPlacement for KeysOnG1 {
+    type PublicKeyGroup = G1Projective;
+    type SignatureGroup = G2Projective;
+
+    fn check_pairing(
+        public_key: &G1Projective,
+        hash: &G2Projective,
+        signature: &G2Projective,
+    ) -> bool {
+        Bls12_377::product_of_pairings(&vec![
+            (
+                G1Affine::prime_subgroup_generator().neg().into(),
+                signature.into_affine().into(),
+            ),
+            (public_key.into_affine().into(), hash.into_affine().into()),
+        ]) == Fq12::one()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hash_to_curve::try_and_increment::{TryAndIncrement, DIRECT_HASH_TO_G1},
+        hashers::DirectHasher,
+        HashToCurve, PrivateKey, PublicKey, Signature,
+    };
+    use algebra::{bls12_377::Parameters, curves::models::bls12::Bls12Parameters};
+
+    fn aggregate_verify<P: GroupPlacement, H: HashToCurve<Output = P::SignatureGroup>>(
+        keys: &[PrivateKey],
+        hash: &H,
+    ) {
+        let sigs = keys
+            .iter()
+            .map(|key| Signature::<P>::sign(key, b"hello", b"extra", hash).unwrap())
+            .collect::<Vec<_>>();
+        let pubkeys = keys
+            .iter()
+            .map(PublicKey::<P>::from_private)
+            .collect::<Vec<_>>();
+
+        for (pubkey, sig) in pubkeys.iter().zip(&sigs) {
+            pubkey.verify(b"hello", b"extra", sig, hash).unwrap();
+        }
+        let aggregate_sig = Signature::<P>::aggregate(&sigs);
+        let aggregate_pubkey = PublicKey::<P>::aggregate(&pubkeys);
+        aggregate_pubkey
+            .verify(b"hello", b"extra", &aggregate_sig, hash)
+            .unwrap();
+
+        // wrong message
+        aggregate_pubkey
+            .verify(b"goodbye", b"extra", &aggregate_sig, hash)
+            .unwrap_err();
+        // missing a signer
+        PublicKey::<P>::aggregate(&pubkeys[1..])
+            .verify(b"hello", b"extra", &aggregate_sig, hash)
+            .unwrap_err();
+    }
+
+    #[test]
+    fn aggregate_verify_in_both_placements() {
+        let rng = &mut rand::thread_rng();
+        let keys = (0..4)
+            .map(|_| PrivateKey::generate(rng))
+            .collect::<Vec<_>>();
+
+        aggregate_verify::<KeysOnG2, _>(&keys, &*DIRECT_HASH_TO_G1);
+        let hash_to_g2 =
+            TryAndIncrement::<_, <Parameters as Bls12Parameters>::G2Parameters>::new(&DirectHasher);
+        aggregate_verify::<KeysOnG1, _>(&keys, &hash_to_g2);
+    }
+
+    #[test]
+    fn default_placement_matches_signatures() {
+        let rng = &mut rand::thread_rng();
+        let key = PrivateKey::generate(rng);
+        let hash = &*DIRECT_HASH_TO_G1;
+
+        let sig = key.sign(b"hello", &[], hash).unwrap();
+        let placed: Signature = Signature::sign(&key, b"hello", &[], hash).unwrap();
+        assert_eq!(sig, placed);
+
+        let pubkey: PublicKey = PublicKey::from_private(&key);
+        assert_eq!(pubkey, key.to_public());
+        pubkey.verify(b"hello", &[], &placed, hash).unwrap();
+    }
+}
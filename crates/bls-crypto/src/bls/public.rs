@@ -1,20 +1,20 @@
-use crate::{BLSError, BlsResult, HashToCurve, PrivateKey, Signature, POP_DOMAIN, SIG_DOMAIN};
+use super::placement::{GroupPlacement, KeysOnG2};
+use crate::{
+    BLSError, BlsResult, Domain, HashToCurve, PrivateKey, Signature, POP_DOMAIN, SIG_DOMAIN,
+};
 
 use algebra::{
-    bls12_377::{Bls12_377, Fq12, G1Projective, G2Affine, G2Projective},
-    AffineCurve, CanonicalDeserialize, CanonicalSerialize, One, PairingEngine, ProjectiveCurve,
-    SerializationError,
+    bls12_377::{G1Projective, G2Affine, G2Projective},
+    AffineCurve, CanonicalDeserialize, CanonicalSerialize, ProjectiveCurve, SerializationError,
 };
 
-use std::{
-    borrow::Borrow,
-    io::{Read, Write},
-    ops::Neg,
-};
+use algebra::io::{Read, Write};
+use core::borrow::Borrow;
 
-/// A BLS public key on G2
+/// A BLS public key, on G2 with the default [`KeysOnG2`](bls/placement/struct.KeysOnG2.html)
+/// placement
 #[derive(Clone, Eq, Debug, PartialEq, Hash)]
-pub struct PublicKey(pub(super) G2Projective);
+pub struct PublicKey<P: GroupPlacement = KeysOnG2>(pub(super) P::PublicKeyGroup);
 
 impl From<G2Projective> for PublicKey {
     fn from(pk: G2Projective) -> PublicKey {
@@ -24,75 +24,104 @@ impl From<G2Projective> for PublicKey {
 
 impl From<&PrivateKey> for PublicKey {
     fn from(pk: &PrivateKey) -> PublicKey {
-        PublicKey::from(G2Projective::prime_subgroup_generator().mul(*pk.as_ref()))
+        PublicKey::from_private(pk)
     }
 }
 
-impl AsRef<G2Projective> for PublicKey {
-    fn as_ref(&self) -> &G2Projective {
+impl<P: GroupPlacement> AsRef<P::PublicKeyGroup> for PublicKey<P> {
+    fn as_ref(&self) -> &P::PublicKeyGroup {
         &self.0
     }
 }
 
-impl PublicKey {
-    /// Sums the provided public keys to produce the aggregate public key.
-    pub fn aggregate<P: Borrow<PublicKey>>(public_keys: impl IntoIterator<Item = P>) -> PublicKey {
-        public_keys
-            .into_iter()
-            .map(|s| s.borrow().0)
-            .sum::<G2Projective>()
-            .into()
+impl<P: GroupPlacement> PublicKey<P> {
+    /// Wraps a point of the keys' group
+    pub fn new(public_key: P::PublicKeyGroup) -> Self {
+        PublicKey(public_key)
+    }
+
+    /// Derives the public key of the private key in the keys' group
+    pub fn from_private(private_key: &PrivateKey) -> Self {
+        PublicKey(ProjectiveCurve::mul(
+            P::PublicKeyGroup::prime_subgroup_generator(),
+            *private_key.as_ref(),
+        ))
+    }
+
+    /// Sums the provided public keys to produce the aggregate public key. The sum does not
+    /// depend on the order of the keys.
+    pub fn aggregate<K: Borrow<Self>>(public_keys: impl IntoIterator<Item = K>) -> Self {
+        PublicKey(public_keys.into_iter().map(|s| s.borrow().0).sum())
+    }
+
+    /// Sums the provided public keys to produce the aggregate public key, erroring if no
+    /// public keys were provided instead of returning the identity.
+    pub fn try_aggregate(public_keys: &[Self]) -> BlsResult<Self> {
+        if public_keys.is_empty() {
+            return Err(BLSError::EmptyAggregation);
+        }
+        Ok(PublicKey::aggregate(public_keys))
+    }
+
+    /// Returns `true` if the key is in the prime order subgroup of its group.
+    ///
+    /// Deserialization only checks that the key is on the curve, so keys which were not
+    /// generated locally must pass this check before being used.
+    pub fn is_in_correct_subgroup(&self) -> bool {
+        self.0
+            .into_affine()
+            .is_in_correct_subgroup_assuming_on_curve()
     }
 
     /// Verifies the provided signature against the message-extra_data pair using the
-    /// `hash_to_g1` hasher.
+    /// `hash` hasher, which must hash to the signatures' group, i.e. to G1 for keys on G2.
     ///
     /// Uses the `SIG_DOMAIN` under the hood.
-    pub fn verify<H: HashToCurve<Output = G1Projective>>(
+    pub fn verify<H: HashToCurve<Output = P::SignatureGroup>>(
         &self,
         message: &[u8],
         extra_data: &[u8],
-        signature: &Signature,
-        hash_to_g1: &H,
+        signature: &Signature<P>,
+        hash: &H,
     ) -> BlsResult<()> {
-        self.verify_sig(SIG_DOMAIN, message, extra_data, signature, hash_to_g1)
+        self.verify_sig(SIG_DOMAIN, message, extra_data, signature, hash)
+    }
+
+    /// Same as [`verify`](#method.verify), but for signatures made in the provided domain
+    pub fn verify_with_domain<H: HashToCurve<Output = P::SignatureGroup>>(
+        &self,
+        domain: Domain,
+        message: &[u8],
+        extra_data: &[u8],
+        signature: &Signature<P>,
+        hash: &H,
+    ) -> BlsResult<()> {
+        self.verify_sig(domain.as_bytes(), message, extra_data, signature, hash)
     }
 
     /// Verifies the provided proof of possession signature against the message using the
-    /// `hash_to_g1` hasher.
+    /// `hash` hasher.
     ///
     /// Uses the `POP_DOMAIN` under the hood.
-    pub fn verify_pop<H: HashToCurve<Output = G1Projective>>(
+    pub fn verify_pop<H: HashToCurve<Output = P::SignatureGroup>>(
         &self,
         message: &[u8],
-        signature: &Signature,
-        hash_to_g1: &H,
+        signature: &Signature<P>,
+        hash: &H,
     ) -> BlsResult<()> {
-        self.verify_sig(POP_DOMAIN, &message, &[], signature, hash_to_g1)
+        self.verify_sig(POP_DOMAIN, &message, &[], signature, hash)
     }
 
-    fn verify_sig<H: HashToCurve<Output = G1Projective>>(
+    fn verify_sig<H: HashToCurve<Output = P::SignatureGroup>>(
         &self,
         domain: &[u8],
         message: &[u8],
         extra_data: &[u8],
-        signature: &Signature,
-        hash_to_g1: &H,
+        signature: &Signature<P>,
+        hash: &H,
     ) -> BlsResult<()> {
-        let pairing = Bls12_377::product_of_pairings(&vec![
-            (
-                signature.as_ref().into_affine().into(),
-                G2Affine::prime_subgroup_generator().neg().into(),
-            ),
-            (
-                hash_to_g1
-                    .hash(domain, message, extra_data)?
-                    .into_affine()
-                    .into(),
-                self.0.into_affine().into(),
-            ),
-        ]);
-        if pairing == Fq12::one() {
+        let hash = hash.hash(domain, message, extra_data)?;
+        if P::check_pairing(&self.0, &hash, signature.as_ref()) {
             Ok(())
         } else {
             Err(BLSError::VerificationFailed)
@@ -100,6 +129,22 @@ impl PublicKey {
     }
 }
 
+impl PublicKey {
+    /// Verifies the provided proof of possession signature over the compressed
+    /// serialization of this public key, as produced by `PrivateKey::sign_key_pop`.
+    ///
+    /// Uses the `POP_DOMAIN` (`b"ULforpop"`) under the hood, with no extra data.
+    pub fn verify_key_pop<H: HashToCurve<Output = G1Projective>>(
+        &self,
+        pop: &Signature,
+        hash_to_g1: &H,
+    ) -> BlsResult<()> {
+        let mut pk_bytes = vec![];
+        self.serialize(&mut pk_bytes)?;
+        self.verify_pop(&pk_bytes, pop, hash_to_g1)
+    }
+}
+
 impl CanonicalSerialize for PublicKey {
     fn serialize<W: Write>(&self, writer: &mut W) -> Result<(), SerializationError> {
         self.0.into_affine().serialize(writer)
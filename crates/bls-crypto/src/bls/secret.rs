@@ -1,16 +1,32 @@
-use crate::{BLSError, HashToCurve, PublicKey, Signature, POP_DOMAIN, SIG_DOMAIN};
+use crate::{BLSError, Domain, HashToCurve, PublicKey, Signature, POP_DOMAIN, SIG_DOMAIN};
 
+use algebra::io::{Read, Write};
 use algebra::{
     bls12_377::{Fr, G1Projective},
     CanonicalDeserialize, CanonicalSerialize, Group, SerializationError, UniformRand,
 };
 use rand::Rng;
-use std::io::{Read, Write};
+use zeroize::Zeroize;
 
 /// A Private Key using a pairing friendly curve's Fr point
+///
+/// The scalar is zeroized when the key is dropped.
 #[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
 pub struct PrivateKey(Fr);
 
+impl Zeroize for PrivateKey {
+    fn zeroize(&mut self) {
+        // the all-zero limbs are the (Montgomery form of the) zero scalar
+        AsMut::<[u64]>::as_mut(&mut (self.0).0).zeroize();
+    }
+}
+
+impl Drop for PrivateKey {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
 impl From<Fr> for PrivateKey {
     fn from(sk: Fr) -> PrivateKey {
         PrivateKey(sk)
@@ -40,6 +56,18 @@ impl PrivateKey {
         self.sign_message(SIG_DOMAIN, message, extra_data, hash_to_g1)
     }
 
+    /// Same as [`sign`](#method.sign), but signs in the provided domain instead of the
+    /// SIG_DOMAIN
+    pub fn sign_with_domain<H: HashToCurve<Output = G1Projective>>(
+        &self,
+        domain: Domain,
+        message: &[u8],
+        extra_data: &[u8],
+        hash_to_g1: &H,
+    ) -> Result<Signature, BLSError> {
+        self.sign_message(domain.as_bytes(), message, extra_data, hash_to_g1)
+    }
+
     /// Hashes the message with the provided `hash_to_g1` function
     /// and then signs it in the POP_DOMAIN
     pub fn sign_pop<H: HashToCurve<Output = G1Projective>>(
@@ -50,6 +78,21 @@ impl PrivateKey {
         self.sign_message(POP_DOMAIN, &message, &[], hash_to_g1)
     }
 
+    /// Produces a proof of possession of the private key by signing the compressed
+    /// serialization of its own public key in the POP_DOMAIN (`b"ULforpop"`), with
+    /// no extra data.
+    ///
+    /// The Celo protocol's on-chain proofs of possession sign the validator's address
+    /// instead, and should be produced with `sign_pop`.
+    pub fn sign_key_pop<H: HashToCurve<Output = G1Projective>>(
+        &self,
+        hash_to_g1: &H,
+    ) -> Result<Signature, BLSError> {
+        let mut pk_bytes = vec![];
+        self.to_public().serialize(&mut pk_bytes)?;
+        self.sign_pop(&pk_bytes, hash_to_g1)
+    }
+
     /// Hashes to G1 and signs the hash
     fn sign_message<H: HashToCurve<Output = G1Projective>>(
         &self,
@@ -82,8 +125,9 @@ mod tests {
             DirectHasher, Hasher,
         },
     };
-    use algebra::{bls12_377::Parameters, curves::models::bls12::Bls12Parameters};
+    use algebra::{bls12_377::Parameters, curves::models::bls12::Bls12Parameters, Zero};
     use rand::{thread_rng, Rng};
+    use std::mem::{size_of, ManuallyDrop};
 
     #[test]
     fn test_simple_sig() {
@@ -137,4 +181,57 @@ mod tests {
         pk2.verify_pop(&pk_bytes, &sig, &try_and_increment)
             .unwrap_err();
     }
+
+    #[test]
+    fn test_key_pop() {
+        let rng = &mut thread_rng();
+        let direct_hasher = DirectHasher;
+        let try_and_increment =
+            TryAndIncrement::<_, <Parameters as Bls12Parameters>::G1Parameters>::new(
+                &direct_hasher,
+            );
+
+        let sk = PrivateKey::generate(rng);
+        let sk2 = PrivateKey::generate(rng);
+        let pk = sk.to_public();
+        let pk2 = sk2.to_public();
+
+        let pop = sk.sign_key_pop(&try_and_increment).unwrap();
+        pk.verify_key_pop(&pop, &try_and_increment).unwrap();
+        // it's a proof of possession signature over the key's serialization
+        let mut pk_bytes = vec![];
+        pk.serialize(&mut pk_bytes).unwrap();
+        pk.verify_pop(&pk_bytes, &pop, &try_and_increment).unwrap();
+
+        // another key cannot use it
+        pk2.verify_key_pop(&pop, &try_and_increment).unwrap_err();
+        // a proof over another key is rejected
+        let pop2 = sk2.sign_pop(&pk_bytes, &try_and_increment).unwrap();
+        pk.verify_key_pop(&pop2, &try_and_increment).unwrap_err();
+        // a regular signature over the key is not a proof of possession
+        let sig = sk.sign(&pk_bytes, &[], &try_and_increment).unwrap();
+        pk.verify_key_pop(&sig, &try_and_increment).unwrap_err();
+    }
+
+    #[test]
+    fn zeroizes_on_drop() {
+        let rng = &mut thread_rng();
+        let mut sk = PrivateKey::generate(rng);
+        assert!(!sk.as_ref().is_zero());
+        sk.zeroize();
+        assert!(sk.as_ref().is_zero());
+
+        let raw_bytes = |sk: &ManuallyDrop<PrivateKey>| unsafe {
+            std::slice::from_raw_parts(
+                &**sk as *const PrivateKey as *const u8,
+                size_of::<PrivateKey>(),
+            )
+            .to_vec()
+        };
+        let mut sk = ManuallyDrop::new(PrivateKey::generate(rng));
+        assert!(raw_bytes(&sk).iter().any(|b| *b != 0));
+        // drop the key in place, its storage remains readable
+        unsafe { ManuallyDrop::drop(&mut sk) };
+        assert!(raw_bytes(&sk).iter().all(|b| *b == 0));
+    }
 }
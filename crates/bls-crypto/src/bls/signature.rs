@@ -1,5 +1,8 @@
-use super::PublicKey;
-use crate::{BLSError, HashToCurve};
+use super::{
+    placement::{GroupPlacement, KeysOnG2},
+    PrivateKey, PublicKey,
+};
+use crate::{BLSError, BlsResult, HashToCurve, SIG_DOMAIN};
 
 use algebra::{
     bls12_377::{Bls12_377, Fq12, G1Affine, G1Projective, G2Affine},
@@ -7,15 +10,14 @@ use algebra::{
     SerializationError,
 };
 
-use std::{
-    borrow::Borrow,
-    io::{Read, Write},
-    ops::Neg,
-};
+use crate::Vec;
+use algebra::io::{Read, Write};
+use core::{borrow::Borrow, ops::Neg};
 
-/// A BLS signature on G1.
+/// A BLS signature, on G1 with the default [`KeysOnG2`](bls/placement/struct.KeysOnG2.html)
+/// placement.
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct Signature(G1Projective);
+pub struct Signature<P: GroupPlacement = KeysOnG2>(P::SignatureGroup);
 
 impl From<G1Projective> for Signature {
     fn from(sig: G1Projective) -> Signature {
@@ -23,8 +25,8 @@ impl From<G1Projective> for Signature {
     }
 }
 
-impl AsRef<G1Projective> for Signature {
-    fn as_ref(&self) -> &G1Projective {
+impl<P: GroupPlacement> AsRef<P::SignatureGroup> for Signature<P> {
+    fn as_ref(&self) -> &P::SignatureGroup {
         &self.0
     }
 }
@@ -57,16 +59,52 @@ impl CanonicalDeserialize for Signature {
     }
 }
 
-impl Signature {
-    /// Sums the provided signatures to produce the aggregate signature.
-    pub fn aggregate<S: Borrow<Signature>>(signatures: impl IntoIterator<Item = S>) -> Signature {
-        signatures
-            .into_iter()
-            .map(|s| s.borrow().0)
-            .sum::<G1Projective>()
-            .into()
+impl<P: GroupPlacement> Signature<P> {
+    /// Wraps a point of the signatures' group
+    pub fn new(signature: P::SignatureGroup) -> Self {
+        Signature(signature)
+    }
+
+    /// Hashes the message/extra_data tuple with the provided `hash` function, which must
+    /// hash to the signatures' group, and then signs it in the SIG_DOMAIN.
+    ///
+    /// With the default placement this is the same as `PrivateKey::sign`.
+    pub fn sign<H: HashToCurve<Output = P::SignatureGroup>>(
+        private_key: &PrivateKey,
+        message: &[u8],
+        extra_data: &[u8],
+        hash: &H,
+    ) -> BlsResult<Self> {
+        let hash = hash.hash(SIG_DOMAIN, message, extra_data)?;
+        Ok(Signature(ProjectiveCurve::mul(hash, *private_key.as_ref())))
+    }
+
+    /// Returns `true` if the signature is in the prime order subgroup of its group.
+    ///
+    /// Deserialization only checks that the signature is on the curve.
+    pub fn is_in_correct_subgroup(&self) -> bool {
+        self.0
+            .into_affine()
+            .is_in_correct_subgroup_assuming_on_curve()
+    }
+
+    /// Sums the provided signatures to produce the aggregate signature. The sum does not
+    /// depend on the order of the signatures.
+    pub fn aggregate<S: Borrow<Self>>(signatures: impl IntoIterator<Item = S>) -> Self {
+        Signature(signatures.into_iter().map(|s| s.borrow().0).sum())
     }
 
+    /// Sums the provided signatures to produce the aggregate signature, erroring if no
+    /// signatures were provided instead of returning the identity.
+    pub fn try_aggregate(signatures: &[Self]) -> BlsResult<Self> {
+        if signatures.is_empty() {
+            return Err(BLSError::EmptyAggregation);
+        }
+        Ok(Signature::aggregate(signatures))
+    }
+}
+
+impl Signature {
     /// Verifies the signature against a vector of pubkey & message tuples, for the provided
     /// messages domain.
     ///
@@ -138,12 +176,41 @@ mod tests {
     };
 
     use algebra::{
-        bls12_377::{Bls12_377, G1Projective, G2Projective, Parameters},
+        bls12_377::{Bls12_377, FrParameters, G1Projective, G2Projective, Parameters},
         curves::bls12::Bls12Parameters,
-        CanonicalDeserialize, CanonicalSerialize, UniformRand, Zero,
+        CanonicalDeserialize, CanonicalSerialize, FpParameters, UniformRand, Zero,
     };
     use rand::{thread_rng, Rng};
 
+    #[test]
+    fn checks_subgroup_membership() {
+        let rng = &mut thread_rng();
+        let key = PrivateKey::generate(rng);
+        assert!(key.to_public().is_in_correct_subgroup());
+        let sig = key.sign(b"hello", &[], &*COMPOSITE_HASH_TO_G1).unwrap();
+        assert!(sig.is_in_correct_subgroup());
+
+        // points on the curves, but outside of the prime order subgroups
+        let g1 = loop {
+            let mut bytes = [0u8; 48];
+            rng.fill(&mut bytes[..]);
+            if let Some(p) = G1Affine::from_random_bytes(&bytes) {
+                break p;
+            }
+        };
+        assert!(!g1.mul(FrParameters::MODULUS).is_zero());
+        assert!(!Signature::from(g1.into_projective()).is_in_correct_subgroup());
+        let g2 = loop {
+            let mut bytes = [0u8; 96];
+            rng.fill(&mut bytes[..]);
+            if let Some(p) = G2Affine::from_random_bytes(&bytes) {
+                break p;
+            }
+        };
+        assert!(!g2.mul(FrParameters::MODULUS).is_zero());
+        assert!(!PublicKey::from(g2.into_projective()).is_in_correct_subgroup());
+    }
+
     #[test]
     fn test_aggregated_sig() {
         let message = b"hello";
@@ -191,6 +258,41 @@ mod tests {
             .unwrap_err();
     }
 
+    #[test]
+    fn try_aggregate_same_message() {
+        let message = b"hello";
+        let rng = &mut thread_rng();
+        let try_and_increment = &*COMPOSITE_HASH_TO_G1;
+
+        let keys = (0..10)
+            .map(|_| PrivateKey::generate(rng))
+            .collect::<Vec<_>>();
+        let sigs = keys
+            .iter()
+            .map(|sk| sk.sign(&message[..], &[], try_and_increment).unwrap())
+            .collect::<Vec<_>>();
+        let pubkeys = keys.iter().map(|sk| sk.to_public()).collect::<Vec<_>>();
+
+        let asig = Signature::try_aggregate(&sigs).unwrap();
+        let apk = PublicKey::try_aggregate(&pubkeys).unwrap();
+        apk.verify(&message[..], &[], &asig, try_and_increment)
+            .unwrap();
+
+        // missing a signature
+        let asig = Signature::try_aggregate(&sigs[1..]).unwrap();
+        apk.verify(&message[..], &[], &asig, try_and_increment)
+            .unwrap_err();
+
+        assert!(matches!(
+            Signature::try_aggregate(&[]),
+            Err(BLSError::EmptyAggregation)
+        ));
+        assert!(matches!(
+            PublicKey::try_aggregate(&[]),
+            Err(BLSError::EmptyAggregation)
+        ));
+    }
+
     #[test]
     fn test_batch_verify() {
         test_batch_verify_with_hasher(&DirectHasher, false);
@@ -43,7 +43,39 @@
 /// ```
 pub mod try_and_increment;
 
+/// Implementation of the Shallue-van de Woestijne map from the SWU family of hash to curve
+/// methods, which does not need to loop over a counter.
+///
+/// **This method is not supported by the epochs SNARK**, use it only for signatures which are
+/// verified natively.
+pub mod swu;
+
 use crate::BLSError;
+use algebra::bls12_377::{Fq, G1Projective};
+
+/// Hashes the message to an element of the base field of BLS12-377, in the domain `dst`.
+///
+/// This is **not** the `hash_to_field` of RFC 9380, but the Celo variant which the signatures
+/// and the epochs SNARK use: the message is prefixed with a one byte counter, compressed
+/// with the Bowe-Hopwood CRH and expanded to 64 bytes with Blake2x, personalized with `dst`
+/// (which must be at most 8 bytes). The first 48 bytes are read as a compressed point, and
+/// the counter is incremented until they are a valid one, whose x coordinate is returned.
+///
+/// The `compat` feature selects the bit extraction of the deployed Celo version, so the
+/// outputs differ between builds with and without it.
+pub fn hash_to_field(message: &[u8], dst: &[u8]) -> Result<Fq, BLSError> {
+    try_and_increment::COMPOSITE_HASH_TO_G1.hash_to_field(dst, message, &[])
+}
+
+/// Hashes the message to BLS12-377's G1, in the domain `dst`.
+///
+/// This is **not** the `hash_to_curve` of RFC 9380, but the Celo variant: the point whose x
+/// coordinate is [`hash_to_field`](fn.hash_to_field.html) (with the sign of y read from the
+/// hash) is multiplied by the cofactor. It is the hash which Celo validators sign, and which
+/// the epochs SNARK enforces unless it is built with the `blake2-crh` feature.
+pub fn hash_to_curve(message: &[u8], dst: &[u8]) -> Result<G1Projective, BLSError> {
+    try_and_increment::COMPOSITE_HASH_TO_G1.hash(dst, message, &[])
+}
 
 /// Trait for hashing arbitrary data to a group element on an elliptic curve
 pub trait HashToCurve {
@@ -59,3 +91,113 @@ pub trait HashToCurve {
         extra_data: &[u8],
     ) -> Result<Self::Output, BLSError>;
 }
+
+/// The method used for mapping the (composite) hash of a message to BLS12-377's G1.
+///
+/// The BLS gadget in the epochs SNARK recomputes the hashes with the try-and-increment
+/// counter, so only `TryAndIncrement` can be used for signing epoch blocks. Signatures
+/// produced with one method do not verify with the other, so the signer and the verifier
+/// must agree on the method.
+///
+/// The method is only selected by the users of this crate: the epochs SNARK's prover and
+/// verifier do not take it, and always hash with try-and-increment and the CRH of their
+/// setup (see [`SnarkCrh`](try_and_increment/enum.SnarkCrh.html)).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashToCurveMethod {
+    /// Try-and-increment, see [`try_and_increment`](try_and_increment/index.html)
+    TryAndIncrement,
+    /// Shallue-van de Woestijne, see [`swu`](swu/index.html)
+    Swu,
+}
+
+impl Default for HashToCurveMethod {
+    fn default() -> Self {
+        HashToCurveMethod::TryAndIncrement
+    }
+}
+
+impl HashToCurveMethod {
+    /// Returns `true` if hashes produced with this method can be verified by the epochs SNARK
+    pub fn is_snark_compatible(self) -> bool {
+        self == HashToCurveMethod::TryAndIncrement
+    }
+}
+
+impl HashToCurve for HashToCurveMethod {
+    type Output = G1Projective;
+
+    fn hash(
+        &self,
+        domain: &[u8],
+        message: &[u8],
+        extra_data: &[u8],
+    ) -> Result<Self::Output, BLSError> {
+        match self {
+            HashToCurveMethod::TryAndIncrement => {
+                try_and_increment::COMPOSITE_HASH_TO_G1.hash(domain, message, extra_data)
+            }
+            HashToCurveMethod::Swu => {
+                swu::COMPOSITE_SWU_HASH_TO_G1.hash(domain, message, extra_data)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hash_to_curve::try_and_increment::COMPOSITE_HASH_TO_G1, PrivateKey, POP_DOMAIN, SIG_DOMAIN,
+    };
+
+    #[test]
+    fn default_method_is_unchanged() {
+        let message = &b"hello"[..];
+        let method = HashToCurveMethod::default();
+        assert!(method.is_snark_compatible());
+        assert_eq!(
+            method.hash(SIG_DOMAIN, message, &[]).unwrap(),
+            COMPOSITE_HASH_TO_G1.hash(SIG_DOMAIN, message, &[]).unwrap()
+        );
+        assert!(!HashToCurveMethod::Swu.is_snark_compatible());
+    }
+
+    #[test]
+    fn field_element_maps_to_curve() {
+        use algebra::{bls12_377::G1Affine, AffineCurve};
+
+        let message = &b"hello"[..];
+        let x = hash_to_field(message, SIG_DOMAIN).unwrap();
+        let hash = hash_to_curve(message, SIG_DOMAIN).unwrap();
+        assert_eq!(
+            hash,
+            HashToCurveMethod::default()
+                .hash(SIG_DOMAIN, message, &[])
+                .unwrap()
+        );
+        // the hash is the cofactor multiple of one of the two points with that x
+        assert!([false, true].iter().any(|&greatest| {
+            G1Affine::get_point_from_x(x, greatest)
+                .unwrap()
+                .scale_by_cofactor()
+                == hash
+        }));
+        assert_ne!(x, hash_to_field(message, POP_DOMAIN).unwrap());
+    }
+
+    #[test]
+    fn methods_cannot_be_mixed() {
+        let rng = &mut rand::thread_rng();
+        let key = PrivateKey::generate(rng);
+        let pubkey = key.to_public();
+        let message = &b"hello"[..];
+        for (method, other) in &[
+            (HashToCurveMethod::TryAndIncrement, HashToCurveMethod::Swu),
+            (HashToCurveMethod::Swu, HashToCurveMethod::TryAndIncrement),
+        ] {
+            let sig = key.sign(message, &[], method).unwrap();
+            pubkey.verify(message, &[], &sig, method).unwrap();
+            pubkey.verify(message, &[], &sig, other).unwrap_err();
+        }
+    }
+}
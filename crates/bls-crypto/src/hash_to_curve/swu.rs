@@ -0,0 +1,325 @@
+use core::marker::PhantomData;
+
+use super::HashToCurve;
+use crate::hashers::{
+    composite::{CompositeHasher, COMPOSITE_HASHER, CRH},
+    DirectHasher, Hasher,
+};
+use crate::{BLSError, Lazy};
+
+use algebra::{
+    bls12_377::Parameters,
+    curves::models::short_weierstrass_jacobian::{GroupAffine, GroupProjective},
+    curves::models::{bls12::Bls12Parameters, SWModelParameters},
+    AffineCurve, BigInteger, Field, One, PrimeField, ProjectiveCurve, SquareRootField, Zero,
+};
+
+/// Number of bytes used to derive each field element. Using 16 more bytes than the size of
+/// the field makes the bias of the modular reduction negligible.
+const FIELD_ELEMENT_BYTES: usize = 64;
+
+/// Composite (Bowe-Hopwood CRH, Blake2x XOF) SWU hasher for BLS 12-377.
+pub static COMPOSITE_SWU_HASH_TO_G1: Lazy<
+    Swu<CompositeHasher<CRH>, <Parameters as Bls12Parameters>::G1Parameters>,
+> = Lazy::new(|| Swu::new(&*COMPOSITE_HASHER));
+
+/// Direct (Blake2s CRH, Blake2x XOF) SWU hasher for BLS 12-377.
+pub static DIRECT_SWU_HASH_TO_G1: Lazy<
+    Swu<DirectHasher, <Parameters as Bls12Parameters>::G1Parameters>,
+> = Lazy::new(|| Swu::new(&DirectHasher));
+
+/// Hashes to the curve using the Shallue-van de Woestijne map, as specified in section 6.6.1
+/// of the [hash to curve draft](https://tools.ietf.org/html/draft-irtf-cfrg-hash-to-curve-07).
+///
+/// The simplified SWU map cannot be used directly on curves with `a = 0` such as BLS12-377's
+/// G1 without an isogeny, so the generic map of the SWU family is used instead. Unlike
+/// try-and-increment, the number of operations does not depend on the input, however the
+/// underlying field operations have not been audited for constant time execution.
+#[derive(Clone)]
+pub struct Swu<'a, H, P: SWModelParameters> {
+    hasher: &'a H,
+    constants: SwuConstants<P::BaseField>,
+    curve_params: PhantomData<P>,
+}
+
+#[derive(Clone)]
+struct SwuConstants<F> {
+    z: F,
+    c1: F,
+    c2: F,
+    c3: F,
+    c4: F,
+}
+
+impl<'a, H, P> Swu<'a, H, P>
+where
+    H: Hasher<Error = BLSError>,
+    P: SWModelParameters,
+    P::BaseField: PrimeField + SquareRootField,
+{
+    /// Instantiates a new SWU hasher with the provided hashing method and curve
+    /// parameters based on the type
+    pub fn new(h: &'a H) -> Self {
+        Swu {
+            hasher: h,
+            constants: SwuConstants::new::<P>(),
+            curve_params: PhantomData,
+        }
+    }
+
+    /// Maps a field element to a point on the curve (not necessarily on the prime order subgroup)
+    fn map_to_curve(&self, u: P::BaseField) -> GroupAffine<P> {
+        let SwuConstants { z, c1, c2, c3, c4 } = self.constants;
+        let one = P::BaseField::one();
+
+        let tv1 = u.square() * &c1;
+        let tv2 = one + &tv1;
+        let tv1 = one - &tv1;
+        let tv3 = (tv1 * &tv2).inverse().unwrap_or_else(P::BaseField::zero);
+        let tv4 = u * &tv1 * &tv3 * &c3;
+
+        let x1 = c2 - &tv4;
+        let e1 = is_square(&curve_rhs::<P>(&x1));
+        let x2 = c2 + &tv4;
+        let e2 = is_square(&curve_rhs::<P>(&x2)) && !e1;
+        let x3 = (tv2.square() * &tv3).square() * &c4 + &z;
+
+        let x = if e1 {
+            x1
+        } else if e2 {
+            x2
+        } else {
+            x3
+        };
+        // one of the 3 candidates is always on the curve
+        let y = curve_rhs::<P>(&x)
+            .sqrt()
+            .expect("the SvdW map must produce a point on the curve");
+        let y = if sgn0(&u) == sgn0(&y) { y } else { -y };
+
+        GroupAffine::new(x, y, false)
+    }
+
+    /// Hashes the message and the extra data to 2 field elements
+    fn hash_to_field(
+        &self,
+        domain: &[u8],
+        message: &[u8],
+        extra_data: &[u8],
+    ) -> Result<[P::BaseField; 2], BLSError> {
+        let msg = &[extra_data, message].concat();
+        let bytes = self.hasher.hash(domain, msg, 2 * FIELD_ELEMENT_BYTES)?;
+        let (u0, u1) = bytes.split_at(FIELD_ELEMENT_BYTES);
+        Ok([from_be_bytes_mod_order(u0), from_be_bytes_mod_order(u1)])
+    }
+}
+
+impl<'a, H, P> HashToCurve for Swu<'a, H, P>
+where
+    H: Hasher<Error = BLSError>,
+    P: SWModelParameters,
+    P::BaseField: PrimeField + SquareRootField,
+{
+    type Output = GroupProjective<P>;
+
+    fn hash(
+        &self,
+        domain: &[u8],
+        message: &[u8],
+        extra_data: &[u8],
+    ) -> Result<Self::Output, BLSError> {
+        let [u0, u1] = self.hash_to_field(domain, message, extra_data)?;
+        let p = self.map_to_curve(u0).into_projective() + &self.map_to_curve(u1).into_projective();
+        let scaled = p.into_affine().scale_by_cofactor();
+        if scaled.is_zero() {
+            return Err(BLSError::HashToCurveError);
+        }
+
+        Ok(scaled)
+    }
+}
+
+impl<F: PrimeField + SquareRootField> SwuConstants<F> {
+    /// Finds the `Z` constant for the curve as specified in the draft's appendix H.1 and
+    /// derives the rest of the constants of the map from it
+    fn new<P: SWModelParameters<BaseField = F>>() -> Self {
+        let two = F::one() + &F::one();
+        let three = two + &F::one();
+        let four = two.double();
+
+        // try Z = 1, -1, 2, -2, ...
+        let mut candidate = F::zero();
+        loop {
+            candidate += &F::one();
+            for z in [candidate, -candidate].iter() {
+                let gz = curve_rhs::<P>(z);
+                let h = three * &z.square() + &P::mul_by_a(&four);
+                if gz.is_zero() || h.is_zero() {
+                    continue;
+                }
+                let ratio = -(h * &(four * &gz).inverse().unwrap());
+                if ratio.is_zero() || !is_square(&ratio) {
+                    continue;
+                }
+                let minus_z_over_two = -(*z * &two.inverse().unwrap());
+                if !is_square(&gz) && !is_square(&curve_rhs::<P>(&minus_z_over_two)) {
+                    continue;
+                }
+
+                let c3 = (-(gz * &h)).sqrt().unwrap();
+                let c3 = if sgn0(&c3) { -c3 } else { c3 };
+                return SwuConstants {
+                    z: *z,
+                    c1: gz,
+                    c2: minus_z_over_two,
+                    c3,
+                    c4: -(four * &gz) * &h.inverse().unwrap(),
+                };
+            }
+        }
+    }
+}
+
+/// Computes `x^3 + ax + b`
+fn curve_rhs<P: SWModelParameters>(x: &P::BaseField) -> P::BaseField {
+    P::add_b(&((x.square() * x) + &P::mul_by_a(x)))
+}
+
+fn is_square<F: SquareRootField>(x: &F) -> bool {
+    x.is_zero() || x.legendre().is_qr()
+}
+
+/// The "sign" of the field element, i.e. whether its canonical representation is odd
+fn sgn0<F: PrimeField>(x: &F) -> bool {
+    x.into_repr().is_odd()
+}
+
+/// Interprets the bytes as a big endian integer and reduces it modulo the field's characteristic
+fn from_be_bytes_mod_order<F: PrimeField>(bytes: &[u8]) -> F {
+    let mut res = F::zero();
+    for byte in bytes {
+        for i in (0..8).rev() {
+            res.double_in_place();
+            if (byte >> i) & 1 == 1 {
+                res += &F::one();
+            }
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::hash_to_curve::try_and_increment::COMPOSITE_HASH_TO_G1;
+    use algebra::{
+        bls12_377::{Fq, G1Affine},
+        CanonicalSerialize,
+    };
+    use core::str::FromStr;
+    use rand::RngCore;
+
+    type G1Parameters = <Parameters as Bls12Parameters>::G1Parameters;
+
+    #[test]
+    fn map_outputs_curve_points() {
+        let hasher = Swu::<_, G1Parameters>::new(&DirectHasher);
+        let mut rng = rand::thread_rng();
+        for _ in 0..20 {
+            let mut bytes = [0u8; FIELD_ELEMENT_BYTES];
+            rng.fill_bytes(&mut bytes);
+            let p = hasher.map_to_curve(from_be_bytes_mod_order::<Fq>(&bytes));
+            assert!(p.is_on_curve());
+        }
+        // the exceptional case where the inversion is of zero
+        assert!(hasher.map_to_curve(Fq::zero()).is_on_curve());
+    }
+
+    #[test]
+    fn hash_to_curve_g1() {
+        let hasher = &*COMPOSITE_SWU_HASH_TO_G1;
+        let mut rng = rand::thread_rng();
+        for length in &[0, 10, 25, 50, 100, 200, 300] {
+            let mut input = vec![0; *length];
+            rng.fill_bytes(&mut input);
+            let hash = hasher.hash(&b"domain"[..], &input, &b"extra"[..]).unwrap();
+            assert!(hash
+                .into_affine()
+                .is_in_correct_subgroup_assuming_on_curve());
+            // deterministic
+            assert_eq!(
+                hash,
+                hasher.hash(&b"domain"[..], &input, &b"extra"[..]).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn direct_hash_known_answers() {
+        // computed with an independent implementation of Blake2xs and of the map, and checked
+        // to be in the prime order subgroup
+        let vectors: [(&[u8], &[u8], &[u8], &str, &str); 3] = [
+            (
+                b"ULforxof",
+                b"hello",
+                b"",
+                "246990075274895567416602039717976319366006490014657574787337397486867373106190859596005442151754245871172325818370",
+                "99656167239238908291514488648037822078547892845769864655051895308477846809519970399982922852478486695161589794991",
+            ),
+            (
+                b"ULforxof",
+                b"hello",
+                b"extra",
+                "198113974542598410131765262466600708030552896212928177723129240349874876696355527637131824215623158179529694993979",
+                "67060961668320569519543374182547533893239625131858053111360538798130774433391642736120631351526424691674784728792",
+            ),
+            (
+                b"",
+                b"",
+                b"",
+                "113423937757186124296102550688889799551513131611011166056892368656954794753225728009173585982499185204321363783016",
+                "53233394313974683746782848781077825533546188208277562531858931003224068751712690542721429508922887561583952851266",
+            ),
+        ];
+        for (domain, message, extra_data, x, y) in vectors.iter() {
+            let expected = G1Affine::new(Fq::from_str(x).unwrap(), Fq::from_str(y).unwrap(), false);
+            let hash = DIRECT_SWU_HASH_TO_G1
+                .hash(domain, message, extra_data)
+                .unwrap();
+            assert_eq!(hash.into_affine(), expected);
+        }
+        // the map's constant is the first candidate
+        assert_eq!(DIRECT_SWU_HASH_TO_G1.constants.z, Fq::one());
+    }
+
+    #[test]
+    fn differs_from_try_and_increment() {
+        let message = &b"hello"[..];
+        let swu = COMPOSITE_SWU_HASH_TO_G1
+            .hash(&b"ULforxof"[..], message, &[])
+            .unwrap();
+        let try_and_increment = COMPOSITE_HASH_TO_G1
+            .hash(&b"ULforxof"[..], message, &[])
+            .unwrap();
+
+        let mut swu_bytes = vec![];
+        swu.into_affine().serialize(&mut swu_bytes).unwrap();
+        let mut try_and_increment_bytes = vec![];
+        try_and_increment
+            .into_affine()
+            .serialize(&mut try_and_increment_bytes)
+            .unwrap();
+        assert_ne!(swu_bytes, try_and_increment_bytes);
+    }
+
+    #[test]
+    fn bytes_to_field() {
+        let mut bytes = [0u8; FIELD_ELEMENT_BYTES];
+        assert_eq!(from_be_bytes_mod_order::<Fq>(&bytes), Fq::zero());
+        bytes[FIELD_ELEMENT_BYTES - 1] = 1;
+        assert_eq!(from_be_bytes_mod_order::<Fq>(&bytes), Fq::one());
+        bytes[FIELD_ELEMENT_BYTES - 2] = 1;
+        let expected = (0..257).fold(Fq::zero(), |acc, _| acc + &Fq::one());
+        assert_eq!(from_be_bytes_mod_order::<Fq>(&bytes), expected);
+    }
+}
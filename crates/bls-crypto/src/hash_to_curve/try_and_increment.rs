@@ -1,24 +1,21 @@
 use bench_utils::{end_timer, start_timer};
-use byteorder::WriteBytesExt;
+use core::marker::PhantomData;
 use log::trace;
-use std::marker::PhantomData;
 
 use super::HashToCurve;
 use crate::hashers::{
     composite::{CompositeHasher, COMPOSITE_HASHER, CRH},
     DirectHasher, Hasher,
 };
-use crate::BLSError;
+use crate::{BLSError, Lazy, Vec};
 
 use algebra::{
-    bls12_377::Parameters,
+    bls12_377::{G1Projective, Parameters},
     curves::models::short_weierstrass_jacobian::{GroupAffine, GroupProjective},
     curves::models::{bls12::Bls12Parameters, SWModelParameters},
     AffineCurve, ConstantSerializedSize, Zero,
 };
 
-use once_cell::sync::Lazy;
-
 const NUM_TRIES: u8 = 255;
 
 /// Composite (Bowe-Hopwood CRH, Blake2x XOF) Try-and-Increment hasher for BLS 12-377.
@@ -32,6 +29,84 @@ pub static DIRECT_HASH_TO_G1: Lazy<
     TryAndIncrement<DirectHasher, <Parameters as Bls12Parameters>::G1Parameters>,
 > = Lazy::new(|| TryAndIncrement::new(&DirectHasher));
 
+/// The CRH which the SNARK circuits compress messages with before hashing them to G1.
+///
+/// Each CRH is also a Try-and-Increment hasher to G1, which hashes like the
+/// [`COMPOSITE_HASH_TO_G1`](static.COMPOSITE_HASH_TO_G1.html) or the
+/// [`DIRECT_HASH_TO_G1`](static.DIRECT_HASH_TO_G1.html) respectively, so that messages can be
+/// hashed outside of the circuit with the CRH of a setup instead of a global hasher.
+///
+/// The `blake2-crh` feature only selects [`SnarkCrh::CURRENT`](#associatedconstant.CURRENT),
+/// i.e. the CRH which the gadgets generate their constraints for. The setup records it, so that
+/// keys generated with one CRH are rejected by a build which uses the other.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SnarkCrh {
+    /// The Bowe-Hopwood Pedersen hash over Edwards SW6, used by the composite hasher
+    BoweHopwood,
+    /// Blake2s, used by the direct hasher
+    Blake2s,
+}
+
+impl SnarkCrh {
+    /// The CRH which this build uses in the SNARK
+    #[cfg(not(feature = "blake2-crh"))]
+    pub const CURRENT: SnarkCrh = SnarkCrh::BoweHopwood;
+    /// The CRH which this build uses in the SNARK
+    #[cfg(feature = "blake2-crh")]
+    pub const CURRENT: SnarkCrh = SnarkCrh::Blake2s;
+
+    /// The number of bits of the CRH's output which are passed to the XOF
+    pub fn output_bits(self) -> usize {
+        match self {
+            // the x coordinate of the Edwards point, padded to a multiple of 8
+            SnarkCrh::BoweHopwood => 384,
+            SnarkCrh::Blake2s => 256,
+        }
+    }
+
+    /// Compresses the message with the CRH, as the G1 hasher does before applying the XOF.
+    /// The message must already include the attempt's counter.
+    pub fn crh(self, domain: &[u8], message: &[u8]) -> Result<Vec<u8>, BLSError> {
+        let hash_bytes = hash_length(
+            GroupAffine::<<Parameters as Bls12Parameters>::G1Parameters>::SERIALIZED_SIZE,
+        );
+        match self {
+            SnarkCrh::BoweHopwood => COMPOSITE_HASHER.crh(domain, message, hash_bytes),
+            SnarkCrh::Blake2s => DirectHasher.crh(domain, message, hash_bytes),
+        }
+    }
+
+    /// Hashes the message to G1 with this CRH, returning the hash along with the counter of
+    /// the successful attempt
+    pub fn hash_with_attempt(
+        self,
+        domain: &[u8],
+        message: &[u8],
+        extra_data: &[u8],
+    ) -> Result<(G1Projective, usize), BLSError> {
+        match self {
+            SnarkCrh::BoweHopwood => {
+                COMPOSITE_HASH_TO_G1.hash_with_attempt(domain, message, extra_data)
+            }
+            SnarkCrh::Blake2s => DIRECT_HASH_TO_G1.hash_with_attempt(domain, message, extra_data),
+        }
+    }
+}
+
+impl HashToCurve for SnarkCrh {
+    type Output = G1Projective;
+
+    fn hash(
+        &self,
+        domain: &[u8],
+        message: &[u8],
+        extra_data: &[u8],
+    ) -> Result<Self::Output, BLSError> {
+        self.hash_with_attempt(domain, message, extra_data)
+            .map(|res| res.0)
+    }
+}
+
 /// A try-and-increment method for hashing to G1 and G2. See page 521 in
 /// https://link.springer.com/content/pdf/10.1007/3-540-45682-1_30.pdf.
 #[derive(Clone)]
@@ -85,13 +160,39 @@ where
         message: &[u8],
         extra_data: &[u8],
     ) -> Result<(GroupProjective<P>, usize), BLSError> {
+        self.try_and_increment(domain, message, extra_data)
+            .map(|(_, hash, attempt)| (hash, attempt))
+    }
+
+    /// Returns the element of the curve's base field which the input is mapped from, i.e.
+    /// the x coordinate of the point found by the try-and-increment loop, before it gets
+    /// multiplied by the cofactor.
+    pub fn hash_to_field(
+        &self,
+        domain: &[u8],
+        message: &[u8],
+        extra_data: &[u8],
+    ) -> Result<P::BaseField, BLSError> {
+        self.try_and_increment(domain, message, extra_data)
+            .map(|(point, _, _)| point.x)
+    }
+
+    /// Returns the point decoded from the hash of the input with the first counter for which
+    /// the hash is the x coordinate of a point whose multiple by the cofactor is not zero,
+    /// along with that multiple and the counter
+    fn try_and_increment(
+        &self,
+        domain: &[u8],
+        message: &[u8],
+        extra_data: &[u8],
+    ) -> Result<(GroupAffine<P>, GroupProjective<P>, usize), BLSError> {
         let num_bytes = GroupAffine::<P>::SERIALIZED_SIZE;
         let hash_loop_time = start_timer!(|| "try_and_increment::hash_loop");
         let hash_bytes = hash_length(num_bytes);
 
         let mut counter = [0; 1];
         for c in 0..NUM_TRIES {
-            (&mut counter[..]).write_u8(c as u8)?;
+            counter[0] = c as u8;
 
             // concatenate the message with the counter
             let msg = &[&counter, extra_data, &message].concat();
@@ -127,7 +228,7 @@ where
                     continue;
                 }
 
-                return Ok((scaled, c as usize));
+                return Ok((p, scaled, c as usize));
             }
         }
         Err(BLSError::HashToCurveError)
@@ -180,6 +281,42 @@ mod test {
         hash_to_curve_test::<<Parameters as Bls12Parameters>::G2Parameters, _>(h)
     }
 
+    #[test]
+    fn snark_crh_matches_hashers() {
+        let domain = &b"domain"[..];
+        let message = &b"some message"[..];
+        let hashers: [(SnarkCrh, &dyn Hasher<Error = BLSError>); 2] = [
+            (SnarkCrh::BoweHopwood, &*COMPOSITE_HASHER),
+            (SnarkCrh::Blake2s, &DirectHasher),
+        ];
+        for (crh, hasher) in &hashers {
+            let compressed = crh.crh(domain, message).unwrap();
+            assert_eq!(compressed.len() * 8, crh.output_bits());
+            assert_eq!(
+                hasher.xof(domain, &compressed, 64).unwrap(),
+                hasher.hash(domain, message, 64).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn snark_crh_hashes_like_the_hashers() {
+        let domain = &b"domain"[..];
+        let message = &b"some message"[..];
+        assert_eq!(
+            SnarkCrh::BoweHopwood.hash(domain, message, &[]).unwrap(),
+            COMPOSITE_HASH_TO_G1.hash(domain, message, &[]).unwrap()
+        );
+        assert_eq!(
+            SnarkCrh::Blake2s
+                .hash_with_attempt(domain, message, &[])
+                .unwrap(),
+            DIRECT_HASH_TO_G1
+                .hash_with_attempt(domain, message, &[])
+                .unwrap()
+        );
+    }
+
     fn hash_to_curve_test<P: SWModelParameters, X: Hasher<Error = BLSError>>(h: X) {
         let hasher = TryAndIncrement::<X, P>::new(&h);
         let mut rng = rand::thread_rng();
@@ -290,7 +427,7 @@ mod compat_tests {
         let mut counter: [u8; 1] = [0; 1];
         let hash_loop_time = start_timer!(|| "try_and_increment::hash_loop");
         for c in 0..NUM_TRIES {
-            (&mut counter[..]).write_u8(c as u8)?;
+            counter[0] = c as u8;
             let hash = hasher.hash(
                 domain,
                 &[&counter, extra_data, &message].concat(),
@@ -393,6 +530,40 @@ mod non_compat_tests {
         super::test::test_hash_to_group(&*COMPOSITE_HASH_TO_G1, &mut rng, expected_hashes)
     }
 
+    #[test]
+    fn standalone_hash_to_curve() {
+        use crate::hash_to_curve::{hash_to_curve, hash_to_field};
+        use algebra::{CanonicalSerialize, ProjectiveCurve};
+
+        // the first inputs of `test_hash_to_curve_g1`, with the extra data prepended to the
+        // message, since the hasher hashes the counter, the extra data and the message
+        let mut rng = XorShiftRng::from_seed([
+            0x5d, 0xbe, 0x62, 0x59, 0x8d, 0x31, 0x3d, 0x76, 0x32, 0x37, 0xdb, 0x17, 0xe5, 0xbc,
+            0x06, 0x54,
+        ]);
+        let expected_hashes = [
+            "a7e17c99126acf78536e64fffe88e1032d834b483584fe5757b1deafa493c97a132572c7825ca4f617f6bcef93b93980",
+            "21e328cfedb263f8c815131cc42f0357ab0ba903d855a11de6e7bcd7e61375a818d1b093bcf9fce224536714efad5c80",
+            "fcc8bc80a528b32762ad3b3f72d40b069083b833ad4b6e135040414e2634657e1cf1ec070235ba1425f350df8c585d81",
+        ];
+        for expected_hash in &expected_hashes {
+            let (domain, msg, extra_data) = super::test::generate_test_data(&mut rng);
+            let message = [&extra_data[..], &msg[..]].concat();
+            let hash = hash_to_curve(&message, &domain).unwrap();
+            let mut bytes = vec![];
+            hash.into_affine().serialize(&mut bytes).unwrap();
+            assert_eq!(hex::encode(bytes), *expected_hash);
+
+            let x = hash_to_field(&message, &domain).unwrap();
+            assert_eq!(
+                x,
+                COMPOSITE_HASH_TO_G1
+                    .hash_to_field(&domain, &msg, &extra_data)
+                    .unwrap()
+            );
+        }
+    }
+
     #[test]
     fn test_hash_to_curve_g2() {
         let mut rng = XorShiftRng::from_seed([
@@ -1,7 +1,7 @@
 //! Utilities for hashing using a fixed-length CRH. Consider using the re-exported
 //! COMPOSITE_HASHER which is already instantiated with the Bowe Hopwood Pedersen CRH and
 //! Blake2x as the XOF
-use crate::{hashers::DirectHasher, BLSError, Hasher};
+use crate::{hashers::DirectHasher, BLSError, Hasher, Lazy, Vec};
 
 use algebra::{edwards_sw6::EdwardsProjective as Edwards, CanonicalSerialize, ProjectiveCurve};
 
@@ -9,7 +9,6 @@ use blake2s_simd::Params;
 use crypto_primitives::crh::{
     bowe_hopwood::BoweHopwoodPedersenCRH, pedersen::PedersenWindow, FixedLengthCRH,
 };
-use once_cell::sync::Lazy;
 use rand::{Rng, SeedableRng};
 use rand_chacha::ChaChaRng;
 
@@ -31,6 +30,9 @@ mod window {
 /// `NUM_WINDOWS = 560`
 pub type CRH = BoweHopwoodPedersenCRH<Edwards, window::Window>;
 
+/// The parameters of the `CRH`, i.e. its generators and their precomputed multiples
+pub type CRHParameters = <CRH as FixedLengthCRH>::Parameters;
+
 /// Lazily evaluated composite hasher instantiated over the
 /// Bowe-Hopwood-Pedersen CRH.
 pub static COMPOSITE_HASHER: Lazy<CompositeHasher<CRH>> =
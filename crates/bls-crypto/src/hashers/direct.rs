@@ -1,6 +1,5 @@
-use crate::{hashers::Hasher, BLSError};
+use crate::{hashers::Hasher, BLSError, Vec};
 use blake2s_simd::Params;
-use byteorder::{LittleEndian, WriteBytesExt};
 
 /// Uses Blake2s as a CRH and Blake2x as the XOF.
 pub struct DirectHasher;
@@ -9,8 +8,7 @@ fn xof_digest_length_to_node_offset(
     node_offset: u64,
     xof_digest_length: usize,
 ) -> Result<u64, BLSError> {
-    let mut xof_digest_length_bytes: [u8; 2] = [0; 2];
-    (&mut xof_digest_length_bytes[..]).write_u16::<LittleEndian>(xof_digest_length as u16)?;
+    let xof_digest_length_bytes = (xof_digest_length as u16).to_le_bytes();
     let offset = node_offset as u64
         | ((xof_digest_length_bytes[0] as u64) << 32)
         | ((xof_digest_length_bytes[1] as u64) << 40);
@@ -1,3 +1,5 @@
+use crate::Vec;
+
 pub mod composite;
 pub use composite::COMPOSITE_HASHER;
 
@@ -49,14 +49,41 @@
 //!
 //! Currently the supported curves are BLS12-377 with signatures on G1 and public keys on G2.
 //! In a future iteration, this will be abstracted to support any curve which implements
-//! algebra's `PairingEngine` trait. We will also support public keys on G1 and signatures on G2.
+//! algebra's `PairingEngine` trait. `PublicKey` and `Signature` are generic over the
+//! [`GroupPlacement`](bls/placement/trait.GroupPlacement.html) of their groups, so public keys
+//! on G1 and signatures on G2 are supported with `KeysOnG1`.
+//!
+//! # `no_std`
+//!
+//! With the default `std` feature disabled the crate only depends on `core` and `alloc`, so
+//! that the epochs SNARK's verifier can hash and check epochs on devices without the standard
+//! library. The [`PublicKeyCache`](struct.PublicKeyCache.html) requires `std`.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(not(feature = "std"))]
+#[macro_use]
+extern crate alloc;
+
+#[cfg(not(feature = "std"))]
+use alloc::{boxed::Box, vec::Vec};
+#[cfg(feature = "std")]
+use std::{boxed::Box, vec::Vec};
+
+// The lazily initialized hashers are shared between threads with `std`, and initialized
+// behind a spin lock without it
+#[cfg(feature = "std")]
+use once_cell::sync::Lazy;
+#[cfg(not(feature = "std"))]
+use spin::Lazy;
 
 pub mod bls;
-pub use bls::{PrivateKey, PublicKey, PublicKeyCache, Signature};
+#[cfg(feature = "std")]
+pub use bls::PublicKeyCache;
+pub use bls::{verify_signature, verify_signature_with_domain, PrivateKey, PublicKey, Signature};
 
 /// Traits and implementations for hashing arbitrary data to an elliptic curve's group element
 pub mod hash_to_curve;
-pub use hash_to_curve::HashToCurve;
+pub use hash_to_curve::{HashToCurve, HashToCurveMethod};
 
 /// Hash function implementations using a CRH followed by a XOF.
 pub mod hashers;
@@ -65,11 +92,13 @@ pub use hashers::Hasher;
 #[cfg(any(test, feature = "test-helpers"))]
 pub mod test_helpers;
 
-use log::error;
-use thiserror::Error;
+/// Group arithmetic helpers, such as multi-scalar multiplication
+pub mod util;
+
+use core::fmt;
 
 /// Convenience result alias
-pub type BlsResult<T> = std::result::Result<T, BLSError>;
+pub type BlsResult<T> = core::result::Result<T, BLSError>;
 
 /// Domain separator for signing messages
 pub const SIG_DOMAIN: &[u8] = b"ULforxof";
@@ -80,30 +109,134 @@ pub const POP_DOMAIN: &[u8] = b"ULforpop";
 /// Domain separator for public inputs to the snark
 pub const OUT_DOMAIN: &[u8] = b"ULforout";
 
-#[derive(Debug, Error)]
+/// A domain separation tag for hashing messages to G1, used as the Blake2 personalization.
+///
+/// Tags shorter than 8 bytes are padded with zeros, which is how Blake2 pads its
+/// personalization anyway. Defaults to the `SIG_DOMAIN` used by the Celo network.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Domain([u8; 8]);
+
+impl Domain {
+    /// Creates a domain from the provided tag, which must be at most 8 bytes long
+    pub fn new(tag: &[u8]) -> BlsResult<Self> {
+        if tag.len() > 8 {
+            return Err(BLSError::DomainTooLarge(tag.len()));
+        }
+        let mut domain = [0; 8];
+        domain[..tag.len()].copy_from_slice(tag);
+        Ok(Domain(domain))
+    }
+
+    /// The tag as a byte slice, to be passed to the hashers
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// The tag as a Blake2 personalization, to be passed to the hashing gadgets
+    pub fn personalization(&self) -> [u8; 8] {
+        self.0
+    }
+}
+
+impl Default for Domain {
+    fn default() -> Self {
+        let mut domain = [0; 8];
+        domain.copy_from_slice(SIG_DOMAIN);
+        Domain(domain)
+    }
+}
+
+#[derive(Debug)]
 /// Error type
 pub enum BLSError {
     /// Error
-    #[error("signature verification failed")]
     VerificationFailed,
 
     /// An IO error
-    #[error("io error {0}")]
-    IoError(#[from] std::io::Error),
+    IoError(algebra::io::Error),
 
     /// Error while hashing
-    #[error("error in hasher {0}")]
-    HashingError(#[from] Box<dyn std::error::Error>),
+    HashingError(Box<dyn algebra::Error>),
 
     /// Personalization string cannot be larger than 8 bytes
-    #[error("domain length is too large: {0}")]
     DomainTooLarge(usize),
 
     /// Hashing to curve failed
-    #[error("Could not hash to curve")]
     HashToCurveError,
 
+    /// Tried to aggregate an empty set of signatures or public keys
+    EmptyAggregation,
+
+    /// The number of bases and scalars of a multi-scalar multiplication differ
+    LengthMismatch { bases: usize, scalars: usize },
+
     /// Serialization error in Zexe
-    #[error(transparent)]
-    SerializationError(#[from] algebra::SerializationError),
+    SerializationError(algebra::SerializationError),
+}
+
+impl fmt::Display for BLSError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BLSError::VerificationFailed => write!(f, "signature verification failed"),
+            BLSError::IoError(e) => write!(f, "io error {}", e),
+            BLSError::HashingError(e) => write!(f, "error in hasher {}", e),
+            BLSError::DomainTooLarge(len) => write!(f, "domain length is too large: {}", len),
+            BLSError::HashToCurveError => write!(f, "Could not hash to curve"),
+            BLSError::EmptyAggregation => write!(f, "cannot aggregate an empty set of elements"),
+            BLSError::LengthMismatch { bases, scalars } => {
+                write!(f, "got {} bases but {} scalars", bases, scalars)
+            }
+            BLSError::SerializationError(e) => fmt::Display::fmt(e, f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BLSError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BLSError::IoError(e) => Some(e),
+            BLSError::SerializationError(e) => std::error::Error::source(e),
+            BLSError::VerificationFailed
+            | BLSError::HashingError(_)
+            | BLSError::DomainTooLarge(_)
+            | BLSError::HashToCurveError
+            | BLSError::EmptyAggregation
+            | BLSError::LengthMismatch { .. } => None,
+        }
+    }
+}
+
+impl From<algebra::io::Error> for BLSError {
+    fn from(e: algebra::io::Error) -> Self {
+        BLSError::IoError(e)
+    }
+}
+
+impl From<Box<dyn algebra::Error>> for BLSError {
+    fn from(e: Box<dyn algebra::Error>) -> Self {
+        BLSError::HashingError(e)
+    }
+}
+
+impl From<algebra::SerializationError> for BLSError {
+    fn from(e: algebra::SerializationError) -> Self {
+        BLSError::SerializationError(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn domain_tags() {
+        assert_eq!(Domain::default().as_bytes(), SIG_DOMAIN);
+        assert_eq!(Domain::new(SIG_DOMAIN).unwrap(), Domain::default());
+        assert_eq!(Domain::new(b"test").unwrap().as_bytes(), b"test\0\0\0\0");
+        assert!(matches!(
+            Domain::new(b"too long!").unwrap_err(),
+            BLSError::DomainTooLarge(9)
+        ));
+    }
 }
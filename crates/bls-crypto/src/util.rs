@@ -0,0 +1,64 @@
+use crate::{BLSError, BlsResult, Vec};
+use algebra::{msm::VariableBaseMSM, PrimeField, ProjectiveCurve};
+
+/// Computes the multi-scalar multiplication `sum(scalars[i] * bases[i])` with zexe's variable
+/// base MSM (Pippenger's algorithm), which is much faster than multiplying and summing each
+/// pair for large inputs.
+///
+/// Returns `BLSError::LengthMismatch` if there is not exactly one scalar per base. An empty
+/// input sums to zero.
+pub fn msm<G: ProjectiveCurve>(bases: &[G], scalars: &[G::ScalarField]) -> BlsResult<G> {
+    if bases.len() != scalars.len() {
+        return Err(BLSError::LengthMismatch {
+            bases: bases.len(),
+            scalars: scalars.len(),
+        });
+    }
+    let bases = G::batch_normalization_into_affine(bases);
+    let scalars = scalars.iter().map(|s| s.into_repr()).collect::<Vec<_>>();
+    Ok(VariableBaseMSM::multi_scalar_mul(&bases, &scalars))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use algebra::{
+        bls12_377::{Fr, G1Projective, G2Projective},
+        UniformRand, Zero,
+    };
+
+    fn naive_msm<G: ProjectiveCurve>(bases: &[G], scalars: &[G::ScalarField]) -> G {
+        bases
+            .iter()
+            .zip(scalars)
+            .map(|(base, scalar)| ProjectiveCurve::mul(*base, *scalar))
+            .sum()
+    }
+
+    fn check_msm<G: ProjectiveCurve<ScalarField = Fr>>() {
+        let rng = &mut rand::thread_rng();
+        for len in 0..10 {
+            let bases = (0..len).map(|_| G::rand(rng)).collect::<Vec<_>>();
+            let scalars = (0..len).map(|_| Fr::rand(rng)).collect::<Vec<_>>();
+            assert_eq!(msm(&bases, &scalars).unwrap(), naive_msm(&bases, &scalars));
+        }
+    }
+
+    #[test]
+    fn msm_matches_naive_sum() {
+        check_msm::<G1Projective>();
+        check_msm::<G2Projective>();
+        assert!(msm::<G1Projective>(&[], &[]).unwrap().is_zero());
+    }
+
+    #[test]
+    fn msm_length_mismatch() {
+        let rng = &mut rand::thread_rng();
+        let bases = vec![G1Projective::rand(rng); 3];
+        let scalars = vec![Fr::rand(rng); 2];
+        match msm(&bases, &scalars).unwrap_err() {
+            BLSError::LengthMismatch { bases, scalars } => assert_eq!((bases, scalars), (3, 2)),
+            err => panic!("unexpected error: {}", err),
+        }
+    }
+}
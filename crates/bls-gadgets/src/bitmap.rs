@@ -8,7 +8,9 @@ use r1cs_std::{
 };
 
 /// Enforces that there are no more than `max_occurrences` of `value` (0 or 1)
-/// present in the provided bitmap
+/// present in the provided bitmap. The bound is inclusive, i.e. a bitmap with exactly
+/// `max_occurrences` matching bits satisfies the constraints and one with
+/// `max_occurrences + 1` does not.
 pub fn enforce_maximum_occurrences_in_bitmap<F: PrimeField, CS: ConstraintSystem<F>>(
     cs: &mut CS,
     bitmap: &[Boolean],
@@ -178,6 +180,14 @@ mod tests {
             assert!(cs_enforce_value(&[false, true, true, false, false], 3, false).is_satisfied());
         }
 
+        #[test]
+        fn bound_is_inclusive() {
+            let bitmap = [true, false, true, false, true];
+            assert!(cs_enforce_value(&bitmap, 3, false).is_satisfied());
+            assert!(cs_enforce_value(&bitmap, 2, false).is_satisfied());
+            assert!(!cs_enforce_value(&bitmap, 1, false).is_satisfied());
+        }
+
         #[test]
         fn four_zeros_not_allowed() {
             assert!(
@@ -8,6 +8,10 @@ use r1cs_std::{
 use std::marker::PhantomData;
 use tracing::{debug, span, trace, Level};
 
+/// The namespace in which the BLS gadget checks the bitmap of the signers against the
+/// maximum number of non signers, e.g. for counting the check's constraints separately
+pub const BITMAP_NAMESPACE: &str = "enforce bitmap";
+
 /// BLS Signature Verification Gadget.
 ///
 /// Implements BLS Verification as written in [BDN18](https://eprint.iacr.org/2018/483.pdf)
@@ -202,7 +206,7 @@ where
     }
 
     /// Enforces that the provided bitmap contains no more than `maximum_non_signers`
-    /// 0s. Also returns a gadget of the prepared message hash and a gadget for the aggregate public key
+    /// 0s (exactly `maximum_non_signers` 0s are allowed). Also returns a gadget of the prepared message hash and a gadget for the aggregate public key
     ///
     /// # Panics
     /// If signed_bitmap length != pub_keys length (due to internal call to `enforced_aggregated_pubkeys`)
@@ -214,7 +218,12 @@ where
         maximum_non_signers: &FpGadget<F>,
     ) -> Result<(P::G1PreparedGadget, P::G2PreparedGadget), SynthesisError> {
         trace!("enforcing bitmap");
-        enforce_maximum_occurrences_in_bitmap(&mut cs, signed_bitmap, maximum_non_signers, false)?;
+        enforce_maximum_occurrences_in_bitmap(
+            &mut cs.ns(|| BITMAP_NAMESPACE),
+            signed_bitmap,
+            maximum_non_signers,
+            false,
+        )?;
 
         trace!("preparing message hash and aggregated pubkey");
         let prepared_message_hash =
@@ -3,11 +3,12 @@ use crate::{
     YToBitGadget,
 };
 use bls_crypto::{
+    hash_to_curve::try_and_increment::SnarkCrh,
     hashers::{
-        composite::{CompositeHasher, CRH},
+        composite::{CRHParameters, CompositeHasher, CRH},
         DirectHasher, Hasher,
     },
-    SIG_DOMAIN,
+    Domain,
 };
 
 // Imported for the BLS12-377 API
@@ -110,6 +111,53 @@ impl HashToGroupGadget<Bls12_377_Parameters> {
         counter: UInt8,
         message: &[UInt8],
         generate_constraints_for_hash: bool,
+    ) -> Result<(G1Gadget<Bls12_377_Parameters>, Vec<Boolean>, Vec<Boolean>), SynthesisError> {
+        Self::enforce_hash_to_group_with_domain(
+            cs,
+            counter,
+            message,
+            Domain::default(),
+            generate_constraints_for_hash,
+        )
+    }
+
+    /// Same as [`enforce_hash_to_group`](#method.enforce_hash_to_group), but hashes the
+    /// message in the provided domain instead of the `SIG_DOMAIN`
+    #[allow(clippy::type_complexity)]
+    pub fn enforce_hash_to_group_with_domain<CS: ConstraintSystem<Bls12_377_Fq>>(
+        cs: &mut CS,
+        counter: UInt8,
+        message: &[UInt8],
+        domain: Domain,
+        generate_constraints_for_hash: bool,
+    ) -> Result<(G1Gadget<Bls12_377_Parameters>, Vec<Boolean>, Vec<Boolean>), SynthesisError> {
+        Self::enforce_hash_to_group_with_parameters(
+            cs,
+            counter,
+            message,
+            domain,
+            None,
+            generate_constraints_for_hash,
+        )
+    }
+
+    /// Same as [`enforce_hash_to_group_with_domain`](#method.enforce_hash_to_group_with_domain),
+    /// but compresses the input with the provided CRH parameters. Generating them is expensive,
+    /// so callers which hash many messages should generate them once with
+    /// `CompositeHasher::setup_crh` and reuse them. If `None` is provided, they are generated
+    /// on every call.
+    ///
+    /// The input is compressed with `SnarkCrh::CURRENT`, i.e. with Blake2s instead of the
+    /// Bowe-Hopwood hash if `bls-crypto`'s `blake2-crh` feature is enabled. In that case the
+    /// CRH parameters are not used.
+    #[allow(clippy::type_complexity)]
+    pub fn enforce_hash_to_group_with_parameters<CS: ConstraintSystem<Bls12_377_Fq>>(
+        cs: &mut CS,
+        counter: UInt8,
+        message: &[UInt8],
+        domain: Domain,
+        crh_parameters: Option<&CRHParameters>,
+        generate_constraints_for_hash: bool,
     ) -> Result<(G1Gadget<Bls12_377_Parameters>, Vec<Boolean>, Vec<Boolean>), SynthesisError> {
         let span = span!(Level::TRACE, "enforce_hash_to_group",);
         let _enter = span.enter();
@@ -118,18 +166,19 @@ impl HashToGroupGadget<Bls12_377_Parameters> {
         let mut input = vec![counter];
         input.extend_from_slice(message);
         // compress the input
-        let crh_bits = Self::pedersen_hash(cs, &input)?;
+        let crh_bits = match SnarkCrh::CURRENT {
+            SnarkCrh::BoweHopwood => Self::pedersen_hash(cs, &input, crh_parameters)?,
+            SnarkCrh::Blake2s => Self::blake2s_hash(cs, &input, domain.personalization())?,
+        };
 
         // Hash to bits
-        let mut personalization = [0; 8];
-        personalization.copy_from_slice(SIG_DOMAIN);
         // We want 378 random bits for hashing to curve, so we get 512 from the hash and will
         // discard any unneeded ones. We do not generate constraints.
         let xof_bits = hash_to_bits(
             cs.ns(|| "hash to bits"),
             &crh_bits,
             512,
-            personalization,
+            domain.personalization(),
             generate_constraints_for_hash,
         )?;
 
@@ -143,14 +192,21 @@ impl HashToGroupGadget<Bls12_377_Parameters> {
     fn pedersen_hash<CS: ConstraintSystem<Bls12_377_Fq>>(
         cs: &mut CS,
         input: &[UInt8],
+        crh_parameters: Option<&CRHParameters>,
     ) -> Result<Vec<Boolean>, SynthesisError> {
-        // We setup by getting the Parameters over the provided CRH
-        let crh_params =
-            <BHHashSW6 as FixedLengthCRHGadget<CRH, _>>::ParametersGadget::alloc_constant(
+        // We setup by getting the Parameters over the provided CRH, unless they were provided
+        type ParametersGadget =
+            <BHHashSW6 as FixedLengthCRHGadget<CRH, Bls12_377_Fq>>::ParametersGadget;
+        let crh_params = match crh_parameters {
+            Some(parameters) => {
+                ParametersGadget::alloc_constant(cs.ns(|| "pedersen parameters"), parameters)?
+            }
+            None => ParametersGadget::alloc_constant(
                 cs.ns(|| "pedersen parameters"),
                 CompositeHasher::<CRH>::setup_crh()
                     .map_err(|_| SynthesisError::AssignmentMissing)?,
-            )?;
+            )?,
+        };
 
         let pedersen_hash = <BHHashSW6 as FixedLengthCRHGadget<CRH, _>>::check_evaluation_gadget(
             &mut cs.ns(|| "pedersen evaluation"),
@@ -168,6 +224,47 @@ impl HashToGroupGadget<Bls12_377_Parameters> {
         }
         Ok(crh_bits)
     }
+
+    /// Compress the input by passing it through Blake2s, personalized with the domain
+    fn blake2s_hash<CS: ConstraintSystem<Bls12_377_Fq>>(
+        cs: &mut CS,
+        input: &[UInt8],
+        personalization: [u8; 8],
+    ) -> Result<Vec<Boolean>, SynthesisError> {
+        // Same parameters as `DirectHasher::crh`: a default Blake2s parameter block, which
+        // encodes the length of the XOF's output
+        let parameters = Blake2sWithParameterBlock {
+            digest_length: 32,
+            key_length: 0,
+            fan_out: 1,
+            depth: 1,
+            leaf_length: 0,
+            node_offset: 0,
+            xof_digest_length: 64,
+            node_depth: 0,
+            inner_length: 0,
+            salt: [0; 8],
+            personalization,
+        };
+        let input_bits = input
+            .iter()
+            .flat_map(|byte| byte.into_bits_le())
+            .collect::<Vec<_>>();
+        let hash = blake2s_gadget_with_parameters(
+            cs.ns(|| "blake2s evaluation"),
+            &input_bits,
+            &parameters.parameters(),
+        )?;
+
+        // The hash is in LE, while the XOF expects its input in BE, like the Pedersen hash's bits
+        let mut crh_bits = hash
+            .into_iter()
+            .map(|n| n.to_bits_le())
+            .flatten()
+            .collect::<Vec<Boolean>>();
+        crh_bits.reverse();
+        Ok(crh_bits)
+    }
 }
 
 /// Hashes the message to produce a `hash_length` hash with the provided personalization
@@ -369,7 +466,7 @@ mod test {
     use algebra::bls12_377;
     use r1cs_std::{groups::GroupGadget, test_constraint_system::TestConstraintSystem};
 
-    use bls_crypto::hash_to_curve::try_and_increment::COMPOSITE_HASH_TO_G1;
+    use bls_crypto::hash_to_curve::try_and_increment::SnarkCrh;
     use r1cs_std::bits::uint8::UInt8;
     use rand::{thread_rng, RngCore};
 
@@ -383,14 +480,64 @@ mod test {
             rng.fill_bytes(&mut input);
             // check that they get hashed properly
             dbg!(length);
-            hash_to_group(&input);
+            hash_to_group(&input, Domain::default());
         }
     }
 
-    fn hash_to_group(input: &[u8]) {
-        let try_and_increment = &*COMPOSITE_HASH_TO_G1;
+    #[test]
+    fn hash_to_group_custom_domain() {
+        let mut input = vec![0; 50];
+        thread_rng().fill_bytes(&mut input);
+        hash_to_group(&input, Domain::new(b"testnet").unwrap());
+    }
+
+    #[test]
+    fn hash_to_group_precomputed_parameters() {
+        let mut input = vec![0; 50];
+        thread_rng().fill_bytes(&mut input);
+        let parameters = CompositeHasher::<CRH>::setup_crh().unwrap();
+        let (hash, num_constraints) = hash_to_group_with_parameters(&input, None);
+        assert_eq!(
+            hash_to_group_with_parameters(&input, Some(&parameters)),
+            (hash, num_constraints)
+        );
+    }
+
+    fn hash_to_group_with_parameters(
+        input: &[u8],
+        parameters: Option<&CRHParameters>,
+    ) -> (bls12_377::G1Projective, usize) {
+        let (_, attempt) = SnarkCrh::CURRENT
+            .hash_with_attempt(Domain::default().as_bytes(), input, &[])
+            .unwrap();
+        let mut cs = TestConstraintSystem::<bls12_377::Fq>::new();
+        let counter = UInt8::alloc(&mut cs.ns(|| "alloc counter"), || Ok(attempt as u8)).unwrap();
+        let input = input
+            .iter()
+            .enumerate()
+            .map(|(i, num)| {
+                UInt8::alloc(&mut cs.ns(|| format!("input {}", i)), || Ok(num)).unwrap()
+            })
+            .collect::<Vec<_>>();
+        let hash =
+            HashToGroupGadget::<bls12_377::Parameters>::enforce_hash_to_group_with_parameters(
+                &mut cs.ns(|| "hash to group"),
+                counter,
+                &input,
+                Domain::default(),
+                parameters,
+                false,
+            )
+            .unwrap()
+            .0;
+        assert!(cs.is_satisfied());
+        (hash.get_value().unwrap(), cs.num_constraints())
+    }
+
+    fn hash_to_group(input: &[u8], domain: Domain) {
+        let try_and_increment = SnarkCrh::CURRENT;
         let (expected_hash, attempt) = try_and_increment
-            .hash_with_attempt(SIG_DOMAIN, input, &[])
+            .hash_with_attempt(domain.as_bytes(), input, &[])
             .unwrap();
 
         let mut cs = TestConstraintSystem::<bls12_377::Fq>::new();
@@ -404,10 +551,11 @@ mod test {
             })
             .collect::<Vec<_>>();
 
-        let hash = HashToGroupGadget::<bls12_377::Parameters>::enforce_hash_to_group(
+        let hash = HashToGroupGadget::<bls12_377::Parameters>::enforce_hash_to_group_with_domain(
             &mut cs.ns(|| "hash to group"),
             counter,
             &input,
+            domain,
             false,
         )
         .unwrap()
@@ -2,17 +2,39 @@
 //!
 //! This module provides gadgets for constructing R1CS involving BLS Signatures
 //! over the BLS12-377 curve.
+//!
+//! The gadgets require the default `r1cs` feature. Without it the crate is `no_std`
+//! (`core` + `alloc`) and only exposes the bit and byte conversions of [`utils`](utils/index.html),
+//! which the epochs SNARK's verifier uses.
+#![cfg_attr(not(feature = "r1cs"), no_std)]
+
+#[cfg(not(feature = "r1cs"))]
+#[macro_use]
+extern crate alloc;
+
+#[cfg(not(feature = "r1cs"))]
+use alloc::{borrow::ToOwned, vec::Vec};
+#[cfg(feature = "r1cs")]
+use std::{borrow::ToOwned, vec::Vec};
 
+#[cfg(feature = "r1cs")]
 mod bls;
-pub use bls::BlsVerifyGadget;
+#[cfg(feature = "r1cs")]
+pub use bls::{BlsVerifyGadget, BITMAP_NAMESPACE};
 
+#[cfg(feature = "r1cs")]
 mod bitmap;
+#[cfg(feature = "r1cs")]
 pub(crate) use bitmap::enforce_maximum_occurrences_in_bitmap;
 
+#[cfg(feature = "r1cs")]
 mod y_to_bit;
+#[cfg(feature = "r1cs")]
 pub use y_to_bit::YToBitGadget;
 
+#[cfg(feature = "r1cs")]
 mod hash_to_group;
+#[cfg(feature = "r1cs")]
 pub use hash_to_group::{hash_to_bits, HashToGroupGadget};
 
 /// Utility functions which do not involve generating constraints
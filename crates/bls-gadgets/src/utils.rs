@@ -1,9 +1,14 @@
+use crate::{ToOwned, Vec};
+#[cfg(feature = "r1cs")]
 use algebra::Field;
+#[cfg(feature = "r1cs")]
 use r1cs_core::{ConstraintSystem, SynthesisError};
+#[cfg(feature = "r1cs")]
 use r1cs_std::{alloc::AllocGadget, boolean::Boolean};
 
 /// Helper used to skip operations which should not be executed when running the
 /// trusted setup
+#[cfg(feature = "r1cs")]
 pub fn is_setup(message: &[Boolean]) -> bool {
     message.iter().any(|m| m.get_value().is_none())
 }
@@ -50,6 +55,7 @@ pub fn bytes_to_bits(bytes: &[u8], bits_to_take: usize) -> Vec<bool> {
         .collect()
 }
 
+#[cfg(feature = "r1cs")]
 pub(crate) fn constrain_bool<F: Field, CS: ConstraintSystem<F>>(
     cs: &mut CS,
     input: &[bool],
@@ -61,7 +67,7 @@ pub(crate) fn constrain_bool<F: Field, CS: ConstraintSystem<F>>(
         .collect::<Result<Vec<_>, _>>()
 }
 
-#[cfg(any(test, feature = "test-helpers"))]
+#[cfg(all(feature = "r1cs", any(test, feature = "test-helpers")))]
 pub mod test_helpers {
     use algebra::{Field, Group};
     use r1cs_core::ConstraintSystem;
@@ -10,6 +10,10 @@ type PublicKeyCache = bls::PublicKeyCache;
 use bls_crypto::hash_to_curve::try_and_increment::{COMPOSITE_HASH_TO_G1, DIRECT_HASH_TO_G1};
 use core::fmt::Display;
 use once_cell::sync::Lazy;
+use std::{
+    panic::{self, AssertUnwindSafe},
+    ptr,
+};
 
 pub(crate) mod cache;
 pub mod serialization;
@@ -18,16 +22,78 @@ pub mod snark;
 pub mod utils;
 
 pub fn convert_result_to_bool<T, E: Display, F: Fn() -> Result<T, E>>(f: F) -> bool {
-    if let Err(e) = f() {
-        log::error!("SNARK library error: {}", e);
-        return false;
-    }
-    true
+    catch_panic(false, || {
+        if let Err(e) = f() {
+            log::error!("SNARK library error: {}", e);
+            return false;
+        }
+        true
+    })
+}
+
+/// Runs `f`, returning `on_panic` instead if it panics, since unwinding across the FFI
+/// boundary is undefined behavior. Every `extern "C"` function which calls into the
+/// libraries runs its body in here (most of them via `convert_result_to_bool`).
+pub fn catch_panic<T, F: FnOnce() -> T>(on_panic: T, f: F) -> T {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|_| {
+        log::error!("SNARK library error: caught a panic");
+        on_panic
+    })
 }
 
 #[no_mangle]
 /// Initializes the lazily evaluated hashers.
 pub extern "C" fn init() {
-    Lazy::force(&COMPOSITE_HASH_TO_G1);
-    Lazy::force(&DIRECT_HASH_TO_G1);
+    catch_panic((), || {
+        Lazy::force(&COMPOSITE_HASH_TO_G1);
+        Lazy::force(&DIRECT_HASH_TO_G1);
+    })
+}
+
+/// The crate's semantic version, with the curves it was built for (the signature curve and
+/// the SNARK's outer curve) as build metadata
+const VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), "+bls12-377.sw6");
+
+#[no_mangle]
+/// Writes the library's version string (e.g. `0.1.4+bls12-377.sw6`) to `out`, without a
+/// trailing NUL byte, and returns its length.
+///
+/// Nothing is written if `out` is null or `out_len` is smaller than the string, so callers can
+/// query the required size by passing a null buffer first.
+pub unsafe extern "C" fn bls_snark_version(out: *mut u8, out_len: u32) -> u32 {
+    let version = VERSION.as_bytes();
+    if !out.is_null() && out_len as usize >= version.len() {
+        ptr::copy_nonoverlapping(version.as_ptr(), out, version.len());
+    }
+    version.len() as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_string() {
+        let len = unsafe { bls_snark_version(ptr::null_mut(), 0) };
+        assert!(len > 0);
+
+        // too small buffers are left untouched
+        let mut out = vec![0u8; len as usize - 1];
+        assert_eq!(unsafe { bls_snark_version(out.as_mut_ptr(), len - 1) }, len);
+        assert!(out.iter().all(|b| *b == 0));
+
+        let mut out = vec![0u8; len as usize];
+        assert_eq!(unsafe { bls_snark_version(out.as_mut_ptr(), len) }, len);
+        let version = String::from_utf8(out).unwrap();
+        assert!(version.starts_with(env!("CARGO_PKG_VERSION")));
+        assert!(version.ends_with("+bls12-377.sw6"));
+    }
+
+    #[test]
+    fn panics_are_caught() {
+        assert_eq!(catch_panic(0, || 1), 1);
+        assert_eq!(catch_panic(0, || panic!("unwind")), 0);
+        let panicking = || -> Result<(), std::io::Error> { panic!("unwind") };
+        assert!(!convert_result_to_bool(panicking));
+    }
 }
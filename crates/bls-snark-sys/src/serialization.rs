@@ -1,5 +1,8 @@
 use super::{convert_result_to_bool, PrivateKey, PublicKey, Signature};
-use crate::cache::PUBLIC_KEY_CACHE;
+use crate::{
+    cache::PUBLIC_KEY_CACHE,
+    utils::{into_raw_buffer, slice_or_empty},
+};
 use algebra::{
     bls12_377::{Fq, Fq2, G1Affine, G2Affine},
     AffineCurve, CanonicalDeserialize, CanonicalSerialize, FromBytes,
@@ -49,7 +52,7 @@ pub extern "C" fn deserialize_public_key_cached(
     convert_result_to_bool::<_, BLSError, _>(|| {
         let mut cache = PUBLIC_KEY_CACHE.lock().expect("mutex poisoned");
         let bytes =
-            unsafe { slice::from_raw_parts(in_public_key_bytes, in_public_key_bytes_len as usize) };
+            unsafe { slice_or_empty(in_public_key_bytes, in_public_key_bytes_len as usize) };
         let key = cache.deserialize(bytes.to_vec())?;
         unsafe {
             *out_public_key = Box::into_raw(Box::new(key));
@@ -92,7 +95,7 @@ fn deserialize<T: CanonicalDeserialize>(
     out: *mut *mut T,
 ) -> bool {
     convert_result_to_bool::<_, BLSError, _>(|| {
-        let bytes = unsafe { slice::from_raw_parts(in_bytes, in_bytes_len as usize) };
+        let bytes = unsafe { slice_or_empty(in_bytes, in_bytes_len as usize) };
         let key = T::deserialize(&mut &bytes[..])?;
         unsafe {
             *out = Box::into_raw(Box::new(key));
@@ -111,12 +114,11 @@ fn serialize<T: CanonicalSerialize>(
         let obj = unsafe { &*in_obj };
         let mut obj_bytes = vec![];
         obj.serialize(&mut obj_bytes)?;
-        obj_bytes.shrink_to_fit();
+        let (ptr, len) = into_raw_buffer(obj_bytes);
         unsafe {
-            *out_bytes = obj_bytes.as_mut_ptr();
-            *out_len = obj_bytes.len() as c_int;
+            *out_bytes = ptr;
+            *out_len = len as c_int;
         }
-        std::mem::forget(obj_bytes);
 
         Ok(())
     })
@@ -132,19 +134,18 @@ pub extern "C" fn compress_signature(
     out_len: *mut c_int,
 ) -> bool {
     convert_result_to_bool::<_, BLSError, _>(|| {
-        let signature = unsafe { slice::from_raw_parts(in_signature, in_signature_len as usize) };
+        let signature = unsafe { slice_or_empty(in_signature, in_signature_len as usize) };
         let x = Fq::read(&signature[0..48]).unwrap();
         let y = Fq::read(&signature[48..96]).unwrap();
         let affine = G1Affine::new(x, y, false);
         let sig = Signature::from(affine.into_projective());
         let mut obj_bytes = vec![];
         sig.serialize(&mut obj_bytes)?;
-        obj_bytes.shrink_to_fit();
+        let (ptr, len) = into_raw_buffer(obj_bytes);
         unsafe {
-            *out_signature = obj_bytes.as_mut_ptr();
-            *out_len = obj_bytes.len() as c_int;
+            *out_signature = ptr;
+            *out_len = len as c_int;
         }
-        std::mem::forget(obj_bytes);
         Ok(())
     })
 }
@@ -157,7 +158,7 @@ pub extern "C" fn compress_pubkey(
     out_len: *mut c_int,
 ) -> bool {
     convert_result_to_bool::<_, BLSError, _>(|| {
-        let pubkey = unsafe { slice::from_raw_parts(in_pubkey, in_pubkey_len as usize) };
+        let pubkey = unsafe { slice_or_empty(in_pubkey, in_pubkey_len as usize) };
         let x = Fq2::read(&pubkey[0..96]).unwrap();
         let y = Fq2::read(&pubkey[96..192]).unwrap();
         let affine = G2Affine::new(x, y, false);
@@ -165,13 +166,11 @@ pub extern "C" fn compress_pubkey(
 
         let mut obj_bytes = vec![];
         pk.serialize(&mut obj_bytes)?;
-        obj_bytes.shrink_to_fit();
-
+        let (ptr, len) = into_raw_buffer(obj_bytes);
         unsafe {
-            *out_pubkey = obj_bytes.as_mut_ptr();
-            *out_len = obj_bytes.len() as c_int;
+            *out_pubkey = ptr;
+            *out_len = len as c_int;
         }
-        std::mem::forget(obj_bytes);
         Ok(())
     })
 }
@@ -198,7 +197,24 @@ pub unsafe extern "C" fn free_vec(bytes: *mut u8, len: c_int) -> bool {
     if bytes.is_null() {
         return false;
     }
-    Vec::from_raw_parts(bytes, len as usize, len as usize);
+    Box::from_raw(slice::from_raw_parts_mut(bytes, len as usize));
+    true
+}
+
+/// Frees a byte buffer which was allocated by this crate and handed out over the FFI.
+///
+/// # Safety
+///
+/// `ptr` and `len` must exactly match a pointer/length pair which was returned by this
+/// crate, and the buffer must not have been freed already. Passing any other pointer,
+/// or a different length, is undefined behavior since the memory is released with the
+/// Rust allocator.
+#[no_mangle]
+pub unsafe extern "C" fn free_buffer(ptr: *mut u8, len: usize) -> bool {
+    if ptr.is_null() {
+        return false;
+    }
+    Box::from_raw(slice::from_raw_parts_mut(ptr, len));
     true
 }
 
@@ -225,3 +241,61 @@ pub unsafe extern "C" fn destroy_signature(signature: *mut Signature) -> bool {
     Box::from_raw(signature);
     true
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn free_buffer_ok() {
+        let (ptr, len) = into_raw_buffer(vec![1u8, 2, 3, 4]);
+        assert_eq!(len, 4);
+        assert_eq!(unsafe { slice::from_raw_parts(ptr, len) }, &[1, 2, 3, 4]);
+        assert!(unsafe { free_buffer(ptr, len) });
+
+        // a vector with spare capacity is handed out without it
+        let mut v = Vec::with_capacity(16);
+        v.extend_from_slice(&[1u8, 2, 3]);
+        let (ptr, len) = into_raw_buffer(v);
+        assert_eq!(len, 3);
+        assert!(unsafe { free_buffer(ptr, len) });
+
+        let (ptr, len) = into_raw_buffer(vec![]);
+        assert_eq!(len, 0);
+        assert!(unsafe { free_buffer(ptr, len) });
+    }
+
+    #[test]
+    fn malformed_points_fail_cleanly() {
+        // too short to read the coordinates, which panics while slicing
+        let bytes = [1u8; 10];
+        let mut out = std::ptr::null_mut();
+        let mut out_len = 0;
+        assert!(!compress_signature(
+            bytes.as_ptr(),
+            bytes.len() as c_int,
+            &mut out,
+            &mut out_len
+        ));
+        assert!(!compress_pubkey(
+            bytes.as_ptr(),
+            bytes.len() as c_int,
+            &mut out,
+            &mut out_len
+        ));
+        assert!(out.is_null());
+
+        let mut key = std::ptr::null_mut();
+        assert!(!deserialize_public_key(
+            bytes.as_ptr(),
+            bytes.len() as c_int,
+            &mut key
+        ));
+        assert!(key.is_null());
+    }
+
+    #[test]
+    fn free_buffer_null() {
+        assert!(!unsafe { free_buffer(std::ptr::null_mut(), 0) });
+    }
+}
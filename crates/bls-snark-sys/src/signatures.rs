@@ -1,10 +1,10 @@
 use crate::{
     cache::PUBLIC_KEY_CACHE,
-    convert_result_to_bool,
-    utils::{Message, MessageFFI},
+    catch_panic, convert_result_to_bool,
+    utils::{into_raw_buffer, slice_or_empty, Message, MessageFFI},
     PrivateKey, PublicKey, Signature, COMPOSITE_HASH_TO_G1, DIRECT_HASH_TO_G1,
 };
-use algebra::{ProjectiveCurve, ToBytes};
+use algebra::{CanonicalDeserialize, CanonicalSerialize, ProjectiveCurve, ToBytes};
 use bls_crypto::{BLSError, HashToCurve, POP_DOMAIN, SIG_DOMAIN};
 use std::{os::raw::c_int, slice};
 
@@ -13,11 +13,13 @@ use std::{os::raw::c_int, slice};
 /// out_private_key must initialized to memory that can contain a pointer.
 #[no_mangle]
 pub unsafe extern "C" fn generate_private_key(out_private_key: *mut *mut PrivateKey) -> bool {
-    let mut rng = rand::thread_rng();
-    let key = PrivateKey::generate(&mut rng);
-    *out_private_key = Box::into_raw(Box::new(key));
+    catch_panic(false, || {
+        let mut rng = rand::thread_rng();
+        let key = PrivateKey::generate(&mut rng);
+        *out_private_key = Box::into_raw(Box::new(key));
 
-    true
+        true
+    })
 }
 
 #[no_mangle]
@@ -48,9 +50,8 @@ pub extern "C" fn sign_message(
 ) -> bool {
     convert_result_to_bool::<_, BLSError, _>(|| {
         let private_key = unsafe { &*in_private_key };
-        let message = unsafe { slice::from_raw_parts(in_message, in_message_len as usize) };
-        let extra_data =
-            unsafe { slice::from_raw_parts(in_extra_data, in_extra_data_len as usize) };
+        let message = unsafe { slice_or_empty(in_message, in_message_len as usize) };
+        let extra_data = unsafe { slice_or_empty(in_extra_data, in_extra_data_len as usize) };
         let signature = if should_use_composite {
             private_key.sign(message, extra_data, &*COMPOSITE_HASH_TO_G1)?
         } else {
@@ -73,7 +74,7 @@ pub extern "C" fn sign_pop(
 ) -> bool {
     convert_result_to_bool::<_, BLSError, _>(|| {
         let private_key = unsafe { &*in_private_key };
-        let message = unsafe { slice::from_raw_parts(in_message, in_message_len as usize) };
+        let message = unsafe { slice_or_empty(in_message, in_message_len as usize) };
         let signature = private_key.sign_pop(message, &*DIRECT_HASH_TO_G1)?;
         unsafe {
             *out_signature = Box::into_raw(Box::new(signature));
@@ -83,6 +84,51 @@ pub extern "C" fn sign_pop(
     })
 }
 
+/// Size of a compressed signature
+const SIGNATURE_BYTES: usize = 48;
+
+/// Produces a proof of possession of the serialized private key, by signing the compressed
+/// serialization of its public key in the POP_DOMAIN, and writes the compressed signature
+/// to `out_signature`. The proof verifies with `verify_pop`, using the compressed public key
+/// as the message.
+///
+/// Returns `false` if the private key cannot be deserialized, or if `out_signature_len` is
+/// smaller than the 48 bytes of the signature, in which case nothing is written.
+///
+/// # Safety
+///
+/// `in_private_key` must point to `in_private_key_len` bytes and `out_signature` must point
+/// to `out_signature_len` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn sign_key_pop(
+    in_private_key: *const u8,
+    in_private_key_len: c_int,
+    out_signature: *mut u8,
+    out_signature_len: c_int,
+) -> bool {
+    if in_private_key.is_null() || out_signature.is_null() {
+        log::error!("SNARK library error: null pointer passed to sign_key_pop");
+        return false;
+    }
+    if (out_signature_len as usize) < SIGNATURE_BYTES {
+        log::error!(
+            "SNARK library error: signature needs {} bytes, got {}",
+            SIGNATURE_BYTES,
+            out_signature_len
+        );
+        return false;
+    }
+    convert_result_to_bool::<_, BLSError, _>(|| {
+        let mut bytes = slice_or_empty(in_private_key, in_private_key_len as usize);
+        let private_key = PrivateKey::deserialize(&mut bytes)?;
+        let signature = private_key.sign_key_pop(&*DIRECT_HASH_TO_G1)?;
+        let out = slice::from_raw_parts_mut(out_signature, SIGNATURE_BYTES);
+        signature.serialize(out)?;
+
+        Ok(())
+    })
+}
+
 #[no_mangle]
 pub extern "C" fn hash_direct(
     in_message: *const u8,
@@ -92,17 +138,16 @@ pub extern "C" fn hash_direct(
     use_pop: bool,
 ) -> bool {
     convert_result_to_bool::<_, BLSError, _>(|| {
-        let message = unsafe { slice::from_raw_parts(in_message, in_message_len as usize) };
+        let message = unsafe { slice_or_empty(in_message, in_message_len as usize) };
         let domain = if use_pop { POP_DOMAIN } else { SIG_DOMAIN };
         let hash = DIRECT_HASH_TO_G1.hash(domain, message, &[])?;
         let mut obj_bytes = vec![];
         hash.into_affine().write(&mut obj_bytes)?;
-        obj_bytes.shrink_to_fit();
+        let (ptr, len) = into_raw_buffer(obj_bytes);
         unsafe {
-            *out_hash = obj_bytes.as_mut_ptr();
-            *out_len = obj_bytes.len() as c_int;
+            *out_hash = ptr;
+            *out_len = len as c_int;
         }
-        std::mem::forget(obj_bytes);
         Ok(())
     })
 }
@@ -117,18 +162,16 @@ pub extern "C" fn hash_composite(
     out_len: *mut c_int,
 ) -> bool {
     convert_result_to_bool::<_, BLSError, _>(|| {
-        let message = unsafe { slice::from_raw_parts(in_message, in_message_len as usize) };
-        let extra_data =
-            unsafe { slice::from_raw_parts(in_extra_data, in_extra_data_len as usize) };
+        let message = unsafe { slice_or_empty(in_message, in_message_len as usize) };
+        let extra_data = unsafe { slice_or_empty(in_extra_data, in_extra_data_len as usize) };
         let hash = COMPOSITE_HASH_TO_G1.hash(SIG_DOMAIN, message, extra_data)?;
         let mut obj_bytes = vec![];
         hash.write(&mut obj_bytes)?;
-        obj_bytes.shrink_to_fit();
+        let (ptr, len) = into_raw_buffer(obj_bytes);
         unsafe {
-            *out_hash = obj_bytes.as_mut_ptr();
-            *out_len = obj_bytes.len() as c_int;
+            *out_hash = ptr;
+            *out_len = len as c_int;
         }
-        std::mem::forget(obj_bytes);
         Ok(())
     })
 }
@@ -146,9 +189,8 @@ pub extern "C" fn verify_signature(
 ) -> bool {
     convert_result_to_bool::<_, BLSError, _>(|| {
         let public_key = unsafe { &*in_public_key };
-        let message = unsafe { slice::from_raw_parts(in_message, in_message_len as usize) };
-        let extra_data =
-            unsafe { slice::from_raw_parts(in_extra_data, in_extra_data_len as usize) };
+        let message = unsafe { slice_or_empty(in_message, in_message_len as usize) };
+        let extra_data = unsafe { slice_or_empty(in_extra_data, in_extra_data_len as usize) };
         let signature = unsafe { &*in_signature };
         let verified = if should_use_composite {
             public_key
@@ -184,7 +226,7 @@ pub extern "C" fn batch_verify_signature(
 ) -> bool {
     convert_result_to_bool::<_, BLSError, _>(|| {
         // Get the pointers slice
-        let messages: &[MessageFFI] = unsafe { slice::from_raw_parts(messages_ptr, messages_len) };
+        let messages: &[MessageFFI] = unsafe { slice_or_empty(messages_ptr, messages_len) };
 
         // Get the data from the underlying pointers in the right format
         let messages = messages.iter().map(Message::from).collect::<Vec<_>>();
@@ -220,7 +262,7 @@ pub extern "C" fn verify_pop(
 ) -> bool {
     convert_result_to_bool::<_, BLSError, _>(|| {
         let public_key = unsafe { &*in_public_key };
-        let message = unsafe { slice::from_raw_parts(in_message, in_message_len as usize) };
+        let message = unsafe { slice_or_empty(in_message, in_message_len as usize) };
         let signature = unsafe { &*in_signature };
         let verified = public_key
             .verify_pop(message, signature, &*DIRECT_HASH_TO_G1)
@@ -239,7 +281,7 @@ pub extern "C" fn aggregate_public_keys(
 ) -> bool {
     convert_result_to_bool::<_, BLSError, _>(|| {
         let public_keys_ptrs =
-            unsafe { slice::from_raw_parts(in_public_keys, in_public_keys_len as usize) };
+            unsafe { slice_or_empty(in_public_keys, in_public_keys_len as usize) };
         let public_keys = public_keys_ptrs
             .to_vec()
             .into_iter()
@@ -267,7 +309,7 @@ pub extern "C" fn aggregate_public_keys_subtract(
     convert_result_to_bool::<_, BLSError, _>(|| {
         let aggregated_public_key = unsafe { &*in_aggregated_public_key };
         let public_keys_ptrs =
-            unsafe { slice::from_raw_parts(in_public_keys, in_public_keys_len as usize) };
+            unsafe { slice_or_empty(in_public_keys, in_public_keys_len as usize) };
         let public_keys = public_keys_ptrs
             .to_vec()
             .into_iter()
@@ -295,8 +337,7 @@ pub extern "C" fn aggregate_signatures(
     out_signature: *mut *mut Signature,
 ) -> bool {
     convert_result_to_bool::<_, BLSError, _>(|| {
-        let signatures_ptrs =
-            unsafe { slice::from_raw_parts(in_signatures, in_signatures_len as usize) };
+        let signatures_ptrs = unsafe { slice_or_empty(in_signatures, in_signatures_len as usize) };
         let signatures = signatures_ptrs
             .to_vec()
             .into_iter()
@@ -310,3 +351,70 @@ pub extern "C" fn aggregate_signatures(
         Ok(())
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serialization::{deserialize_public_key, deserialize_signature};
+
+    #[test]
+    fn key_pop_roundtrip() {
+        let key = PrivateKey::generate(&mut rand::thread_rng());
+        let mut key_bytes = vec![];
+        key.serialize(&mut key_bytes).unwrap();
+        let mut pubkey_bytes = vec![];
+        key.to_public().serialize(&mut pubkey_bytes).unwrap();
+
+        let mut pop = [0u8; SIGNATURE_BYTES];
+        assert!(unsafe {
+            sign_key_pop(
+                key_bytes.as_ptr(),
+                key_bytes.len() as c_int,
+                pop.as_mut_ptr(),
+                pop.len() as c_int,
+            )
+        });
+        // too small buffers are left untouched
+        let mut small = [0u8; SIGNATURE_BYTES - 1];
+        assert!(!unsafe {
+            sign_key_pop(
+                key_bytes.as_ptr(),
+                key_bytes.len() as c_int,
+                small.as_mut_ptr(),
+                small.len() as c_int,
+            )
+        });
+        assert_eq!(small, [0u8; SIGNATURE_BYTES - 1]);
+
+        let mut pubkey = std::ptr::null_mut();
+        assert!(deserialize_public_key(
+            pubkey_bytes.as_ptr(),
+            pubkey_bytes.len() as c_int,
+            &mut pubkey
+        ));
+        let mut signature = std::ptr::null_mut();
+        assert!(deserialize_signature(
+            pop.as_ptr(),
+            pop.len() as c_int,
+            &mut signature
+        ));
+        let verify = |message: &[u8]| {
+            let mut verified = false;
+            assert!(verify_pop(
+                pubkey,
+                message.as_ptr(),
+                message.len() as c_int,
+                signature,
+                &mut verified
+            ));
+            verified
+        };
+        assert!(verify(&pubkey_bytes));
+        assert!(!verify(b"address"));
+
+        unsafe {
+            crate::serialization::destroy_public_key(pubkey);
+            crate::serialization::destroy_signature(signature);
+        }
+    }
+}
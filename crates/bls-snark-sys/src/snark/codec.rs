@@ -0,0 +1,219 @@
+//! Helpers for decoding hex or base64 encoded proofs and verifying keys before passing them
+//! to the verifier.
+//!
+//! The decoded bytes are only checked to have a length which a compressed or uncompressed
+//! encoding could have, the elements themselves are deserialized by the verifier.
+//!
+//! Blobs which store a verifying key together with a proof are laid out as the VK's length
+//! as a 4 byte little endian integer, followed by the VK and then the proof. They are
+//! created with `join_vk_proof` and split with `split_vk_proof`.
+use algebra::{sw6::SW6 as CPCurve, CanonicalSerialize, PairingEngine};
+use groth16::{Proof, VerifyingKey};
+use std::convert::TryFrom;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+/// Error raised while decoding a proof or a verifying key
+pub enum CodecError {
+    #[error("invalid hex: {0}")]
+    Hex(#[from] hex::FromHexError),
+    #[error("invalid base64: {0}")]
+    Base64(#[from] base64::DecodeError),
+    #[error("{len} bytes cannot be an encoded {kind}")]
+    InvalidLength { kind: &'static str, len: usize },
+    #[error("truncated blob: expected at least {expected} bytes, got {got}")]
+    Truncated { expected: usize, got: usize },
+}
+
+/// Size of the VK length prefix of a combined VK and proof blob
+const VK_LEN_PREFIX_BYTES: usize = 4;
+
+/// Concatenates the verifying key and the proof into a single length prefixed blob, which
+/// can be split again with `split_vk_proof`
+pub fn join_vk_proof(vk: &[u8], proof: &[u8]) -> Result<Vec<u8>, CodecError> {
+    let vk_len = u32::try_from(vk.len()).map_err(|_| CodecError::InvalidLength {
+        kind: "verifying key",
+        len: vk.len(),
+    })?;
+    check_vk_len(vk.to_vec())?;
+    check_proof_len(proof.to_vec())?;
+
+    let mut blob = Vec::with_capacity(VK_LEN_PREFIX_BYTES + vk.len() + proof.len());
+    blob.extend_from_slice(&vk_len.to_le_bytes());
+    blob.extend_from_slice(vk);
+    blob.extend_from_slice(proof);
+    Ok(blob)
+}
+
+/// Splits a blob created with `join_vk_proof` into the verifying key and the proof, checking
+/// that the prefix does not point past the end of the blob and that both parts have a valid
+/// length
+pub fn split_vk_proof(blob: &[u8]) -> Result<(Vec<u8>, Vec<u8>), CodecError> {
+    if blob.len() < VK_LEN_PREFIX_BYTES {
+        return Err(CodecError::Truncated {
+            expected: VK_LEN_PREFIX_BYTES,
+            got: blob.len(),
+        });
+    }
+    let (prefix, rest) = blob.split_at(VK_LEN_PREFIX_BYTES);
+    let mut vk_len = [0u8; VK_LEN_PREFIX_BYTES];
+    vk_len.copy_from_slice(prefix);
+    let vk_len = u32::from_le_bytes(vk_len) as usize;
+    if rest.len() < vk_len {
+        return Err(CodecError::Truncated {
+            expected: VK_LEN_PREFIX_BYTES + vk_len,
+            got: blob.len(),
+        });
+    }
+
+    let (vk, proof) = rest.split_at(vk_len);
+    Ok((check_vk_len(vk.to_vec())?, check_proof_len(proof.to_vec())?))
+}
+
+/// Decodes a hex encoded proof
+pub fn decode_proof_hex(encoded: &str) -> Result<Vec<u8>, CodecError> {
+    check_proof_len(hex::decode(encoded)?)
+}
+
+/// Decodes a base64 encoded proof
+pub fn decode_proof_base64(encoded: &str) -> Result<Vec<u8>, CodecError> {
+    check_proof_len(base64::decode(encoded)?)
+}
+
+/// Decodes a hex encoded verifying key
+pub fn decode_vk_hex(encoded: &str) -> Result<Vec<u8>, CodecError> {
+    check_vk_len(hex::decode(encoded)?)
+}
+
+/// Decodes a base64 encoded verifying key
+pub fn decode_vk_base64(encoded: &str) -> Result<Vec<u8>, CodecError> {
+    check_vk_len(base64::decode(encoded)?)
+}
+
+/// A proof has a fixed size in each encoding
+fn check_proof_len(bytes: Vec<u8>) -> Result<Vec<u8>, CodecError> {
+    let proof = Proof::<CPCurve>::default();
+    if bytes.len() != proof.serialized_size() && bytes.len() != proof.uncompressed_size() {
+        return Err(CodecError::InvalidLength {
+            kind: "proof",
+            len: bytes.len(),
+        });
+    }
+    Ok(bytes)
+}
+
+/// A VK is made of fixed size elements followed by a length prefixed vector of G1 elements,
+/// one for each public input
+fn check_vk_len(bytes: Vec<u8>) -> Result<Vec<u8>, CodecError> {
+    let vk = VerifyingKey::<CPCurve>::default();
+    let g1 = <CPCurve as PairingEngine>::G1Affine::default();
+    let fits =
+        |base: usize, element: usize| bytes.len() >= base && (bytes.len() - base) % element == 0;
+    if !fits(vk.serialized_size(), g1.serialized_size())
+        && !fits(vk.uncompressed_size(), g1.uncompressed_size())
+    {
+        return Err(CodecError::InvalidLength {
+            kind: "verifying key",
+            len: bytes.len(),
+        });
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snark::tests::{PROOF, VK};
+    use algebra::CanonicalDeserialize;
+
+    #[test]
+    fn decodes_hex_and_base64() {
+        let proof = decode_proof_hex(PROOF).unwrap();
+        assert_eq!(hex::encode(&proof), PROOF);
+        assert_eq!(decode_proof_base64(&base64::encode(&proof)).unwrap(), proof);
+
+        let vk = decode_vk_hex(VK).unwrap();
+        assert_eq!(hex::encode(&vk), VK);
+        assert_eq!(decode_vk_base64(&base64::encode(&vk)).unwrap(), vk);
+
+        // the uncompressed encodings are also accepted
+        let mut uncompressed = vec![];
+        Proof::<CPCurve>::deserialize(&mut &proof[..])
+            .unwrap()
+            .serialize_uncompressed(&mut uncompressed)
+            .unwrap();
+        assert_eq!(
+            decode_proof_hex(&hex::encode(&uncompressed)).unwrap(),
+            uncompressed
+        );
+    }
+
+    #[test]
+    fn joins_and_splits_vk_and_proof() {
+        let proof = decode_proof_hex(PROOF).unwrap();
+        let vk = decode_vk_hex(VK).unwrap();
+
+        let blob = join_vk_proof(&vk, &proof).unwrap();
+        assert_eq!(blob.len(), 4 + vk.len() + proof.len());
+        assert_eq!(&blob[..4], &(vk.len() as u32).to_le_bytes());
+        assert_eq!(split_vk_proof(&blob).unwrap(), (vk.clone(), proof.clone()));
+
+        // the parts are checked in both directions
+        assert!(matches!(
+            join_vk_proof(&proof, &vk).unwrap_err(),
+            CodecError::InvalidLength { .. }
+        ));
+        assert!(matches!(
+            split_vk_proof(&blob[..blob.len() - 1]).unwrap_err(),
+            CodecError::InvalidLength { kind: "proof", .. }
+        ));
+    }
+
+    #[test]
+    fn rejects_truncated_blobs() {
+        let proof = decode_proof_hex(PROOF).unwrap();
+        let vk = decode_vk_hex(VK).unwrap();
+        let blob = join_vk_proof(&vk, &proof).unwrap();
+
+        assert!(matches!(
+            split_vk_proof(&blob[..3]).unwrap_err(),
+            CodecError::Truncated {
+                expected: 4,
+                got: 3
+            }
+        ));
+        // the prefix points past the end of the blob
+        let truncated = &blob[..4 + vk.len() - 1];
+        match split_vk_proof(truncated).unwrap_err() {
+            CodecError::Truncated { expected, got } => {
+                assert_eq!((expected, got), (4 + vk.len(), truncated.len()))
+            }
+            err => panic!("unexpected error: {}", err),
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_encodings() {
+        assert!(matches!(
+            decode_proof_hex("zz").unwrap_err(),
+            CodecError::Hex(_)
+        ));
+        assert!(matches!(
+            decode_vk_base64("not base64!").unwrap_err(),
+            CodecError::Base64(_)
+        ));
+        assert!(matches!(
+            decode_proof_hex(&PROOF[2..]).unwrap_err(),
+            CodecError::InvalidLength { kind: "proof", .. }
+        ));
+        // a truncated public input element
+        assert!(matches!(
+            decode_vk_hex(&VK[..VK.len() - 2]).unwrap_err(),
+            CodecError::InvalidLength {
+                kind: "verifying key",
+                ..
+            }
+        ));
+        assert!(decode_vk_base64("").is_err());
+    }
+}
@@ -1,17 +1,31 @@
-use crate::convert_result_to_bool;
+use crate::{
+    convert_result_to_bool,
+    utils::{into_raw_buffer, slice_or_empty},
+};
 use algebra::{
     bls12_377::G2Affine, AffineCurve, CanonicalDeserialize, CanonicalSerialize, ProjectiveCurve,
+    SerializationError,
+};
+use bls_crypto::{PublicKey, Signature};
+use epoch_snark::{
+    EncodingError, EpochBlock, EpochTransition, EPOCH_BLOCK_HEADER_BYTES, MAX_VALIDATORS,
 };
-use bls_crypto::PublicKey;
-use epoch_snark::{EncodingError, EpochBlock};
 use std::{
     convert::TryFrom,
+    io,
     os::raw::{c_int, c_uint, c_ushort},
     slice,
 };
+use thiserror::Error;
 
 /// Each pubkey is a BLS G2Projective element
-const PUBKEY_BYTES: usize = 96;
+pub(crate) const PUBKEY_BYTES: usize = 96;
+
+/// Size of each pubkey when serialized without compression
+const PUBKEY_UNCOMPRESSED_BYTES: usize = 192;
+
+/// Each signature is a BLS G1Projective element
+const SIGNATURE_BYTES: usize = 48;
 
 #[no_mangle]
 pub extern "C" fn encode_epoch_block_to_bytes(
@@ -25,7 +39,7 @@ pub extern "C" fn encode_epoch_block_to_bytes(
 ) -> bool {
     convert_result_to_bool::<_, EncodingError, _>(|| {
         let added_public_keys_ptrs = unsafe {
-            slice::from_raw_parts(in_added_public_keys, in_added_public_keys_len as usize)
+            read_slice_or_empty(in_added_public_keys, in_added_public_keys_len as usize)?
         };
         let added_public_keys = added_public_keys_ptrs
             .to_vec()
@@ -38,21 +52,76 @@ pub extern "C" fn encode_epoch_block_to_bytes(
             in_maximum_non_signers as u32,
             added_public_keys,
         );
-        let mut encoded = if in_should_encode_aggregated_pk {
+        let encoded = if in_should_encode_aggregated_pk {
             epoch_block.encode_to_bytes_with_aggregated_pk()?
         } else {
             epoch_block.encode_to_bytes()?
         };
-        encoded.shrink_to_fit();
+        let (ptr, len) = into_raw_buffer(encoded);
         unsafe {
-            *out_bytes = encoded.as_mut_ptr();
-            *out_len = encoded.len() as c_int;
+            *out_bytes = ptr;
+            *out_len = len as c_int;
+        }
+        Ok(())
+    })
+}
+
+#[no_mangle]
+/// Decodes `count` compressed pubkeys starting from the pointer's location,
+/// and writes their compressed sum to `out`.
+///
+/// Returns `false` if any of the pubkeys is invalid, or if `out_len` is smaller than
+/// the size of a compressed pubkey (96 bytes).
+///
+/// # Safety
+/// 1. `ptr` must point to `count` compressed pubkeys
+/// 1. `out` must point to at least `out_len` writable bytes
+pub unsafe extern "C" fn aggregate_pubkeys(
+    ptr: *const u8,
+    count: u32,
+    out: *mut u8,
+    out_len: u32,
+) -> bool {
+    if ptr.is_null() || out.is_null() {
+        log::error!("SNARK library error: null pointer passed to aggregate_pubkeys");
+        return false;
+    }
+    if (out_len as usize) < PUBKEY_BYTES {
+        log::error!(
+            "SNARK library error: output buffer too small: need {} bytes, got {}",
+            PUBKEY_BYTES,
+            out_len
+        );
+        return false;
+    }
+    let count = count as usize;
+    let len = match count.checked_mul(PUBKEY_BYTES) {
+        Some(len) => len,
+        None => {
+            log::error!(
+                "SNARK library error: {} pubkeys do not fit in memory",
+                count
+            );
+            return false;
         }
-        std::mem::forget(encoded);
+    };
+    convert_result_to_bool::<_, AggregateError, _>(|| {
+        let pubkeys = decode_pubkeys(ptr, len, count).collect::<Result<Vec<_>, _>>()?;
+        let aggregate = PublicKey::aggregate(&pubkeys);
+        let out = slice::from_raw_parts_mut(out, PUBKEY_BYTES);
+        aggregate.serialize(&mut &mut out[..])?;
         Ok(())
     })
 }
 
+#[derive(Debug, Error)]
+enum AggregateError {
+    #[error(transparent)]
+    Decode(#[from] PubkeyDecodeError),
+    #[error("could not serialize the aggregate pubkey: {0}")]
+    Serialization(#[from] SerializationError),
+}
+
 /// Data structure received from consumers of the FFI interface describing
 /// an epoch block.
 #[repr(C)]
@@ -67,41 +136,350 @@ pub struct EpochBlockFFI {
     pub maximum_non_signers: u32,
 }
 
+impl EpochBlockFFI {
+    /// Serializes the block's compressed pubkeys into `buf`, replacing its contents, and
+    /// returns an FFI view of the block whose `pubkeys` point into it, e.g. to pass an owned
+    /// block to the functions of the C ABI.
+    ///
+    /// The view does not borrow `buf`, so it is only valid for as long as `buf` is alive and
+    /// is not modified: dropping, clearing or growing the buffer (which may reallocate it)
+    /// leaves the `pubkeys` pointer dangling. Converting the view back with
+    /// `EpochBlock::try_from` yields the original block.
+    pub fn from_epoch_block(
+        block: &EpochBlock,
+        buf: &mut Vec<u8>,
+    ) -> Result<EpochBlockFFI, EncodingError> {
+        *buf = serialize_pubkeys(&block.new_public_keys)?;
+        Ok(EpochBlockFFI {
+            index: block.index,
+            pubkeys: buf.as_ptr(),
+            pubkeys_num: block.new_public_keys.len(),
+            maximum_non_signers: block.maximum_non_signers,
+        })
+    }
+}
+
 impl TryFrom<&EpochBlockFFI> for EpochBlock {
     type Error = EncodingError;
 
     fn try_from(src: &EpochBlockFFI) -> Result<EpochBlock, Self::Error> {
-        let pubkeys = unsafe { read_pubkeys(src.pubkeys, src.pubkeys_num as usize)? };
-        Ok(EpochBlock {
-            index: src.index,
-            maximum_non_signers: src.maximum_non_signers,
-            new_public_keys: pubkeys,
+        unsafe { read_epoch_block(src, true) }
+    }
+}
+
+/// Converts the FFI block to an `EpochBlock`, reading its pubkeys as compressed or
+/// uncompressed elements. Fails with `EncodingError::TooManyValidators` if the block has
+/// more than `MAX_VALIDATORS` pubkeys, and with `EncodingError::EmptyValidatorSet` if it
+/// has none, without reading the pointer.
+///
+/// # Safety
+///
+/// The pubkeys pointer must point to `pubkeys_num` elements of the chosen encoding.
+pub(crate) unsafe fn read_epoch_block(
+    src: &EpochBlockFFI,
+    compressed: bool,
+) -> Result<EpochBlock, EncodingError> {
+    if src.pubkeys_num == 0 {
+        return Err(EncodingError::EmptyValidatorSet);
+    }
+    if src.pubkeys_num > MAX_VALIDATORS {
+        return Err(EncodingError::TooManyValidators {
+            got: src.pubkeys_num,
+            max: MAX_VALIDATORS,
+        });
+    }
+    let pubkeys = read_pubkeys(src.pubkeys, src.pubkeys_num as usize, compressed)?;
+    Ok(EpochBlock {
+        index: src.index,
+        maximum_non_signers: src.maximum_non_signers,
+        new_public_keys: pubkeys,
+    })
+}
+
+#[derive(Debug, Error)]
+/// A problem found while converting an `EpochBlockFFI` with
+/// [`try_from_verbose`](fn.try_from_verbose.html)
+pub enum EpochBlockFFIError {
+    #[error(transparent)]
+    Encoding(#[from] EncodingError),
+    #[error(transparent)]
+    Pubkey(#[from] PubkeyDecodeError),
+    #[error("null pubkeys pointer")]
+    NullPointer,
+}
+
+/// Same as `EpochBlock::try_from`, but decodes every pubkey instead of stopping at the
+/// first invalid one, and returns the errors of all of them along with their indices, e.g.
+/// to diagnose malformed upstream data in one go. Pubkeys which are not in the prime order
+/// subgroup are reported as well.
+///
+/// The pubkeys are not read if the block's `pubkeys_num` is 0 or larger than `MAX_VALIDATORS`,
+/// or if its pointer is null, in which case that is the only reported error.
+pub fn try_from_verbose(src: &EpochBlockFFI) -> Result<EpochBlock, Vec<EpochBlockFFIError>> {
+    if src.pubkeys_num == 0 {
+        return Err(vec![EncodingError::EmptyValidatorSet.into()]);
+    }
+    if src.pubkeys_num > MAX_VALIDATORS {
+        return Err(vec![EncodingError::TooManyValidators {
+            got: src.pubkeys_num,
+            max: MAX_VALIDATORS,
+        }
+        .into()]);
+    }
+    if src.pubkeys.is_null() {
+        return Err(vec![EpochBlockFFIError::NullPointer]);
+    }
+
+    let mut decoder =
+        unsafe { decode_pubkeys(src.pubkeys, src.pubkeys_num * PUBKEY_BYTES, src.pubkeys_num) };
+    let mut pubkeys = Vec::with_capacity(src.pubkeys_num);
+    let mut errors = vec![];
+    // the decoder's iterator stops at the first error, so decode each pubkey directly
+    for index in 0..src.pubkeys_num {
+        match decoder.decode(index) {
+            Ok(pubkey) => pubkeys.push(pubkey),
+            Err(e) => errors.push(e.into()),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    Ok(EpochBlock {
+        index: src.index,
+        maximum_non_signers: src.maximum_non_signers,
+        new_public_keys: pubkeys,
+    })
+}
+
+/// An epoch block deserialized with `epoch_block_deserialize`, which owns the pubkeys
+/// pointed to by the `EpochBlockFFI` view filled alongside it
+pub struct EpochBlockHandle {
+    block: EpochBlock,
+    pubkeys: Vec<u8>,
+}
+
+impl EpochBlockHandle {
+    /// The deserialized epoch block
+    pub fn block(&self) -> &EpochBlock {
+        &self.block
+    }
+
+    /// An FFI view of the block, which is valid for as long as the handle is alive
+    pub fn as_ffi(&self) -> EpochBlockFFI {
+        EpochBlockFFI {
+            index: self.block.index,
+            pubkeys: self.pubkeys.as_ptr(),
+            pubkeys_num: self.block.new_public_keys.len(),
+            maximum_non_signers: self.block.maximum_non_signers,
+        }
+    }
+}
+
+#[no_mangle]
+/// Deserializes an epoch block, as serialized by `EpochBlock::serialize_to_bytes`, and
+/// fills `out` with a view of it. The view's pubkeys point to memory owned by the handle
+/// written to `out_handle`, which must be freed with `epoch_block_free` once the view is not
+/// used anymore.
+///
+/// Returns `false` if the bytes are not a valid serialization, or if any of the pubkeys is
+/// not in the prime order subgroup.
+///
+/// # Safety
+/// 1. `bytes` must point to `len` bytes of valid memory
+/// 1. `out_handle` and `out` must point to writable memory
+pub unsafe extern "C" fn epoch_block_deserialize(
+    bytes: *const u8,
+    len: usize,
+    out_handle: *mut *mut EpochBlockHandle,
+    out: *mut EpochBlockFFI,
+) -> bool {
+    if out_handle.is_null() || out.is_null() {
+        log::error!("SNARK library error: null pointer passed to epoch_block_deserialize");
+        return false;
+    }
+    convert_result_to_bool::<_, EncodingError, _>(|| {
+        let bytes = read_slice(bytes, len)?;
+        let handle = Box::new(deserialize_epoch_block(bytes)?);
+        *out = handle.as_ffi();
+        *out_handle = Box::into_raw(handle);
+        Ok(())
+    })
+}
+
+#[no_mangle]
+/// Frees a handle returned by `epoch_block_deserialize`, invalidating its FFI view
+///
+/// # Safety
+/// The handle must have been returned by `epoch_block_deserialize` and not freed yet
+pub unsafe extern "C" fn epoch_block_free(handle: *mut EpochBlockHandle) -> bool {
+    if handle.is_null() {
+        return false;
+    }
+    Box::from_raw(handle);
+    true
+}
+
+/// Deserializes a block serialized with `EpochBlock::serialize_to_bytes` into a handle
+/// which owns its pubkeys, checking them as in `EpochBlock::deserialize_from_bytes`
+pub fn deserialize_epoch_block(bytes: &[u8]) -> Result<EpochBlockHandle, EncodingError> {
+    let block = EpochBlock::deserialize_from_bytes(bytes)?;
+    Ok(EpochBlockHandle {
+        block,
+        pubkeys: bytes[EPOCH_BLOCK_HEADER_BYTES..].to_vec(),
+    })
+}
+
+/// Data structure received from consumers of the FFI interface describing
+/// which of an epoch's validators signed, and their aggregate public key.
+#[repr(C)]
+pub struct SignersFFI {
+    /// Pointer to the bitmap of the epoch's validators which signed
+    pub bitmap: *const bool,
+    /// The number of entries to be read from the bitmap pointer
+    pub bitmap_num: usize,
+    /// Pointer to the compressed aggregate public key of the signers
+    pub aggregate_pubkey: *const u8,
+}
+
+impl SignersFFI {
+    /// Reads the bitmap and the aggregate public key
+    ///
+    /// # Safety
+    ///
+    /// The bitmap pointer must point to `bitmap_num` entries and the aggregate pubkey
+    /// pointer to a compressed pubkey.
+    pub(crate) unsafe fn read(&self) -> Result<(Vec<bool>, PublicKey), EncodingError> {
+        let bitmap = read_slice_or_empty(self.bitmap, self.bitmap_num)?;
+        let aggregate_pubkey = deserialize_slice(self.aggregate_pubkey, PUBKEY_BYTES)?;
+        Ok((bitmap.to_vec(), aggregate_pubkey))
+    }
+}
+
+/// Data structure received from consumers of the FFI interface describing
+/// an epoch transition which is to be proven.
+#[repr(C)]
+pub struct EpochTransitionFFI {
+    /// The new epoch block
+    pub block: EpochBlockFFI,
+    /// Pointer to the aggregate signature produced over the block by the
+    /// validators of the previous epoch
+    pub aggregate_signature: *const u8,
+    /// Pointer to the bitmap of the previous epoch's validators which signed the block
+    pub bitmap: *const bool,
+    /// The number of entries to be read from the bitmap pointer
+    pub bitmap_num: usize,
+}
+
+impl TryFrom<&EpochTransitionFFI> for EpochTransition {
+    type Error = EncodingError;
+
+    fn try_from(src: &EpochTransitionFFI) -> Result<EpochTransition, Self::Error> {
+        let block = EpochBlock::try_from(&src.block)?;
+        let aggregate_signature: Signature =
+            unsafe { deserialize_slice(src.aggregate_signature, SIGNATURE_BYTES)? };
+        let bitmap = unsafe { read_slice_or_empty(src.bitmap, src.bitmap_num)? };
+        Ok(EpochTransition {
+            block,
+            aggregate_signature,
+            bitmap: bitmap.to_vec(),
         })
     }
 }
 
-/// Reads `len` bytes starting from the pointer's location
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Error)]
+/// Error raised when a buffer received over the FFI cannot be read
+pub enum SliceError {
+    #[error("null pointer")]
+    NullPointer,
+    #[error("zero length buffer")]
+    ZeroLength,
+}
+
+impl From<SliceError> for EncodingError {
+    fn from(err: SliceError) -> Self {
+        EncodingError::IoError(io::Error::new(io::ErrorKind::InvalidInput, err))
+    }
+}
+
+/// Reads `len` elements starting from the pointer's location. Errors if the pointer
+/// is null, or if `len` is 0 since no data would be read.
 ///
 /// # Safety
 ///
-/// This WILL read invalid data if you give it a larger `num` argument
+/// This WILL read invalid data if you give it a larger `len` argument
+/// than expected. Use with caution.
+pub unsafe fn read_slice<'a, T>(ptr: *const T, len: usize) -> Result<&'a [T], SliceError> {
+    if ptr.is_null() {
+        return Err(SliceError::NullPointer);
+    }
+    if len == 0 {
+        return Err(SliceError::ZeroLength);
+    }
+    Ok(slice::from_raw_parts(ptr, len))
+}
+
+/// Same as [`read_slice`](fn.read_slice.html), but a non-null pointer with a `len` of 0
+/// is read as an empty slice, for inputs which may legitimately be empty.
+///
+/// # Safety
+///
+/// This WILL read invalid data if you give it a larger `len` argument
 /// than expected. Use with caution.
-pub unsafe fn read_slice<C: CanonicalDeserialize>(
+pub unsafe fn read_slice_or_empty<'a, T>(ptr: *const T, len: usize) -> Result<&'a [T], SliceError> {
+    match read_slice(ptr, len) {
+        Err(SliceError::ZeroLength) => Ok(&[]),
+        res => res,
+    }
+}
+
+/// Deserializes an element from the `len` bytes starting from the pointer's location
+///
+/// # Safety
+///
+/// This WILL read invalid data if you give it a larger `len` argument
+/// than expected. Use with caution.
+pub unsafe fn deserialize_slice<C: CanonicalDeserialize>(
     ptr: *const u8,
     len: usize,
 ) -> Result<C, EncodingError> {
-    let mut data = slice::from_raw_parts(ptr, len);
+    let mut data = read_slice(ptr, len)?;
     Ok(C::deserialize(&mut data)?)
 }
 
-/// Reads `num` * `PUBKEY_BYTES` bytes starting from the pointer's location
+/// Deserializes an element from the `len` bytes of an uncompressed serialization starting
+/// from the pointer's location
+///
+/// # Safety
+///
+/// This WILL read invalid data if you give it a larger `len` argument
+/// than expected. Use with caution.
+pub unsafe fn deserialize_slice_uncompressed<C: CanonicalDeserialize>(
+    ptr: *const u8,
+    len: usize,
+) -> Result<C, EncodingError> {
+    let mut data = read_slice(ptr, len)?;
+    Ok(C::deserialize_uncompressed(&mut data)?)
+}
+
+/// Reads `num` * `pubkey_bytes` bytes starting from the pointer's location. Fails with
+/// `EncodingError::TooManyValidators` if the length overflows.
 ///
 /// # Safety
 ///
 /// This WILL read invalid data if you give it a larger `num` argument
 /// than expected. Use with caution.
-unsafe fn read_serialized_pubkeys<'a>(ptr: *const u8, num: usize) -> &'a [u8] {
-    slice::from_raw_parts(ptr, num * PUBKEY_BYTES)
+unsafe fn read_serialized_pubkeys<'a>(
+    ptr: *const u8,
+    num: usize,
+    pubkey_bytes: usize,
+) -> Result<&'a [u8], EncodingError> {
+    let len = num
+        .checked_mul(pubkey_bytes)
+        .ok_or(EncodingError::TooManyValidators {
+            got: num,
+            max: MAX_VALIDATORS,
+        })?;
+    Ok(slice::from_raw_parts(ptr, len))
 }
 
 /// Serializes the inner G2 elements of the pubkeys to a vector
@@ -115,29 +493,132 @@ pub fn serialize_pubkeys(pubkeys: &[PublicKey]) -> Result<Vec<u8>, EncodingError
 
 /// Reads `num` PublicKey elements starting from the memory that the pointer points to.
 ///
+/// The decoding is not constant time, and stops at the first invalid pubkey: the square
+/// root computed by zexe when decompressing a point, and its check that the point is on
+/// the curve, branch on the encoded coordinates. This does not leak anything, since the
+/// pubkeys of an epoch are public data from the chain and no secret is ever decoded here.
+///
 /// # Safety
 /// This WILL NOT fail if the `num` variable is larger than the expected elements, and will
 /// simply return an array of `PublicKeys` whose internals will be whatever data was in the memory.
 /// Use with caution.
-unsafe fn read_pubkeys(ptr: *const u8, num: usize) -> Result<Vec<PublicKey>, EncodingError> {
-    let mut data = read_serialized_pubkeys(ptr, num);
-    let mut pubkeys = Vec::new();
-    for _ in 0..num {
-        let key = G2Affine::deserialize(&mut data)?;
-        let key = key.into_projective();
-        pubkeys.push(PublicKey::from(key))
+unsafe fn read_pubkeys(
+    ptr: *const u8,
+    num: usize,
+    compressed: bool,
+) -> Result<Vec<PublicKey>, EncodingError> {
+    let pubkey_bytes = if compressed {
+        PUBKEY_BYTES
+    } else {
+        PUBKEY_UNCOMPRESSED_BYTES
+    };
+    let data = read_serialized_pubkeys(ptr, num, pubkey_bytes)?;
+    let mut pubkeys = Vec::with_capacity(num);
+    for mut element in data.chunks_exact(pubkey_bytes) {
+        let key = if compressed {
+            G2Affine::deserialize(&mut element)?
+        } else {
+            G2Affine::deserialize_uncompressed(&mut element)?
+        };
+        pubkeys.push(PublicKey::from(key.into_projective()))
     }
     Ok(pubkeys)
 }
 
+#[derive(Debug, Error)]
+/// Error raised while decoding a pubkey with [`decode_pubkeys`](fn.decode_pubkeys.html)
+pub enum PubkeyDecodeError {
+    #[error(
+        "pubkey {index} is truncated: {available} of {} bytes available",
+        PUBKEY_BYTES
+    )]
+    Truncated { index: usize, available: usize },
+    #[error("pubkey {index} could not be deserialized: {source}")]
+    Invalid {
+        index: usize,
+        source: SerializationError,
+    },
+    #[error("pubkey {index} is not in the prime order subgroup")]
+    NotInSubgroup { index: usize },
+}
+
+/// Iterator over compressed pubkeys, created by [`decode_pubkeys`](fn.decode_pubkeys.html)
+pub struct PubkeyDecoder<'a> {
+    data: &'a [u8],
+    index: usize,
+    count: usize,
+    failed: bool,
+}
+
+/// Lazily decodes `count` compressed pubkeys from the `len` bytes starting at the
+/// pointer's location, one at a time, checking that each one is in the prime order subgroup.
+///
+/// The iterator stops after the first error, which contains the index of the offending pubkey.
+///
+/// # Safety
+///
+/// The pointer must point to at least `len` bytes of valid memory, or be null
+/// in which case no bytes are read
+pub unsafe fn decode_pubkeys<'a>(ptr: *const u8, len: usize, count: usize) -> PubkeyDecoder<'a> {
+    PubkeyDecoder {
+        data: slice_or_empty(ptr, len),
+        index: 0,
+        count,
+        failed: false,
+    }
+}
+
+impl<'a> Iterator for PubkeyDecoder<'a> {
+    type Item = Result<PublicKey, PubkeyDecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.index == self.count {
+            return None;
+        }
+        let index = self.index;
+        self.index += 1;
+        let res = self.decode(index);
+        self.failed = res.is_err();
+        Some(res)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.failed {
+            (0, Some(0))
+        } else {
+            (0, Some(self.count - self.index))
+        }
+    }
+}
+
+impl<'a> PubkeyDecoder<'a> {
+    fn decode(&mut self, index: usize) -> Result<PublicKey, PubkeyDecodeError> {
+        if self.data.len() < PUBKEY_BYTES {
+            return Err(PubkeyDecodeError::Truncated {
+                index,
+                available: self.data.len(),
+            });
+        }
+        let (mut element, rest) = self.data.split_at(PUBKEY_BYTES);
+        self.data = rest;
+        let key = G2Affine::deserialize(&mut element)
+            .map_err(|source| PubkeyDecodeError::Invalid { index, source })?;
+        let key = PublicKey::from(key.into_projective());
+        if !key.is_in_correct_subgroup() {
+            return Err(PubkeyDecodeError::NotInSubgroup { index });
+        }
+        Ok(key)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::snark::test_helpers::TestCircuit;
+    use crate::snark::test_helpers::{generate_test_data, to_ffi_block, TestCircuit};
     use algebra::{
         bls12_377::{Fr, G2Projective},
         serialize::CanonicalSerialize,
-        Bls12_377, ProjectiveCurve, UniformRand,
+        Bls12_377, ProjectiveCurve, UniformRand, Zero,
     };
     use groth16::{create_proof_no_zk, generate_random_parameters, Proof, VerifyingKey};
 
@@ -162,6 +643,180 @@ mod tests {
         assert_eq!(block_from_ffi, src);
     }
 
+    #[test]
+    fn ffi_block_from_epoch_block() {
+        let block = EpochBlock::new(7, 3, rand_pubkeys(10));
+        let mut buf = vec![1, 2, 3];
+        let ffi_block = EpochBlockFFI::from_epoch_block(&block, &mut buf).unwrap();
+        assert_eq!(buf.len(), 10 * PUBKEY_BYTES);
+        assert_eq!(ffi_block.pubkeys, buf.as_ptr());
+        assert_eq!(EpochBlock::try_from(&ffi_block).unwrap(), block);
+    }
+
+    #[test]
+    fn ffi_block_max_validators() {
+        let pubkey = serialize_pubkeys(&rand_pubkeys(1)).unwrap();
+        let serialized_pubkeys = pubkey.repeat(MAX_VALIDATORS + 1);
+        let ffi_block = |pubkeys_num| EpochBlockFFI {
+            index: 1,
+            maximum_non_signers: 1,
+            pubkeys_num,
+            pubkeys: serialized_pubkeys.as_ptr(),
+        };
+
+        let block = EpochBlock::try_from(&ffi_block(MAX_VALIDATORS)).unwrap();
+        assert_eq!(block.new_public_keys.len(), MAX_VALIDATORS);
+        match EpochBlock::try_from(&ffi_block(MAX_VALIDATORS + 1)).unwrap_err() {
+            EncodingError::TooManyValidators { got, max } => {
+                assert_eq!((got, max), (MAX_VALIDATORS + 1, MAX_VALIDATORS))
+            }
+            err => panic!("unexpected error: {}", err),
+        }
+    }
+
+    #[test]
+    fn ffi_block_without_validators() {
+        let pubkeys = serialize_pubkeys(&rand_pubkeys(1)).unwrap();
+        for ptr in &[std::ptr::null(), pubkeys.as_ptr()] {
+            let ffi_block = EpochBlockFFI {
+                index: 1,
+                maximum_non_signers: 0,
+                pubkeys_num: 0,
+                pubkeys: *ptr,
+            };
+            assert!(matches!(
+                EpochBlock::try_from(&ffi_block).unwrap_err(),
+                EncodingError::EmptyValidatorSet
+            ));
+            assert!(matches!(
+                unsafe { read_epoch_block(&ffi_block, false) }.unwrap_err(),
+                EncodingError::EmptyValidatorSet
+            ));
+        }
+    }
+
+    #[test]
+    fn ffi_block_reports_all_errors() {
+        let block = EpochBlock::new(1, 1, rand_pubkeys(5));
+        let mut pubkeys = serialize_pubkeys(&block.new_public_keys).unwrap();
+        let ffi_block = |pubkeys: &[u8], pubkeys_num: usize| EpochBlockFFI {
+            index: block.index,
+            maximum_non_signers: block.maximum_non_signers,
+            pubkeys_num,
+            pubkeys: pubkeys.as_ptr(),
+        };
+        assert_eq!(try_from_verbose(&ffi_block(&pubkeys, 5)).unwrap(), block);
+
+        // two invalid pubkeys are both reported
+        for index in &[1, 3] {
+            for byte in &mut pubkeys[index * PUBKEY_BYTES..(index + 1) * PUBKEY_BYTES] {
+                *byte = 0xff;
+            }
+        }
+        assert!(EpochBlock::try_from(&ffi_block(&pubkeys, 5)).is_err());
+        let errors = try_from_verbose(&ffi_block(&pubkeys, 5)).unwrap_err();
+        let indices = errors
+            .iter()
+            .map(|e| match e {
+                EpochBlockFFIError::Pubkey(PubkeyDecodeError::Invalid { index, .. }) => *index,
+                e => panic!("unexpected error: {}", e),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(indices, vec![1, 3]);
+
+        // bad counts are reported without reading the pubkeys
+        let errors = try_from_verbose(&ffi_block(&pubkeys, 0)).unwrap_err();
+        assert!(matches!(
+            errors[..],
+            [EpochBlockFFIError::Encoding(
+                EncodingError::EmptyValidatorSet
+            )]
+        ));
+        let errors = try_from_verbose(&ffi_block(&pubkeys, MAX_VALIDATORS + 1)).unwrap_err();
+        assert!(matches!(
+            errors[..],
+            [EpochBlockFFIError::Encoding(
+                EncodingError::TooManyValidators { .. }
+            )]
+        ));
+        let errors = try_from_verbose(&EpochBlockFFI {
+            pubkeys: std::ptr::null(),
+            ..ffi_block(&pubkeys, 5)
+        })
+        .unwrap_err();
+        assert!(matches!(errors[..], [EpochBlockFFIError::NullPointer]));
+    }
+
+    #[test]
+    fn ffi_block_deserialization() {
+        let block = EpochBlock::new(3, 2, rand_pubkeys(5));
+        let serialized = block.serialize_to_bytes().unwrap();
+        assert_eq!(
+            serialized.len(),
+            EPOCH_BLOCK_HEADER_BYTES + 5 * PUBKEY_BYTES
+        );
+
+        let mut handle = std::ptr::null_mut();
+        let mut ffi_block = EpochBlockFFI {
+            index: 0,
+            pubkeys: std::ptr::null(),
+            pubkeys_num: 0,
+            maximum_non_signers: 0,
+        };
+        unsafe {
+            assert!(epoch_block_deserialize(
+                serialized.as_ptr(),
+                serialized.len(),
+                &mut handle,
+                &mut ffi_block,
+            ));
+            assert_eq!((*handle).block(), &block);
+        }
+        // the view can be used like any other FFI block while the handle is alive
+        assert_eq!(ffi_block.index, 3);
+        assert_eq!(ffi_block.maximum_non_signers, 2);
+        assert_eq!(ffi_block.pubkeys_num, 5);
+        assert_eq!(EpochBlock::try_from(&ffi_block).unwrap(), block);
+        unsafe {
+            assert!(epoch_block_free(handle));
+            assert!(!epoch_block_free(std::ptr::null_mut()));
+        }
+
+        // invalid pubkey
+        let mut corrupted = serialized.clone();
+        for byte in &mut corrupted[EPOCH_BLOCK_HEADER_BYTES..][..PUBKEY_BYTES] {
+            *byte = 0xff;
+        }
+        assert!(deserialize_epoch_block(&corrupted).is_err());
+        unsafe {
+            assert!(!epoch_block_deserialize(
+                corrupted.as_ptr(),
+                corrupted.len(),
+                &mut handle,
+                &mut ffi_block,
+            ));
+        }
+    }
+
+    #[test]
+    fn ffi_transition_conversion() {
+        let (_, transitions, _) = generate_test_data(4, 1, 1);
+        let src = &transitions[0];
+        let serialized_pubkeys = serialize_pubkeys(&src.block.new_public_keys).unwrap();
+        let mut serialized_signature = vec![];
+        src.aggregate_signature
+            .serialize(&mut serialized_signature)
+            .unwrap();
+        let ffi_transition = EpochTransitionFFI {
+            block: to_ffi_block(&src.block, &serialized_pubkeys),
+            aggregate_signature: &serialized_signature[0] as *const u8,
+            bitmap: &src.bitmap[0] as *const bool,
+            bitmap_num: src.bitmap.len(),
+        };
+        let transition_from_ffi = EpochTransition::try_from(&ffi_transition).unwrap();
+        assert_eq!(&transition_from_ffi, src);
+    }
+
     #[test]
     fn groth_verifying_key_from_pointer() {
         let rng = &mut rand::thread_rng();
@@ -172,16 +827,18 @@ mod tests {
         vk.serialize(&mut serialized).unwrap();
         let ptr = &serialized[0] as *const u8;
         let deserialized: VerifyingKey<Bls12_377> =
-            unsafe { read_slice(ptr, serialized.len()).unwrap() };
+            unsafe { deserialize_slice(ptr, serialized.len()).unwrap() };
         assert_eq!(deserialized, vk);
 
         // reading a bigger slice is fine
         let deserialized: VerifyingKey<Bls12_377> =
-            unsafe { read_slice(ptr, 2 * serialized.len()).unwrap() };
+            unsafe { deserialize_slice(ptr, 2 * serialized.len()).unwrap() };
         assert_eq!(deserialized, vk);
 
         // reading a smaller slice is not
-        unsafe { read_slice::<VerifyingKey<Bls12_377>>(ptr, serialized.len() - 1).unwrap_err() };
+        unsafe {
+            deserialize_slice::<VerifyingKey<Bls12_377>>(ptr, serialized.len() - 1).unwrap_err()
+        };
     }
 
     #[test]
@@ -194,16 +851,45 @@ mod tests {
         let mut serialized = vec![];
         proof.serialize(&mut serialized).unwrap();
         let ptr = &serialized[0] as *const u8;
-        let deserialized: Proof<Bls12_377> = unsafe { read_slice(ptr, serialized.len()).unwrap() };
+        let deserialized: Proof<Bls12_377> =
+            unsafe { deserialize_slice(ptr, serialized.len()).unwrap() };
         assert_eq!(deserialized, proof);
 
         // reading a bigger slice is fine (although still mis-use of the code)
         let deserialized: Proof<Bls12_377> =
-            unsafe { read_slice(ptr, 2 * serialized.len()).unwrap() };
+            unsafe { deserialize_slice(ptr, 2 * serialized.len()).unwrap() };
         assert_eq!(deserialized, proof);
 
         // reading a smaller slice is not
-        unsafe { read_slice::<Proof<Bls12_377>>(ptr, serialized.len() - 1).unwrap_err() };
+        unsafe { deserialize_slice::<Proof<Bls12_377>>(ptr, serialized.len() - 1).unwrap_err() };
+    }
+
+    #[test]
+    fn slices_from_pointer() {
+        let data = [1u8, 2, 3];
+        let ptr = data.as_ptr();
+        unsafe {
+            assert_eq!(read_slice(ptr, 3).unwrap(), &data[..]);
+            assert_eq!(read_slice(ptr, 2).unwrap(), &data[..2]);
+            assert_eq!(
+                read_slice(std::ptr::null::<u8>(), 3).unwrap_err(),
+                SliceError::NullPointer
+            );
+            assert_eq!(read_slice(ptr, 0).unwrap_err(), SliceError::ZeroLength);
+
+            // empty inputs are only allowed with a valid pointer
+            assert_eq!(read_slice_or_empty(ptr, 0).unwrap(), &[] as &[u8]);
+            assert_eq!(read_slice_or_empty(ptr, 3).unwrap(), &data[..]);
+            assert_eq!(
+                read_slice_or_empty(std::ptr::null::<u8>(), 0).unwrap_err(),
+                SliceError::NullPointer
+            );
+
+            // the error is kept when deserializing
+            let err =
+                deserialize_slice::<Signature>(std::ptr::null(), SIGNATURE_BYTES).unwrap_err();
+            assert!(err.to_string().contains("null pointer"));
+        }
     }
 
     #[test]
@@ -212,8 +898,164 @@ mod tests {
         let pubkeys = rand_pubkeys(num_keys);
         let serialized = serialize_pubkeys(&pubkeys).unwrap();
         let ptr = &serialized[0] as *const u8;
-        let deserialized_from_ptr = unsafe { read_pubkeys(ptr, num_keys).unwrap() };
+        let deserialized_from_ptr = unsafe { read_pubkeys(ptr, num_keys, true).unwrap() };
+        assert_eq!(deserialized_from_ptr, pubkeys);
+    }
+
+    #[test]
+    fn uncompressed_pubkeys_from_pointer() {
+        let num_keys = 10;
+        let pubkeys = rand_pubkeys(num_keys);
+        let mut serialized = vec![];
+        for p in &pubkeys {
+            p.as_ref()
+                .into_affine()
+                .serialize_uncompressed(&mut serialized)
+                .unwrap();
+        }
+        assert_eq!(serialized.len(), num_keys * PUBKEY_UNCOMPRESSED_BYTES);
+        let ptr = &serialized[0] as *const u8;
+        let deserialized_from_ptr = unsafe { read_pubkeys(ptr, num_keys, false).unwrap() };
         assert_eq!(deserialized_from_ptr, pubkeys);
+
+        // the uncompressed encoding cannot be read as compressed and vice versa
+        unsafe { read_pubkeys(ptr, num_keys, true).unwrap_err() };
+        let compressed = serialize_pubkeys(&pubkeys).unwrap();
+        unsafe { read_pubkeys(&compressed[0] as *const u8, num_keys / 2, false).unwrap_err() };
+    }
+
+    #[test]
+    fn decode_pubkeys_one_by_one() {
+        let num_keys = 10;
+        let pubkeys = rand_pubkeys(num_keys);
+        let serialized = serialize_pubkeys(&pubkeys).unwrap();
+        let decoded = unsafe { decode_pubkeys(serialized.as_ptr(), serialized.len(), num_keys) }
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(decoded, pubkeys);
+
+        // corrupt the 5th key
+        let mut corrupted = serialized.clone();
+        for byte in &mut corrupted[4 * PUBKEY_BYTES..5 * PUBKEY_BYTES] {
+            *byte = 0xff;
+        }
+        let decoded = unsafe { decode_pubkeys(corrupted.as_ptr(), corrupted.len(), num_keys) }
+            .collect::<Vec<_>>();
+        // stops at the first error
+        assert_eq!(decoded.len(), 5);
+        assert!(decoded[..4].iter().all(|key| key.is_ok()));
+        match &decoded[4] {
+            Err(PubkeyDecodeError::Invalid { index, .. }) => assert_eq!(*index, 4),
+            _ => panic!("expected the 5th key to be invalid"),
+        }
+
+        // truncated buffer
+        let truncated = &serialized[..serialized.len() - 10];
+        let err = unsafe { decode_pubkeys(truncated.as_ptr(), truncated.len(), num_keys) }
+            .last()
+            .unwrap()
+            .unwrap_err();
+        match err {
+            PubkeyDecodeError::Truncated { index, available } => {
+                assert_eq!(index, num_keys - 1);
+                assert_eq!(available, PUBKEY_BYTES - 10);
+            }
+            _ => panic!("expected the last key to be truncated"),
+        }
+    }
+
+    #[test]
+    fn aggregate_pubkeys_from_pointer() {
+        let num_keys = 10;
+        let pubkeys = rand_pubkeys(num_keys);
+        let serialized = serialize_pubkeys(&pubkeys).unwrap();
+        let mut expected = vec![];
+        PublicKey::aggregate(&pubkeys)
+            .serialize(&mut expected)
+            .unwrap();
+
+        let mut out = vec![0u8; PUBKEY_BYTES];
+        assert!(unsafe {
+            aggregate_pubkeys(
+                serialized.as_ptr(),
+                num_keys as u32,
+                out.as_mut_ptr(),
+                out.len() as u32,
+            )
+        });
+        assert_eq!(out, expected);
+
+        // output buffer too small
+        assert!(!unsafe {
+            aggregate_pubkeys(
+                serialized.as_ptr(),
+                num_keys as u32,
+                out.as_mut_ptr(),
+                out.len() as u32 - 1,
+            )
+        });
+
+        // invalid pubkey
+        let mut corrupted = serialized.clone();
+        for byte in &mut corrupted[..PUBKEY_BYTES] {
+            *byte = 0xff;
+        }
+        assert!(!unsafe {
+            aggregate_pubkeys(
+                corrupted.as_ptr(),
+                num_keys as u32,
+                out.as_mut_ptr(),
+                out.len() as u32,
+            )
+        });
+    }
+
+    #[test]
+    fn pubkey_edge_case_encodings() {
+        let pubkeys = rand_pubkeys(1);
+        let serialized = serialize_pubkeys(&pubkeys).unwrap();
+        let flags = PUBKEY_BYTES - 1;
+
+        // flipping the high bit selects the other square root, i.e. the negated key
+        let mut negated = serialized.clone();
+        negated[flags] ^= 1 << 7;
+        let decoded = unsafe { read_pubkeys(negated.as_ptr(), 1, true).unwrap() };
+        assert_eq!(decoded[0].as_ref(), &-*pubkeys[0].as_ref());
+
+        // the point at infinity
+        let mut infinity = vec![];
+        G2Affine::zero().serialize(&mut infinity).unwrap();
+        assert_eq!(infinity.len(), PUBKEY_BYTES);
+        let decoded = unsafe { read_pubkeys(infinity.as_ptr(), 1, true).unwrap() };
+        assert!(decoded[0].as_ref().is_zero());
+
+        // all bits set, i.e. both the infinity and the high bit flags
+        let invalid = vec![0xff; PUBKEY_BYTES];
+        unsafe { read_pubkeys(invalid.as_ptr(), 1, true).unwrap_err() };
+
+        // an invalid key fails the read wherever it is
+        let mut corrupted = invalid.clone();
+        corrupted.extend_from_slice(&serialized);
+        unsafe { read_pubkeys(corrupted.as_ptr(), 2, true).unwrap_err() };
+        let mut corrupted = serialized.clone();
+        corrupted.extend_from_slice(&invalid);
+        unsafe { read_pubkeys(corrupted.as_ptr(), 2, true).unwrap_err() };
+    }
+
+    #[test]
+    fn pubkeys_len_overflow() {
+        let serialized = serialize_pubkeys(&rand_pubkeys(1)).unwrap();
+        // the length of the pubkeys is checked before reading them
+        match unsafe { read_pubkeys(serialized.as_ptr(), usize::MAX, true).unwrap_err() } {
+            EncodingError::TooManyValidators { got, max } => {
+                assert_eq!((got, max), (usize::MAX, MAX_VALIDATORS))
+            }
+            err => panic!("unexpected error: {}", err),
+        }
+        match unsafe { read_pubkeys(serialized.as_ptr(), usize::MAX / 2, false).unwrap_err() } {
+            EncodingError::TooManyValidators { got, .. } => assert_eq!(got, usize::MAX / 2),
+            err => panic!("unexpected error: {}", err),
+        }
     }
 
     #[test]
@@ -224,7 +1066,7 @@ mod tests {
         let ptr = &serialized[0] as *const u8;
         // We read a bunch of junk data, hence why this MUST
         // be unsafe :)
-        unsafe { read_pubkeys(ptr, 99).unwrap_err() };
+        unsafe { read_pubkeys(ptr, 99, true).unwrap_err() };
     }
 
     fn rand_pubkeys(num_keys: usize) -> Vec<PublicKey> {
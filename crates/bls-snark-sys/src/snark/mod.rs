@@ -1,12 +1,181 @@
+pub mod codec;
 pub mod epoch_block;
-use epoch_block::{read_slice, EpochBlockFFI};
+#[cfg(feature = "prover")]
+use epoch_block::{deserialize_slice, read_slice_or_empty, EpochTransitionFFI};
+use epoch_block::{read_epoch_block, read_slice, EpochBlockFFI, SignersFFI, SliceError};
 
 #[cfg(test)]
 mod test_helpers;
 
-use crate::convert_result_to_bool;
-use epoch_snark::EpochBlock;
-use std::convert::TryFrom;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+use crate::{catch_panic, convert_result_to_bool, utils::Buffer};
+use algebra::{sw6::SW6 as CPCurve, AffineCurve, CanonicalDeserialize, CanonicalSerialize};
+#[cfg(feature = "prover")]
+use bls_crypto::Domain;
+use epoch_snark::{
+    deserialize_compressed, CircuitConfig, CircuitVersion, EncodingError, EpochBlock, Strictness,
+    VerificationError, VerifyOptions,
+};
+#[cfg(feature = "prover")]
+use epoch_snark::{EpochTransition, Parameters, ProvingKey, ProvingKeyError, MAX_VALIDATORS};
+use groth16::{prepare_verifying_key, PreparedVerifyingKey, Proof, VerifyingKey};
+use std::{convert::TryFrom, fmt::Display, ptr, slice};
+#[cfg(feature = "prover")]
+use thiserror::Error;
+
+#[cfg(feature = "prover")]
+#[derive(Debug, Error)]
+/// Errors which may occur while generating a proof over the FFI
+pub enum ProveError {
+    #[error("encoding error: {0}")]
+    EncodingError(#[from] EncodingError),
+    #[error("prover error: {0}")]
+    SnarkError(#[from] epoch_snark::ProveError),
+    #[error("invalid epochs proving key: {0}")]
+    ProvingKeyError(#[from] ProvingKeyError),
+    #[error("output buffer too small: proof needs {0} bytes, got {1}")]
+    BufferTooSmall(usize, usize),
+    #[error("got {got} validators, the circuit supports at most {max}")]
+    TooManyValidators { got: usize, max: usize },
+}
+
+#[cfg(feature = "prover")]
+#[no_mangle]
+/// Generates a Groth16 proof about the validity of the provided epoch transitions,
+/// starting from the `initial_epoch` block.
+///
+/// All elements are assumed to be sent as serialized byte arrays
+/// of **compressed elements**. The epochs proving key is a serialized `ProvingKey`, i.e.
+/// the configuration of the circuit and the domain of the epochs' signatures followed by the
+/// Groth16 parameters, so that the proof is generated for the configuration and the domain of
+/// the setup. The hash-to-bits proving key is the
+/// serialized Groth16 parameters as generated by the trusted setup. If the setup was done
+/// without the hash-to-bits helper circuit, `hash_to_bits_pk` must be null.
+///
+/// The serialized proof is written to `out_proof` and its length to `out_proof_len`.
+/// If `out_proof_capacity` is not enough to fit the proof, nothing is written to
+/// `out_proof`, `out_proof_len` is set to the required length and `false` is returned,
+/// so that the caller can retry with a larger buffer. On any other error
+/// `out_proof_len` is set to 0.
+///
+/// # Safety
+/// 1. The proving keys must be valid pointers
+/// 1. `transitions` must point to `transitions_num` valid EpochTransitionFFI structs
+/// 1. The vectors of pubkeys, signatures and bitmaps inside the FFI structs must point
+/// to valid memory
+/// 1. `out_proof` must point to at least `out_proof_capacity` writable bytes, and
+/// `out_proof_len` must be a valid pointer
+pub unsafe extern "C" fn prove(
+    // Serialized epochs proving key
+    epochs_pk: *const u8,
+    // Length of serialized epochs proving key
+    epochs_pk_len: u32,
+    // Serialized hash to bits proving key (null if not used)
+    hash_to_bits_pk: *const u8,
+    // Length of serialized hash to bits proving key
+    hash_to_bits_pk_len: u32,
+    // Number of validators per epoch
+    num_validators: u32,
+    // Initial epoch data (pubkeys serialized)
+    initial_epoch: EpochBlockFFI,
+    // Epoch transitions (pubkeys and signatures serialized)
+    transitions: *const EpochTransitionFFI,
+    // Number of epoch transitions
+    transitions_num: usize,
+    // Buffer to write the serialized proof to
+    out_proof: *mut u8,
+    // Size of the proof buffer
+    out_proof_capacity: u32,
+    // Length of the serialized proof
+    out_proof_len: *mut u32,
+) -> bool {
+    *out_proof_len = 0;
+    convert_result_to_bool::<_, ProveError, _>(|| {
+        if num_validators as usize > MAX_VALIDATORS {
+            return Err(ProveError::TooManyValidators {
+                got: num_validators as usize,
+                max: MAX_VALIDATORS,
+            });
+        }
+        let epochs_pk =
+            read_slice(epochs_pk, epochs_pk_len as usize).map_err(EncodingError::from)?;
+        let (epochs, config, domain) = ProvingKey::from_bytes(epochs_pk)?.into_inner();
+        let parameters = Parameters {
+            epochs,
+            hash_to_bits: if hash_to_bits_pk.is_null() {
+                None
+            } else {
+                Some(deserialize_slice(
+                    hash_to_bits_pk,
+                    hash_to_bits_pk_len as usize,
+                )?)
+            },
+            domain,
+            config,
+        };
+        let initial_epoch = EpochBlock::try_from(&initial_epoch)?;
+        let transitions = read_slice_or_empty(transitions, transitions_num)
+            .map_err(EncodingError::from)?
+            .iter()
+            .map(EpochTransition::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let proof = epoch_snark::prove(&parameters, num_validators, &initial_epoch, &transitions)?;
+        let mut serialized = vec![];
+        proof
+            .serialize(&mut serialized)
+            .map_err(EncodingError::from)?;
+
+        *out_proof_len = serialized.len() as u32;
+        if serialized.len() > out_proof_capacity as usize {
+            return Err(ProveError::BufferTooSmall(
+                serialized.len(),
+                out_proof_capacity as usize,
+            ));
+        }
+        ptr::copy_nonoverlapping(serialized.as_ptr(), out_proof, serialized.len());
+        Ok(())
+    })
+}
+
+/// Status codes returned by [`verify_with_status`](fn.verify_with_status.html)
+#[repr(i32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VerifyStatus {
+    /// The proof is valid
+    Ok = 0,
+    /// The verifying key could not be deserialized
+    InvalidVk = 1,
+    /// The proof could not be deserialized
+    InvalidProof = 2,
+    /// One of the epoch blocks could not be deserialized or encoded
+    InvalidEpochBlock = 3,
+    /// One of the provided pointers was null
+    NullPointer = 4,
+    /// The proof was deserialized correctly but did not verify
+    ProofRejected = 5,
+    /// The signers bitmap does not meet the quorum or does not match the aggregate pubkey
+    InvalidSigners = 6,
+    /// The verifying key or the proof buffer had a length of 0
+    ZeroLength = 7,
+    /// The verifier panicked, e.g. on malformed input which slipped past the decoding checks
+    Panicked = 8,
+    /// The circuit version passed to `verify_with_version` is not supported
+    UnsupportedCircuitVersion = 9,
+    /// The epochs are further apart than the maximum gap of `verify_with_max_gap`
+    EpochGapTooLarge = 10,
+}
+
+impl From<SliceError> for VerifyStatus {
+    fn from(err: SliceError) -> Self {
+        match err {
+            SliceError::NullPointer => VerifyStatus::NullPointer,
+            SliceError::ZeroLength => VerifyStatus::ZeroLength,
+        }
+    }
+}
 
 #[no_mangle]
 /// Verifies a Groth16 proof about the validity of the epoch transitions
@@ -17,6 +186,9 @@ use std::convert::TryFrom;
 /// the length of the verifying key or the proof, so that must be
 /// provided by the caller.
 ///
+/// Returns `false` if verification failed for any reason. Use `verify_with_status`
+/// to find out why.
+///
 /// # Safety
 /// 1. VK and Proof must be valid pointers
 /// 1. The vector of pubkeys inside EpochBlockFFI must point to valid memory
@@ -34,68 +206,1580 @@ pub unsafe extern "C" fn verify(
     // Last epoch data (pubkeys serialized)
     last_epoch: EpochBlockFFI,
 ) -> bool {
-    convert_result_to_bool(|| {
-        let first_epoch = EpochBlock::try_from(&first_epoch)?;
-        let last_epoch = EpochBlock::try_from(&last_epoch)?;
-        let vk = read_slice(vk, vk_len as usize)?;
-        let proof = read_slice(proof, proof_len as usize)?;
+    verify_with_status(vk, vk_len, proof, proof_len, first_epoch, last_epoch)
+        == VerifyStatus::Ok as i32
+}
+
+#[no_mangle]
+/// Verifies a Groth16 proof about the validity of the epoch transitions
+/// between the provided `first_epoch` and `last_epoch` blocks, in the same way
+/// as `verify`.
+///
+/// Returns one of the `VerifyStatus` codes, where `0` means that the proof is valid
+/// and any other value indicates why verification failed.
+///
+/// # Safety
+/// 1. VK and Proof must be valid pointers
+/// 1. The vector of pubkeys inside EpochBlockFFI must point to valid memory
+pub unsafe extern "C" fn verify_with_status(
+    // Serialized verifying key
+    vk: *const u8,
+    // Length of serialized verifying key
+    vk_len: u32,
+    // Serialized proof
+    proof: *const u8,
+    // Length of serialized proof
+    proof_len: u32,
+    // First epoch data (pubkeys serialized)
+    first_epoch: EpochBlockFFI,
+    // Last epoch data (pubkeys serialized)
+    last_epoch: EpochBlockFFI,
+) -> i32 {
+    verify_with_strictness(vk, vk_len, proof, proof_len, first_epoch, last_epoch, true)
+}
+
+#[no_mangle]
+/// Verifies a Groth16 proof about the validity of the epoch transitions
+/// between the provided `first_epoch` and `last_epoch` blocks, in the same way
+/// as `verify_with_status`.
+///
+/// If `strict` is true, the verifying key and the proof must be the canonical encodings of
+/// the elements, i.e. unreduced coordinates, unexpected flags and trailing bytes are rejected
+/// with `VerifyStatus::InvalidVk` or `VerifyStatus::InvalidProof`. This is what `verify` and
+/// `verify_with_status` do. Set it to false only to verify legacy proofs whose encodings
+/// were not canonical, in which case uncompressed encodings and trailing bytes are accepted
+/// as well.
+///
+/// # Safety
+/// 1. VK and Proof must be valid pointers
+/// 1. The vector of pubkeys inside EpochBlockFFI must point to valid memory
+pub unsafe extern "C" fn verify_with_strictness(
+    // Serialized verifying key
+    vk: *const u8,
+    // Length of serialized verifying key
+    vk_len: u32,
+    // Serialized proof
+    proof: *const u8,
+    // Length of serialized proof
+    proof_len: u32,
+    // First epoch data (pubkeys serialized)
+    first_epoch: EpochBlockFFI,
+    // Last epoch data (pubkeys serialized)
+    last_epoch: EpochBlockFFI,
+    // Whether to reject non-canonical encodings
+    strict: bool,
+) -> i32 {
+    let strictness = if strict {
+        Strictness::Strict
+    } else {
+        Strictness::Lenient
+    };
+    let status = catch_panic(Err(VerifyStatus::Panicked), || {
+        try_verify(
+            vk,
+            vk_len,
+            proof,
+            proof_len,
+            &first_epoch,
+            &last_epoch,
+            true,
+            strictness,
+            CircuitVersion::CURRENT,
+            &VerifyOptions::default(),
+        )
+    });
+    match status {
+        Ok(()) => VerifyStatus::Ok as i32,
+        Err(status) => status as i32,
+    }
+}
+
+#[no_mangle]
+/// Verifies a Groth16 proof about the validity of the epoch transitions
+/// between the provided `first_epoch` and `last_epoch` blocks, in the same way
+/// as `verify_with_status`, for a proof of the provided version of the circuit.
+///
+/// The current circuit is version 1, which is the only supported one, and
+/// `VerifyStatus::UnsupportedCircuitVersion` is returned for any other version. Proofs of
+/// a future circuit will be verified with their version.
+///
+/// # Safety
+/// 1. VK and Proof must be valid pointers
+/// 1. The vector of pubkeys inside EpochBlockFFI must point to valid memory
+pub unsafe extern "C" fn verify_with_version(
+    // Serialized verifying key
+    vk: *const u8,
+    // Length of serialized verifying key
+    vk_len: u32,
+    // Serialized proof
+    proof: *const u8,
+    // Length of serialized proof
+    proof_len: u32,
+    // First epoch data (pubkeys serialized)
+    first_epoch: EpochBlockFFI,
+    // Last epoch data (pubkeys serialized)
+    last_epoch: EpochBlockFFI,
+    // Version of the circuit which the proof was generated with
+    circuit_version: u32,
+) -> i32 {
+    let status = catch_panic(Err(VerifyStatus::Panicked), || {
+        let version = with_status(
+            CircuitVersion::try_from(circuit_version),
+            VerifyStatus::UnsupportedCircuitVersion,
+        )?;
+        try_verify(
+            vk,
+            vk_len,
+            proof,
+            proof_len,
+            &first_epoch,
+            &last_epoch,
+            true,
+            Strictness::Strict,
+            version,
+            &VerifyOptions::default(),
+        )
+    });
+    match status {
+        Ok(()) => VerifyStatus::Ok as i32,
+        Err(status) => status as i32,
+    }
+}
+
+#[no_mangle]
+/// Verifies a Groth16 proof about the validity of the epoch transitions
+/// between the provided `first_epoch` and `last_epoch` blocks, in the same way
+/// as `verify_with_status`, but rejects epochs which are more than `max_gap` epochs apart
+/// with `VerifyStatus::EpochGapTooLarge` before verifying the proof, as with
+/// `VerifyOptions::max_gap`. Reversed epochs are rejected with
+/// `VerifyStatus::InvalidEpochBlock`, as in `verify`.
+///
+/// `verify` accepts any gap, so this allows bounding it to the window which the network's
+/// proofs are generated for.
+///
+/// # Safety
+/// 1. VK and Proof must be valid pointers
+/// 1. The vector of pubkeys inside EpochBlockFFI must point to valid memory
+pub unsafe extern "C" fn verify_with_max_gap(
+    // Serialized verifying key
+    vk: *const u8,
+    // Length of serialized verifying key
+    vk_len: u32,
+    // Serialized proof
+    proof: *const u8,
+    // Length of serialized proof
+    proof_len: u32,
+    // First epoch data (pubkeys serialized)
+    first_epoch: EpochBlockFFI,
+    // Last epoch data (pubkeys serialized)
+    last_epoch: EpochBlockFFI,
+    // Maximum number of epochs between the first and the last epoch
+    max_gap: u32,
+) -> i32 {
+    let status = catch_panic(Err(VerifyStatus::Panicked), || {
+        let options = VerifyOptions {
+            max_gap: Some(max_gap as usize),
+            ..Default::default()
+        };
+        try_verify(
+            vk,
+            vk_len,
+            proof,
+            proof_len,
+            &first_epoch,
+            &last_epoch,
+            true,
+            Strictness::Strict,
+            CircuitVersion::CURRENT,
+            &options,
+        )
+    });
+    match status {
+        Ok(()) => VerifyStatus::Ok as i32,
+        Err(status) => status as i32,
+    }
+}
+
+#[no_mangle]
+/// Verifies a Groth16 proof about the validity of the epoch transitions
+/// between the provided `first_epoch` and `last_epoch` blocks, in the same way
+/// as `verify_with_status`, and also checks which of the last epoch's validators signed.
+///
+/// `signers` holds a bitmap with an entry for each of the last epoch's pubkeys and the
+/// aggregate pubkey which the signers' aggregate signature is verified against (e.g. for the
+/// header signed by the last epoch's validators). The bitmap must have at most
+/// `maximum_non_signers` unset entries, and the aggregate pubkey must be the sum of the pubkeys
+/// whose entry is set, otherwise `VerifyStatus::InvalidSigners` is returned. The signers are
+/// checked before the proof.
+///
+/// # Safety
+/// 1. VK and Proof must be valid pointers
+/// 1. The vector of pubkeys inside EpochBlockFFI must point to valid memory
+/// 1. `signers` must be a valid pointer, whose bitmap and aggregate pubkey point to valid memory
+pub unsafe extern "C" fn verify_with_signers(
+    // Serialized verifying key
+    vk: *const u8,
+    // Length of serialized verifying key
+    vk_len: u32,
+    // Serialized proof
+    proof: *const u8,
+    // Length of serialized proof
+    proof_len: u32,
+    // First epoch data (pubkeys serialized)
+    first_epoch: EpochBlockFFI,
+    // Last epoch data (pubkeys serialized)
+    last_epoch: EpochBlockFFI,
+    // The last epoch's signers
+    signers: *const SignersFFI,
+) -> i32 {
+    let status = catch_panic(Err(VerifyStatus::Panicked), || {
+        check_signers(&last_epoch, signers).and_then(|_| {
+            try_verify(
+                vk,
+                vk_len,
+                proof,
+                proof_len,
+                &first_epoch,
+                &last_epoch,
+                true,
+                Strictness::Strict,
+                CircuitVersion::CURRENT,
+                &VerifyOptions::default(),
+            )
+        })
+    });
+    match status {
+        Ok(()) => VerifyStatus::Ok as i32,
+        Err(status) => status as i32,
+    }
+}
+
+/// Checks the signers against the epoch's pubkeys and maximum number of non signers
+unsafe fn check_signers(
+    epoch: &EpochBlockFFI,
+    signers: *const SignersFFI,
+) -> Result<(), VerifyStatus> {
+    if signers.is_null() || (*signers).bitmap.is_null() || (*signers).aggregate_pubkey.is_null() {
+        log::error!("SNARK library error: null signers pointer");
+        return Err(VerifyStatus::NullPointer);
+    }
+    if epoch.pubkeys.is_null() {
+        log::error!("SNARK library error: null pointer passed to verify");
+        return Err(VerifyStatus::NullPointer);
+    }
+    let epoch = with_status(EpochBlock::try_from(epoch), VerifyStatus::InvalidEpochBlock)?;
+    let (bitmap, aggregate_pubkey) = with_status((*signers).read(), VerifyStatus::InvalidSigners)?;
+    with_status(
+        epoch.check_signers(&bitmap, &aggregate_pubkey),
+        VerifyStatus::InvalidSigners,
+    )
+}
+
+#[no_mangle]
+/// Verifies a Groth16 proof about the validity of the epoch transitions
+/// between the provided `first_epoch` and `last_epoch` blocks, in the same way
+/// as `verify`, but without decompressing any elements.
+///
+/// The verifying key, the proof and the pubkeys of both epoch blocks are all expected
+/// to be **uncompressed** serializations, i.e. each pubkey is 192 bytes long.
+///
+/// # Safety
+/// 1. VK and Proof must be valid pointers
+/// 1. The vector of pubkeys inside EpochBlockFFI must point to valid memory
+pub unsafe extern "C" fn verify_uncompressed(
+    // Serialized verifying key
+    vk: *const u8,
+    // Length of serialized verifying key
+    vk_len: u32,
+    // Serialized proof
+    proof: *const u8,
+    // Length of serialized proof
+    proof_len: u32,
+    // First epoch data (pubkeys serialized)
+    first_epoch: EpochBlockFFI,
+    // Last epoch data (pubkeys serialized)
+    last_epoch: EpochBlockFFI,
+) -> bool {
+    catch_panic(false, || {
+        try_verify(
+            vk,
+            vk_len,
+            proof,
+            proof_len,
+            &first_epoch,
+            &last_epoch,
+            false,
+            Strictness::Strict,
+            CircuitVersion::CURRENT,
+            &VerifyOptions::default(),
+        )
+        .is_ok()
+    })
+}
+
+#[no_mangle]
+/// Verifies multiple Groth16 proofs about the validity of epoch transitions against
+/// the same verifying key, which is deserialized only once.
+///
+/// The `i`th proof is checked against the `i`th pair of `first_epochs` and `last_epochs`
+/// blocks, and the result is written to `out_results[i]`. Returns `true` only if all
+/// proofs verified. An empty batch is rejected, so that a caller which failed to fill
+/// in its proofs does not read it as a successful verification.
+///
+/// # Safety
+/// 1. VK must be a valid pointer
+/// 1. `proofs`, `first_epochs`, `last_epochs` and `out_results` must point to arrays
+/// of `num_proofs` elements
+/// 1. The proof buffers and the vectors of pubkeys inside EpochBlockFFI must point to valid memory
+pub unsafe extern "C" fn verify_batch(
+    // Serialized verifying key
+    vk: *const u8,
+    // Length of serialized verifying key
+    vk_len: u32,
+    // Serialized proofs
+    proofs: *const Buffer,
+    // First epoch data of each proof (pubkeys serialized)
+    first_epochs: *const EpochBlockFFI,
+    // Last epoch data of each proof (pubkeys serialized)
+    last_epochs: *const EpochBlockFFI,
+    // Number of proofs
+    num_proofs: usize,
+    // Per-proof verification results
+    out_results: *mut bool,
+) -> bool {
+    if proofs.is_null() || first_epochs.is_null() || last_epochs.is_null() || out_results.is_null()
+    {
+        log::error!("SNARK library error: null pointer passed to verify_batch");
+        return false;
+    }
+    if num_proofs == 0 {
+        log::error!("SNARK library error: empty batch passed to verify_batch");
+        return false;
+    }
+    let results = slice::from_raw_parts_mut(out_results, num_proofs);
+    results.iter_mut().for_each(|result| *result = false);
+
+    catch_panic(false, || {
+        let pvk = match read_vk(vk, vk_len, true, Strictness::Strict) {
+            Ok(pvk) => pvk,
+            Err(_) => return false,
+        };
+        let proofs = slice::from_raw_parts(proofs, num_proofs);
+        let first_epochs = slice::from_raw_parts(first_epochs, num_proofs);
+        let last_epochs = slice::from_raw_parts(last_epochs, num_proofs);
+        for (i, result) in results.iter_mut().enumerate() {
+            *result = verify_with_vk(
+                &pvk,
+                proofs[i].ptr,
+                proofs[i].len,
+                &first_epochs[i],
+                &last_epochs[i],
+                true,
+                Strictness::Strict,
+                CircuitVersion::CURRENT,
+                &VerifyOptions::default(),
+            )
+            .is_ok();
+        }
+
+        results.iter().all(|result| *result)
+    })
+}
+
+#[no_mangle]
+/// Checks that the proof is well formed without verifying it, i.e. that it is the canonical
+/// compressed serialization of three points which are on their curves and in the prime
+/// order subgroups. This is much cheaper than the pairing checks of `verify`, so that it can
+/// be used to discard malformed proofs early (e.g. from a pool of candidate proofs).
+///
+/// A well formed proof may still be rejected by `verify`.
+///
+/// # Safety
+/// Proof must be a valid pointer
+pub unsafe extern "C" fn proof_is_well_formed(
+    // Serialized proof
+    proof: *const u8,
+    // Length of serialized proof
+    proof_len: u32,
+) -> bool {
+    catch_panic(false, || {
+        let proof = match read_buffer(proof, proof_len as usize, "proof") {
+            Ok(proof) => proof,
+            Err(_) => return false,
+        };
+        match deserialize_compressed::<Proof<CPCurve>>(proof, Strictness::Strict) {
+            Ok(proof) => {
+                proof.a.is_on_curve()
+                    && proof.a.is_in_correct_subgroup_assuming_on_curve()
+                    && proof.b.is_on_curve()
+                    && proof.b.is_in_correct_subgroup_assuming_on_curve()
+                    && proof.c.is_on_curve()
+                    && proof.c.is_in_correct_subgroup_assuming_on_curve()
+            }
+            Err(e) => {
+                log::error!("SNARK library error: {}", e);
+                false
+            }
+        }
+    })
+}
+
+/// A verifying key which has been deserialized and prepared for the pairing checks,
+/// to be used with `verify_prepared`
+pub struct PreparedVk(PreparedVerifyingKey<CPCurve>);
 
-        epoch_snark::verify(&vk, &first_epoch, &last_epoch, &proof)
+#[no_mangle]
+/// Deserializes and prepares the verifying key so that it can be reused across calls to
+/// `verify_prepared`. Returns null if the VK could not be deserialized.
+///
+/// The returned handle must be freed with `vk_free`.
+///
+/// # Safety
+/// VK must be a valid pointer
+pub unsafe extern "C" fn vk_prepare(
+    // Serialized verifying key
+    vk: *const u8,
+    // Length of serialized verifying key
+    vk_len: u32,
+) -> *mut PreparedVk {
+    catch_panic(ptr::null_mut(), || {
+        match read_vk(vk, vk_len, true, Strictness::Strict) {
+            Ok(pvk) => Box::into_raw(Box::new(PreparedVk(pvk))),
+            Err(_) => ptr::null_mut(),
+        }
+    })
+}
+
+#[no_mangle]
+/// Verifies a Groth16 proof about the validity of the epoch transitions
+/// between the provided `first_epoch` and `last_epoch` blocks, against a verifying
+/// key which was prepared with `vk_prepare`.
+///
+/// # Safety
+/// 1. The handle must have been returned by `vk_prepare` and not freed yet
+/// 1. Proof must be a valid pointer
+/// 1. The vector of pubkeys inside EpochBlockFFI must point to valid memory
+pub unsafe extern "C" fn verify_prepared(
+    // Prepared verifying key
+    handle: *const PreparedVk,
+    // Serialized proof
+    proof: *const u8,
+    // Length of serialized proof
+    proof_len: u32,
+    // First epoch data (pubkeys serialized)
+    first_epoch: EpochBlockFFI,
+    // Last epoch data (pubkeys serialized)
+    last_epoch: EpochBlockFFI,
+) -> bool {
+    if handle.is_null() {
+        log::error!("SNARK library error: null verifying key handle");
+        return false;
+    }
+    catch_panic(false, || {
+        verify_with_vk(
+            &(*handle).0,
+            proof,
+            proof_len as usize,
+            &first_epoch,
+            &last_epoch,
+            true,
+            Strictness::Strict,
+            CircuitVersion::CURRENT,
+            &VerifyOptions::default(),
+        )
+        .is_ok()
+    })
+}
+
+#[no_mangle]
+/// Frees a verifying key handle returned by `vk_prepare`
+///
+/// # Safety
+/// The handle must have been returned by `vk_prepare` and not freed yet
+pub unsafe extern "C" fn vk_free(handle: *mut PreparedVk) -> bool {
+    if handle.is_null() {
+        return false;
+    }
+    Box::from_raw(handle);
+    true
+}
+
+#[no_mangle]
+/// Writes the 32 byte fingerprint of the verifying key to `out_fingerprint`, i.e. the Blake2s
+/// hash of its canonical compressed serialization. The VK may be compressed or uncompressed,
+/// and both have the same fingerprint.
+///
+/// Returns `false` if the VK could not be deserialized, in which case nothing is written.
+///
+/// # Safety
+/// 1. VK must be a valid pointer
+/// 1. `out_fingerprint` must point to at least 32 writable bytes
+pub unsafe extern "C" fn vk_fingerprint(
+    // Serialized verifying key
+    vk: *const u8,
+    // Length of serialized verifying key
+    vk_len: u32,
+    // The fingerprint
+    out_fingerprint: *mut u8,
+) -> bool {
+    if out_fingerprint.is_null() {
+        log::error!("SNARK library error: null pointer passed to vk_fingerprint");
+        return false;
+    }
+    catch_panic(false, || {
+        let vk = match read_buffer(vk, vk_len as usize, "verifying key") {
+            Ok(vk) => vk,
+            Err(_) => return false,
+        };
+        match epoch_snark::vk_fingerprint(vk) {
+            Ok(fingerprint) => {
+                slice::from_raw_parts_mut(out_fingerprint, fingerprint.len())
+                    .copy_from_slice(&fingerprint);
+                true
+            }
+            Err(e) => {
+                log::error!("SNARK library error: {}", e);
+                false
+            }
+        }
+    })
+}
+
+/// The bytes of a proof which is passed in chunks, to be verified with `verify_ctx_finalize`.
+/// It never holds more than the `proof_size` bytes of a compressed proof.
+#[derive(Default)]
+pub struct VerifyCtx {
+    proof: Vec<u8>,
+}
+
+#[no_mangle]
+/// Creates an empty verification context, for embedders which pass the proof in chunks
+/// with `verify_ctx_push` instead of in a single buffer.
+///
+/// The returned context must be freed with `verify_ctx_free`.
+pub extern "C" fn verify_ctx_new() -> *mut VerifyCtx {
+    Box::into_raw(Box::new(VerifyCtx::default()))
+}
+
+#[no_mangle]
+/// Appends the chunk to the proof bytes of the context. The bytes are only deserialized
+/// by `verify_ctx_finalize`.
+///
+/// Returns `false` and leaves the context unchanged if the chunk would make the proof longer
+/// than a compressed proof, so that the context's memory is bounded however it is fed.
+///
+/// # Safety
+/// 1. The context must have been returned by `verify_ctx_new` and not freed yet
+/// 1. Chunk must be a valid pointer
+pub unsafe extern "C" fn verify_ctx_push(
+    // Verification context
+    ctx: *mut VerifyCtx,
+    // Chunk of the serialized proof
+    chunk: *const u8,
+    // Length of the chunk
+    chunk_len: u32,
+) -> bool {
+    if ctx.is_null() {
+        log::error!("SNARK library error: null verification context");
+        return false;
+    }
+    catch_panic(false, || {
+        let chunk = match read_buffer(chunk, chunk_len as usize, "proof chunk") {
+            Ok(chunk) => chunk,
+            Err(_) => return false,
+        };
+        let proof = &mut (*ctx).proof;
+        let max_len = epoch_snark::proof_size();
+        if proof.len() + chunk.len() > max_len {
+            log::error!(
+                "SNARK library error: proof chunk overflows the proof: {} + {} bytes, at most {}",
+                proof.len(),
+                chunk.len(),
+                max_len
+            );
+            return false;
+        }
+        proof.extend_from_slice(chunk);
+        true
+    })
+}
+
+#[no_mangle]
+/// Verifies the proof whose bytes were pushed to the context in the same way as
+/// `verify_prepared`, against a verifying key which was prepared with `vk_prepare`.
+///
+/// The context only holds the proof, and the VK is passed as a prepared handle instead of
+/// as bytes: deserializing and preparing the VK is what makes verification expensive
+/// besides the pairings, and embedders which stream proofs usually verify many of them
+/// against the same VK, so it is done once with `vk_prepare` and shared by all contexts.
+///
+/// The context keeps its bytes, so it must still be freed with `verify_ctx_free`.
+///
+/// # Safety
+/// 1. The context must have been returned by `verify_ctx_new` and not freed yet
+/// 1. The handle must have been returned by `vk_prepare` and not freed yet
+/// 1. The vector of pubkeys inside EpochBlockFFI must point to valid memory
+pub unsafe extern "C" fn verify_ctx_finalize(
+    // Verification context
+    ctx: *const VerifyCtx,
+    // Prepared verifying key
+    handle: *const PreparedVk,
+    // First epoch data (pubkeys serialized)
+    first_epoch: EpochBlockFFI,
+    // Last epoch data (pubkeys serialized)
+    last_epoch: EpochBlockFFI,
+) -> bool {
+    if ctx.is_null() {
+        log::error!("SNARK library error: null verification context");
+        return false;
+    }
+    let proof = &(*ctx).proof;
+    verify_prepared(
+        handle,
+        proof.as_ptr(),
+        proof.len() as u32,
+        first_epoch,
+        last_epoch,
+    )
+}
+
+#[no_mangle]
+/// Frees a verification context returned by `verify_ctx_new`
+///
+/// # Safety
+/// The context must have been returned by `verify_ctx_new` and not freed yet
+pub unsafe extern "C" fn verify_ctx_free(ctx: *mut VerifyCtx) -> bool {
+    if ctx.is_null() {
+        return false;
+    }
+    Box::from_raw(ctx);
+    true
+}
+
+#[allow(clippy::too_many_arguments)]
+unsafe fn try_verify(
+    vk: *const u8,
+    vk_len: u32,
+    proof: *const u8,
+    proof_len: u32,
+    first_epoch: &EpochBlockFFI,
+    last_epoch: &EpochBlockFFI,
+    compressed: bool,
+    strictness: Strictness,
+    version: CircuitVersion,
+    options: &VerifyOptions,
+) -> Result<(), VerifyStatus> {
+    let pvk = read_vk(vk, vk_len, compressed, strictness)?;
+    verify_with_vk(
+        &pvk,
+        proof,
+        proof_len as usize,
+        first_epoch,
+        last_epoch,
+        compressed,
+        strictness,
+        version,
+        options,
+    )
+}
+
+/// Deserializes the verifying key and prepares it for the pairing checks
+unsafe fn read_vk(
+    vk: *const u8,
+    vk_len: u32,
+    compressed: bool,
+    strictness: Strictness,
+) -> Result<PreparedVerifyingKey<CPCurve>, VerifyStatus> {
+    let vk: VerifyingKey<CPCurve> = read_element(
+        vk,
+        vk_len as usize,
+        "verifying key",
+        compressed,
+        strictness,
+        VerifyStatus::InvalidVk,
+    )?;
+    Ok(prepare_verifying_key(&vk))
+}
+
+/// Reads the buffer, logging which one it was if it is null or empty
+unsafe fn read_buffer<'a>(
+    ptr: *const u8,
+    len: usize,
+    name: &str,
+) -> Result<&'a [u8], VerifyStatus> {
+    read_slice(ptr, len).map_err(|e| {
+        log::error!("SNARK library error: {}: {}", name, e);
+        VerifyStatus::from(e)
+    })
+}
+
+/// Reads the buffer with [`read_buffer`](fn.read_buffer.html) and deserializes a compressed
+/// or uncompressed element from it, failing with `status` if it cannot be deserialized. The
+/// strictness only applies to compressed elements.
+unsafe fn read_element<C: CanonicalSerialize + CanonicalDeserialize>(
+    ptr: *const u8,
+    len: usize,
+    name: &str,
+    compressed: bool,
+    strictness: Strictness,
+    status: VerifyStatus,
+) -> Result<C, VerifyStatus> {
+    let mut bytes = read_buffer(ptr, len, name)?;
+    let element = if compressed {
+        deserialize_compressed(bytes, strictness)
+    } else {
+        C::deserialize_uncompressed(&mut bytes).map_err(EncodingError::from)
+    };
+    with_status(element, status)
+}
+
+#[allow(clippy::too_many_arguments)]
+unsafe fn verify_with_vk(
+    pvk: &PreparedVerifyingKey<CPCurve>,
+    proof: *const u8,
+    proof_len: usize,
+    first_epoch: &EpochBlockFFI,
+    last_epoch: &EpochBlockFFI,
+    compressed: bool,
+    strictness: Strictness,
+    version: CircuitVersion,
+    options: &VerifyOptions,
+) -> Result<(), VerifyStatus> {
+    if first_epoch.pubkeys.is_null() || last_epoch.pubkeys.is_null() {
+        log::error!("SNARK library error: null pointer passed to verify");
+        return Err(VerifyStatus::NullPointer);
+    }
+
+    let first_epoch = with_status(
+        read_epoch_block(first_epoch, compressed),
+        VerifyStatus::InvalidEpochBlock,
+    )?;
+    let last_epoch = with_status(
+        read_epoch_block(last_epoch, compressed),
+        VerifyStatus::InvalidEpochBlock,
+    )?;
+    let proof = read_element(
+        proof,
+        proof_len,
+        "proof",
+        compressed,
+        strictness,
+        VerifyStatus::InvalidProof,
+    )?;
+
+    let config = CircuitConfig {
+        version,
+        ..Default::default()
+    };
+    epoch_snark::verify_prepared_with_config(
+        pvk,
+        &first_epoch,
+        &last_epoch,
+        &proof,
+        &config,
+        options,
+    )
+    .map_err(verification_status)
+}
+
+/// Verifies a Groth16 proof about the validity of the epoch transitions between the
+/// `first_epoch` and `last_epoch` blocks, which are serialized as in
+/// `EpochBlock::serialize_to_bytes`. The VK and the proof must be canonical compressed
+/// serializations, as in `verify`.
+///
+/// Unlike the `extern "C"` functions this only takes owned slices, and returns `false`
+/// instead of panicking on any malformed input, so that it can be driven by a fuzzer
+/// without unsafe harness code.
+pub fn verify_bytes(vk: &[u8], proof: &[u8], first_epoch: &[u8], last_epoch: &[u8]) -> bool {
+    catch_panic(false, || {
+        try_verify_bytes(vk, proof, first_epoch, last_epoch).is_ok()
+    })
+}
+
+fn try_verify_bytes(
+    vk: &[u8],
+    proof: &[u8],
+    first_epoch: &[u8],
+    last_epoch: &[u8],
+) -> Result<(), VerifyStatus> {
+    let vk: VerifyingKey<CPCurve> = with_status(
+        deserialize_compressed(vk, Strictness::Strict),
+        VerifyStatus::InvalidVk,
+    )?;
+    let proof = with_status(
+        deserialize_compressed(proof, Strictness::Strict),
+        VerifyStatus::InvalidProof,
+    )?;
+    let first_epoch = with_status(
+        EpochBlock::deserialize_from_bytes(first_epoch),
+        VerifyStatus::InvalidEpochBlock,
+    )?;
+    let last_epoch = with_status(
+        EpochBlock::deserialize_from_bytes(last_epoch),
+        VerifyStatus::InvalidEpochBlock,
+    )?;
+
+    epoch_snark::verify(&vk, &first_epoch, &last_epoch, &proof).map_err(verification_status)
+}
+
+/// Logs the verifier's error and maps it to its status code
+fn verification_status(e: VerificationError) -> VerifyStatus {
+    log::error!("SNARK library error: {}", e);
+    match e {
+        VerificationError::VerificationFailed | VerificationError::BatchVerificationFailed(_) => {
+            VerifyStatus::ProofRejected
+        }
+        VerificationError::EpochEncodingError(_)
+        | VerificationError::EpochOrderingError { .. }
+        | VerificationError::EpochCountMismatch { .. }
+        | VerificationError::EpochBoundaryMismatch { .. }
+        | VerificationError::ApkCommitmentMismatch => VerifyStatus::InvalidEpochBlock,
+        VerificationError::EpochGapTooLarge { .. } => VerifyStatus::EpochGapTooLarge,
+        VerificationError::InvalidSigners(_) => VerifyStatus::InvalidSigners,
+        // the VK's input elements do not match the public inputs
+        VerificationError::PublicInputMismatch { .. }
+        | VerificationError::ZexeSynthesisError(_) => VerifyStatus::InvalidVk,
+        // the elements are deserialized before calling the verifier
+        VerificationError::DeserializationError(_) => VerifyStatus::InvalidProof,
+        VerificationError::UnsupportedCircuitVersion(_) => VerifyStatus::UnsupportedCircuitVersion,
+    }
+}
+
+/// Logs the error (if any) and replaces it with the provided status code
+fn with_status<T, E: Display>(res: Result<T, E>, status: VerifyStatus) -> Result<T, VerifyStatus> {
+    res.map_err(|e| {
+        log::error!("SNARK library error: {}", e);
+        status
     })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::snark::EpochBlockFFI;
+    use crate::snark::{
+        epoch_block::{serialize_pubkeys, SignersFFI},
+        test_helpers::{generate_test_data_with_domain, to_ffi_block, verify_safe, Fixture},
+    };
 
     #[test]
     // Trimmed down version of the other E2E groth test to ensure
     // that the verifier works correctly for a proof which we have verified on our own
     fn simple_verifier_groth16() {
-        let serialized_proof = hex::decode(PROOF).unwrap();
-        let serialized_vk = hex::decode(VK).unwrap();
+        let fixture = Fixture::decode();
+        let first_epoch = fixture.first_epoch(0);
+        let last_epoch = fixture.last_epoch(2);
 
-        // Get the corresponding pointers
-        let proof_ptr = &serialized_proof[0] as *const u8;
-        let vk_ptr = &serialized_vk[0] as *const u8;
+        // Make the verification
+        assert!(verify_safe(
+            &fixture.vk,
+            &fixture.proof,
+            &first_epoch,
+            &last_epoch
+        ));
+        // the proof is only valid for these epochs
+        assert!(!verify_safe(
+            &fixture.vk,
+            &fixture.proof,
+            &first_epoch,
+            &fixture.first_epoch(2)
+        ));
+    }
 
-        let first_pubkeys = hex::decode(FIRST_PUBKEYS).unwrap();
-        let last_pubkeys = hex::decode(LAST_PUBKEYS).unwrap();
+    #[test]
+    fn verifies_fixture_from_commitments() {
+        use epoch_snark::{verify_with_commitments, verify_with_config};
 
-        let first_epoch = EpochBlockFFI {
-            index: 0,
-            maximum_non_signers: 1,
-            pubkeys_num: 4,
-            pubkeys: &first_pubkeys[0] as *const u8,
+        let fixture = Fixture::decode();
+        let vk = VerifyingKey::<CPCurve>::deserialize(&mut &fixture.vk[..]).unwrap();
+        let proof = Proof::<CPCurve>::deserialize(&mut &fixture.proof[..]).unwrap();
+        let first_epoch = fixture.first_epoch(0);
+        let last_epoch = fixture.last_epoch(2);
+        let config = CircuitConfig::default();
+        let verify_epochs = |first: &EpochBlock, last: &EpochBlock| {
+            verify_with_config(&vk, first, last, &proof, &config, &VerifyOptions::default()).is_ok()
+        };
+        let verify_commitments = |first: &EpochBlock, last: &EpochBlock| {
+            verify_with_commitments(
+                &vk,
+                &first.commitment().unwrap(),
+                &last.apk_commitment().unwrap(),
+                &proof,
+                &config,
+            )
+            .is_ok()
         };
 
-        let last_epoch = EpochBlockFFI {
-            index: 2,
-            maximum_non_signers: 1,
-            pubkeys_num: 4,
-            pubkeys: &last_pubkeys[0] as *const u8,
+        // both paths accept the fixture's epochs and reject any other pair of them
+        let epochs = [&first_epoch, &last_epoch];
+        for first in epochs.iter() {
+            for last in epochs.iter() {
+                let valid = (first.index, last.index) == (0, 2);
+                assert_eq!(verify_epochs(*first, *last), valid);
+                assert_eq!(verify_commitments(*first, *last), valid);
+            }
+        }
+    }
+
+    #[test]
+    fn verify_bytes_corpus() {
+        use rand::Rng;
+
+        let fixture = Fixture::decode();
+        let first_epoch = fixture.first_epoch(0).serialize_to_bytes().unwrap();
+        let last_epoch = fixture.last_epoch(2).serialize_to_bytes().unwrap();
+        let (vk, proof) = (fixture.vk, fixture.proof);
+        assert!(verify_bytes(&vk, &proof, &first_epoch, &last_epoch));
+
+        let inputs = [&vk, &proof, &first_epoch, &last_epoch];
+        let verify_with = |i: usize, bytes: &[u8]| {
+            let mut inputs = inputs.iter().map(|v| &v[..]).collect::<Vec<_>>();
+            inputs[i] = bytes;
+            verify_bytes(inputs[0], inputs[1], inputs[2], inputs[3])
         };
+        let rng = &mut rand::thread_rng();
+        for (i, input) in inputs.iter().enumerate() {
+            // empty and truncated inputs
+            for len in &[0, 1, input.len() / 2, input.len() - 1] {
+                assert!(!verify_with(i, &input[..*len]));
+            }
+            // trailing bytes
+            let mut padded = input.to_vec();
+            padded.push(0);
+            assert!(!verify_with(i, &padded));
+            // random bytes of the same length and of arbitrary lengths
+            for len in &[input.len(), 7, 100] {
+                let random = (0..*len).map(|_| rng.gen()).collect::<Vec<u8>>();
+                assert!(!verify_with(i, &random));
+            }
+        }
+    }
 
-        // Make the verification
-        let res = unsafe {
-            verify(
-                vk_ptr,
-                serialized_vk.len() as u32,
-                proof_ptr,
-                serialized_proof.len() as u32,
-                first_epoch,
-                last_epoch,
+    #[test]
+    fn fingerprints_vk() {
+        let compressed = Fixture::decode().vk;
+        let vk: VerifyingKey<CPCurve> =
+            deserialize_compressed(&compressed, Strictness::Strict).unwrap();
+        let mut uncompressed = vec![];
+        vk.serialize_uncompressed(&mut uncompressed).unwrap();
+
+        let fingerprint = |bytes: &[u8]| {
+            let mut out = [0u8; 32];
+            let ok =
+                unsafe { vk_fingerprint(bytes.as_ptr(), bytes.len() as u32, out.as_mut_ptr()) };
+            (ok, out)
+        };
+        let expected = epoch_snark::vk_fingerprint(&compressed).unwrap();
+        assert_eq!(fingerprint(&compressed), (true, expected));
+        assert_eq!(fingerprint(&uncompressed), (true, expected));
+        assert_eq!(fingerprint(&compressed[1..]), (false, [0u8; 32]));
+        assert!(!unsafe {
+            vk_fingerprint(
+                compressed.as_ptr(),
+                compressed.len() as u32,
+                ptr::null_mut(),
+            )
+        });
+    }
+
+    #[test]
+    fn checks_proof_structure() {
+        let proof = Fixture::decode().proof;
+        let well_formed =
+            |bytes: &[u8]| unsafe { proof_is_well_formed(bytes.as_ptr(), bytes.len() as u32) };
+        assert!(well_formed(&proof));
+
+        // wrong lengths
+        assert!(!well_formed(&[]));
+        assert!(!well_formed(&proof[..proof.len() - 1]));
+        let mut padded = proof.clone();
+        padded.push(0);
+        assert!(!well_formed(&padded));
+
+        // changing the x coordinate of each point moves it off the curve (or at least out
+        // of the subgroup)
+        let point_len = proof.len() / 3;
+        for i in 0..3 {
+            let mut tampered = proof.clone();
+            tampered[i * point_len] ^= 1;
+            assert!(!well_formed(&tampered));
+        }
+    }
+
+    #[test]
+    fn prepared_vk_agrees_with_verify() {
+        let fixture = Fixture::decode();
+        let vk: VerifyingKey<CPCurve> =
+            deserialize_compressed(&fixture.vk, Strictness::Strict).unwrap();
+        let pvk = epoch_snark::prepare_vk_from_bytes(&fixture.vk).unwrap();
+        let proof = deserialize_compressed(&fixture.proof, Strictness::Strict).unwrap();
+
+        let first = fixture.first_epoch(0);
+        for last in &[fixture.last_epoch(2), fixture.first_epoch(2)] {
+            let expected = epoch_snark::verify(&vk, &first, last, &proof);
+            let prepared = epoch_snark::verify_prepared(&pvk, &first, last, &proof);
+            assert_eq!(expected.is_ok(), prepared.is_ok());
+        }
+        assert!(epoch_snark::verify_prepared(&pvk, &first, &fixture.last_epoch(2), &proof).is_ok());
+    }
+
+    #[test]
+    fn verifier_returns_min_signers() {
+        let fixture = Fixture::decode();
+        let vk = deserialize_compressed(&fixture.vk, Strictness::Strict).unwrap();
+        let proof = deserialize_compressed(&fixture.proof, Strictness::Strict).unwrap();
+        let first = fixture.first_epoch(0);
+        let last = fixture.last_epoch(2);
+
+        // 4 validators, of which at most 1 may not sign
+        assert_eq!(
+            epoch_snark::verify_with_min_signers(&vk, &first, &last, &proof).unwrap(),
+            3
+        );
+        // the proof is still checked
+        let err =
+            epoch_snark::verify_with_min_signers(&vk, &first, &fixture.first_epoch(2), &proof)
+                .unwrap_err();
+        assert!(matches!(err, VerificationError::VerificationFailed));
+    }
+
+    #[test]
+    fn verifier_dispatches_on_circuit_version() {
+        let fixture = Fixture::decode();
+        let verify_version = |version: u32| unsafe {
+            verify_with_version(
+                fixture.vk.as_ptr(),
+                fixture.vk.len() as u32,
+                fixture.proof.as_ptr(),
+                fixture.proof.len() as u32,
+                fixture.first_ffi(0),
+                fixture.last_ffi(2),
+                version,
+            )
+        };
+
+        // the fixture was generated with the current circuit
+        assert_eq!(
+            verify_version(CircuitVersion::CURRENT as u32),
+            VerifyStatus::Ok as i32
+        );
+        for version in &[0, 2, 3, u32::MAX] {
+            assert_eq!(
+                verify_version(*version),
+                VerifyStatus::UnsupportedCircuitVersion as i32
+            );
+        }
+    }
+
+    #[test]
+    fn verifier_status_codes() {
+        let fixture = Fixture::decode();
+        let verify_status = |vk: *const u8, vk_len: usize, proof_len: usize, last_index: u16| unsafe {
+            verify_with_status(
+                vk,
+                vk_len as u32,
+                fixture.proof.as_ptr(),
+                proof_len as u32,
+                fixture.first_ffi(0),
+                fixture.last_ffi(last_index),
+            )
+        };
+        let vk = fixture.vk.as_ptr();
+        let vk_len = fixture.vk.len();
+        let proof_len = fixture.proof.len();
+
+        assert_eq!(
+            verify_status(vk, vk_len, proof_len, 2),
+            VerifyStatus::Ok as i32
+        );
+        assert_eq!(
+            verify_status(std::ptr::null(), vk_len, proof_len, 2),
+            VerifyStatus::NullPointer as i32
+        );
+        assert_eq!(
+            verify_status(vk, vk_len - 1, proof_len, 2),
+            VerifyStatus::InvalidVk as i32
+        );
+        assert_eq!(
+            verify_status(vk, 0, proof_len, 2),
+            VerifyStatus::ZeroLength as i32
+        );
+        assert_eq!(
+            verify_status(vk, vk_len, 0, 2),
+            VerifyStatus::ZeroLength as i32
+        );
+        assert_eq!(
+            verify_status(vk, vk_len, proof_len - 1, 2),
+            VerifyStatus::InvalidProof as i32
+        );
+        // the proof was not made for this epoch
+        assert_eq!(
+            verify_status(vk, vk_len, proof_len, 3),
+            VerifyStatus::ProofRejected as i32
+        );
+    }
+
+    #[test]
+    fn verifier_checks_max_gap() {
+        let fixture = Fixture::decode();
+        let verify_gap = |first_index: u16, last_index: u16, max_gap: u32| unsafe {
+            verify_with_max_gap(
+                fixture.vk.as_ptr(),
+                fixture.vk.len() as u32,
+                fixture.proof.as_ptr(),
+                fixture.proof.len() as u32,
+                fixture.first_ffi(first_index),
+                fixture.last_ffi(last_index),
+                max_gap,
+            )
+        };
+
+        // the proof spans 2 epochs
+        assert_eq!(verify_gap(0, 2, 2), VerifyStatus::Ok as i32);
+        assert_eq!(verify_gap(0, 2, 1), VerifyStatus::EpochGapTooLarge as i32);
+        // the gap is checked before the proof
+        assert_eq!(verify_gap(0, 3, 3), VerifyStatus::ProofRejected as i32);
+        assert_eq!(verify_gap(0, 3, 2), VerifyStatus::EpochGapTooLarge as i32);
+        // reversed epochs do not have a gap of 0
+        assert_eq!(
+            verify_gap(3, 0, u32::MAX),
+            VerifyStatus::InvalidEpochBlock as i32
+        );
+    }
+
+    #[test]
+    fn verifier_checks_signers() {
+        let fixture = Fixture::decode();
+        let pubkeys = fixture.last_epoch(2).new_public_keys;
+        let verify_signers = |bitmap: &[bool], signed: &[usize]| {
+            let aggregate = bls_crypto::PublicKey::aggregate(signed.iter().map(|i| &pubkeys[*i]));
+            let mut aggregate_pubkey = vec![];
+            aggregate.serialize(&mut aggregate_pubkey).unwrap();
+            let signers = SignersFFI {
+                bitmap: bitmap.as_ptr(),
+                bitmap_num: bitmap.len(),
+                aggregate_pubkey: aggregate_pubkey.as_ptr(),
+            };
+            unsafe {
+                verify_with_signers(
+                    fixture.vk.as_ptr(),
+                    fixture.vk.len() as u32,
+                    fixture.proof.as_ptr(),
+                    fixture.proof.len() as u32,
+                    fixture.first_ffi(0),
+                    fixture.last_ffi(2),
+                    &signers,
+                )
+            }
+        };
+
+        // all signers
+        assert_eq!(
+            verify_signers(&[true; 4], &[0, 1, 2, 3]),
+            VerifyStatus::Ok as i32
+        );
+        // minimal quorum
+        assert_eq!(
+            verify_signers(&[true, false, true, true], &[0, 2, 3]),
+            VerifyStatus::Ok as i32
+        );
+        // the aggregate pubkey of a different subset
+        assert_eq!(
+            verify_signers(&[true, false, true, true], &[0, 1, 2]),
+            VerifyStatus::InvalidSigners as i32
+        );
+        // below the quorum
+        assert_eq!(
+            verify_signers(&[true, false, false, true], &[0, 3]),
+            VerifyStatus::InvalidSigners as i32
+        );
+        // missing entries
+        assert_eq!(
+            verify_signers(&[true; 3], &[0, 1, 2]),
+            VerifyStatus::InvalidSigners as i32
+        );
+    }
+
+    #[test]
+    fn verifier_strictness() {
+        let fixture = Fixture::decode();
+        let verify_vk = |vk: &[u8], strict: bool| unsafe {
+            verify_with_strictness(
+                vk.as_ptr(),
+                vk.len() as u32,
+                fixture.proof.as_ptr(),
+                fixture.proof.len() as u32,
+                fixture.first_ffi(0),
+                fixture.last_ffi(2),
+                strict,
+            )
+        };
+
+        assert_eq!(verify_vk(&fixture.vk, true), VerifyStatus::Ok as i32);
+        assert_eq!(verify_vk(&fixture.vk, false), VerifyStatus::Ok as i32);
+
+        // a non canonical byte after the VK
+        let mut trailing = fixture.vk.clone();
+        trailing.push(0);
+        assert_eq!(verify_vk(&trailing, true), VerifyStatus::InvalidVk as i32);
+        assert_eq!(verify_vk(&trailing, false), VerifyStatus::Ok as i32);
+        // strict is the default
+        let status = unsafe {
+            verify_with_status(
+                trailing.as_ptr(),
+                trailing.len() as u32,
+                fixture.proof.as_ptr(),
+                fixture.proof.len() as u32,
+                fixture.first_ffi(0),
+                fixture.last_ffi(2),
+            )
+        };
+        assert_eq!(status, VerifyStatus::InvalidVk as i32);
+    }
+
+    #[test]
+    fn uncompressed_verifier_groth16() {
+        let fixture = Fixture::decode();
+
+        // re-encode everything without compression
+        let vk = VerifyingKey::<CPCurve>::deserialize(&mut &fixture.vk[..]).unwrap();
+        let mut uncompressed_vk = vec![];
+        vk.serialize_uncompressed(&mut uncompressed_vk).unwrap();
+        let proof = groth16::Proof::<CPCurve>::deserialize(&mut &fixture.proof[..]).unwrap();
+        let mut uncompressed_proof = vec![];
+        proof
+            .serialize_uncompressed(&mut uncompressed_proof)
+            .unwrap();
+        let uncompress_pubkeys = |pubkeys: &[u8]| {
+            let mut buf = vec![];
+            for chunk in pubkeys.chunks(96) {
+                let pubkey = bls_crypto::PublicKey::deserialize(&mut &chunk[..]).unwrap();
+                pubkey.serialize_uncompressed(&mut buf).unwrap();
+            }
+            buf
+        };
+        let first_uncompressed = uncompress_pubkeys(&fixture.first_pubkeys);
+        let last_uncompressed = uncompress_pubkeys(&fixture.last_pubkeys);
+        let first_ffi = to_ffi_block(&fixture.first_epoch(0), &first_uncompressed);
+        let last_ffi = to_ffi_block(&fixture.last_epoch(2), &last_uncompressed);
+
+        // the same proof verifies from both encodings
+        assert!(verify_safe(
+            &fixture.vk,
+            &fixture.proof,
+            &fixture.first_epoch(0),
+            &fixture.last_epoch(2)
+        ));
+        unsafe {
+            assert!(verify_uncompressed(
+                uncompressed_vk.as_ptr(),
+                uncompressed_vk.len() as u32,
+                uncompressed_proof.as_ptr(),
+                uncompressed_proof.len() as u32,
+                first_ffi,
+                last_ffi,
+            ));
+
+            // a truncated uncompressed element fails
+            assert!(!verify_uncompressed(
+                uncompressed_vk.as_ptr(),
+                uncompressed_vk.len() as u32,
+                uncompressed_proof.as_ptr(),
+                uncompressed_proof.len() as u32 - 1,
+                first_ffi,
+                last_ffi,
+            ));
+            // the compressed encodings are rejected
+            assert!(!verify_uncompressed(
+                fixture.vk.as_ptr(),
+                fixture.vk.len() as u32,
+                fixture.proof.as_ptr(),
+                fixture.proof.len() as u32,
+                first_ffi,
+                last_ffi,
+            ));
+        }
+    }
+
+    #[test]
+    fn message_hash_matches_signatures() {
+        use algebra::{
+            bls12_377::{G1Affine, G1Projective},
+            PrimeField, ProjectiveCurve,
+        };
+        use bls_crypto::{hash_to_curve::try_and_increment::SnarkCrh, HashToCurve, PrivateKey};
+
+        let block = Fixture::decode().first_epoch(0);
+        let message = block.hash_to_message().unwrap();
+        assert_eq!(message.len(), 48);
+
+        // the message is the compressed hash of the block's encoding in the default domain
+        let encoding = block.encode_to_bytes().unwrap();
+        let expected: G1Projective = SnarkCrh::CURRENT
+            .hash(Domain::default().as_bytes(), &encoding, &[])
+            .unwrap();
+        let mut expected_bytes = vec![];
+        expected
+            .into_affine()
+            .serialize(&mut expected_bytes)
+            .unwrap();
+        assert_eq!(message, expected_bytes);
+
+        // a signature over the block's encoding is the message point times the secret key
+        let sk = PrivateKey::generate(&mut rand::thread_rng());
+        let signature = sk
+            .sign(&block.encode_to_bytes().unwrap(), &[], &SnarkCrh::CURRENT)
+            .unwrap();
+        let point = G1Affine::deserialize(&mut &message[..]).unwrap();
+        assert_eq!(*signature.as_ref(), point.mul(sk.as_ref().into_repr()));
+    }
+
+    #[test]
+    fn batch_verifier_groth16() {
+        let fixture = Fixture::decode();
+
+        // the same proof, where the last one is checked against the wrong epoch
+        let proofs = vec![Buffer::from(fixture.proof.as_ref()); 3];
+        let first_epochs = vec![
+            fixture.first_ffi(0),
+            fixture.first_ffi(0),
+            fixture.first_ffi(0),
+        ];
+        let last_epochs = vec![
+            fixture.last_ffi(2),
+            fixture.last_ffi(2),
+            fixture.last_ffi(3),
+        ];
+        let verify = |vk_len: usize, results: &mut [bool]| unsafe {
+            verify_batch(
+                fixture.vk.as_ptr(),
+                vk_len as u32,
+                proofs.as_ptr(),
+                first_epochs.as_ptr(),
+                last_epochs.as_ptr(),
+                proofs.len(),
+                results.as_mut_ptr(),
+            )
+        };
+
+        let mut results = vec![false; 3];
+        assert!(!verify(fixture.vk.len(), &mut results));
+        assert_eq!(results, vec![true, true, false]);
+
+        // nothing passes with an invalid VK
+        let mut results = vec![true; 3];
+        assert!(!verify(fixture.vk.len() - 1, &mut results));
+        assert_eq!(results, vec![false; 3]);
+
+        // an empty batch is rejected
+        let mut results = vec![];
+        assert!(!unsafe {
+            verify_batch(
+                fixture.vk.as_ptr(),
+                fixture.vk.len() as u32,
+                proofs.as_ptr(),
+                first_epochs.as_ptr(),
+                last_epochs.as_ptr(),
+                0,
+                results.as_mut_ptr(),
+            )
+        });
+    }
+
+    #[test]
+    fn prepared_verifier_groth16() {
+        let fixture = Fixture::decode();
+
+        unsafe {
+            // an invalid VK cannot be prepared
+            assert!(vk_prepare(fixture.vk.as_ptr(), fixture.vk.len() as u32 - 1).is_null());
+
+            let handle = vk_prepare(fixture.vk.as_ptr(), fixture.vk.len() as u32);
+            assert!(!handle.is_null());
+            // the handle can be reused
+            for _ in 0..2 {
+                assert!(verify_prepared(
+                    handle,
+                    fixture.proof.as_ptr(),
+                    fixture.proof.len() as u32,
+                    fixture.first_ffi(0),
+                    fixture.last_ffi(2),
+                ));
+            }
+            assert!(!verify_prepared(
+                handle,
+                fixture.proof.as_ptr(),
+                fixture.proof.len() as u32,
+                fixture.first_ffi(0),
+                fixture.last_ffi(3),
+            ));
+            assert!(vk_free(handle));
+        }
+    }
+
+    #[test]
+    fn chunked_verifier_groth16() {
+        let fixture = Fixture::decode();
+
+        unsafe {
+            let handle = vk_prepare(fixture.vk.as_ptr(), fixture.vk.len() as u32);
+            assert!(!handle.is_null());
+
+            let ctx = verify_ctx_new();
+            for chunk in fixture.proof.chunks(16) {
+                assert!(verify_ctx_push(ctx, chunk.as_ptr(), chunk.len() as u32));
+            }
+            assert!(verify_ctx_finalize(
+                ctx,
+                handle,
+                fixture.first_ffi(0),
+                fixture.last_ffi(2),
+            ));
+            assert!(!verify_ctx_finalize(
+                ctx,
+                handle,
+                fixture.first_ffi(0),
+                fixture.last_ffi(3),
+            ));
+            // trailing bytes are not pushed
+            assert!(!verify_ctx_push(ctx, [0u8].as_ptr(), 1));
+            assert!(verify_ctx_finalize(
+                ctx,
+                handle,
+                fixture.first_ffi(0),
+                fixture.last_ffi(2),
+            ));
+            assert!(!verify_ctx_push(ctx, ptr::null(), 1));
+            assert!(verify_ctx_free(ctx));
+            assert!(!verify_ctx_free(ptr::null_mut()));
+
+            // an incomplete proof is rejected, and a chunk longer than a proof is not pushed
+            let ctx = verify_ctx_new();
+            let mut padded = fixture.proof.clone();
+            padded.push(0);
+            assert!(!verify_ctx_push(ctx, padded.as_ptr(), padded.len() as u32));
+            let chunk = &fixture.proof[..16];
+            assert!(verify_ctx_push(ctx, chunk.as_ptr(), chunk.len() as u32));
+            assert!(!verify_ctx_finalize(
+                ctx,
+                handle,
+                fixture.first_ffi(0),
+                fixture.last_ffi(2),
+            ));
+            assert!(verify_ctx_free(ctx));
+            assert!(vk_free(handle));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "prover")]
+    #[ignore] // expensive, run with `cargo test -- --ignored`
+    fn prove_verify_roundtrip() {
+        check_prove_verify_roundtrip(Domain::default());
+    }
+
+    #[test]
+    #[cfg(feature = "prover")]
+    #[ignore] // expensive, run with `cargo test -- --ignored`
+    fn prove_verify_roundtrip_with_domain() {
+        // the prover must hash the epochs in the domain recorded in the proving key
+        check_prove_verify_roundtrip(Domain::new(b"testnet").unwrap());
+    }
+
+    // Proves with the FFI for a setup in the provided domain and verifies the proof
+    #[cfg(feature = "prover")]
+    fn check_prove_verify_roundtrip(domain: Domain) {
+        let num_validators = 4;
+        let faults = 1;
+        let num_epochs = 2;
+        let rng = &mut rand::thread_rng();
+        let params = epoch_snark::trusted_setup_with_domain(
+            num_validators,
+            num_epochs,
+            faults,
+            rng,
+            true,
+            domain,
+        )
+        .unwrap();
+        let (first_epoch, transitions, last_epoch) =
+            generate_test_data_with_domain(num_validators, faults, num_epochs, domain);
+
+        let serialized_epochs_pk = params.proving_key().to_bytes().unwrap();
+        let mut serialized_hash_to_bits_pk = vec![];
+        params
+            .hash_to_bits
+            .as_ref()
+            .unwrap()
+            .serialize(&mut serialized_hash_to_bits_pk)
+            .unwrap();
+        let mut serialized_vk = vec![];
+        params.epochs.vk.serialize(&mut serialized_vk).unwrap();
+
+        // the serialized data must outlive the FFI structs which point to it
+        let first_pubkeys = serialize_pubkeys(&first_epoch.new_public_keys).unwrap();
+        let transition_data = transitions
+            .iter()
+            .map(|t| {
+                let mut signature = vec![];
+                t.aggregate_signature.serialize(&mut signature).unwrap();
+                (
+                    serialize_pubkeys(&t.block.new_public_keys).unwrap(),
+                    signature,
+                )
+            })
+            .collect::<Vec<_>>();
+        let ffi_transitions = transitions
+            .iter()
+            .zip(&transition_data)
+            .map(|(t, (pubkeys, signature))| EpochTransitionFFI {
+                block: to_ffi_block(&t.block, pubkeys),
+                aggregate_signature: &signature[0] as *const u8,
+                bitmap: &t.bitmap[0] as *const bool,
+                bitmap_num: t.bitmap.len(),
+            })
+            .collect::<Vec<_>>();
+
+        let call_prove = |out_proof: &mut [u8], out_proof_len: &mut u32| unsafe {
+            prove(
+                &serialized_epochs_pk[0] as *const u8,
+                serialized_epochs_pk.len() as u32,
+                &serialized_hash_to_bits_pk[0] as *const u8,
+                serialized_hash_to_bits_pk.len() as u32,
+                num_validators as u32,
+                to_ffi_block(&first_epoch, &first_pubkeys),
+                ffi_transitions.as_ptr(),
+                ffi_transitions.len(),
+                out_proof.as_mut_ptr(),
+                out_proof.len() as u32,
+                out_proof_len as *mut u32,
             )
         };
-        assert!(res);
+
+        // a buffer which is too small is rejected and the required size is returned
+        let mut proof = vec![0u8; 1];
+        let mut proof_len = 0;
+        assert!(!call_prove(&mut proof, &mut proof_len));
+        assert!(proof_len > 1);
+        assert_eq!(proof, vec![0u8]);
+
+        let mut proof = vec![0u8; proof_len as usize];
+        let mut new_proof_len = 0;
+        assert!(call_prove(&mut proof, &mut new_proof_len));
+        assert_eq!(new_proof_len, proof_len);
+
+        assert!(verify_safe(
+            &serialized_vk,
+            &proof,
+            &first_epoch,
+            &last_epoch
+        ));
     }
 
-    const PROOF: &str = "33796bc0cdbc50464a385a36e2c1ef80ae43372efc3a61f6274d6d51e6e3416986255d51a2259a11bf1195b25ac99f45958aaf352bfbd95be29d452aebdc566b54db0088f4ef5ca5365e2f3932c753c54c285ecd320e2a909edc4ac4ee3b2a82fc26bc53b4823a344578112646803524e13835d82ec38437d309d8e7d4d2094444bf0ecc61e3342e3260361fec644dc092346f03f9d1b796be7ef33579a38bddff69b4a9e080d90ce9712e974a450c5f6757807459ff257ccbb76e654ccccb90b4e82e1be04756b49f07f52a9a9eefd5f1ed3896df3ea10d55a6504d38012f60a6dda539ddc258a27004a9f30206c280230ee2928a6562f8e0bf67c60e2770cbc0020051b88c3c087abe93951e492e25a5b15dd99fa04fa0638dbc3b9fe358b9874f68d88e247cbaa0fae4ce250f432acafcc01ec6d248910884a3c9f78f5b0a1020db8c7b5cdca1a8dccb697d56f1a3592c5ae9f629fa17df7df08f94c31d21955dc4de4d5429ef742a69e9b35ff22b1649d4528a52d2f28f97abeeac93c665e1296da84a03723165e9e8fc71c09fd389bb1282fa212777ade68a7bed836ffb79dc1d2f9c091d5dd12c39705ccb4121de3bcf0e21a571a2c777e6271bb9c1e556ed46276fbe31a20aa488f13211883e5cce80692fe08b3ac3f6131435b2dbd28208fc114accdc69cb8b";
+    pub(crate) const PROOF: &str = "33796bc0cdbc50464a385a36e2c1ef80ae43372efc3a61f6274d6d51e6e3416986255d51a2259a11bf1195b25ac99f45958aaf352bfbd95be29d452aebdc566b54db0088f4ef5ca5365e2f3932c753c54c285ecd320e2a909edc4ac4ee3b2a82fc26bc53b4823a344578112646803524e13835d82ec38437d309d8e7d4d2094444bf0ecc61e3342e3260361fec644dc092346f03f9d1b796be7ef33579a38bddff69b4a9e080d90ce9712e974a450c5f6757807459ff257ccbb76e654ccccb90b4e82e1be04756b49f07f52a9a9eefd5f1ed3896df3ea10d55a6504d38012f60a6dda539ddc258a27004a9f30206c280230ee2928a6562f8e0bf67c60e2770cbc0020051b88c3c087abe93951e492e25a5b15dd99fa04fa0638dbc3b9fe358b9874f68d88e247cbaa0fae4ce250f432acafcc01ec6d248910884a3c9f78f5b0a1020db8c7b5cdca1a8dccb697d56f1a3592c5ae9f629fa17df7df08f94c31d21955dc4de4d5429ef742a69e9b35ff22b1649d4528a52d2f28f97abeeac93c665e1296da84a03723165e9e8fc71c09fd389bb1282fa212777ade68a7bed836ffb79dc1d2f9c091d5dd12c39705ccb4121de3bcf0e21a571a2c777e6271bb9c1e556ed46276fbe31a20aa488f13211883e5cce80692fe08b3ac3f6131435b2dbd28208fc114accdc69cb8b";
 
-    const VK: &str = "27de9b5798fd5c832aaabe2b1bb480473df29203a3a1971aed07a21441a6f21573031b01b821546c16f8470dda69131439854051daf5fb6ab7b6815cd9acc0c583ca1376d37343d69fc397323be1d601c7cd43fc42d4361aa27cdf8bc565d3731a93514b0fd38659ebc40f3c44ba9c6882a2e820828135d292d9bb7734afb8094e1e8a9c755420b93155ac4caefe44dd6a64e732daf6e0e141b26c88a30353d627e3a4afec307e285607d18261c04cf8b568eb4fdb3f48da889e8d46c4ff2712fd5664326a9642161463b234dd7b89f1fccb1a6c9acc26b1994c58b68106276345ecf79e0137a941be4bca06489616810b7db4d93fd8b3f6615e84b9683112cbf8e696867656616b5dbd60553df7f92fc1300c0aaf6217b2561b7f10671ae540c209f22a8e2cb98fb60e118aa2c372a44db6a404eb19d7e68ec9d0f0b4521a7f405ca2f421cf4b4128176a34cdf49a9a471dc197e4ee5c8ccb9342fb967173eb3137659930767af5228d1c36038885512286189c358cfc053c1319d4a2f8e50ca32ab13468d86aa059ed201de13f0eae7f4a687baa5d212df991cc5e656200fe705cb8e293614eebc196acc1a5d493d189162e0eeef2c68a37d58cc6717e9b649a595293cd18cc27f8aed9863b0a727b5e12cfa4e90513a282bb65658bd786968f89993ebbc7006f810f0cb6a7002599739fa40f337eaab7e639f79ebdf85ffecf106d5fa8464a197803f8a742a1ef2f05d03c592c97abdd0847c45f5b8878443319618ff04b7a3de13ce778c58c79b1c7f8d6e900d02fa1279dde856c7a62e1b13aff7a42b63bed3c2100318d515c0175c0c067b1ae5b4dc17c3423290c0d5c5e4c6da641073562f5ba99de560671bce41e2aa0abbeb2176b13802e58ba424ce8d790c7d5b610a409e26c718ef1a939198d3a32aef7f5b83347b15fc2b4adfebc468e7bb4f5667a70ea45c3b7135e0efc2e1738ab5b033176f75780fa17415fb5037c748d6be0a6ae68235cd307bf08f890261378aad62ef89395e33004c1e5f50a9fc261fd6a6f175e64110f15af68d3e888f132348039e3fb8db3ac974b8b07867b452bb73584d4c6e7ba5f790c119500b7b538cddcfc81ee754db65aca15f5853c806a40b38e02c0ed2a797fcc11afe2e26f972b4a7f8fe70e626e5d3b4fa600ee730f6a27b9fb448daa9b364ede0feefba1fc74a2d546eb74ac2c61f7d8033e0df3e214d3f12b8bf229692148305e2d82e3ad4c8266b7da641a42c21689e1e738b2689d1bc31afcb6d2b53a87afabd970de2da2e26431a1d4178a6808a23ca803a479e15ea5b7875adc1f4456ce88a3a94a7aecc9763899a353bcc1363a746ab55bd3322d44919eb0a3350c1a8eb574f7220300000000000000a21df711258265911c858a49dc5e866c47f4da8c6f24c4d5002156f719631fcf22af144657300ce576ca5523c69a2cef3e1771a054f51365321a78e2877a8505a02a25300f414035e4f84517141ada5e702376358634fe0b2d5477bfcda04f6ced04908edbd11f470d8e060600031662f9bb513919ab3b21846461a9c70c486adb9a200f16eeb66bf4d550bcfc44b2b29af9338b9c054f04c00eda28301a5d78ad5352fb88ee7623a5554a08a8f886e325a9a5cc4071fbe168860614a0a4e6ffd3aa7c2837fea5669ccd78c6c4c6573d1512ac83ecca8bb2b31d2a7a8870e84763eee2e6f47dc83ab5a78c6a45254c003853a563d59a00bc9cfec4586ef5b5b6e37564304f1ea1ad6c002b0f016863e0af2229a473258e9f1d91926ad75a310c5adead0bbda1";
+    pub(crate) const VK: &str = "27de9b5798fd5c832aaabe2b1bb480473df29203a3a1971aed07a21441a6f21573031b01b821546c16f8470dda69131439854051daf5fb6ab7b6815cd9acc0c583ca1376d37343d69fc397323be1d601c7cd43fc42d4361aa27cdf8bc565d3731a93514b0fd38659ebc40f3c44ba9c6882a2e820828135d292d9bb7734afb8094e1e8a9c755420b93155ac4caefe44dd6a64e732daf6e0e141b26c88a30353d627e3a4afec307e285607d18261c04cf8b568eb4fdb3f48da889e8d46c4ff2712fd5664326a9642161463b234dd7b89f1fccb1a6c9acc26b1994c58b68106276345ecf79e0137a941be4bca06489616810b7db4d93fd8b3f6615e84b9683112cbf8e696867656616b5dbd60553df7f92fc1300c0aaf6217b2561b7f10671ae540c209f22a8e2cb98fb60e118aa2c372a44db6a404eb19d7e68ec9d0f0b4521a7f405ca2f421cf4b4128176a34cdf49a9a471dc197e4ee5c8ccb9342fb967173eb3137659930767af5228d1c36038885512286189c358cfc053c1319d4a2f8e50ca32ab13468d86aa059ed201de13f0eae7f4a687baa5d212df991cc5e656200fe705cb8e293614eebc196acc1a5d493d189162e0eeef2c68a37d58cc6717e9b649a595293cd18cc27f8aed9863b0a727b5e12cfa4e90513a282bb65658bd786968f89993ebbc7006f810f0cb6a7002599739fa40f337eaab7e639f79ebdf85ffecf106d5fa8464a197803f8a742a1ef2f05d03c592c97abdd0847c45f5b8878443319618ff04b7a3de13ce778c58c79b1c7f8d6e900d02fa1279dde856c7a62e1b13aff7a42b63bed3c2100318d515c0175c0c067b1ae5b4dc17c3423290c0d5c5e4c6da641073562f5ba99de560671bce41e2aa0abbeb2176b13802e58ba424ce8d790c7d5b610a409e26c718ef1a939198d3a32aef7f5b83347b15fc2b4adfebc468e7bb4f5667a70ea45c3b7135e0efc2e1738ab5b033176f75780fa17415fb5037c748d6be0a6ae68235cd307bf08f890261378aad62ef89395e33004c1e5f50a9fc261fd6a6f175e64110f15af68d3e888f132348039e3fb8db3ac974b8b07867b452bb73584d4c6e7ba5f790c119500b7b538cddcfc81ee754db65aca15f5853c806a40b38e02c0ed2a797fcc11afe2e26f972b4a7f8fe70e626e5d3b4fa600ee730f6a27b9fb448daa9b364ede0feefba1fc74a2d546eb74ac2c61f7d8033e0df3e214d3f12b8bf229692148305e2d82e3ad4c8266b7da641a42c21689e1e738b2689d1bc31afcb6d2b53a87afabd970de2da2e26431a1d4178a6808a23ca803a479e15ea5b7875adc1f4456ce88a3a94a7aecc9763899a353bcc1363a746ab55bd3322d44919eb0a3350c1a8eb574f7220300000000000000a21df711258265911c858a49dc5e866c47f4da8c6f24c4d5002156f719631fcf22af144657300ce576ca5523c69a2cef3e1771a054f51365321a78e2877a8505a02a25300f414035e4f84517141ada5e702376358634fe0b2d5477bfcda04f6ced04908edbd11f470d8e060600031662f9bb513919ab3b21846461a9c70c486adb9a200f16eeb66bf4d550bcfc44b2b29af9338b9c054f04c00eda28301a5d78ad5352fb88ee7623a5554a08a8f886e325a9a5cc4071fbe168860614a0a4e6ffd3aa7c2837fea5669ccd78c6c4c6573d1512ac83ecca8bb2b31d2a7a8870e84763eee2e6f47dc83ab5a78c6a45254c003853a563d59a00bc9cfec4586ef5b5b6e37564304f1ea1ad6c002b0f016863e0af2229a473258e9f1d91926ad75a310c5adead0bbda1";
 
-    const FIRST_PUBKEYS: &str = "3dfaa2527c6e3870170898aca8b7285c3dd2f748427d9b0d368e3764917831c82d0e5571819162d721f4b1aeb02f4601b7709ef14786ca43c16fd0416265426734af20e705ad97a255d20176759cb0392d29cfffc21733e4e25e86767324a08153e8b0c43b72bfdac10fcdbcd8438a180d11ae7741b8bf34261652eb61d67022beba80d99a1611536188bf3d1966ac000a6c31c78230a841a97191ccd40aa30a5163bcbf655c3d4984750a31753145e5592b3795087996d80869b2ee1708a60026ce5dc1b1519987439e0791eb34ee50c9cc67919d0010232a2b106d60262189e237f228b731d30adfec4152947b5401b92cdf69ad0a3fd355465b29358aefb8ba99b2742fad5d4a032bac6e378ca4a84dfce4bfdad6a5858f0954876687b3803414bb082c1e925500d85971007e42432a5d34b865fd4fc3f91d514617401a8ec47b9b4e72c4c831886f48a71738c400583b8796cf9cf098dcb40548202946fdea0be1c6806d4ecd7784e23eab19b5dcbfec78de74c994717609f39e3d9c9001";
+    pub(crate) const FIRST_PUBKEYS: &str = "3dfaa2527c6e3870170898aca8b7285c3dd2f748427d9b0d368e3764917831c82d0e5571819162d721f4b1aeb02f4601b7709ef14786ca43c16fd0416265426734af20e705ad97a255d20176759cb0392d29cfffc21733e4e25e86767324a08153e8b0c43b72bfdac10fcdbcd8438a180d11ae7741b8bf34261652eb61d67022beba80d99a1611536188bf3d1966ac000a6c31c78230a841a97191ccd40aa30a5163bcbf655c3d4984750a31753145e5592b3795087996d80869b2ee1708a60026ce5dc1b1519987439e0791eb34ee50c9cc67919d0010232a2b106d60262189e237f228b731d30adfec4152947b5401b92cdf69ad0a3fd355465b29358aefb8ba99b2742fad5d4a032bac6e378ca4a84dfce4bfdad6a5858f0954876687b3803414bb082c1e925500d85971007e42432a5d34b865fd4fc3f91d514617401a8ec47b9b4e72c4c831886f48a71738c400583b8796cf9cf098dcb40548202946fdea0be1c6806d4ecd7784e23eab19b5dcbfec78de74c994717609f39e3d9c9001";
 
-    const LAST_PUBKEYS: &str = "a3fa866f5b51ff226f70a6872aedb89020372b88847a75f1c20dee118a679aa077dd16d1c1d06d079dad2e91808c11002199f32b651a010807264c6a00d034c3d4a31fad24935b2395d2fa80e02b6ac3e1a25e0f202ba99aa6647efe28d68081134bc536f6a172b50ad3a75e633272e5bc53f0a1c9520c584e922dda377eb2b2858ca443769753ba182012e609cc9f01c0df40d4c108fa01e9e4805ccc4947e5ff9dd90ca30bcf4ee258a957118a3b1b1ed8493c595d7598ae4e639147ef4901a84ba4f0cf1beda01fa86c8b3a71f97f657456991a20210ef73f1379156967c822d984e459209961d892c376a67c650056ca5d645a01131836a9382afb936c8ee2a430f9af098df29d776b9b9c2000c3326630a98ba48f4eb610685bb9d66b00ff530875d838c4b68ced77572837a5b335f5056973a41249c6049bab694e1e54bffc3a3c6a940ef69d5029300b7d98009534be42de14dce9d0a74a6ff52ffd36efa050cae9efe47a409bf73f748f795e81a914a9863edad412cf2cfa3b2c9e80";
+    pub(crate) const LAST_PUBKEYS: &str = "a3fa866f5b51ff226f70a6872aedb89020372b88847a75f1c20dee118a679aa077dd16d1c1d06d079dad2e91808c11002199f32b651a010807264c6a00d034c3d4a31fad24935b2395d2fa80e02b6ac3e1a25e0f202ba99aa6647efe28d68081134bc536f6a172b50ad3a75e633272e5bc53f0a1c9520c584e922dda377eb2b2858ca443769753ba182012e609cc9f01c0df40d4c108fa01e9e4805ccc4947e5ff9dd90ca30bcf4ee258a957118a3b1b1ed8493c595d7598ae4e639147ef4901a84ba4f0cf1beda01fa86c8b3a71f97f657456991a20210ef73f1379156967c822d984e459209961d892c376a67c650056ca5d645a01131836a9382afb936c8ee2a430f9af098df29d776b9b9c2000c3326630a98ba48f4eb610685bb9d66b00ff530875d838c4b68ced77572837a5b335f5056973a41249c6049bab694e1e54bffc3a3c6a940ef69d5029300b7d98009534be42de14dce9d0a74a6ff52ffd36efa050cae9efe47a409bf73f748f795e81a914a9863edad412cf2cfa3b2c9e80";
 }
@@ -1,4 +1,11 @@
-use algebra::{Field, PairingEngine};
+use super::{
+    codec,
+    epoch_block::{serialize_pubkeys, EpochBlockFFI, PUBKEY_BYTES},
+    verify, FIRST_PUBKEYS, LAST_PUBKEYS, PROOF, VK,
+};
+use algebra::{CanonicalDeserialize, Field, PairingEngine};
+use bls_crypto::{hash_to_curve::try_and_increment::SnarkCrh, Domain, PublicKey, Signature};
+use epoch_snark::{testing::random_epoch_block, EpochBlock, EpochTransition};
 use r1cs_core::{ConstraintSynthesizer, ConstraintSystem, SynthesisError};
 
 // circuit proving knowledge of a square root
@@ -43,3 +50,137 @@ impl<E: PairingEngine> ConstraintSynthesizer<E::Fr> for TestCircuit<E> {
         Ok(())
     }
 }
+
+// Returns the initial epoch, a list of `num_epochs` signed state transitions and the last
+// epoch. The first `faults` validators of each epoch do not sign the next epoch
+pub fn generate_test_data(
+    num_validators: usize,
+    faults: usize,
+    num_epochs: usize,
+) -> (EpochBlock, Vec<EpochTransition>, EpochBlock) {
+    generate_test_data_with_domain(num_validators, faults, num_epochs, Domain::default())
+}
+
+// Same as `generate_test_data`, but the transitions are signed in the provided domain
+pub fn generate_test_data_with_domain(
+    num_validators: usize,
+    faults: usize,
+    num_epochs: usize,
+    domain: Domain,
+) -> (EpochBlock, Vec<EpochTransition>, EpochBlock) {
+    let rng = &mut rand::thread_rng();
+    let (first_epoch, mut signers) = random_epoch_block(rng, num_validators, 0, faults as u32);
+
+    let bitmap = (0..num_validators).map(|i| i >= faults).collect::<Vec<_>>();
+    let mut transitions = vec![];
+    for i in 0..num_epochs {
+        let (block, validators) =
+            random_epoch_block(rng, num_validators, i as u16 + 1, faults as u32);
+
+        // the previous epoch's validators sign on the new block
+        let message = block.encode_to_bytes().unwrap();
+        let aggregate_signature = Signature::aggregate(
+            signers
+                .iter()
+                .zip(&bitmap)
+                .filter(|(_, signed)| **signed)
+                .map(|(key, _)| {
+                    key.sign_with_domain(domain, &message, &[], &SnarkCrh::CURRENT)
+                        .unwrap()
+                }),
+        );
+
+        transitions.push(EpochTransition {
+            block,
+            aggregate_signature,
+            bitmap: bitmap.clone(),
+        });
+        signers = validators;
+    }
+    let last_epoch = transitions[transitions.len() - 1].block.clone();
+
+    (first_epoch, transitions, last_epoch)
+}
+
+// Points the FFI block to the provided serialized pubkeys
+pub fn to_ffi_block(block: &EpochBlock, serialized_pubkeys: &[u8]) -> EpochBlockFFI {
+    EpochBlockFFI {
+        index: block.index,
+        maximum_non_signers: block.maximum_non_signers,
+        pubkeys_num: block.new_public_keys.len(),
+        pubkeys: &serialized_pubkeys[0] as *const u8,
+    }
+}
+
+// Deserializes the concatenated compressed pubkeys into an epoch block
+pub fn epoch_from_pubkeys(
+    index: u16,
+    maximum_non_signers: u32,
+    serialized_pubkeys: &[u8],
+) -> EpochBlock {
+    let pubkeys = serialized_pubkeys
+        .chunks(PUBKEY_BYTES)
+        .map(|mut pubkey| PublicKey::deserialize(&mut pubkey).unwrap())
+        .collect();
+    EpochBlock::new(index, maximum_non_signers, pubkeys)
+}
+
+// The serialized VK and proof of the fixture, and the serialized pubkeys of its first and
+// last epochs, which have 4 validators of which at most 1 may not sign. The proof is for
+// the first pubkeys at epoch 0 and the last pubkeys at epoch 2.
+pub struct Fixture {
+    pub vk: Vec<u8>,
+    pub proof: Vec<u8>,
+    pub first_pubkeys: Vec<u8>,
+    pub last_pubkeys: Vec<u8>,
+}
+
+impl Fixture {
+    // Decodes the fixture's hex constants
+    pub fn decode() -> Self {
+        Fixture {
+            vk: codec::decode_vk_hex(VK).unwrap(),
+            proof: codec::decode_proof_hex(PROOF).unwrap(),
+            first_pubkeys: hex::decode(FIRST_PUBKEYS).unwrap(),
+            last_pubkeys: hex::decode(LAST_PUBKEYS).unwrap(),
+        }
+    }
+
+    // An epoch at `index` with the first epoch's pubkeys
+    pub fn first_epoch(&self, index: u16) -> EpochBlock {
+        epoch_from_pubkeys(index, 1, &self.first_pubkeys)
+    }
+
+    // An epoch at `index` with the last epoch's pubkeys
+    pub fn last_epoch(&self, index: u16) -> EpochBlock {
+        epoch_from_pubkeys(index, 1, &self.last_pubkeys)
+    }
+
+    // Same as `first_epoch`, as an FFI block pointing to the fixture's pubkeys
+    pub fn first_ffi(&self, index: u16) -> EpochBlockFFI {
+        to_ffi_block(&self.first_epoch(index), &self.first_pubkeys)
+    }
+
+    // Same as `last_epoch`, as an FFI block pointing to the fixture's pubkeys
+    pub fn last_ffi(&self, index: u16) -> EpochBlockFFI {
+        to_ffi_block(&self.last_epoch(index), &self.last_pubkeys)
+    }
+}
+
+// Calls the `verify` FFI with the serialized VK and proof, pointing the FFI blocks to
+// the serialized pubkeys of the epochs
+pub fn verify_safe(vk: &[u8], proof: &[u8], first: &EpochBlock, last: &EpochBlock) -> bool {
+    let first_pubkeys = serialize_pubkeys(&first.new_public_keys).unwrap();
+    let last_pubkeys = serialize_pubkeys(&last.new_public_keys).unwrap();
+    // Safety: the pointers point to the vectors' memory, which outlives the call
+    unsafe {
+        verify(
+            vk.as_ptr(),
+            vk.len() as u32,
+            proof.as_ptr(),
+            proof.len() as u32,
+            to_ffi_block(first, &first_pubkeys),
+            to_ffi_block(last, &last_pubkeys),
+        )
+    }
+}
@@ -0,0 +1,95 @@
+//! Verifier bindings for `wasm-bindgen`, enabled with the `wasm` feature.
+//!
+//! Unlike the C FFI, these take byte slices and owned JS objects instead of raw pointers.
+//! All elements are expected to be serialized as **compressed elements**.
+use super::{
+    epoch_block::{EpochBlockFFI, PUBKEY_BYTES},
+    try_verify,
+};
+use epoch_snark::{CircuitVersion, Strictness, VerifyOptions};
+use wasm_bindgen::prelude::*;
+
+/// An epoch block passed from JS, containing the concatenated serialized pubkeys
+#[wasm_bindgen]
+pub struct JsEpochBlock {
+    index: u16,
+    maximum_non_signers: u32,
+    pubkeys: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl JsEpochBlock {
+    #[wasm_bindgen(constructor)]
+    pub fn new(index: u16, maximum_non_signers: u32, pubkeys: Vec<u8>) -> JsEpochBlock {
+        JsEpochBlock {
+            index,
+            maximum_non_signers,
+            pubkeys,
+        }
+    }
+
+    /// Points an `EpochBlockFFI` to the block's pubkeys, so that it gets converted to an
+    /// `EpochBlock` in the same way as in the C FFI. Returns `None` if the pubkeys buffer
+    /// does not contain a whole number of pubkeys.
+    fn as_ffi(&self) -> Option<EpochBlockFFI> {
+        if self.pubkeys.len() % PUBKEY_BYTES != 0 {
+            return None;
+        }
+        Some(EpochBlockFFI {
+            index: self.index,
+            pubkeys: self.pubkeys.as_ptr(),
+            pubkeys_num: self.pubkeys.len() / PUBKEY_BYTES,
+            maximum_non_signers: self.maximum_non_signers,
+        })
+    }
+}
+
+/// Verifies a Groth16 proof about the validity of the epoch transitions
+/// between the provided `first` and `last` epoch blocks.
+#[wasm_bindgen]
+pub fn verify(vk: &[u8], proof: &[u8], first: &JsEpochBlock, last: &JsEpochBlock) -> bool {
+    let (first, last) = match (first.as_ffi(), last.as_ffi()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return false,
+    };
+    // Safety: the pointers point to the slices' memory, which outlives this call
+    unsafe {
+        try_verify(
+            vk.as_ptr(),
+            vk.len() as u32,
+            proof.as_ptr(),
+            proof.len() as u32,
+            &first,
+            &last,
+            true,
+            Strictness::Strict,
+            CircuitVersion::CURRENT,
+            &VerifyOptions::default(),
+        )
+    }
+    .is_ok()
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+    use super::*;
+    use crate::snark::tests::{FIRST_PUBKEYS, LAST_PUBKEYS, PROOF, VK};
+    use wasm_bindgen_test::*;
+
+    #[wasm_bindgen_test]
+    fn wasm_verifier_groth16() {
+        let proof = hex::decode(PROOF).unwrap();
+        let vk = hex::decode(VK).unwrap();
+        let first = JsEpochBlock::new(0, 1, hex::decode(FIRST_PUBKEYS).unwrap());
+        let last = JsEpochBlock::new(2, 1, hex::decode(LAST_PUBKEYS).unwrap());
+        assert!(verify(&vk, &proof, &first, &last));
+
+        let wrong_last = JsEpochBlock::new(3, 1, hex::decode(LAST_PUBKEYS).unwrap());
+        assert!(!verify(&vk, &proof, &first, &wrong_last));
+
+        let mut truncated = hex::decode(LAST_PUBKEYS).unwrap();
+        truncated.pop();
+        let truncated_last = JsEpochBlock::new(2, 1, truncated);
+        assert!(!verify(&vk, &proof, &first, &truncated_last));
+    }
+}
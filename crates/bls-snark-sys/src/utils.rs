@@ -68,7 +68,7 @@ pub struct Buffer {
 impl From<&[u8]> for Buffer {
     fn from(src: &[u8]) -> Self {
         Self {
-            ptr: &src[0] as *const u8,
+            ptr: src.as_ptr(),
             len: src.len(),
         }
     }
@@ -76,10 +76,33 @@ impl From<&[u8]> for Buffer {
 
 impl<'a> From<&Buffer> for &'a [u8] {
     fn from(src: &Buffer) -> &'a [u8] {
-        unsafe { slice::from_raw_parts(src.ptr, src.len) }
+        unsafe { slice_or_empty(src.ptr, src.len) }
     }
 }
 
+/// Reads a `len`-sized slice from a caller-provided pointer. A null pointer or a zero
+/// length yields an empty slice, since `slice::from_raw_parts` requires a non-null
+/// pointer even when nothing is read.
+///
+/// # Safety
+///
+/// If non-null, `ptr` must point to `len` consecutive initialized values of `T`.
+pub(crate) unsafe fn slice_or_empty<'a, T>(ptr: *const T, len: usize) -> &'a [T] {
+    if ptr.is_null() || len == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(ptr, len)
+    }
+}
+
+/// Leaks the vector as a boxed slice and returns its pointer/length pair. The buffer
+/// must be freed with `free_buffer`, which rebuilds the same boxed slice.
+pub(crate) fn into_raw_buffer(v: Vec<u8>) -> (*mut u8, usize) {
+    let len = v.len();
+    let ptr = Box::into_raw(v.into_boxed_slice()) as *mut u8;
+    (ptr, len)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -97,6 +120,20 @@ mod tests {
         assert_eq!(buf.as_ref() as &[u8], de);
     }
 
+    #[test]
+    fn empty_buffers_convert_ok() {
+        let buffer = Buffer::from(&[][..]);
+        assert_eq!(buffer.len, 0);
+        assert!(<&[u8]>::from(&buffer).is_empty());
+
+        let null = Buffer {
+            ptr: std::ptr::null(),
+            len: 0,
+        };
+        assert!(<&[u8]>::from(&null).is_empty());
+        assert!(unsafe { slice_or_empty::<u8>(std::ptr::null(), 4) }.is_empty());
+    }
+
     #[test]
     fn msg_convert_ok() {
         let rng = &mut rand::thread_rng();
@@ -1,13 +1,10 @@
-use algebra::bls12_377::Bls12_377;
-use epoch_snark::ValidatorSetUpdate;
-use r1cs_core::ConstraintSynthesizer;
-use r1cs_std::test_constraint_counter::ConstraintCounter;
+use epoch_snark::circuit_info;
 use std::env;
 
 fn main() {
     let mut args = env::args();
     args.next().unwrap(); // discard the program name
-    let num_validators = args
+    let num_validators: usize = args
         .next()
         .expect("num validators was expected")
         .parse()
@@ -19,15 +16,15 @@ fn main() {
         .expect("NaN");
     let faults = (num_validators - 1) / 3;
 
-    let mut cs = ConstraintCounter::new();
-    let circuit = ValidatorSetUpdate::<Bls12_377>::empty(num_validators, num_epochs, faults, None);
-    circuit.generate_constraints(&mut cs).unwrap();
+    let info = circuit_info(num_validators, num_epochs).unwrap();
 
     println!(
-        "Number of constraints for {} epochs ({} validators, {} faults, hashes in SW6): {}",
+        "Number of constraints for {} epochs ({} validators, {} faults, hashes in SW6): {} ({} variables, {} public inputs)",
         num_epochs,
         num_validators,
         faults,
-        cs.num_constraints()
+        info.num_constraints,
+        info.num_variables,
+        info.num_public_inputs,
     )
 }
@@ -0,0 +1,46 @@
+//! Prints the number of constraints which the CRH adds to the circuit. Run it with and
+//! without the `blake2-crh` feature to compare the Bowe-Hopwood hash with Blake2s:
+//!
+//! ```text
+//! cargo run --release --example crh_constraints -- 10 2
+//! cargo run --release --example crh_constraints --features blake2-crh -- 10 2
+//! ```
+use bls_crypto::hash_to_curve::try_and_increment::SnarkCrh;
+use epoch_snark::{circuit_info, constraint_breakdown};
+use std::env;
+
+fn main() {
+    let mut args = env::args();
+    args.next().unwrap(); // discard the program name
+    let num_validators: usize = args
+        .next()
+        .expect("num validators was expected")
+        .parse()
+        .expect("NaN");
+    let num_epochs = args
+        .next()
+        .expect("num epochs was expected")
+        .parse()
+        .expect("NaN");
+
+    let info = circuit_info(num_validators, num_epochs).unwrap();
+    let breakdown = constraint_breakdown(num_validators, num_epochs).unwrap();
+    let hash_to_g1 = breakdown.get("hash_to_g1").copied().unwrap_or(0);
+
+    println!(
+        "CRH: {:?} ({} output bits), {} epochs, {} validators",
+        SnarkCrh::CURRENT,
+        SnarkCrh::CURRENT.output_bits(),
+        num_epochs,
+        num_validators,
+    );
+    println!(
+        "hash to G1: {} constraints ({} per epoch)",
+        hash_to_g1,
+        hash_to_g1 / num_epochs,
+    );
+    println!(
+        "total: {} constraints ({} variables, {} public inputs)",
+        info.num_constraints, info.num_variables, info.num_public_inputs,
+    );
+}
@@ -0,0 +1,56 @@
+use epoch_snark::{prove, prove_with_context, trusted_setup, verify, ProverContext};
+use std::{env, time::Instant};
+
+#[path = "../tests/fixtures.rs"]
+mod fixtures;
+use fixtures::generate_test_data;
+
+// Measures the time it takes to generate a few proofs for 5 epochs, with a fresh prover
+// context for each proof and with a single reused one
+fn main() {
+    let rng = &mut rand::thread_rng();
+    let mut args = env::args();
+    args.next().unwrap(); // discard the program name
+    let num_validators = args.next().map(|n| n.parse().expect("NaN")).unwrap_or(4);
+    let num_proofs = args.next().map(|n| n.parse().expect("NaN")).unwrap_or(3);
+    let num_epochs = 5;
+    let faults = (num_validators - 1) / 3;
+
+    let params = trusted_setup(num_validators, num_epochs, faults, rng, true).unwrap();
+    let (first_epoch, transitions, last_epoch) =
+        generate_test_data(num_validators, faults, num_epochs);
+
+    let time = Instant::now();
+    for _ in 0..num_proofs {
+        let proof = prove(&params, num_validators as u32, &first_epoch, &transitions).unwrap();
+        verify(&params.epochs.vk, &first_epoch, &last_epoch, &proof).unwrap();
+    }
+    println!(
+        "Proving {} epochs ({} validators) {} times without a context: {:?}",
+        num_epochs,
+        num_validators,
+        num_proofs,
+        time.elapsed()
+    );
+
+    let time = Instant::now();
+    let context = ProverContext::new().unwrap();
+    for _ in 0..num_proofs {
+        let proof = prove_with_context(
+            &context,
+            &params,
+            num_validators as u32,
+            &first_epoch,
+            &transitions,
+        )
+        .unwrap();
+        verify(&params.epochs.vk, &first_epoch, &last_epoch, &proof).unwrap();
+    }
+    println!(
+        "Proving {} epochs ({} validators) {} times with a reused context: {:?}",
+        num_epochs,
+        num_validators,
+        num_proofs,
+        time.elapsed()
+    );
+}
@@ -0,0 +1,53 @@
+use epoch_snark::{prove_with_threads, trusted_setup, verify};
+use std::{env, time::Instant};
+
+#[path = "../tests/fixtures.rs"]
+mod fixtures;
+use fixtures::generate_test_data;
+
+// Measures the proving time for the same inputs with 1, 2, 4 and 8 threads
+fn main() {
+    let rng = &mut rand::thread_rng();
+    let mut args = env::args();
+    args.next().unwrap(); // discard the program name
+    let num_validators = args
+        .next()
+        .expect("num validators was expected")
+        .parse()
+        .expect("NaN");
+    let num_epochs = args
+        .next()
+        .expect("num epochs was expected")
+        .parse()
+        .expect("NaN");
+    let faults = (num_validators - 1) / 3;
+
+    let params = trusted_setup(num_validators, num_epochs, faults, rng, true).unwrap();
+    let (first_epoch, transitions, last_epoch) =
+        generate_test_data(num_validators, faults, num_epochs);
+
+    let mut proofs = vec![];
+    for num_threads in &[1, 2, 4, 8] {
+        let time = Instant::now();
+        let proof = prove_with_threads(
+            &params,
+            num_validators as u32,
+            &first_epoch,
+            &transitions,
+            Some(*num_threads),
+        )
+        .unwrap();
+        println!(
+            "Proving {} epochs ({} validators) with {} threads: {:?}",
+            num_epochs,
+            num_validators,
+            num_threads,
+            time.elapsed()
+        );
+        verify(&params.epochs.vk, &first_epoch, &last_epoch, &proof).unwrap();
+        proofs.push(proof);
+    }
+
+    // the proof must not depend on the number of threads
+    assert!(proofs.windows(2).all(|w| w[0] == w[1]));
+}
@@ -0,0 +1,34 @@
+use epoch_snark::{prove, trusted_setup, verify};
+use std::{env, time::Instant};
+
+#[path = "../tests/fixtures.rs"]
+mod fixtures;
+use fixtures::generate_test_data;
+
+// Measures the average time it takes to verify a single proof. Run it with and without
+// the `parallel` feature to compare the serial and the parallel pairing checks.
+fn main() {
+    let rng = &mut rand::thread_rng();
+    let mut args = env::args();
+    args.next().unwrap(); // discard the program name
+    let iterations = args.next().map(|n| n.parse().expect("NaN")).unwrap_or(20);
+    let num_validators = 4;
+    let num_epochs = 2;
+    let faults = (num_validators - 1) / 3;
+
+    let params = trusted_setup(num_validators, num_epochs, faults, rng, true).unwrap();
+    let (first_epoch, transitions, last_epoch) =
+        generate_test_data(num_validators, faults, num_epochs);
+    let proof = prove(&params, num_validators as u32, &first_epoch, &transitions).unwrap();
+
+    let time = Instant::now();
+    for _ in 0..iterations {
+        verify(&params.epochs.vk, &first_epoch, &last_epoch, &proof).unwrap();
+    }
+    println!(
+        "Average verification time over {} runs (parallel: {}): {:?}",
+        iterations,
+        cfg!(feature = "parallel"),
+        time.elapsed() / iterations
+    );
+}
@@ -0,0 +1,53 @@
+use epoch_snark::{prove, trusted_setup, verify, verify_many};
+use std::{env, time::Instant};
+
+#[path = "../tests/fixtures.rs"]
+mod fixtures;
+use fixtures::generate_test_data;
+
+// Compares the time it takes to verify `num_proofs` proofs sequentially
+// against verifying them in a single batch
+fn main() {
+    let rng = &mut rand::thread_rng();
+    let mut args = env::args();
+    args.next().unwrap(); // discard the program name
+    let num_proofs = args.next().map(|n| n.parse().expect("NaN")).unwrap_or(50);
+    let num_validators = 4;
+    let num_epochs = 2;
+    let faults = (num_validators - 1) / 3;
+
+    let params = trusted_setup(num_validators, num_epochs, faults, rng, true).unwrap();
+    // proving is expensive, so only a few distinct proofs get generated and repeated
+    let distinct = (0..4)
+        .map(|_| {
+            let (first_epoch, transitions, last_epoch) =
+                generate_test_data(num_validators, faults, num_epochs);
+            let proof = prove(&params, num_validators as u32, &first_epoch, &transitions).unwrap();
+            (proof, first_epoch, last_epoch)
+        })
+        .collect::<Vec<_>>();
+    let proofs = distinct
+        .iter()
+        .cycle()
+        .take(num_proofs)
+        .map(|(proof, first, last)| (proof, first, last))
+        .collect::<Vec<_>>();
+
+    let time = Instant::now();
+    for (proof, first, last) in &proofs {
+        verify(&params.epochs.vk, first, last, proof).unwrap();
+    }
+    println!(
+        "Sequential verification of {} proofs: {:?}",
+        num_proofs,
+        time.elapsed()
+    );
+
+    let time = Instant::now();
+    verify_many(&params.epochs.vk, &proofs).unwrap();
+    println!(
+        "Batch verification of {} proofs: {:?}",
+        num_proofs,
+        time.elapsed()
+    );
+}
@@ -0,0 +1,160 @@
+use super::{
+    prove, prover::ProveError, setup::Parameters, verifier::AccumulatedProof, BLSCurve, CPCurve,
+};
+use crate::epoch_block::{EpochBlock, EpochBlockError, EpochTransition};
+
+/// Collects a chain of epoch transitions one at a time and proves all of them with a
+/// single Groth16 proof.
+///
+/// This is not a recursive composition: the epoch circuit already proves an arbitrary
+/// (but fixed at setup time) number of consecutive transitions, so the accumulated
+/// statement is the one of [`prove`](fn.prove.html) over every pushed transition. The
+/// proof must therefore be generated with parameters for exactly as many epochs as were
+/// pushed, and verified with [`verify_accumulated`](fn.verify_accumulated.html) against
+/// the VK of that setup.
+///
+/// Each pushed transition is checked against the previous epoch as soon as it is pushed,
+/// so that invalid chains get rejected before the expensive proving step.
+#[derive(Clone, Debug)]
+pub struct EpochProofAccumulator {
+    num_validators: u32,
+    initial_epoch: EpochBlock,
+    transitions: Vec<EpochTransition>,
+}
+
+impl EpochProofAccumulator {
+    /// Starts a chain from the provided epoch, whose validators sign the first transition
+    pub fn new(num_validators: u32, initial_epoch: EpochBlock) -> Self {
+        Self {
+            num_validators,
+            initial_epoch,
+            transitions: vec![],
+        }
+    }
+
+    /// Appends the transition to the chain. The transition's epoch must come right after
+    /// the last accumulated epoch, and enough of the last epoch's validators must have
+    /// signed it.
+    pub fn push(&mut self, transition: EpochTransition) -> Result<(), EpochBlockError> {
+        let previous = self.last_epoch();
+        if !previous.is_consecutive_with(&transition.block, 1) {
+            return Err(EpochBlockError::NonConsecutiveIndex {
+                expected: previous.index.saturating_add(1),
+                got: transition.block.index,
+            });
+        }
+        previous.check_quorum(&transition.bitmap)?;
+
+        self.transitions.push(transition);
+        Ok(())
+    }
+
+    /// The number of accumulated transitions
+    pub fn len(&self) -> usize {
+        self.transitions.len()
+    }
+
+    /// Returns true if no transitions have been pushed
+    pub fn is_empty(&self) -> bool {
+        self.transitions.is_empty()
+    }
+
+    /// The most recent epoch of the chain
+    pub fn last_epoch(&self) -> &EpochBlock {
+        self.transitions
+            .last()
+            .map(|transition| &transition.block)
+            .unwrap_or(&self.initial_epoch)
+    }
+
+    /// Proves the accumulated chain. The parameters must have been generated for
+    /// `self.len()` epochs and `num_validators` validators. Fails with
+    /// `ProveError::EmptyChain` if no transitions have been pushed.
+    pub fn finalize(
+        self,
+        parameters: &Parameters<CPCurve, BLSCurve>,
+    ) -> Result<AccumulatedProof, ProveError> {
+        if self.transitions.is_empty() {
+            return Err(ProveError::EmptyChain);
+        }
+        let proof = prove(
+            parameters,
+            self.num_validators,
+            &self.initial_epoch,
+            &self.transitions,
+        )?;
+        let num_epochs = self.transitions.len();
+        let last_epoch = self.last_epoch().clone();
+
+        Ok(AccumulatedProof {
+            proof,
+            first_epoch: self.initial_epoch,
+            last_epoch,
+            num_epochs,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::prover::tests::empty_parameters;
+    use crate::testing::random_epoch_block;
+    use algebra::{bls12_377::G1Projective, UniformRand};
+    use bls_crypto::Signature;
+
+    fn epoch(index: u16) -> EpochBlock {
+        random_epoch_block(&mut rand::thread_rng(), 4, index, 1).0
+    }
+
+    fn transition(index: u16, bitmap: Vec<bool>) -> EpochTransition {
+        EpochTransition {
+            block: epoch(index),
+            aggregate_signature: Signature::from(G1Projective::rand(&mut rand::thread_rng())),
+            bitmap,
+        }
+    }
+
+    #[test]
+    fn checks_pushed_transitions() {
+        let mut accumulator = EpochProofAccumulator::new(4, epoch(7));
+        accumulator
+            .push(transition(8, vec![true, true, false, true]))
+            .unwrap();
+        assert_eq!(accumulator.len(), 1);
+        assert_eq!(accumulator.last_epoch().index, 8);
+
+        // skips an epoch
+        assert_eq!(
+            accumulator.push(transition(10, vec![true; 4])).unwrap_err(),
+            EpochBlockError::NonConsecutiveIndex {
+                expected: 9,
+                got: 10
+            }
+        );
+        // not enough signers
+        assert_eq!(
+            accumulator
+                .push(transition(9, vec![true, false, false, true]))
+                .unwrap_err(),
+            EpochBlockError::NotEnoughSigners {
+                signers: 2,
+                required: 3
+            }
+        );
+        // the rejected transitions do not get accumulated
+        assert_eq!(accumulator.len(), 1);
+        accumulator.push(transition(9, vec![true; 4])).unwrap();
+        assert_eq!(accumulator.last_epoch().index, 9);
+    }
+
+    #[test]
+    fn rejects_empty_chain() {
+        let accumulator = EpochProofAccumulator::new(4, epoch(7));
+        assert!(accumulator.is_empty());
+        assert!(matches!(
+            accumulator.finalize(&empty_parameters()).unwrap_err(),
+            ProveError::EmptyChain
+        ));
+    }
+}
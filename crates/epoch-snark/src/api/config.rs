@@ -0,0 +1,153 @@
+use super::verifier::CircuitVersion;
+use crate::encoding::{EncodingError, PackingWidth, PublicFields};
+use bls_crypto::hash_to_curve::try_and_increment::SnarkCrh;
+use core::convert::TryFrom;
+
+/// The number of bits of the first and last epochs' hash, which is packed into the public
+/// inputs
+const HASH_BITS: usize = 2 * 256;
+
+/// The configuration of the epochs circuit, i.e. everything besides the epochs which
+/// determines its public inputs, along with the CRH which it hashes the epochs to G1 with.
+///
+/// The setup records the configuration in its [`Parameters`](struct.Parameters.html), the
+/// prover generates the proofs for it and the verifier derives the public inputs with it, e.g.
+/// in [`verify_with_config`](fn.verify_with_config.html). Proofs verified with another
+/// configuration are rejected. The default is the configuration of the Celo network, which
+/// [`trusted_setup`](fn.trusted_setup.html) and [`verify`](fn.verify.html) use.
+///
+/// A serialized [`ProvingKey`](struct.ProvingKey.html) starts with the configuration, as
+/// encoded by [`to_bytes`](#method.to_bytes), so that it is loaded along with the key instead
+/// of having to be passed separately.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CircuitConfig {
+    /// The version of the circuit, i.e. how the first and last epochs are hashed
+    pub version: CircuitVersion,
+    /// The width with which the hash of the first and last epochs is packed into the public
+    /// inputs
+    pub packing: PackingWidth,
+    /// The fields of the last epoch which are exposed as public inputs after the packed hash
+    pub public_fields: PublicFields,
+    /// The CRH which the circuit compresses the epochs with before hashing them to G1. It
+    /// does not change the public inputs, but keys can only be used by a build which hashes
+    /// with the same CRH, i.e. for which this is `SnarkCrh::CURRENT`.
+    pub crh: SnarkCrh,
+}
+
+impl Default for CircuitConfig {
+    fn default() -> Self {
+        CircuitConfig {
+            version: CircuitVersion::default(),
+            packing: PackingWidth::default(),
+            public_fields: PublicFields::default(),
+            crh: SnarkCrh::CURRENT,
+        }
+    }
+}
+
+impl CircuitConfig {
+    /// The length of a serialized configuration
+    pub const SERIALIZED_SIZE: usize = 4 + 4 + 1 + 1;
+
+    /// The number of public inputs of the circuit, i.e. the number of input elements of its
+    /// VK but the first one
+    pub fn num_public_inputs(&self) -> usize {
+        let packed = (HASH_BITS + self.packing.bits() - 1) / self.packing.bits();
+        packed + self.public_fields.maximum_non_signers as usize
+    }
+
+    /// Serializes the configuration as the little endian version and packing width, followed
+    /// by a byte with a bit per exposed field and the CRH's identifier, i.e. 0 for the
+    /// Bowe-Hopwood hash and 1 for Blake2s
+    pub fn to_bytes(&self) -> [u8; Self::SERIALIZED_SIZE] {
+        let mut bytes = [0u8; Self::SERIALIZED_SIZE];
+        bytes[..4].copy_from_slice(&(self.version as u32).to_le_bytes());
+        bytes[4..8].copy_from_slice(&(self.packing.bits() as u32).to_le_bytes());
+        bytes[8] = self.public_fields.maximum_non_signers as u8;
+        bytes[9] = match self.crh {
+            SnarkCrh::BoweHopwood => 0,
+            SnarkCrh::Blake2s => 1,
+        };
+        bytes
+    }
+
+    /// Deserializes a configuration encoded by [`to_bytes`](#method.to_bytes).
+    ///
+    /// Fails with `EncodingError::InvalidCircuitConfig` if the version is not supported,
+    /// unknown fields are exposed or the CRH is unknown, and with
+    /// `EncodingError::InvalidPackingWidth` if the width is out of range.
+    pub fn from_bytes(bytes: &[u8; Self::SERIALIZED_SIZE]) -> Result<Self, EncodingError> {
+        let mut word = [0u8; 4];
+        word.copy_from_slice(&bytes[..4]);
+        let version = CircuitVersion::try_from(u32::from_le_bytes(word))
+            .map_err(|_| EncodingError::InvalidCircuitConfig)?;
+        word.copy_from_slice(&bytes[4..8]);
+        let packing = PackingWidth::new(u32::from_le_bytes(word))?;
+        let maximum_non_signers = match bytes[8] {
+            0 => false,
+            1 => true,
+            _ => return Err(EncodingError::InvalidCircuitConfig),
+        };
+        let crh = match bytes[9] {
+            0 => SnarkCrh::BoweHopwood,
+            1 => SnarkCrh::Blake2s,
+            _ => return Err(EncodingError::InvalidCircuitConfig),
+        };
+        Ok(CircuitConfig {
+            version,
+            packing,
+            public_fields: PublicFields {
+                maximum_non_signers,
+            },
+            crh,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_roundtrip() {
+        let configs = [
+            CircuitConfig::default(),
+            CircuitConfig {
+                packing: PackingWidth::new(128).unwrap(),
+                public_fields: PublicFields {
+                    maximum_non_signers: true,
+                },
+                crh: SnarkCrh::Blake2s,
+                ..Default::default()
+            },
+        ];
+        for config in configs.iter() {
+            let bytes = config.to_bytes();
+            assert_eq!(CircuitConfig::from_bytes(&bytes).unwrap(), *config);
+        }
+        assert_eq!(CircuitConfig::default().num_public_inputs(), 2);
+        assert_eq!(configs[1].num_public_inputs(), 5);
+
+        let corrupted = |index: usize, byte: u8| {
+            let mut bytes = CircuitConfig::default().to_bytes();
+            bytes[index] = byte;
+            CircuitConfig::from_bytes(&bytes).unwrap_err()
+        };
+        assert!(matches!(
+            corrupted(0, 2),
+            EncodingError::InvalidCircuitConfig
+        ));
+        assert!(matches!(
+            corrupted(7, 0xff),
+            EncodingError::InvalidPackingWidth { .. }
+        ));
+        assert!(matches!(
+            corrupted(8, 2),
+            EncodingError::InvalidCircuitConfig
+        ));
+        assert!(matches!(
+            corrupted(9, 2),
+            EncodingError::InvalidCircuitConfig
+        ));
+    }
+}
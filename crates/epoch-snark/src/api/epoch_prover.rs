@@ -0,0 +1,275 @@
+use super::{
+    prover::{prove_with_context, ProveError, ProverContext},
+    setup::{trusted_setup_with_domain, Parameters},
+    verifier::{verify_prepared_with_config, VerificationError, VerifyOptions},
+    BLSCurve, CPCurve,
+};
+use crate::{
+    epoch_block::{EpochBlock, EpochTransition, MAX_VALIDATORS},
+    gadgets::{HashToBits, ValidatorSetUpdate},
+};
+use bls_crypto::{hash_to_curve::try_and_increment::SnarkCrh, Domain};
+
+use algebra::PairingEngine;
+use groth16::{
+    prepare_verifying_key, Parameters as Groth16Parameters, PreparedVerifyingKey, Proof,
+};
+use r1cs_core::{ConstraintSynthesizer, SynthesisError};
+use r1cs_std::test_constraint_counter::ConstraintCounter;
+use rand::Rng;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+/// Error raised by an [`EpochProver`](struct.EpochProver.html)
+pub enum EpochProverError {
+    #[error(
+        "the {circuit} keys have {got} witness variables, the configured window needs {expected}"
+    )]
+    KeyMismatch {
+        circuit: &'static str,
+        expected: usize,
+        got: usize,
+    },
+    #[error("got {got} validators, the circuit supports at most {max}")]
+    TooManyValidators { got: usize, max: usize },
+    #[error("the keys are for the {got:?} CRH, but this build hashes with {expected:?}")]
+    CrhMismatch { expected: SnarkCrh, got: SnarkCrh },
+    #[error("the prover is configured for {expected} epochs, got {got} transitions")]
+    WindowMismatch { expected: usize, got: usize },
+    #[error("zexe synthesis error: {0}")]
+    Synthesis(#[from] SynthesisError),
+    #[error("prover error: {0}")]
+    Prove(#[from] ProveError),
+    #[error("verification error: {0}")]
+    Verification(#[from] VerificationError),
+}
+
+/// Proves and verifies windows of a fixed number of epochs with the same keys.
+///
+/// The keys are checked against the window on construction, so that every proof generated
+/// by [`prove`](#method.prove) can be verified by [`verify`](#method.verify). The CRH
+/// tables and the prepared verifying key are computed once and reused for every call.
+pub struct EpochProver {
+    num_validators: usize,
+    num_epochs: usize,
+    parameters: Parameters<CPCurve, BLSCurve>,
+    pvk: PreparedVerifyingKey<CPCurve>,
+    context: ProverContext,
+}
+
+impl EpochProver {
+    /// Wraps parameters which were generated for `num_validators` validators,
+    /// `num_epochs` epochs and `maximum_non_signers` non signers per epoch.
+    ///
+    /// Fails with `EpochProverError::KeyMismatch` if the keys are for a different circuit,
+    /// with `EpochProverError::CrhMismatch` if they were generated for a different CRH than
+    /// `SnarkCrh::CURRENT`, and with `EpochProverError::TooManyValidators` if
+    /// `num_validators` is larger than `MAX_VALIDATORS`.
+    pub fn new(
+        num_validators: usize,
+        num_epochs: usize,
+        maximum_non_signers: usize,
+        parameters: Parameters<CPCurve, BLSCurve>,
+    ) -> Result<Self, EpochProverError> {
+        if num_validators > MAX_VALIDATORS {
+            return Err(EpochProverError::TooManyValidators {
+                got: num_validators,
+                max: MAX_VALIDATORS,
+            });
+        }
+        if parameters.config.crh != SnarkCrh::CURRENT {
+            return Err(EpochProverError::CrhMismatch {
+                expected: SnarkCrh::CURRENT,
+                got: parameters.config.crh,
+            });
+        }
+        if let Some(ref hash_to_bits) = parameters.hash_to_bits {
+            let circuit = HashToBits::empty(num_epochs, parameters.domain);
+            check_keys("CRH->XOF", circuit, hash_to_bits)?;
+        }
+        let circuit = ValidatorSetUpdate::empty(
+            num_validators,
+            num_epochs,
+            maximum_non_signers,
+            parameters
+                .hash_to_bits
+                .as_ref()
+                .map(|params| params.vk.clone()),
+            parameters.domain,
+        )
+        .with_config(parameters.config);
+        check_keys("epochs", circuit, &parameters.epochs)?;
+
+        Ok(Self {
+            num_validators,
+            num_epochs,
+            pvk: prepare_verifying_key(&parameters.epochs.vk),
+            parameters,
+            context: ProverContext::new()?,
+        })
+    }
+
+    /// Runs a [`trusted_setup_with_domain`](fn.trusted_setup_with_domain.html) for the
+    /// window and wraps its parameters
+    pub fn setup<R: Rng>(
+        num_validators: usize,
+        num_epochs: usize,
+        maximum_non_signers: usize,
+        rng: &mut R,
+        hashes_in_bls12_377: bool,
+        domain: Domain,
+    ) -> Result<Self, EpochProverError> {
+        let parameters = trusted_setup_with_domain(
+            num_validators,
+            num_epochs,
+            maximum_non_signers,
+            rng,
+            hashes_in_bls12_377,
+            domain,
+        )?;
+        Self::new(num_validators, num_epochs, maximum_non_signers, parameters)
+    }
+
+    /// Proves the transitions from `initial_epoch`, of which there must be exactly as many
+    /// as the configured number of epochs
+    pub fn prove(
+        &self,
+        initial_epoch: &EpochBlock,
+        transitions: &[EpochTransition],
+    ) -> Result<Proof<CPCurve>, EpochProverError> {
+        if transitions.len() != self.num_epochs {
+            return Err(EpochProverError::WindowMismatch {
+                expected: self.num_epochs,
+                got: transitions.len(),
+            });
+        }
+        Ok(prove_with_context(
+            &self.context,
+            &self.parameters,
+            self.num_validators as u32,
+            initial_epoch,
+            transitions,
+        )?)
+    }
+
+    /// Verifies a proof over the configured number of epochs, like
+    /// [`verify_window`](fn.verify_window.html), with the circuit configuration of the
+    /// parameters and the epochs padded to the configured number of validators
+    pub fn verify(
+        &self,
+        proof: &Proof<CPCurve>,
+        first_epoch: &EpochBlock,
+        last_epoch: &EpochBlock,
+    ) -> Result<(), EpochProverError> {
+        let options = VerifyOptions {
+            num_epochs: Some(self.num_epochs),
+            num_validators: Some(self.num_validators),
+            ..Default::default()
+        };
+        Ok(verify_prepared_with_config(
+            &self.pvk,
+            first_epoch,
+            last_epoch,
+            proof,
+            &self.parameters.config,
+            &options,
+        )?)
+    }
+
+    /// The number of validators in each epoch
+    pub fn num_validators(&self) -> usize {
+        self.num_validators
+    }
+
+    /// The number of epochs of each proven window
+    pub fn num_epochs(&self) -> usize {
+        self.num_epochs
+    }
+
+    /// The wrapped parameters, e.g. for serializing the verifying key
+    pub fn parameters(&self) -> &Parameters<CPCurve, BLSCurve> {
+        &self.parameters
+    }
+}
+
+/// Ensures that the keys were generated for the circuit, by comparing the number of
+/// witness variables the circuit allocates with the keys' query for them
+fn check_keys<C, E>(
+    name: &'static str,
+    circuit: C,
+    parameters: &Groth16Parameters<E>,
+) -> Result<(), EpochProverError>
+where
+    E: PairingEngine,
+    C: ConstraintSynthesizer<E::Fr>,
+{
+    let mut cs = ConstraintCounter::new();
+    circuit.generate_constraints(&mut cs)?;
+    let (expected, got) = (cs.num_aux, parameters.l_query.len());
+    // the public inputs are also fixed by the circuit, plus the constant `one`
+    if expected != got || cs.num_inputs + 1 != parameters.vk.gamma_abc_g1.len() {
+        return Err(EpochProverError::KeyMismatch {
+            circuit: name,
+            expected,
+            got,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_keys_for_another_window() {
+        let rng = &mut rand::thread_rng();
+        let prover = EpochProver::setup(3, 2, 1, rng, false, Domain::default()).unwrap();
+        assert_eq!(prover.num_epochs(), 2);
+
+        let epoch = EpochBlock::new(0, 1, vec![]);
+        let err = prover.prove(&epoch, &[]).unwrap_err();
+        assert!(matches!(
+            err,
+            EpochProverError::WindowMismatch {
+                expected: 2,
+                got: 0
+            }
+        ));
+        let err = prover
+            .verify(&Proof::default(), &epoch, &EpochBlock::new(3, 1, vec![]))
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            EpochProverError::Verification(VerificationError::EpochCountMismatch {
+                expected: 2,
+                got: 3
+            })
+        ));
+
+        // keys for 2 epochs, for a window of 1
+        let parameters = prover.parameters;
+        let err = EpochProver::new(3, 1, 1, parameters).err().unwrap();
+        assert!(matches!(
+            err,
+            EpochProverError::KeyMismatch {
+                circuit: "epochs",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn rejects_keys_for_another_crh() {
+        let rng = &mut rand::thread_rng();
+        let mut parameters = EpochProver::setup(3, 1, 1, rng, false, Domain::default())
+            .unwrap()
+            .parameters;
+        parameters.config.crh = match SnarkCrh::CURRENT {
+            SnarkCrh::BoweHopwood => SnarkCrh::Blake2s,
+            SnarkCrh::Blake2s => SnarkCrh::BoweHopwood,
+        };
+        let err = EpochProver::new(3, 1, 1, parameters).err().unwrap();
+        assert!(matches!(err, EpochProverError::CrhMismatch { .. }));
+    }
+}
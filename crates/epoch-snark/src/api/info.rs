@@ -0,0 +1,350 @@
+use super::{BLSCurve, CPField};
+use crate::gadgets::{
+    ValidatorSetUpdate, AGGREGATION_NAMESPACE, BLS_VERIFICATION_NAMESPACE, HASH_TO_G1_NAMESPACE,
+    PUBLIC_INPUTS_NAMESPACE,
+};
+use bls_crypto::Domain;
+use bls_gadgets::BITMAP_NAMESPACE;
+
+use algebra::{Field, Zero};
+use r1cs_core::{
+    ConstraintSynthesizer, ConstraintSystem, Index, LinearCombination, SynthesisError, Variable,
+};
+use r1cs_std::test_constraint_counter::ConstraintCounter;
+use std::collections::BTreeMap;
+
+/// Size metrics of the epochs circuit
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CircuitInfo {
+    /// The number of R1CS constraints
+    pub num_constraints: usize,
+    /// The number of allocated variables (public and private)
+    pub num_variables: usize,
+    /// The number of public inputs, excluding the constant `one`
+    pub num_public_inputs: usize,
+}
+
+/// Counts the constraints and variables of the epochs circuit for `num_validators` validators
+/// and `num_epochs` epochs, without performing a setup. The circuit is synthesized in counting
+/// mode, so no witness is computed.
+///
+/// The count assumes that the CRH->XOF hashes are done in SW6 and that up to a third of
+/// the validators may not sign, as the `constraints` example does.
+pub fn circuit_info(
+    num_validators: usize,
+    num_epochs: usize,
+) -> Result<CircuitInfo, SynthesisError> {
+    let mut cs = ConstraintCounter::new();
+    empty_circuit(num_validators, num_epochs).generate_constraints(&mut cs)?;
+
+    Ok(CircuitInfo {
+        num_constraints: cs.num_constraints(),
+        num_variables: cs.num_inputs + cs.num_aux,
+        num_public_inputs: cs.num_inputs,
+    })
+}
+
+/// The gadgets whose constraints are counted separately by `constraint_breakdown`, by the
+/// namespace which they are synthesized in
+const REGIONS: &[(&str, &str)] = &[
+    (BITMAP_NAMESPACE, "bitmap"),
+    (AGGREGATION_NAMESPACE, "bls_aggregation"),
+    (BLS_VERIFICATION_NAMESPACE, "bls_verification"),
+    (HASH_TO_G1_NAMESPACE, "hash_to_g1"),
+    (PUBLIC_INPUTS_NAMESPACE, "public_inputs"),
+];
+
+/// Label of the constraints which are not part of any of the `REGIONS`, e.g. the encoding
+/// of the epochs to bits
+const OTHER_REGION: &str = "other";
+
+/// Counts the constraints of the epochs circuit (configured like in
+/// [`circuit_info`](fn.circuit_info.html)) per gadget, i.e. for the bitmap checks, the
+/// aggregation of the signers' pubkeys, the BLS verification, the hashes to G1 and the
+/// public inputs. All other constraints are counted as `"other"`, so the counts sum up to
+/// the circuit's number of constraints.
+pub fn constraint_breakdown(
+    num_validators: usize,
+    num_epochs: usize,
+) -> Result<BTreeMap<String, usize>, SynthesisError> {
+    let mut cs = RegionCounter::default();
+    empty_circuit(num_validators, num_epochs).generate_constraints(&mut cs)?;
+    Ok(cs.constraints)
+}
+
+/// The R1CS matrices of the epochs circuit, in a sparse format.
+///
+/// The variables are numbered with the `num_inputs` public inputs first, starting with the
+/// constant `one` at index 0, followed by the `num_aux` private variables, i.e. private
+/// variable `i` is at index `num_inputs + i`. Each row of `a`, `b` and `c` is a constraint,
+/// given as the `(variable, coefficient)` pairs of its linear combination in increasing order
+/// of the variables and without zero coefficients, such that the constraint is satisfied if
+/// `<a, z> * <b, z> = <c, z>` for the assignment `z` of the variables.
+#[derive(Clone, Debug, PartialEq)]
+pub struct R1csExport {
+    /// The number of public inputs, including the constant `one`
+    pub num_inputs: usize,
+    /// The number of private variables
+    pub num_aux: usize,
+    /// The left linear combinations of the constraints
+    pub a: Vec<Vec<(usize, CPField)>>,
+    /// The right linear combinations of the constraints
+    pub b: Vec<Vec<(usize, CPField)>>,
+    /// The output linear combinations of the constraints
+    pub c: Vec<Vec<(usize, CPField)>>,
+}
+
+impl R1csExport {
+    /// The number of constraints
+    pub fn num_constraints(&self) -> usize {
+        self.a.len()
+    }
+}
+
+/// Synthesizes the epochs circuit (configured like in [`circuit_info`](fn.circuit_info.html))
+/// and returns its R1CS matrices, e.g. for auditing it with external tools. No witness is
+/// computed.
+pub fn export_r1cs(num_validators: usize, num_epochs: usize) -> Result<R1csExport, SynthesisError> {
+    let mut cs = MatrixAssembly::default();
+    empty_circuit(num_validators, num_epochs).generate_constraints(&mut cs)?;
+
+    // the constant `one` is input 0
+    let num_inputs = cs.num_inputs + 1;
+    let column = |index: Index| match index {
+        Index::Input(i) => i,
+        Index::Aux(i) => num_inputs + i,
+    };
+    let to_rows = |rows: Vec<Vec<(Index, CPField)>>| {
+        rows.into_iter()
+            .map(|row| {
+                row.into_iter()
+                    .map(|(index, coeff)| (column(index), coeff))
+                    .collect()
+            })
+            .collect()
+    };
+    Ok(R1csExport {
+        num_inputs,
+        num_aux: cs.num_aux,
+        a: to_rows(cs.a),
+        b: to_rows(cs.b),
+        c: to_rows(cs.c),
+    })
+}
+
+fn empty_circuit(num_validators: usize, num_epochs: usize) -> ValidatorSetUpdate<BLSCurve> {
+    let maximum_non_signers = num_validators.saturating_sub(1) / 3;
+    ValidatorSetUpdate::<BLSCurve>::empty(
+        num_validators,
+        num_epochs,
+        maximum_non_signers,
+        None,
+        Domain::default(),
+    )
+}
+
+/// Constraint system which counts the constraints of each of the `REGIONS`, attributing
+/// each constraint to the innermost region among the namespaces it is enforced in
+#[derive(Default)]
+struct RegionCounter {
+    namespaces: Vec<String>,
+    num_inputs: usize,
+    num_aux: usize,
+    constraints: BTreeMap<String, usize>,
+}
+
+impl RegionCounter {
+    fn region(&self) -> &'static str {
+        self.namespaces
+            .iter()
+            .rev()
+            .find_map(|namespace| {
+                REGIONS
+                    .iter()
+                    .find(|(name, _)| name == namespace)
+                    .map(|(_, region)| *region)
+            })
+            .unwrap_or(OTHER_REGION)
+    }
+}
+
+/// Constraint system which records the linear combinations of each constraint
+#[derive(Default)]
+struct MatrixAssembly {
+    num_inputs: usize,
+    num_aux: usize,
+    a: Vec<Vec<(Index, CPField)>>,
+    b: Vec<Vec<(Index, CPField)>>,
+    c: Vec<Vec<(Index, CPField)>>,
+}
+
+/// Sums the coefficients of each variable of the linear combination, dropping the zero ones
+fn to_row(lc: LinearCombination<CPField>) -> Vec<(Index, CPField)> {
+    let mut row = BTreeMap::new();
+    for (var, coeff) in lc.0 {
+        *row.entry(var.get_unchecked()).or_insert_with(CPField::zero) += &coeff;
+    }
+    row.into_iter()
+        .filter(|(_, coeff)| !coeff.is_zero())
+        .collect()
+}
+
+impl ConstraintSystem<CPField> for MatrixAssembly {
+    type Root = Self;
+
+    fn alloc<FN, A, AR>(&mut self, _: A, _: FN) -> Result<Variable, SynthesisError>
+    where
+        FN: FnOnce() -> Result<CPField, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.num_aux += 1;
+        Ok(Variable::new_unchecked(Index::Aux(self.num_aux - 1)))
+    }
+
+    fn alloc_input<FN, A, AR>(&mut self, _: A, _: FN) -> Result<Variable, SynthesisError>
+    where
+        FN: FnOnce() -> Result<CPField, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        // input 0 is the constant `one`
+        self.num_inputs += 1;
+        Ok(Variable::new_unchecked(Index::Input(self.num_inputs)))
+    }
+
+    fn enforce<A, AR, LA, LB, LC>(&mut self, _: A, a: LA, b: LB, c: LC)
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+        LA: FnOnce(LinearCombination<CPField>) -> LinearCombination<CPField>,
+        LB: FnOnce(LinearCombination<CPField>) -> LinearCombination<CPField>,
+        LC: FnOnce(LinearCombination<CPField>) -> LinearCombination<CPField>,
+    {
+        self.a.push(to_row(a(LinearCombination::zero())));
+        self.b.push(to_row(b(LinearCombination::zero())));
+        self.c.push(to_row(c(LinearCombination::zero())));
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn pop_namespace(&mut self) {}
+
+    fn get_root(&mut self) -> &mut Self::Root {
+        self
+    }
+
+    fn num_constraints(&self) -> usize {
+        self.a.len()
+    }
+}
+
+impl<F: Field> ConstraintSystem<F> for RegionCounter {
+    type Root = Self;
+
+    fn alloc<FN, A, AR>(&mut self, _: A, _: FN) -> Result<Variable, SynthesisError>
+    where
+        FN: FnOnce() -> Result<F, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.num_aux += 1;
+        Ok(Variable::new_unchecked(Index::Aux(self.num_aux - 1)))
+    }
+
+    fn alloc_input<FN, A, AR>(&mut self, _: A, _: FN) -> Result<Variable, SynthesisError>
+    where
+        FN: FnOnce() -> Result<F, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.num_inputs += 1;
+        Ok(Variable::new_unchecked(Index::Input(self.num_inputs - 1)))
+    }
+
+    fn enforce<A, AR, LA, LB, LC>(&mut self, _: A, _: LA, _: LB, _: LC)
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+        LA: FnOnce(LinearCombination<F>) -> LinearCombination<F>,
+        LB: FnOnce(LinearCombination<F>) -> LinearCombination<F>,
+        LC: FnOnce(LinearCombination<F>) -> LinearCombination<F>,
+    {
+        *self
+            .constraints
+            .entry(self.region().to_owned())
+            .or_insert(0) += 1;
+    }
+
+    fn push_namespace<NR, N>(&mut self, name: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.namespaces.push(name().into());
+    }
+
+    fn pop_namespace(&mut self) {
+        self.namespaces.pop();
+    }
+
+    fn get_root(&mut self) -> &mut Self::Root {
+        self
+    }
+
+    fn num_constraints(&self) -> usize {
+        self.constraints.values().sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn circuit_grows_with_epochs() {
+        let small = circuit_info(3, 1).unwrap();
+        let large = circuit_info(3, 2).unwrap();
+        assert!(small.num_constraints > 0);
+        assert!(small.num_variables > small.num_public_inputs);
+        assert!(large.num_constraints > small.num_constraints);
+        // the public inputs only depend on the first and last epoch
+        assert_eq!(large.num_public_inputs, small.num_public_inputs);
+    }
+
+    #[test]
+    fn breakdown_sums_to_total() {
+        let info = circuit_info(3, 2).unwrap();
+        let breakdown = constraint_breakdown(3, 2).unwrap();
+        assert_eq!(breakdown.values().sum::<usize>(), info.num_constraints);
+        for (_, region) in REGIONS {
+            assert!(breakdown[*region] > 0, "no constraints in {}", region);
+        }
+        // the bitmap check is much cheaper than the hashes
+        assert!(breakdown["bitmap"] < breakdown["hash_to_g1"]);
+    }
+
+    #[test]
+    fn exported_r1cs_matches_info() {
+        let info = circuit_info(2, 1).unwrap();
+        let r1cs = export_r1cs(2, 1).unwrap();
+        assert_eq!(r1cs.num_constraints(), info.num_constraints);
+        assert_eq!(r1cs.b.len(), info.num_constraints);
+        assert_eq!(r1cs.c.len(), info.num_constraints);
+        assert_eq!(r1cs.num_inputs, info.num_public_inputs + 1);
+        assert_eq!(r1cs.num_inputs - 1 + r1cs.num_aux, info.num_variables);
+
+        let num_columns = r1cs.num_inputs + r1cs.num_aux;
+        for row in r1cs.a.iter().chain(&r1cs.b).chain(&r1cs.c) {
+            assert!(row.windows(2).all(|w| w[0].0 < w[1].0));
+            assert!(row
+                .iter()
+                .all(|(i, coeff)| *i < num_columns && !coeff.is_zero()));
+        }
+    }
+}
@@ -1,15 +1,57 @@
+#[cfg(feature = "prover")]
+mod accumulator;
+#[cfg(feature = "prover")]
+pub use accumulator::EpochProofAccumulator;
+
+#[cfg(feature = "prover")]
+mod epoch_prover;
+#[cfg(feature = "prover")]
+pub use epoch_prover::{EpochProver, EpochProverError};
+
+#[cfg(feature = "prover")]
+mod info;
+#[cfg(feature = "prover")]
+pub use info::{circuit_info, constraint_breakdown, export_r1cs, CircuitInfo, R1csExport};
+
+#[cfg(feature = "prover")]
 mod prover;
-pub use prover::prove;
+#[cfg(all(feature = "prover", feature = "tokio"))]
+pub use prover::prove_async;
+#[cfg(feature = "prover")]
+pub use prover::{
+    prove, prove_deterministic, prove_with_context, prove_with_metrics, prove_with_options,
+    prove_with_threads, prove_with_witness, ProveError, ProveMetrics, ProveOptions, ProverContext,
+    Witness,
+};
 
+#[cfg(feature = "prover")]
 mod setup;
-pub use setup::trusted_setup;
+#[cfg(feature = "prover")]
+pub use setup::{
+    setup_from_seed, trusted_setup, trusted_setup_with_config, trusted_setup_with_domain,
+    Parameters, ProvingKey, ProvingKeyError,
+};
+
+mod config;
+pub use config::CircuitConfig;
 
 mod verifier;
-pub use verifier::{verify, VerificationError};
+#[cfg(feature = "std")]
+pub use verifier::verify_many;
+#[cfg(feature = "parallel")]
+pub use verifier::verify_parallel;
+pub use verifier::{
+    canonicalize_vk, link_proofs, prepare_vk_from_bytes, proof_size, public_inputs,
+    public_inputs_with_config, verify, verify_accumulated, verify_any_vk, verify_chain,
+    verify_envelope, verify_linked, verify_prepared, verify_prepared_with_config,
+    verify_serialized, verify_serialized_with_strictness, verify_window, verify_with_commitments,
+    verify_with_config, verify_with_min_signers, vk_equal, vk_fingerprint, vk_size,
+    AccumulatedProof, CircuitVersion, LinkedProof, VerificationError, VerifyOptions,
+};
 
 // Instantiate certain types to avoid confusion
-use algebra::{bls12_377, sw6};
-type BLSCurve = bls12_377::Bls12_377;
+use algebra::sw6;
+#[cfg(feature = "prover")]
+type BLSCurve = algebra::bls12_377::Bls12_377;
 type CPField = sw6::Fr;
 type CPCurve = sw6::SW6;
-type CPFrParams = sw6::FrParameters;
@@ -1,30 +1,459 @@
-use super::{setup::Parameters, BLSCurve, CPCurve};
+use super::{setup::Parameters, BLSCurve, CPCurve, CPField};
 use crate::{
-    epoch_block::{EpochBlock, EpochTransition},
+    encoding::EncodingError,
+    epoch_block::{EpochBlock, EpochBlockError, EpochTransition, MAX_VALIDATORS},
     gadgets::{EpochData, HashToBits, HashToBitsHelper, SingleUpdate, ValidatorSetUpdate},
 };
 use bls_crypto::{
-    hash_to_curve::try_and_increment::COMPOSITE_HASH_TO_G1,
-    hashers::{Hasher, COMPOSITE_HASHER},
-    Signature, SIG_DOMAIN,
+    hash_to_curve::try_and_increment::SnarkCrh,
+    hashers::composite::{CRHParameters, CompositeHasher, CRH},
+    BLSError, Domain, Signature,
 };
 use bls_gadgets::utils::bytes_to_bits;
 
-use groth16::{create_proof_no_zk, Parameters as Groth16Parameters, Proof as Groth16Proof};
-use r1cs_core::SynthesisError;
+use algebra::{CanonicalSerialize, Field, UniformRand};
+use groth16::{
+    create_proof, create_proof_no_zk, Parameters as Groth16Parameters, Proof as Groth16Proof,
+};
+use r1cs_core::{
+    ConstraintSynthesizer, ConstraintSystem, LinearCombination, SynthesisError, Variable,
+};
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use rayon::ThreadPoolBuilder;
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    io,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use thiserror::Error;
+use tracing::{info, span, warn, Level};
+
+#[derive(Debug, Error)]
+/// Error raised while generating a proof with [`prove`](fn.prove.html) or any of its
+/// variants
+pub enum ProveError {
+    #[error("got {got} validators, the circuit supports at most {max}")]
+    TooManyValidators { got: usize, max: usize },
+    #[error("a transition does not meet the quorum of the previous epoch: {0}")]
+    NotEnoughSigners(EpochBlockError),
+    #[error("the parameters are for the {got:?} CRH, but this build hashes with {expected:?}")]
+    CrhMismatch { expected: SnarkCrh, got: SnarkCrh },
+    #[error("there are no transitions to prove")]
+    EmptyChain,
+    #[error("encoding error: {0}")]
+    Encoding(#[from] EncodingError),
+    #[error("CRH error: {0}")]
+    Crh(#[from] BLSError),
+    #[error("zexe synthesis error: {0}")]
+    Synthesis(#[from] SynthesisError),
+}
+
+/// Time spent in each phase of [`prove_with_metrics`](fn.prove_with_metrics.html), and the
+/// size of the proven circuit
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ProveMetrics {
+    /// Time spent synthesizing the epochs circuit with the transitions' assignment, i.e.
+    /// computing the witness
+    pub witness_generation: Duration,
+    /// Time spent hashing the epochs outside of the circuit (unless their witness was
+    /// precomputed) and generating the CRH->XOF helper proof over BLS12-377, if the
+    /// parameters include it
+    pub hash_to_bits: Duration,
+    /// Time spent in the Groth16 prover for the epochs proof apart from the synthesis, which
+    /// includes the FFTs over the evaluation domain and the multi-scalar multiplications
+    pub proving: Duration,
+    /// The number of R1CS constraints of the epochs circuit
+    pub num_constraints: usize,
+    /// The number of allocated variables (public and private) of the epochs circuit
+    pub num_variables: usize,
+}
+
+/// Precomputed data which does not depend on the proven epochs, to be reused across calls
+/// to [`prove_with_context`](fn.prove_with_context.html).
+///
+/// This holds the generators of the Bowe-Hopwood-Pedersen CRH which hashes each epoch to G1
+/// inside the circuit, along with their precomputed multiples for the fixed-base windows.
+/// Without a context they get regenerated for every epoch of every proof.
+#[derive(Clone)]
+pub struct ProverContext {
+    crh_parameters: Arc<CRHParameters>,
+}
 
-use tracing::{info, span, Level};
+impl ProverContext {
+    /// Generates the precomputed tables, failing with `ProveError::Crh` if the CRH cannot
+    /// generate its parameters
+    pub fn new() -> Result<Self, ProveError> {
+        let crh_parameters = CompositeHasher::<CRH>::setup_crh()?;
+        Ok(Self {
+            crh_parameters: Arc::new(crh_parameters),
+        })
+    }
+}
+
+/// Precomputed hashes of epochs, to be reused across calls to
+/// [`prove_with_witness`](fn.prove_with_witness.html).
+///
+/// Hashing an epoch to G1 requires searching for its try-and-increment counter and
+/// evaluating the CRH outside of the circuit. The witness records both per epoch (keyed by
+/// the epoch's encoding and the domain it is hashed in), so that proofs of overlapping
+/// windows only hash the epochs they have not seen yet. Entries may be added or removed
+/// freely between proofs, since missing ones are recomputed.
+///
+/// This is the only part of the witness which can be cached: the Groth16 prover assigns
+/// every other variable (the epochs' bits, the in-circuit hashes and the aggregated public
+/// keys) while synthesizing the circuit, and cannot take them precomputed.
+#[derive(Clone, Debug, Default)]
+pub struct Witness {
+    epochs: HashMap<(Domain, Vec<u8>), EpochWitness>,
+}
+
+/// The hash witness of a single epoch
+#[derive(Clone, Debug)]
+struct EpochWitness {
+    counter: u8,
+    crh_bits: Vec<bool>,
+}
+
+impl Witness {
+    /// Creates an empty witness
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hashes the epoch in the provided domain if it is not already part of the witness
+    pub fn precompute(&mut self, block: &EpochBlock, domain: Domain) -> Result<(), ProveError> {
+        self.get_or_compute(block, domain).map(|_| ())
+    }
+
+    /// Removes the epoch's hash in the provided domain, returning whether it was present
+    pub fn remove(&mut self, block: &EpochBlock, domain: Domain) -> bool {
+        match block.encode_to_bytes() {
+            Ok(epoch_bytes) => self.epochs.remove(&(domain, epoch_bytes)).is_some(),
+            Err(_) => false,
+        }
+    }
+
+    /// Removes all the epochs
+    pub fn clear(&mut self) {
+        self.epochs.clear()
+    }
+
+    /// The number of hashed epochs
+    pub fn len(&self) -> usize {
+        self.epochs.len()
+    }
+
+    /// Whether no epochs are hashed
+    pub fn is_empty(&self) -> bool {
+        self.epochs.is_empty()
+    }
+
+    fn get_or_compute(
+        &mut self,
+        block: &EpochBlock,
+        domain: Domain,
+    ) -> Result<&EpochWitness, ProveError> {
+        let epoch_bytes = block.encode_to_bytes()?;
+        match self.epochs.entry((domain, epoch_bytes)) {
+            Entry::Occupied(entry) => Ok(entry.into_mut()),
+            Entry::Vacant(entry) => {
+                let epoch_bytes = &entry.key().1;
+                // We need to find the counter so that the CRH hash we use will eventually
+                // result on an element on the curve
+                let crh = SnarkCrh::CURRENT;
+                let (_, counter) = crh.hash_with_attempt(domain.as_bytes(), epoch_bytes, &[])?;
+                let crh_bytes = crh.crh(
+                    domain.as_bytes(),
+                    &[&[counter as u8][..], epoch_bytes].concat(),
+                )?;
+                Ok(entry.insert(EpochWitness {
+                    counter: counter as u8,
+                    crh_bits: bytes_to_bits(&crh_bytes, crh.output_bits()),
+                }))
+            }
+        }
+    }
+}
 
 /// Given the SNARK's Public Parameters, the initial epoch, and a list of state transitions,
 /// generates a SNARK which proves that the final epoch is correctly calculated from the first
 /// epoch. The proof can then be verified only with constant amount of data (the first and last
 /// epochs)
+///
+/// The number of transitions is the window covered by the proof, and it must be the same
+/// as the `num_epochs` the parameters were generated for. Proofs for a window different from
+/// the VK's are rejected by the verifier (see [`verify_window`](fn.verify_window.html)).
+///
+/// The circuit is instantiated for a fixed number of validators per epoch, so epochs
+/// (including the initial one) with fewer than `num_validators` public keys are
+/// [`padded`](struct.EpochBlock.html#method.padded) to it. Each transition's aggregate
+/// signature must therefore be over its padded block, while its bitmap has an entry for each
+/// validator of the previous, unpadded, epoch.
+///
+/// Fails with `ProveError::TooManyValidators` if `num_validators` is larger than
+/// `MAX_VALIDATORS`, with `ProveError::NotEnoughSigners` if a bitmap does not meet the
+/// quorum of the previous epoch, with `ProveError::CrhMismatch` if the parameters were
+/// generated for a different CRH than `SnarkCrh::CURRENT`, and with `ProveError::Encoding`
+/// if an epoch has more than `num_validators` public keys. The verifier pads the first and last epochs the same way if
+/// [`VerifyOptions::num_validators`](struct.VerifyOptions.html#structfield.num_validators) is
+/// set.
 pub fn prove(
     parameters: &Parameters<CPCurve, BLSCurve>,
     num_validators: u32,
     initial_epoch: &EpochBlock,
     transitions: &[EpochTransition],
-) -> Result<Groth16Proof<CPCurve>, SynthesisError> {
+) -> Result<Groth16Proof<CPCurve>, ProveError> {
+    prove_with_options(
+        parameters,
+        num_validators,
+        initial_epoch,
+        transitions,
+        ProveOptions::default(),
+    )
+}
+
+/// Same as [`prove`](fn.prove.html), but uses the tables precomputed in the context instead
+/// of generating them, so that proving multiple times amortizes their cost. The proof is the
+/// same as the one `prove` generates.
+pub fn prove_with_context(
+    context: &ProverContext,
+    parameters: &Parameters<CPCurve, BLSCurve>,
+    num_validators: u32,
+    initial_epoch: &EpochBlock,
+    transitions: &[EpochTransition],
+) -> Result<Groth16Proof<CPCurve>, ProveError> {
+    let options = ProveOptions {
+        context: Some(context),
+        ..Default::default()
+    };
+    prove_with_options(
+        parameters,
+        num_validators,
+        initial_epoch,
+        transitions,
+        options,
+    )
+}
+
+/// Same as [`prove`](fn.prove.html), but the epochs get hashed with the precomputed witness
+/// instead of from scratch. Epochs which are missing from the witness are hashed and added
+/// to it, so that proving overlapping windows with the same witness (e.g. while following
+/// the chain) hashes every epoch once. The proof is the same as the one `prove` generates.
+pub fn prove_with_witness(
+    parameters: &Parameters<CPCurve, BLSCurve>,
+    num_validators: u32,
+    initial_epoch: &EpochBlock,
+    transitions: &[EpochTransition],
+    witness: &mut Witness,
+) -> Result<Groth16Proof<CPCurve>, ProveError> {
+    let options = ProveOptions {
+        witness: Some(witness),
+        ..Default::default()
+    };
+    prove_with_options(
+        parameters,
+        num_validators,
+        initial_epoch,
+        transitions,
+        options,
+    )
+}
+
+/// Same as [`prove`](fn.prove.html), but the proof is randomized for zero knowledge with
+/// randomness derived from `rng_seed`, so that the same inputs and seed always yield the same
+/// proof, e.g. for differential testing.
+///
+/// `prove` does not randomize its proofs at all. The proofs of this function only hide the
+/// witness from verifiers who do not know the seed, so it must be kept secret (or sampled
+/// freshly) if that matters. Both proofs verify against the same keys.
+pub fn prove_deterministic(
+    parameters: &Parameters<CPCurve, BLSCurve>,
+    num_validators: u32,
+    initial_epoch: &EpochBlock,
+    transitions: &[EpochTransition],
+    rng_seed: [u8; 32],
+) -> Result<Groth16Proof<CPCurve>, ProveError> {
+    let options = ProveOptions {
+        seed: Some(rng_seed),
+        ..Default::default()
+    };
+    prove_with_options(
+        parameters,
+        num_validators,
+        initial_epoch,
+        transitions,
+        options,
+    )
+}
+
+/// Same as [`prove`](fn.prove.html), but returns the compressed serialization of the proof
+/// along with how long each proving phase took and the size of the circuit.
+///
+/// The Groth16 prover does not expose the time split between its FFTs and MSMs, so both are
+/// reported together in `ProveMetrics::proving` (building with the `print-trace` feature
+/// prints zexe's per step timings). The witness generation and the circuit's size are
+/// measured while the prover synthesizes the circuit, which happens once like in `prove`.
+pub fn prove_with_metrics(
+    parameters: &Parameters<CPCurve, BLSCurve>,
+    num_validators: u32,
+    initial_epoch: &EpochBlock,
+    transitions: &[EpochTransition],
+) -> Result<(Vec<u8>, ProveMetrics), ProveError> {
+    let mut metrics = ProveMetrics::default();
+    let options = ProveOptions {
+        metrics: Some(&mut metrics),
+        ..Default::default()
+    };
+    let proof = prove_with_options(
+        parameters,
+        num_validators,
+        initial_epoch,
+        transitions,
+        options,
+    )?;
+
+    Ok((serialize_proof(&proof)?, metrics))
+}
+
+/// Same as [`prove`](fn.prove.html), but runs the prover on tokio's blocking thread pool so
+/// that it does not block the runtime, and returns the compressed serialization of the proof.
+///
+/// The arguments are moved to the blocking thread, so the parameters are shared via an `Arc`
+/// to avoid copying them for each proof.
+#[cfg(feature = "tokio")]
+pub async fn prove_async(
+    parameters: Arc<Parameters<CPCurve, BLSCurve>>,
+    num_validators: u32,
+    initial_epoch: EpochBlock,
+    transitions: Vec<EpochTransition>,
+) -> Result<Vec<u8>, ProveError> {
+    tokio::task::spawn_blocking(move || -> Result<_, ProveError> {
+        let proof = prove(&parameters, num_validators, &initial_epoch, &transitions)?;
+        Ok(serialize_proof(&proof)?)
+    })
+    .await
+    .map_err(|e| SynthesisError::IoError(io::Error::new(io::ErrorKind::Other, e)))?
+}
+
+fn serialize_proof(proof: &Groth16Proof<CPCurve>) -> Result<Vec<u8>, SynthesisError> {
+    let mut serialized = vec![];
+    proof
+        .serialize(&mut serialized)
+        .map_err(|e| SynthesisError::IoError(io::Error::new(io::ErrorKind::Other, e)))?;
+    Ok(serialized)
+}
+
+/// Same as [`prove`](fn.prove.html), but runs the proof generation (and the multi-scalar
+/// multiplications inside it) on a dedicated thread pool of `num_threads` threads. If no
+/// thread count is provided, the global rayon thread pool is used like in `prove`, which has
+/// one thread per CPU unless configured otherwise.
+///
+/// The proof does not depend on the number of threads.
+pub fn prove_with_threads(
+    parameters: &Parameters<CPCurve, BLSCurve>,
+    num_validators: u32,
+    initial_epoch: &EpochBlock,
+    transitions: &[EpochTransition],
+    num_threads: Option<usize>,
+) -> Result<Groth16Proof<CPCurve>, ProveError> {
+    let options = ProveOptions {
+        num_threads,
+        ..Default::default()
+    };
+    prove_with_options(
+        parameters,
+        num_validators,
+        initial_epoch,
+        transitions,
+        options,
+    )
+}
+
+/// The optional inputs of [`prove_with_options`](fn.prove_with_options.html). Each of them
+/// is the one taken by the corresponding `prove_with_*` function, and the default options
+/// prove like [`prove`](fn.prove.html).
+#[derive(Default)]
+pub struct ProveOptions<'a> {
+    /// The precomputed tables to use, see [`ProverContext`](struct.ProverContext.html)
+    pub context: Option<&'a ProverContext>,
+    /// Filled in with the time spent in each phase and the size of the circuit
+    pub metrics: Option<&'a mut ProveMetrics>,
+    /// Randomizes the proof with randomness derived from the seed if provided, see
+    /// [`prove_deterministic`](fn.prove_deterministic.html). Proofs are not randomized
+    /// otherwise.
+    pub seed: Option<[u8; 32]>,
+    /// The epochs' hashes are taken from (and added to) the witness if provided
+    pub witness: Option<&'a mut Witness>,
+    /// Runs the whole proof generation on a dedicated thread pool of this many threads if
+    /// provided, and on the global rayon thread pool otherwise
+    pub num_threads: Option<usize>,
+}
+
+/// Same as [`prove`](fn.prove.html), with any combination of the options of the other
+/// `prove_with_*` functions, e.g. a context and a witness when following the chain.
+pub fn prove_with_options(
+    parameters: &Parameters<CPCurve, BLSCurve>,
+    num_validators: u32,
+    initial_epoch: &EpochBlock,
+    transitions: &[EpochTransition],
+    options: ProveOptions,
+) -> Result<Groth16Proof<CPCurve>, ProveError> {
+    let num_threads = match options.num_threads {
+        Some(num_threads) => num_threads,
+        None => {
+            return prove_on_current_pool(
+                parameters,
+                num_validators,
+                initial_epoch,
+                transitions,
+                options,
+            )
+        }
+    };
+    match ThreadPoolBuilder::new().num_threads(num_threads).build() {
+        Ok(pool) => pool.install(|| {
+            prove_on_current_pool(
+                parameters,
+                num_validators,
+                initial_epoch,
+                transitions,
+                options,
+            )
+        }),
+        Err(e) => {
+            warn!(
+                "could not build a thread pool with {} threads, using the global one: {}",
+                num_threads, e
+            );
+            prove_on_current_pool(
+                parameters,
+                num_validators,
+                initial_epoch,
+                transitions,
+                options,
+            )
+        }
+    }
+}
+
+/// Generates the proof on the thread pool of the caller, ignoring `options.num_threads`
+fn prove_on_current_pool(
+    parameters: &Parameters<CPCurve, BLSCurve>,
+    num_validators: u32,
+    initial_epoch: &EpochBlock,
+    transitions: &[EpochTransition],
+    options: ProveOptions,
+) -> Result<Groth16Proof<CPCurve>, ProveError> {
+    let ProveOptions {
+        context,
+        mut metrics,
+        seed,
+        witness,
+        ..
+    } = options;
     info!(
         "Generating proof for {} epochs (first epoch: {}, {} validators per epoch)",
         transitions.len(),
@@ -35,69 +464,230 @@ pub fn prove(
     let span = span!(Level::TRACE, "prove");
     let _enter = span.enter();
 
+    if parameters.config.crh != SnarkCrh::CURRENT {
+        return Err(ProveError::CrhMismatch {
+            expected: SnarkCrh::CURRENT,
+            got: parameters.config.crh,
+        });
+    }
+
+    let expected_len = num_validators as usize;
+    if expected_len > MAX_VALIDATORS {
+        return Err(ProveError::TooManyValidators {
+            got: expected_len,
+            max: MAX_VALIDATORS,
+        });
+    }
+
+    // each epoch's validators must have signed the next one
+    let mut previous = initial_epoch;
+    for transition in transitions {
+        previous
+            .check_quorum(&transition.bitmap)
+            .map_err(ProveError::NotEnoughSigners)?;
+        previous = &transition.block;
+    }
+
+    // pad the epochs to the circuit's number of validators, which keeps their quorums
+    let initial_epoch = &initial_epoch.padded(expected_len)?;
+    let transitions = transitions
+        .iter()
+        .map(|transition| transition.padded(expected_len))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // Hash the initial epoch and every transition's epoch, or reuse their hashes
+    let time = Instant::now();
+    let mut fresh_witness = Witness::new();
+    let witness = witness.unwrap_or(&mut fresh_witness);
+    let hash_witnesses = std::iter::once(initial_epoch)
+        .chain(transitions.iter().map(|transition| &transition.block))
+        .map(|block| witness.get_or_compute(block, parameters.domain).cloned())
+        .collect::<Result<Vec<_>, _>>()?;
+
     let epochs = transitions
         .iter()
-        .map(|transition| to_update(transition))
+        .zip(&hash_witnesses[1..])
+        .map(|(transition, hash_witness)| to_update(transition, hash_witness.counter))
         .collect::<Vec<_>>();
 
     // Generate a helping proof if a Proving Key for the HashToBits
     // circuit was provided
     let hash_helper = if let Some(ref params) = parameters.hash_to_bits {
-        Some(generate_hash_helper(&params, transitions)?)
+        Some(generate_hash_helper(
+            &params,
+            &hash_witnesses[1..],
+            parameters.domain,
+        )?)
     } else {
         None
     };
+    if let Some(metrics) = metrics.as_mut() {
+        metrics.hash_to_bits = time.elapsed();
+    }
 
     // Generate the BLS proof
     let asig = Signature::aggregate(transitions.iter().map(|epoch| &epoch.aggregate_signature));
 
     let circuit = ValidatorSetUpdate::<BLSCurve> {
-        initial_epoch: to_epoch_data(initial_epoch),
+        initial_epoch: to_epoch_data(initial_epoch, hash_witnesses[0].counter),
         epochs,
         aggregated_signature: Some(*asig.as_ref()),
         num_validators,
         hash_helper,
+        domain: parameters.domain,
+        crh_parameters: context.map(|context| context.crh_parameters.clone()),
+        config: parameters.config,
     };
+
     info!("BLS");
-    let bls_proof = create_proof_no_zk(circuit, &parameters.epochs)?;
+    let time = Instant::now();
+    let bls_proof = match metrics {
+        Some(metrics) => {
+            let measured = Measured {
+                circuit,
+                metrics: &mut *metrics,
+            };
+            let proof = create_epochs_proof(measured, &parameters.epochs, seed)?;
+            metrics.proving = time
+                .elapsed()
+                .checked_sub(metrics.witness_generation)
+                .unwrap_or_default();
+            proof
+        }
+        None => create_epochs_proof(circuit, &parameters.epochs, seed)?,
+    };
 
     Ok(bls_proof)
 }
 
+/// Generates the Groth16 proof of the epochs circuit, randomized with randomness derived from
+/// `seed` if provided and not randomized otherwise
+fn create_epochs_proof<C: ConstraintSynthesizer<CPField>>(
+    circuit: C,
+    params: &Groth16Parameters<CPCurve>,
+    seed: Option<[u8; 32]>,
+) -> Result<Groth16Proof<CPCurve>, SynthesisError> {
+    match seed {
+        Some(seed) => {
+            let rng = &mut ChaCha20Rng::from_seed(seed);
+            let (r, s) = (CPField::rand(rng), CPField::rand(rng));
+            create_proof(circuit, params, r, s)
+        }
+        None => create_proof_no_zk(circuit, params),
+    }
+}
+
+/// Wraps a circuit to measure its synthesis while the Groth16 prover synthesizes it, so that
+/// the metrics do not require synthesizing the circuit a second time
+struct Measured<'a, C> {
+    circuit: C,
+    metrics: &'a mut ProveMetrics,
+}
+
+impl<'a, F: Field, C: ConstraintSynthesizer<F>> ConstraintSynthesizer<F> for Measured<'a, C> {
+    fn generate_constraints<CS: ConstraintSystem<F>>(
+        self,
+        cs: &mut CS,
+    ) -> Result<(), SynthesisError> {
+        let time = Instant::now();
+        let mut counter = Counter {
+            cs,
+            num_inputs: 0,
+            num_aux: 0,
+            num_constraints: 0,
+        };
+        self.circuit.generate_constraints(&mut counter)?;
+        self.metrics.witness_generation = time.elapsed();
+        self.metrics.num_constraints = counter.num_constraints;
+        self.metrics.num_variables = counter.num_inputs + counter.num_aux;
+        Ok(())
+    }
+}
+
+/// Constraint system which forwards everything to the wrapped one, counting the variables
+/// and constraints which are added through it
+struct Counter<'a, CS> {
+    cs: &'a mut CS,
+    num_inputs: usize,
+    num_aux: usize,
+    num_constraints: usize,
+}
+
+impl<'a, F: Field, CS: ConstraintSystem<F>> ConstraintSystem<F> for Counter<'a, CS> {
+    type Root = Self;
+
+    fn alloc<FN, A, AR>(&mut self, annotation: A, f: FN) -> Result<Variable, SynthesisError>
+    where
+        FN: FnOnce() -> Result<F, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.num_aux += 1;
+        self.cs.alloc(annotation, f)
+    }
+
+    fn alloc_input<FN, A, AR>(&mut self, annotation: A, f: FN) -> Result<Variable, SynthesisError>
+    where
+        FN: FnOnce() -> Result<F, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.num_inputs += 1;
+        self.cs.alloc_input(annotation, f)
+    }
+
+    fn enforce<A, AR, LA, LB, LC>(&mut self, annotation: A, a: LA, b: LB, c: LC)
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+        LA: FnOnce(LinearCombination<F>) -> LinearCombination<F>,
+        LB: FnOnce(LinearCombination<F>) -> LinearCombination<F>,
+        LC: FnOnce(LinearCombination<F>) -> LinearCombination<F>,
+    {
+        self.num_constraints += 1;
+        self.cs.enforce(annotation, a, b, c)
+    }
+
+    fn push_namespace<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.cs.push_namespace(name_fn)
+    }
+
+    fn pop_namespace(&mut self) {
+        self.cs.pop_namespace()
+    }
+
+    fn get_root(&mut self) -> &mut Self::Root {
+        self
+    }
+
+    fn num_constraints(&self) -> usize {
+        self.cs.num_constraints()
+    }
+}
+
 /// Helper which creates the hashproof inside BLS12-377
 fn generate_hash_helper(
     params: &Groth16Parameters<BLSCurve>,
-    transitions: &[EpochTransition],
+    hash_witnesses: &[EpochWitness],
+    domain: Domain,
 ) -> Result<HashToBitsHelper<BLSCurve>, SynthesisError> {
-    let hash_to_g1 = &COMPOSITE_HASH_TO_G1;
-    let composite_hasher = &COMPOSITE_HASHER;
-
-    // Generate the CRH per epoch
-    let message_bits = transitions
+    // The CRH per epoch. The verifier should run both the crh and the xof here to
+    // generate a valid statement for the verify
+    let message_bits = hash_witnesses
         .iter()
-        .map(|transition| {
-            let block = &transition.block;
-            let epoch_bytes = block.encode_to_bytes().unwrap();
-
-            // We need to find the counter so that the CRH hash we use will eventually result on an element on the curve
-            let (_, counter) = hash_to_g1
-                .hash_with_attempt(SIG_DOMAIN, &epoch_bytes, &[])
-                .unwrap();
-            let crh_bytes = composite_hasher
-                .crh(&[], &[&[counter as u8][..], &epoch_bytes].concat(), 0)
-                .unwrap();
-            // The verifier should run both the crh and the xof here to generate a
-            // valid statement for the verify
-            bytes_to_bits(&crh_bytes, 384)
-                .iter()
-                .map(|b| Some(*b))
-                .collect()
-        })
+        .map(|hash_witness| hash_witness.crh_bits.iter().map(|b| Some(*b)).collect())
         .collect::<Vec<_>>();
 
     // Generate proof of correct calculation of the CRH->Blake hashes
     // to make Hash to G1 cheaper
-    let circuit = HashToBits { message_bits };
+    let circuit = HashToBits {
+        message_bits,
+        domain,
+    };
     info!("CRH->XOF");
     let hash_proof = create_proof_no_zk(circuit, params)?;
 
@@ -107,7 +697,7 @@ fn generate_hash_helper(
     })
 }
 
-fn to_epoch_data(block: &EpochBlock) -> EpochData<BLSCurve> {
+fn to_epoch_data(block: &EpochBlock, hash_counter: u8) -> EpochData<BLSCurve> {
     EpochData {
         index: Some(block.index),
         maximum_non_signers: block.maximum_non_signers,
@@ -116,12 +706,13 @@ fn to_epoch_data(block: &EpochBlock) -> EpochData<BLSCurve> {
             .iter()
             .map(|pubkey| Some(*pubkey.as_ref()))
             .collect(),
+        hash_counter: Some(hash_counter),
     }
 }
 
-fn to_update(transition: &EpochTransition) -> SingleUpdate<BLSCurve> {
+fn to_update(transition: &EpochTransition, hash_counter: u8) -> SingleUpdate<BLSCurve> {
     SingleUpdate {
-        epoch_data: to_epoch_data(&transition.block),
+        epoch_data: to_epoch_data(&transition.block, hash_counter),
         signed_bitmap: transition
             .bitmap
             .iter()
@@ -129,3 +720,94 @@ fn to_update(transition: &EpochTransition) -> SingleUpdate<BLSCurve> {
             .collect::<Vec<_>>(),
     }
 }
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+    use crate::{api::CircuitConfig, testing::random_epoch_block};
+    use algebra::{bls12_377::G1Projective, AffineCurve, PairingEngine};
+    use groth16::VerifyingKey;
+
+    /// Parameters without any keys, which are enough to reach the checks done before the
+    /// epochs get hashed
+    pub(crate) fn empty_parameters() -> Parameters<CPCurve, BLSCurve> {
+        let g1 = <CPCurve as PairingEngine>::G1Affine::prime_subgroup_generator();
+        let g2 = <CPCurve as PairingEngine>::G2Affine::prime_subgroup_generator();
+        Parameters {
+            epochs: Groth16Parameters {
+                vk: VerifyingKey {
+                    alpha_g1: g1,
+                    beta_g2: g2,
+                    gamma_g2: g2,
+                    delta_g2: g2,
+                    gamma_abc_g1: vec![],
+                },
+                alpha_g1: g1,
+                beta_g1: g1,
+                beta_g2: g2,
+                delta_g1: g1,
+                delta_g2: g2,
+                a_query: vec![],
+                b_g1_query: vec![],
+                b_g2_query: vec![],
+                h_query: vec![],
+                l_query: vec![],
+            },
+            hash_to_bits: None,
+            domain: Domain::default(),
+            config: CircuitConfig::default(),
+        }
+    }
+
+    fn transition(index: u16, bitmap: Vec<bool>) -> EpochTransition {
+        let rng = &mut rand::thread_rng();
+        EpochTransition {
+            block: random_epoch_block(rng, bitmap.len(), index, 1).0,
+            aggregate_signature: Signature::from(G1Projective::rand(rng)),
+            bitmap,
+        }
+    }
+
+    #[test]
+    fn rejects_too_many_validators() {
+        let parameters = empty_parameters();
+        let (epoch, _) = random_epoch_block(&mut rand::thread_rng(), 4, 0, 1);
+        let num_validators = MAX_VALIDATORS as u32 + 1;
+        let err = crate::prove(&parameters, num_validators, &epoch, &[]).unwrap_err();
+        assert!(matches!(
+            err,
+            ProveError::TooManyValidators { got, max: MAX_VALIDATORS } if got == MAX_VALIDATORS + 1
+        ));
+    }
+
+    #[test]
+    fn rejects_invalid_epochs() {
+        let mut parameters = empty_parameters();
+        let (epoch, _) = random_epoch_block(&mut rand::thread_rng(), 4, 0, 1);
+
+        // only 2 of the 3 required signers
+        let transitions = [transition(1, vec![true, false, false, true])];
+        let err = prove(&parameters, 4, &epoch, &transitions).unwrap_err();
+        assert!(matches!(
+            err,
+            ProveError::NotEnoughSigners(EpochBlockError::NotEnoughSigners {
+                signers: 2,
+                required: 3
+            })
+        ));
+
+        // the epoch does not fit in the circuit
+        let err = prove(&parameters, 3, &epoch, &[]).unwrap_err();
+        assert!(matches!(
+            err,
+            ProveError::Encoding(EncodingError::TooManyValidators { got: 4, max: 3 })
+        ));
+
+        parameters.config.crh = match SnarkCrh::CURRENT {
+            SnarkCrh::BoweHopwood => SnarkCrh::Blake2s,
+            SnarkCrh::Blake2s => SnarkCrh::BoweHopwood,
+        };
+        let err = prove(&parameters, 4, &epoch, &[]).unwrap_err();
+        assert!(matches!(err, ProveError::CrhMismatch { .. }));
+    }
+}
@@ -1,13 +1,17 @@
 /// Prover Verifier Generator
 ///
 /// Setup: Trusted setup over Groth16 for the Hash To Bits and the Epoch Transition circuits
+use crate::encoding::{deserialize_compressed, EncodingError, Strictness};
 use crate::gadgets::{HashToBits, ValidatorSetUpdate};
 
-use algebra::PairingEngine;
+use algebra::{CanonicalSerialize, PairingEngine, SerializationError};
+use bls_crypto::{hash_to_curve::try_and_increment::SnarkCrh, Domain};
 use r1cs_core::SynthesisError;
-use rand::Rng;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use thiserror::Error;
 
-use super::{BLSCurve, CPCurve, CPFrParams};
+use super::{BLSCurve, CPCurve, CircuitConfig};
 
 use groth16::{generate_random_parameters, Parameters as Groth16Parameters};
 use tracing::{info, span, Level};
@@ -18,6 +22,194 @@ type Result<T> = std::result::Result<T, SynthesisError>;
 pub struct Parameters<CP: PairingEngine, BLS: PairingEngine> {
     pub epochs: Groth16Parameters<CP>,
     pub hash_to_bits: Option<Groth16Parameters<BLS>>,
+    /// The domain in which the validators sign the epochs. The circuits are specialized to
+    /// it, so proofs can only be generated for epochs signed in this domain.
+    pub domain: Domain,
+    /// The configuration of the epochs circuit, including the CRH which the circuits hash the
+    /// epochs to G1 with. Proofs must be verified with the same configuration, e.g. with
+    /// [`verify_with_config`](fn.verify_with_config.html).
+    pub config: CircuitConfig,
+}
+
+#[derive(Debug, Error)]
+/// Error raised while deserializing a [`ProvingKey`](struct.ProvingKey.html)
+pub enum ProvingKeyError {
+    #[error("encoding error: {0}")]
+    Encoding(#[from] EncodingError),
+    #[error("the proving key has {got} {query} query elements, expected {expected}")]
+    QueryLength {
+        query: &'static str,
+        expected: usize,
+        got: usize,
+    },
+    #[error("the proving key's H query has {0} elements, the domain size must be a power of 2")]
+    DomainSize(usize),
+    #[error("the proving key's {0} does not match its verifying key")]
+    VkMismatch(&'static str),
+    #[error("the proving key has {got} public inputs, its configuration has {expected}")]
+    InputCount { expected: usize, got: usize },
+    #[error("the proving key is for the {got:?} CRH, but this build hashes with {expected:?}")]
+    CrhMismatch { expected: SnarkCrh, got: SnarkCrh },
+}
+
+/// The Groth16 proving key of the epochs circuit, i.e. the `epochs` parameters of a setup,
+/// along with the configuration of the circuit and the domain which they were generated for,
+/// which can be loaded and checked without generating a proof
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProvingKey {
+    parameters: Groth16Parameters<CPCurve>,
+    config: CircuitConfig,
+    domain: Domain,
+}
+
+impl ProvingKey {
+    /// The length of the header which precedes the Groth16 parameters, i.e. the serialized
+    /// configuration followed by the 8 bytes of the domain
+    pub const HEADER_SIZE: usize = CircuitConfig::SERIALIZED_SIZE + 8;
+
+    /// Wraps the `epochs` parameters of a setup for a circuit with the provided configuration,
+    /// whose epochs are signed in the provided domain
+    pub fn new(
+        parameters: Groth16Parameters<CPCurve>,
+        config: CircuitConfig,
+        domain: Domain,
+    ) -> Self {
+        Self {
+            parameters,
+            config,
+            domain,
+        }
+    }
+
+    /// Deserializes a proving key, i.e. the configuration of its circuit and its domain
+    /// followed by the compressed Groth16 parameters, rejecting non-canonical encodings, and
+    /// checks that its elements are consistent with each other and with the configuration.
+    ///
+    /// Keys for another CRH than `SnarkCrh::CURRENT` are rejected with
+    /// `ProvingKeyError::CrhMismatch`, since this build cannot generate proofs with them.
+    ///
+    /// Only the lengths of the queries and the elements shared with the verifying key are
+    /// checked, a key for another circuit with the same number of public inputs is not
+    /// detected.
+    pub fn from_bytes(bytes: &[u8]) -> std::result::Result<Self, ProvingKeyError> {
+        if bytes.len() < Self::HEADER_SIZE {
+            let eof = std::io::Error::from(std::io::ErrorKind::UnexpectedEof);
+            return Err(EncodingError::from(eof).into());
+        }
+        let (header, bytes) = bytes.split_at(Self::HEADER_SIZE);
+        let (config_bytes, domain) = header.split_at(CircuitConfig::SERIALIZED_SIZE);
+        let mut config = [0u8; CircuitConfig::SERIALIZED_SIZE];
+        config.copy_from_slice(config_bytes);
+        let config = CircuitConfig::from_bytes(&config)?;
+        let domain = Domain::new(domain).expect("the domain has 8 bytes");
+        if config.crh != SnarkCrh::CURRENT {
+            return Err(ProvingKeyError::CrhMismatch {
+                expected: SnarkCrh::CURRENT,
+                got: config.crh,
+            });
+        }
+        let parameters: Groth16Parameters<CPCurve> =
+            deserialize_compressed(bytes, Strictness::Strict)?;
+        check_proving_key(&parameters, &config)?;
+        Ok(Self {
+            parameters,
+            config,
+            domain,
+        })
+    }
+
+    /// Serializes the configuration and the domain followed by the proving key with
+    /// compressed elements
+    pub fn to_bytes(&self) -> std::result::Result<Vec<u8>, SerializationError> {
+        let mut bytes = Vec::with_capacity(Self::HEADER_SIZE + self.parameters.serialized_size());
+        bytes.extend_from_slice(&self.config.to_bytes());
+        bytes.extend_from_slice(&self.domain.personalization());
+        self.parameters.serialize(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// The wrapped Groth16 parameters
+    pub fn parameters(&self) -> &Groth16Parameters<CPCurve> {
+        &self.parameters
+    }
+
+    /// The configuration of the circuit which the key was generated for, which proofs
+    /// generated with it must be verified with
+    pub fn config(&self) -> CircuitConfig {
+        self.config
+    }
+
+    /// The domain in which the epochs proven with the key are signed
+    pub fn domain(&self) -> Domain {
+        self.domain
+    }
+
+    /// Unwraps the Groth16 parameters, e.g. to use them as the `epochs` parameters along
+    /// with the configuration and the domain
+    pub fn into_inner(self) -> (Groth16Parameters<CPCurve>, CircuitConfig, Domain) {
+        (self.parameters, self.config, self.domain)
+    }
+}
+
+impl Parameters<CPCurve, BLSCurve> {
+    /// The proving key of the epochs circuit, e.g. for serializing it along with its
+    /// configuration and domain
+    pub fn proving_key(&self) -> ProvingKey {
+        ProvingKey::new(self.epochs.clone(), self.config, self.domain)
+    }
+}
+
+/// Checks that the queries have an element for each variable, that the H query is over an
+/// evaluation domain, whose size is a power of 2, and that the VK has an element per public
+/// input of the configuration
+fn check_proving_key(
+    parameters: &Groth16Parameters<CPCurve>,
+    config: &CircuitConfig,
+) -> std::result::Result<(), ProvingKeyError> {
+    let expected = config.num_public_inputs() + 1;
+    if parameters.vk.gamma_abc_g1.len() != expected {
+        return Err(ProvingKeyError::InputCount {
+            expected: expected - 1,
+            got: parameters.vk.gamma_abc_g1.len().saturating_sub(1),
+        });
+    }
+    let num_variables = parameters.a_query.len();
+    let queries = [
+        ("B in G1", parameters.b_g1_query.len()),
+        ("B in G2", parameters.b_g2_query.len()),
+    ];
+    for &(query, len) in queries.iter() {
+        if len != num_variables {
+            return Err(ProvingKeyError::QueryLength {
+                query,
+                expected: num_variables,
+                got: len,
+            });
+        }
+    }
+    // the VK has an element for each input, the L query one for each witness variable
+    let num_inputs = parameters.vk.gamma_abc_g1.len();
+    if num_inputs + parameters.l_query.len() != num_variables {
+        return Err(ProvingKeyError::QueryLength {
+            query: "L",
+            expected: num_variables.saturating_sub(num_inputs),
+            got: parameters.l_query.len(),
+        });
+    }
+    // there is an element for each power of the domain's generator but the last one
+    if !(parameters.h_query.len() + 1).is_power_of_two() {
+        return Err(ProvingKeyError::DomainSize(parameters.h_query.len()));
+    }
+    if parameters.vk.alpha_g1 != parameters.alpha_g1 {
+        return Err(ProvingKeyError::VkMismatch("alpha"));
+    }
+    if parameters.vk.beta_g2 != parameters.beta_g2 {
+        return Err(ProvingKeyError::VkMismatch("beta"));
+    }
+    if parameters.vk.delta_g2 != parameters.delta_g2 {
+        return Err(ProvingKeyError::VkMismatch("delta"));
+    }
+    Ok(())
 }
 
 /// Initializes the Hash To Bits and Validator Set Update circuits with random parameters
@@ -33,6 +225,54 @@ pub fn trusted_setup<R: Rng>(
     maximum_non_signers: usize,
     rng: &mut R,
     hashes_in_bls12_377: bool,
+) -> Result<Parameters<CPCurve, BLSCurve>> {
+    trusted_setup_with_domain(
+        num_validators,
+        num_epochs,
+        maximum_non_signers,
+        rng,
+        hashes_in_bls12_377,
+        Domain::default(),
+    )
+}
+
+/// Same as [`trusted_setup`](fn.trusted_setup.html), but for epochs signed in the provided
+/// domain instead of the Celo network's `SIG_DOMAIN`, e.g. on a test network.
+pub fn trusted_setup_with_domain<R: Rng>(
+    num_validators: usize,
+    num_epochs: usize,
+    maximum_non_signers: usize,
+    rng: &mut R,
+    hashes_in_bls12_377: bool,
+    domain: Domain,
+) -> Result<Parameters<CPCurve, BLSCurve>> {
+    trusted_setup_with_config(
+        num_validators,
+        num_epochs,
+        maximum_non_signers,
+        rng,
+        hashes_in_bls12_377,
+        domain,
+        CircuitConfig::default(),
+    )
+}
+
+/// Same as [`trusted_setup_with_domain`](fn.trusted_setup_with_domain.html), but for a circuit
+/// with the provided configuration instead of the default one, e.g. a non-default packing
+/// width or exposed fields of the last epoch.
+///
+/// The returned parameters record the configuration, so that the prover uses it as well, but
+/// proofs can only be verified by passing the same configuration to
+/// [`verify_with_config`](fn.verify_with_config.html). The circuits are always generated for
+/// `SnarkCrh::CURRENT`, so the configuration's CRH is replaced with it.
+pub fn trusted_setup_with_config<R: Rng>(
+    num_validators: usize,
+    num_epochs: usize,
+    maximum_non_signers: usize,
+    rng: &mut R,
+    hashes_in_bls12_377: bool,
+    domain: Domain,
+    config: CircuitConfig,
 ) -> Result<Parameters<CPCurve, BLSCurve>> {
     setup(
         num_validators,
@@ -42,17 +282,172 @@ pub fn trusted_setup<R: Rng>(
         |c, rng| generate_random_parameters(c, rng),
         |c, rng| generate_random_parameters(c, rng),
         hashes_in_bls12_377,
+        domain,
+        config,
+    )
+}
+
+/// Same as [`trusted_setup`](fn.trusted_setup.html), but the parameters are generated
+/// deterministically from the provided seed, so that the same seed always yields the same keys.
+///
+/// **This is only meant for generating reproducible test fixtures.** Anyone who knows the seed
+/// can recompute the setup's toxic waste and forge proofs, so these parameters must never be
+/// used in place of a real ceremony.
+pub fn setup_from_seed(
+    seed: [u8; 32],
+    num_validators: usize,
+    num_epochs: usize,
+    maximum_non_signers: usize,
+    hashes_in_bls12_377: bool,
+) -> Result<Parameters<CPCurve, BLSCurve>> {
+    let rng = &mut ChaCha20Rng::from_seed(seed);
+    trusted_setup(
+        num_validators,
+        num_epochs,
+        maximum_non_signers,
+        rng,
+        hashes_in_bls12_377,
     )
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use algebra::CanonicalSerialize;
+
     #[test]
     fn runs_setup() {
         let rng = &mut rand::thread_rng();
         assert!(trusted_setup(3, 2, 1, rng, false).is_ok())
     }
+
+    #[test]
+    fn proving_key_roundtrip() {
+        let params = setup_from_seed([3; 32], 3, 2, 1, false).unwrap();
+        let pk = params.proving_key();
+        let bytes = pk.to_bytes().unwrap();
+        assert_eq!(
+            &bytes[..CircuitConfig::SERIALIZED_SIZE],
+            &params.config.to_bytes()
+        );
+        assert_eq!(
+            &bytes[CircuitConfig::SERIALIZED_SIZE..ProvingKey::HEADER_SIZE],
+            Domain::default().as_bytes()
+        );
+        let decoded = ProvingKey::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded, pk);
+        assert_eq!(decoded.to_bytes().unwrap(), bytes);
+
+        // the domain is kept
+        let testnet = Domain::new(b"testnet").unwrap();
+        let (parameters, config, _) = pk.clone().into_inner();
+        let bytes = ProvingKey::new(parameters, config, testnet)
+            .to_bytes()
+            .unwrap();
+        assert_eq!(ProvingKey::from_bytes(&bytes).unwrap().domain(), testnet);
+        let bytes = pk.to_bytes().unwrap();
+
+        // corrupted encodings
+        assert!(matches!(
+            ProvingKey::from_bytes(&bytes[..bytes.len() - 1]).unwrap_err(),
+            ProvingKeyError::Encoding(_)
+        ));
+        let mut padded = bytes.clone();
+        padded.push(0);
+        assert!(matches!(
+            ProvingKey::from_bytes(&padded).unwrap_err(),
+            ProvingKeyError::Encoding(EncodingError::NonCanonical)
+        ));
+
+        // inconsistent keys
+        let inconsistent = |f: fn(&mut Groth16Parameters<CPCurve>)| {
+            let (mut parameters, config, domain) = pk.clone().into_inner();
+            f(&mut parameters);
+            let bytes = ProvingKey::new(parameters, config, domain)
+                .to_bytes()
+                .unwrap();
+            ProvingKey::from_bytes(&bytes).unwrap_err()
+        };
+        assert!(matches!(
+            inconsistent(|p| {
+                p.l_query.pop();
+            }),
+            ProvingKeyError::QueryLength { query: "L", .. }
+        ));
+        assert!(matches!(
+            inconsistent(|p| {
+                p.b_g2_query.pop();
+            }),
+            ProvingKeyError::QueryLength {
+                query: "B in G2",
+                ..
+            }
+        ));
+        assert!(matches!(
+            inconsistent(|p| {
+                p.h_query.pop();
+            }),
+            ProvingKeyError::DomainSize(_)
+        ));
+        assert!(matches!(
+            inconsistent(|p| p.vk.delta_g2 = p.beta_g2),
+            ProvingKeyError::VkMismatch("delta")
+        ));
+
+        // a configuration with another number of public inputs
+        let exposed = CircuitConfig {
+            public_fields: crate::encoding::PublicFields {
+                maximum_non_signers: true,
+            },
+            ..Default::default()
+        };
+        let bytes = ProvingKey::new(pk.parameters().clone(), exposed, pk.domain())
+            .to_bytes()
+            .unwrap();
+        assert!(matches!(
+            ProvingKey::from_bytes(&bytes).unwrap_err(),
+            ProvingKeyError::InputCount {
+                expected: 3,
+                got: 2
+            }
+        ));
+
+        // a key for the other CRH
+        let other_crh = CircuitConfig {
+            crh: match SnarkCrh::CURRENT {
+                SnarkCrh::BoweHopwood => SnarkCrh::Blake2s,
+                SnarkCrh::Blake2s => SnarkCrh::BoweHopwood,
+            },
+            ..Default::default()
+        };
+        let bytes = ProvingKey::new(pk.parameters().clone(), other_crh, pk.domain())
+            .to_bytes()
+            .unwrap();
+        assert!(matches!(
+            ProvingKey::from_bytes(&bytes).unwrap_err(),
+            ProvingKeyError::CrhMismatch { .. }
+        ));
+        assert!(matches!(
+            ProvingKey::from_bytes(&bytes[..4]).unwrap_err(),
+            ProvingKeyError::Encoding(EncodingError::IoError(_))
+        ));
+    }
+
+    #[test]
+    fn seeded_setup_is_deterministic() {
+        let params1 = setup_from_seed([1; 32], 3, 2, 1, false).unwrap();
+        let params2 = setup_from_seed([1; 32], 3, 2, 1, false).unwrap();
+        assert_eq!(params1.epochs.vk, params2.epochs.vk);
+        let serialize = |params: &Parameters<CPCurve, BLSCurve>| {
+            let mut bytes = vec![];
+            params.epochs.serialize(&mut bytes).unwrap();
+            bytes
+        };
+        assert_eq!(serialize(&params1), serialize(&params2));
+
+        let params3 = setup_from_seed([2; 32], 3, 2, 1, false).unwrap();
+        assert_ne!(params1.epochs.vk, params3.epochs.vk);
+    }
 }
 
 /// Performs a Groth16 setup over the 2 provided Pairing-friendly curves for the Hash to Bits and Validator set update circuits
@@ -60,6 +455,7 @@ mod tests {
 /// parameters which were computed via an [MPC](https://eprint.iacr.org/2017/1050)
 ///
 /// If you do not know what this means, use the `trusted_setup` function
+#[allow(clippy::too_many_arguments)]
 fn setup<CP, BLS, F, G, R>(
     num_validators: usize,
     num_epochs: usize,
@@ -68,6 +464,8 @@ fn setup<CP, BLS, F, G, R>(
     hash_to_bits_setup: F,
     validator_setup_fn: G,
     hashes_in_bls12_377: bool,
+    domain: Domain,
+    config: CircuitConfig,
 ) -> Result<Parameters<CP, BLS>>
 where
     CP: PairingEngine,
@@ -86,7 +484,7 @@ where
 
     let (vk, hash_to_bits) = if hashes_in_bls12_377 {
         info!("CRH->XOF");
-        let empty_hash_to_bits = HashToBits::empty::<CPFrParams>(num_epochs);
+        let empty_hash_to_bits = HashToBits::empty(num_epochs, domain);
         let hash_to_bits = hash_to_bits_setup(empty_hash_to_bits, rng)?;
         (Some(hash_to_bits.vk.clone()), Some(hash_to_bits))
     } else {
@@ -95,11 +493,17 @@ where
 
     info!("BLS");
     let empty_epochs =
-        ValidatorSetUpdate::empty(num_validators, num_epochs, maximum_non_signers, vk);
+        ValidatorSetUpdate::empty(num_validators, num_epochs, maximum_non_signers, vk, domain)
+            .with_config(config);
     let epochs = validator_setup_fn(empty_epochs, rng)?;
 
     Ok(Parameters {
         epochs,
         hash_to_bits,
+        domain,
+        config: CircuitConfig {
+            crh: SnarkCrh::CURRENT,
+            ..config
+        },
     })
 }
@@ -1,40 +1,1942 @@
-use super::{CPCurve, CPField, CPFrParams};
-use crate::encoding::EncodingError;
-use crate::epoch_block::{hash_first_last_epoch_block, EpochBlock};
-use crate::gadgets::pack;
-use groth16::{prepare_verifying_key, verify_proof, Proof, VerifyingKey};
+use super::{CPCurve, CPField, CircuitConfig};
+use crate::encoding::{
+    decode_proof_envelope, deserialize_any, deserialize_compressed, pack_with_width, EncodingError,
+    Strictness, PROOF_ENVELOPE_CURVE_SW6,
+};
+use crate::epoch_block::{hash_first_last_epoch_block, EpochBlock, EpochBlockError};
+use crate::Vec;
+use algebra::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+use bls_gadgets::utils::bytes_to_bits;
+use core::{convert::TryFrom, fmt};
+use groth16::{prepare_verifying_key, verify_proof, PreparedVerifyingKey, Proof, VerifyingKey};
 use r1cs_core::SynthesisError;
-use thiserror::Error;
-use tracing::info;
+use tracing::{info, warn};
 
-#[derive(Debug, Error)]
+#[derive(Debug)]
 /// Error raised while verifying the SNARK proof
+///
+/// Only `VerificationFailed` means that the proof was checked and rejected, all other
+/// variants mean that the proof could not be checked against the provided data.
 pub enum VerificationError {
-    #[error("Verification failed")]
+    /// The pairing check failed, i.e. the proof is invalid for the provided epochs
     VerificationFailed,
-    #[error("Synthesis Error: {0}")]
-    ZexeSynthesisError(#[from] SynthesisError),
-    #[error("Encoding Error: {0}")]
-    EpochEncodingError(#[from] EncodingError),
+    /// The VK expects a different number of public inputs than the ones
+    /// derived from the epochs
+    PublicInputMismatch {
+        expected: usize,
+        got: usize,
+    },
+    /// The last epoch's index is not greater than the first epoch's
+    EpochOrderingError {
+        first: u16,
+        last: u16,
+    },
+    /// The epochs are not as far apart as the number of epochs the VK was generated for
+    EpochCountMismatch {
+        expected: usize,
+        got: usize,
+    },
+    /// The proofs passed to `link_proofs` do not share their boundary epoch, i.e. the
+    /// first one ends at epoch `end` and the second one starts at epoch `start`
+    EpochBoundaryMismatch {
+        end: u16,
+        start: u16,
+    },
+    /// The epochs are `got` epochs apart, more than the `max` allowed by the caller
+    EpochGapTooLarge {
+        max: usize,
+        got: usize,
+    },
+    /// The last epoch's aggregated public key commitment is not the one expected by the caller
+    ApkCommitmentMismatch,
+    /// The bitmap of the last epoch's signers does not meet the epoch's quorum
+    InvalidSigners(EpochBlockError),
+    /// The batch contained invalid proofs, at the provided indices
+    BatchVerificationFailed(Vec<usize>),
+    /// The VK or the proof could not be deserialized
+    DeserializationError(SerializationError),
+    ZexeSynthesisError(SynthesisError),
+    EpochEncodingError(EncodingError),
+    /// The circuit version is not one of the `CircuitVersion`s
+    UnsupportedCircuitVersion(u32),
+}
+
+impl fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerificationError::VerificationFailed => write!(f, "Verification failed"),
+            VerificationError::PublicInputMismatch { expected, got } => write!(
+                f,
+                "Public input mismatch: VK expects {} inputs, got {}",
+                expected, got
+            ),
+            VerificationError::EpochOrderingError { first, last } => write!(
+                f,
+                "Epoch ordering error: last epoch {} must come after first epoch {}",
+                last, first
+            ),
+            VerificationError::EpochCountMismatch { expected, got } => write!(
+                f,
+                "Epoch count mismatch: VK is for {} epochs, got {}",
+                expected, got
+            ),
+            VerificationError::EpochBoundaryMismatch { end, start } => write!(
+                f,
+                "Epoch boundary mismatch: first proof ends at epoch {}, second starts at epoch {}",
+                end, start
+            ),
+            VerificationError::EpochGapTooLarge { max, got } => write!(
+                f,
+                "Epoch gap too large: epochs are {} apart, at most {} allowed",
+                got, max
+            ),
+            VerificationError::ApkCommitmentMismatch => write!(
+                f,
+                "APK commitment mismatch: the last epoch's commitment is not the expected one"
+            ),
+            VerificationError::InvalidSigners(e) => write!(f, "Invalid signers: {}", e),
+            VerificationError::BatchVerificationFailed(failed) => {
+                write!(f, "Batch verification failed for proofs {:?}", failed)
+            }
+            VerificationError::DeserializationError(e) => write!(f, "Deserialization Error: {}", e),
+            VerificationError::ZexeSynthesisError(e) => write!(f, "Synthesis Error: {}", e),
+            VerificationError::EpochEncodingError(e) => write!(f, "Encoding Error: {}", e),
+            VerificationError::UnsupportedCircuitVersion(version) => {
+                write!(f, "Unsupported circuit version: {}", version)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for VerificationError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            VerificationError::DeserializationError(e) => Some(e),
+            VerificationError::ZexeSynthesisError(e) => Some(e),
+            VerificationError::EpochEncodingError(e) => Some(e),
+            VerificationError::InvalidSigners(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<SerializationError> for VerificationError {
+    fn from(e: SerializationError) -> Self {
+        VerificationError::DeserializationError(e)
+    }
+}
+
+impl From<SynthesisError> for VerificationError {
+    fn from(e: SynthesisError) -> Self {
+        VerificationError::ZexeSynthesisError(e)
+    }
+}
+
+impl From<EncodingError> for VerificationError {
+    fn from(e: EncodingError) -> Self {
+        VerificationError::EpochEncodingError(e)
+    }
+}
+
+/// The versions of the circuit, which would differ in how the public inputs are derived from
+/// the first and last epoch blocks. Proofs must be verified with the version of the circuit
+/// which the VK was generated for.
+///
+/// Only the current circuit is supported. The version is carried by proof envelopes and the
+/// FFI so that the layout of a future circuit can be added without changing them, and so
+/// that proofs claiming another version are rejected with `UnsupportedCircuitVersion`.
+///
+/// The current circuit is the first versioned one: no circuit predates `V1`, so there is no
+/// older layout of the public inputs to verify proofs with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CircuitVersion {
+    /// The hash of the first epoch and the hash of the last epoch with its aggregated public
+    /// key, as computed by the current circuit
+    V1 = 1,
+}
+
+impl CircuitVersion {
+    /// The version of the circuit which this crate proves
+    pub const CURRENT: CircuitVersion = CircuitVersion::V1;
+}
+
+impl Default for CircuitVersion {
+    fn default() -> Self {
+        Self::CURRENT
+    }
+}
+
+impl TryFrom<u32> for CircuitVersion {
+    type Error = VerificationError;
+
+    fn try_from(version: u32) -> Result<Self, Self::Error> {
+        match version {
+            1 => Ok(CircuitVersion::V1),
+            _ => Err(VerificationError::UnsupportedCircuitVersion(version)),
+        }
+    }
+}
+
+/// Checks of the epochs which [`verify_with_config`](fn.verify_with_config.html) runs before
+/// the pairing check, so that proofs for unexpected epochs are rejected with a specific error
+/// instead of `VerificationFailed`, along with the number of validators which the epochs are
+/// padded to.
+///
+/// The default runs none of them, like [`verify`](fn.verify.html). The window of a proof is
+/// fixed by the VK's setup, so it is verified for any gap between the epochs unless the
+/// caller bounds it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct VerifyOptions {
+    /// The number of epochs which the VK's setup was done for. If set, the epochs must be
+    /// exactly that many apart, otherwise the proof is rejected with `EpochCountMismatch`.
+    pub num_epochs: Option<usize>,
+    /// The maximum number of epochs between the first and the last epoch, e.g. the window of
+    /// the network's proofs. If set, epochs which are further apart are rejected with
+    /// `EpochGapTooLarge`. A `num_epochs` larger than the maximum gap is rejected the same
+    /// way, since no epochs could satisfy both.
+    pub max_gap: Option<usize>,
+    /// The expected [`apk_commitment`](struct.EpochBlock.html#method.apk_commitment) of the
+    /// last epoch (once padded), e.g. one stored alongside the proof. If set, a last epoch with
+    /// another commitment is rejected with `ApkCommitmentMismatch`. The commitment is part of
+    /// the public inputs, so such a proof would fail the pairing check anyway.
+    pub expected_last_apk_commitment: Option<[u8; 32]>,
+    /// The number of validators which the VK's setup was done for. If set, the epochs are
+    /// [`padded`](struct.EpochBlock.html#method.padded) to it like the prover pads them,
+    /// before the other checks, and epochs with more validators are rejected with
+    /// `EncodingError::TooManyValidators`. Otherwise the epochs must have exactly as many
+    /// validators as the setup for the proof to be accepted.
+    pub num_validators: Option<usize>,
 }
 
 /// Given the Verifying Key for the circuit and the SNARK proof and _only the first and last epoch_,
 /// this function ensures that the state transition between epochs has been calculated correctly.
+///
+/// The epochs' indices are part of the hashed [`public_inputs`](fn.public_inputs.html), so
+/// a proof for epochs `(N, N + k)` is rejected for any other pair of indices, even if the
+/// validator sets are the same.
+///
+/// Before the pairing check, the number of the VK's input elements is checked against the
+/// public inputs derived from the epochs, so that a VK for another circuit fails with
+/// `PublicInputMismatch` instead of `VerificationFailed`. The number of public inputs does not
+/// depend on the window, so use [`verify_window`](fn.verify_window.html) to also check that.
 pub fn verify(
     vk: &VerifyingKey<CPCurve>,
     first_epoch: &EpochBlock,
     last_epoch: &EpochBlock,
     proof: &Proof<CPCurve>,
 ) -> Result<(), VerificationError> {
-    info!("Verifying proof");
+    verify_prepared(&prepare_verifying_key(vk), first_epoch, last_epoch, proof)
+}
+
+/// Same as [`verify`](fn.verify.html), but returns the minimum number of the first epoch's
+/// validators which signed the first transition, i.e. the first epoch's
+/// [`min_signers`](struct.EpochBlock.html#method.min_signers).
+///
+/// This is the only signer count which the proof attests to. The circuit checks each
+/// transition's bitmap against the previous epoch's `maximum_non_signers`, but the bitmaps
+/// and the intermediate epochs are private inputs, so neither the exact number of signers
+/// nor the quorum of the later transitions can be derived from the public inputs.
+pub fn verify_with_min_signers(
+    vk: &VerifyingKey<CPCurve>,
+    first_epoch: &EpochBlock,
+    last_epoch: &EpochBlock,
+    proof: &Proof<CPCurve>,
+) -> Result<u32, VerificationError> {
+    verify(vk, first_epoch, last_epoch, proof)?;
+    Ok(first_epoch.min_signers())
+}
+
+/// Same as [`verify`](fn.verify.html), but also checks that the proof covers exactly
+/// `num_epochs` transitions, which must be the number of epochs the VK's setup was done for.
+///
+/// Since the circuit enforces that each epoch's index is the previous one's plus 1, a
+/// mismatched window is rejected before running the pairing check. This is a shorthand for
+/// setting [`VerifyOptions::num_epochs`](struct.VerifyOptions.html#structfield.num_epochs).
+pub fn verify_window(
+    vk: &VerifyingKey<CPCurve>,
+    num_epochs: usize,
+    first_epoch: &EpochBlock,
+    last_epoch: &EpochBlock,
+    proof: &Proof<CPCurve>,
+) -> Result<(), VerificationError> {
+    let options = VerifyOptions {
+        num_epochs: Some(num_epochs),
+        ..Default::default()
+    };
+    verify_with_config(
+        vk,
+        first_epoch,
+        last_epoch,
+        proof,
+        &CircuitConfig::default(),
+        &options,
+    )
+}
+
+/// A proof over a chain of consecutive epochs, as produced by an
+/// [`EpochProofAccumulator`](struct.EpochProofAccumulator.html)
+#[derive(Clone, Debug)]
+pub struct AccumulatedProof {
+    /// The proof over every accumulated transition
+    pub proof: Proof<CPCurve>,
+    /// The epoch the chain started from
+    pub first_epoch: EpochBlock,
+    /// The epoch of the last accumulated transition
+    pub last_epoch: EpochBlock,
+    /// The number of accumulated transitions
+    pub num_epochs: usize,
+}
+
+/// Verifies an accumulated proof against the VK of a setup for `accumulated.num_epochs`
+/// epochs. Like [`verify_window`](fn.verify_window.html), a proof spanning a different
+/// number of epochs than its first and last epochs are apart is rejected before the
+/// pairing check.
+pub fn verify_accumulated(
+    vk: &VerifyingKey<CPCurve>,
+    accumulated: &AccumulatedProof,
+) -> Result<(), VerificationError> {
+    verify_window(
+        vk,
+        accumulated.num_epochs,
+        &accumulated.first_epoch,
+        &accumulated.last_epoch,
+        &accumulated.proof,
+    )
+}
+
+/// Two proofs over adjacent windows of epochs, as produced by
+/// [`link_proofs`](fn.link_proofs.html)
+///
+/// The epoch both windows share is only stored once, so that the proofs are always
+/// checked against the same boundary.
+#[derive(Clone, Debug)]
+pub struct LinkedProof {
+    /// The proof from `first_epoch` to `boundary_epoch`
+    pub first: Proof<CPCurve>,
+    /// The proof from `boundary_epoch` to `last_epoch`
+    pub second: Proof<CPCurve>,
+    /// The epoch the first window starts from
+    pub first_epoch: EpochBlock,
+    /// The epoch the first window ends at and the second one starts from
+    pub boundary_epoch: EpochBlock,
+    /// The epoch the second window ends at
+    pub last_epoch: EpochBlock,
+}
+
+/// Links the proof over `[first_epoch, boundary_epoch]` with the proof over
+/// `[second_start, last_epoch]` into a single proof over `[first_epoch, last_epoch]`,
+/// without re-proving any of the transitions.
+///
+/// Fails with `VerificationError::EpochBoundaryMismatch` if `second_start` is not the
+/// same epoch as `boundary_epoch`. The proofs themselves are only checked by
+/// [`verify_linked`](fn.verify_linked.html).
+pub fn link_proofs(
+    first: &Proof<CPCurve>,
+    first_epoch: &EpochBlock,
+    boundary_epoch: &EpochBlock,
+    second: &Proof<CPCurve>,
+    second_start: &EpochBlock,
+    last_epoch: &EpochBlock,
+) -> Result<LinkedProof, VerificationError> {
+    if boundary_epoch != second_start {
+        return Err(VerificationError::EpochBoundaryMismatch {
+            end: boundary_epoch.index,
+            start: second_start.index,
+        });
+    }
+    check_ordering(first_epoch, boundary_epoch)?;
+    check_ordering(boundary_epoch, last_epoch)?;
+    Ok(LinkedProof {
+        first: first.clone(),
+        second: second.clone(),
+        first_epoch: first_epoch.clone(),
+        boundary_epoch: boundary_epoch.clone(),
+        last_epoch: last_epoch.clone(),
+    })
+}
+
+/// Verifies a linked proof, which is valid for `[linked.first_epoch, linked.last_epoch]`
+/// if both of its proofs are valid for their windows. The windows may differ in length,
+/// so each proof is checked against the VK of its own setup.
+pub fn verify_linked(
+    first_vk: &VerifyingKey<CPCurve>,
+    second_vk: &VerifyingKey<CPCurve>,
+    linked: &LinkedProof,
+) -> Result<(), VerificationError> {
+    // the windows must still be contiguous, since the fields can be modified after linking
+    check_ordering(&linked.first_epoch, &linked.boundary_epoch)?;
+    check_ordering(&linked.boundary_epoch, &linked.last_epoch)?;
+    verify(
+        first_vk,
+        &linked.first_epoch,
+        &linked.boundary_epoch,
+        &linked.first,
+    )?;
+    verify(
+        second_vk,
+        &linked.boundary_epoch,
+        &linked.last_epoch,
+        &linked.second,
+    )
+}
+
+/// Verifies a chain of epochs, where `proofs[i]` is the compressed proof of the transitions
+/// from `epochs[i]` to `epochs[i + 1]`. The proofs must be canonical (see
+/// `Strictness::Strict`) and each epoch must come after the previous one.
+///
+/// Every proof is checked, and if any of them fails the indices of the failed ones are
+/// returned in order. Those include the proofs which could not be decoded, the ones whose
+/// epochs are misordered, the extra proofs for which there are not enough epochs and the
+/// missing proofs (i.e. `proofs.len()..epochs.len() - 1`) if there are too few of them.
+pub fn verify_chain(
+    vk: &VerifyingKey<CPCurve>,
+    epochs: &[EpochBlock],
+    proofs: &[Vec<u8>],
+) -> Result<(), Vec<usize>> {
+    info!(
+        "Verifying a chain of {} epochs with {} proofs",
+        epochs.len(),
+        proofs.len()
+    );
+    let pvk = prepare_verifying_key(vk);
+    let num_links = core::cmp::max(epochs.len().saturating_sub(1), proofs.len());
+    let failed = (0..num_links)
+        .filter(|&i| {
+            let result = match (epochs.get(i), epochs.get(i + 1), proofs.get(i)) {
+                (Some(first_epoch), Some(last_epoch), Some(proof)) => {
+                    verify_link(&pvk, first_epoch, last_epoch, proof)
+                }
+                _ => {
+                    warn!("proof {}: no proof or epochs for the link", i);
+                    return true;
+                }
+            };
+            if let Err(ref e) = result {
+                warn!("proof {}: {}", i, e);
+            }
+            result.is_err()
+        })
+        .collect::<Vec<_>>();
+
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(failed)
+    }
+}
+
+/// Verifies the compressed proof of a single link of a chain, from `first_epoch` to `last_epoch`
+fn verify_link(
+    pvk: &PreparedVerifyingKey<CPCurve>,
+    first_epoch: &EpochBlock,
+    last_epoch: &EpochBlock,
+    proof: &[u8],
+) -> Result<(), VerificationError> {
+    check_ordering(first_epoch, last_epoch)?;
+    let proof: Proof<CPCurve> = deserialize_compressed(proof, Strictness::Strict)?;
+    verify_prepared(pvk, first_epoch, last_epoch, &proof)
+}
+
+/// Verifies multiple proofs against the same VK with a single product of pairings, by
+/// combining their verification equations with random scalars.
+///
+/// If the combined check fails, each proof is verified individually and
+/// `VerificationError::BatchVerificationFailed` is returned with the indices of the
+/// invalid proofs.
+///
+/// The random scalars are sampled from the thread RNG, so this requires the `std` feature.
+#[cfg(feature = "std")]
+pub fn verify_many(
+    vk: &VerifyingKey<CPCurve>,
+    proofs: &[(&Proof<CPCurve>, &EpochBlock, &EpochBlock)],
+) -> Result<(), VerificationError> {
+    info!("Verifying {} proofs", proofs.len());
+    for (_, first_epoch, last_epoch) in proofs {
+        check_ordering(first_epoch, last_epoch)?;
+    }
+    let pvk = prepare_verifying_key(vk);
+    let inputs = proofs
+        .iter()
+        .map(|(_, first_epoch, last_epoch)| public_inputs(first_epoch, last_epoch))
+        .collect::<Result<Vec<_>, _>>()?;
+    for public_inputs in &inputs {
+        check_inputs_len(&pvk, public_inputs)?;
+    }
+
+    if proofs.is_empty() || batch_check(&pvk, proofs, &inputs) {
+        return Ok(());
+    }
+
+    // find the culprits
+    let failed = proofs
+        .iter()
+        .zip(&inputs)
+        .enumerate()
+        .filter(|(_, ((proof, _, _), public_inputs))| {
+            !verify_proof(&pvk, proof, public_inputs).unwrap_or(false)
+        })
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(VerificationError::BatchVerificationFailed(failed))
+    }
+}
+
+/// Verifies each proof independently against the same VK on the rayon thread pool, and
+/// returns whether each of them is valid, in the order of `proofs`.
+///
+/// Unlike [`verify_many`](fn.verify_many.html), which combines all the proofs into a single
+/// check and only falls back to checking them one by one if it fails, the cost does not
+/// depend on how many of the proofs are invalid. The VK is prepared once and shared by all
+/// threads.
+#[cfg(feature = "parallel")]
+pub fn verify_parallel(
+    vk: &VerifyingKey<CPCurve>,
+    proofs: &[(&Proof<CPCurve>, &EpochBlock, &EpochBlock)],
+) -> Vec<bool> {
+    use rayon::prelude::*;
+
+    info!("Verifying {} proofs in parallel", proofs.len());
+    let pvk = prepare_verifying_key(vk);
+    proofs
+        .par_iter()
+        .map(|(proof, first_epoch, last_epoch)| {
+            verify_prepared(&pvk, first_epoch, last_epoch, proof).is_ok()
+        })
+        .collect()
+}
+
+/// Checks the random linear combination of the Groth16 verification equations:
+///
+/// `prod(e(r_i * A_i, B_i)) * e(sum(r_i * L_i), -gamma) * e(sum(r_i * C_i), -delta) = e(alpha, beta)^sum(r_i)`
+///
+/// where `L_i` is the linear combination of the VK's input elements with the `i`th proof's
+/// public inputs
+#[cfg(feature = "std")]
+fn batch_check(
+    pvk: &PreparedVerifyingKey<CPCurve>,
+    proofs: &[(&Proof<CPCurve>, &EpochBlock, &EpochBlock)],
+    inputs: &[Vec<CPField>],
+) -> bool {
+    use algebra::{
+        AffineCurve, Field, PairingEngine, PrimeField, ProjectiveCurve, UniformRand, Zero,
+    };
+
+    let rng = &mut rand::thread_rng();
+    let mut pairs = Vec::with_capacity(proofs.len() + 2);
+    let mut sum_r = CPField::zero();
+    let mut acc_inputs = <CPCurve as PairingEngine>::G1Projective::zero();
+    let mut acc_c = <CPCurve as PairingEngine>::G1Projective::zero();
+    for ((proof, _, _), public_inputs) in proofs.iter().zip(inputs) {
+        let r = CPField::rand(rng);
+
+        let mut g_ic = pvk.vk.gamma_abc_g1[0].into_projective();
+        for (x, b) in public_inputs.iter().zip(pvk.vk.gamma_abc_g1.iter().skip(1)) {
+            g_ic += &b.mul(x.into_repr());
+        }
+        acc_inputs += &g_ic.into_affine().mul(r.into_repr());
+        acc_c += &proof.c.mul(r.into_repr());
+        pairs.push((
+            proof.a.mul(r.into_repr()).into_affine().into(),
+            proof.b.into(),
+        ));
+        sum_r += &r;
+    }
+    pairs.push((acc_inputs.into_affine().into(), pvk.gamma_g2_neg_pc.clone()));
+    pairs.push((acc_c.into_affine().into(), pvk.delta_g2_neg_pc.clone()));
+
+    CPCurve::product_of_pairings(&pairs) == pvk.alpha_g1_beta_g2.pow(sum_r.into_repr())
+}
+
+/// Ensures that the last epoch comes after the first one, since each transition
+/// increments the epoch index by 1
+fn check_ordering(
+    first_epoch: &EpochBlock,
+    last_epoch: &EpochBlock,
+) -> Result<(), VerificationError> {
+    epoch_gap(first_epoch, last_epoch).map(|_| ())
+}
+
+/// Returns the number of epochs between the first and the last epoch, failing with
+/// `EpochOrderingError` instead of wrapping if the last epoch does not come after the first
+fn epoch_gap(
+    first_epoch: &EpochBlock,
+    last_epoch: &EpochBlock,
+) -> Result<usize, VerificationError> {
+    match last_epoch.index.checked_sub(first_epoch.index) {
+        Some(gap) if gap > 0 => Ok(gap as usize),
+        _ => Err(VerificationError::EpochOrderingError {
+            first: first_epoch.index,
+            last: last_epoch.index,
+        }),
+    }
+}
+
+/// Ensures that the epochs are exactly `num_epochs` apart
+fn check_window(
+    num_epochs: usize,
+    first_epoch: &EpochBlock,
+    last_epoch: &EpochBlock,
+) -> Result<(), VerificationError> {
+    let gap = epoch_gap(first_epoch, last_epoch)?;
+    if !first_epoch.is_consecutive_with(last_epoch, num_epochs) {
+        return Err(VerificationError::EpochCountMismatch {
+            expected: num_epochs,
+            got: gap,
+        });
+    }
+    Ok(())
+}
+
+/// Ensures that the epochs are at most `max_gap` apart
+fn check_gap(
+    max_gap: usize,
+    first_epoch: &EpochBlock,
+    last_epoch: &EpochBlock,
+) -> Result<(), VerificationError> {
+    let gap = epoch_gap(first_epoch, last_epoch)?;
+    if gap > max_gap {
+        return Err(VerificationError::EpochGapTooLarge {
+            max: max_gap,
+            got: gap,
+        });
+    }
+    Ok(())
+}
+
+/// Runs the checks of the options which are set
+fn check_options(
+    options: &VerifyOptions,
+    first_epoch: &EpochBlock,
+    last_epoch: &EpochBlock,
+) -> Result<(), VerificationError> {
+    if let (Some(num_epochs), Some(max_gap)) = (options.num_epochs, options.max_gap) {
+        if num_epochs > max_gap {
+            return Err(VerificationError::EpochGapTooLarge {
+                max: max_gap,
+                got: num_epochs,
+            });
+        }
+    }
+    if let Some(num_epochs) = options.num_epochs {
+        check_window(num_epochs, first_epoch, last_epoch)?;
+    }
+    if let Some(max_gap) = options.max_gap {
+        check_gap(max_gap, first_epoch, last_epoch)?;
+    }
+    if let Some(expected) = options.expected_last_apk_commitment {
+        if last_epoch.apk_commitment()? != expected {
+            return Err(VerificationError::ApkCommitmentMismatch);
+        }
+    }
+    Ok(())
+}
+
+/// Ensures that the VK has an input element for each public input
+fn check_inputs_len(
+    pvk: &PreparedVerifyingKey<CPCurve>,
+    public_inputs: &[CPField],
+) -> Result<(), VerificationError> {
+    // the first element of the VK's inputs is for the constant term
+    let expected = pvk.vk.gamma_abc_g1.len().saturating_sub(1);
+    if expected != public_inputs.len() {
+        return Err(VerificationError::PublicInputMismatch {
+            expected,
+            got: public_inputs.len(),
+        });
+    }
+    Ok(())
+}
+
+/// Computes the public inputs of the proof which the verifier derives from the first and
+/// last epoch blocks, i.e. the packed hash of the two blocks.
+///
+/// Each block is hashed with its index and maximum number of non signers, which the circuit
+/// constrains to the ones of the proven epochs.
+pub fn public_inputs(
+    first_epoch: &EpochBlock,
+    last_epoch: &EpochBlock,
+) -> Result<Vec<CPField>, EncodingError> {
+    public_inputs_with_config(first_epoch, last_epoch, &CircuitConfig::default())
+}
+
+/// Same as [`public_inputs`](fn.public_inputs.html), but for a circuit with the provided
+/// configuration, whose version determines how the blocks are hashed and whose packing width
+/// the hash is packed with. The packed hash is followed by the fields of the last epoch which
+/// the configuration exposes.
+pub fn public_inputs_with_config(
+    first_epoch: &EpochBlock,
+    last_epoch: &EpochBlock,
+    config: &CircuitConfig,
+) -> Result<Vec<CPField>, EncodingError> {
     // Hash the first-last block together
-    let hash = hash_first_last_epoch_block(first_epoch, last_epoch)?;
+    let hash = match config.version {
+        CircuitVersion::V1 => hash_first_last_epoch_block(first_epoch, last_epoch)?,
+    };
     // packs them
-    let public_inputs = pack::<CPField, CPFrParams>(&hash);
+    let mut inputs = pack_with_width::<CPField>(&hash, config.packing.bits());
+    if config.public_fields.maximum_non_signers {
+        inputs.push(CPField::from(last_epoch.maximum_non_signers as u64));
+    }
+    Ok(inputs)
+}
+
+/// The length of a compressed proof, e.g. for allocating the output buffer of the prover.
+///
+/// Groth16 proofs have a fixed size, so unlike [`vk_size`](fn.vk_size.html) this depends
+/// neither on the configuration of the setup nor on the number of epochs proven.
+pub fn proof_size() -> usize {
+    Proof::<CPCurve>::default().serialized_size()
+}
+
+/// The length of the compressed VK of a setup with the provided configuration, e.g. the one
+/// of its [`ProvingKey`](struct.ProvingKey.html).
+///
+/// The VK has an element per public input plus one, and the public inputs only depend on
+/// the configuration and the first and last epoch, so this does not depend on the number of
+/// epochs.
+pub fn vk_size(config: &CircuitConfig) -> usize {
+    let vk = VerifyingKey::<CPCurve> {
+        gamma_abc_g1: vec![Default::default(); config.num_public_inputs() + 1],
+        ..Default::default()
+    };
+    vk.serialized_size()
+}
+
+/// Deserializes a VK and re-serializes it in its canonical compressed form.
+///
+/// Both compressed and uncompressed serializations are accepted, however the input must be
+/// exactly the canonical encoding of the key in either form: encodings with wrongly set flags
+/// or trailing bytes are rejected with `EncodingError::NonCanonical` instead of being normalized.
+pub fn canonicalize_vk(bytes: &[u8]) -> Result<Vec<u8>, EncodingError> {
+    let (vk, compressed) = deserialize_any::<VerifyingKey<CPCurve>>(bytes, true)?;
+
+    let mut canonical = vec![];
+    vk.serialize(&mut canonical)?;
+    let reencoded = if compressed {
+        canonical.clone()
+    } else {
+        let mut uncompressed = vec![];
+        vk.serialize_uncompressed(&mut uncompressed)?;
+        uncompressed
+    };
+    if reencoded != bytes {
+        return Err(EncodingError::NonCanonical);
+    }
+
+    Ok(canonical)
+}
+
+/// Returns the Blake2s hash of the VK's canonical compressed serialization, as a stable
+/// fingerprint to identify the key by, e.g. when distributing it. Since the key is
+/// canonicalized first, its compressed and uncompressed serializations have the same
+/// fingerprint. The VK is accepted under the same rules as in
+/// [`canonicalize_vk`](fn.canonicalize_vk.html).
+pub fn vk_fingerprint(vk: &[u8]) -> Result<[u8; 32], EncodingError> {
+    let canonical = canonicalize_vk(vk)?;
+    Ok(*blake2s_simd::blake2s(&canonical).as_array())
+}
+
+/// Returns `true` if the two serialized VKs decode to the same key, e.g. to check that a
+/// distributed VK matches the canonical one. Either VK may be compressed or uncompressed,
+/// and they are accepted under the same rules as in [`canonicalize_vk`](fn.canonicalize_vk.html).
+pub fn vk_equal(a: &[u8], b: &[u8]) -> Result<bool, EncodingError> {
+    let (a, _) = deserialize_any::<VerifyingKey<CPCurve>>(a, true)?;
+    let (b, _) = deserialize_any::<VerifyingKey<CPCurve>>(b, true)?;
+    Ok(a == b)
+}
+
+/// Same as [`verify`](fn.verify.html), but takes the compressed serializations
+/// of the VK and the proof, which must be canonical (see `Strictness::Strict`).
+pub fn verify_serialized(
+    vk: &[u8],
+    first_epoch: &EpochBlock,
+    last_epoch: &EpochBlock,
+    proof: &[u8],
+) -> Result<(), VerificationError> {
+    verify_serialized_with_strictness(vk, first_epoch, last_epoch, proof, Strictness::Strict)
+}
+
+/// Same as [`verify_serialized`](fn.verify_serialized.html), but non-canonical encodings
+/// of the VK and the proof are accepted if `strictness` is `Strictness::Lenient`.
+pub fn verify_serialized_with_strictness(
+    vk: &[u8],
+    first_epoch: &EpochBlock,
+    last_epoch: &EpochBlock,
+    proof: &[u8],
+    strictness: Strictness,
+) -> Result<(), VerificationError> {
+    let vk: VerifyingKey<CPCurve> = deserialize_compressed(vk, strictness)?;
+    let proof: Proof<CPCurve> = deserialize_compressed(proof, strictness)?;
+    verify(&vk, first_epoch, last_epoch, &proof)
+}
+
+/// Same as [`verify_serialized`](fn.verify_serialized.html), but the proof is wrapped in an
+/// envelope (see [`encode_proof_envelope`](fn.encode_proof_envelope.html)), whose version
+/// byte selects the `CircuitVersion` it is verified with. Envelopes of proofs over other
+/// curves than SW6 are rejected with `EncodingError::InvalidProofEnvelope`.
+pub fn verify_envelope(
+    vk: &[u8],
+    first_epoch: &EpochBlock,
+    last_epoch: &EpochBlock,
+    envelope: &[u8],
+) -> Result<(), VerificationError> {
+    let envelope = decode_proof_envelope(envelope)?;
+    if envelope.curve != PROOF_ENVELOPE_CURVE_SW6 {
+        warn!("the proof envelope is for curve {}", envelope.curve);
+        return Err(EncodingError::InvalidProofEnvelope.into());
+    }
+    let version = CircuitVersion::try_from(u32::from(envelope.version))?;
+    let vk: VerifyingKey<CPCurve> = deserialize_compressed(vk, Strictness::Strict)?;
+    let proof: Proof<CPCurve> = deserialize_compressed(envelope.proof, Strictness::Strict)?;
+    let config = CircuitConfig {
+        version,
+        ..Default::default()
+    };
+    verify_with_config(
+        &vk,
+        first_epoch,
+        last_epoch,
+        &proof,
+        &config,
+        &VerifyOptions::default(),
+    )
+}
+
+/// Same as [`verify_serialized`](fn.verify_serialized.html), but accepts the proof if it
+/// verifies against any of the compressed `vks`, e.g. while rotating from an old VK to a new
+/// one. The VKs are tried in order until one of them succeeds, and its index is returned.
+///
+/// If none of them verifies the proof, the error of the last VK is returned, or
+/// `VerificationFailed` if `vks` is empty.
+pub fn verify_any_vk(
+    vks: &[&[u8]],
+    first_epoch: &EpochBlock,
+    last_epoch: &EpochBlock,
+    proof: &[u8],
+) -> Result<usize, VerificationError> {
+    let proof: Proof<CPCurve> = deserialize_compressed(proof, Strictness::Strict)?;
+    let mut last_error = VerificationError::VerificationFailed;
+    for (i, vk) in vks.iter().enumerate() {
+        let result = deserialize_compressed(vk, Strictness::Strict)
+            .map_err(VerificationError::from)
+            .and_then(|vk| verify(&vk, first_epoch, last_epoch, &proof));
+        match result {
+            Ok(()) => return Ok(i),
+            Err(err) => last_error = err,
+        }
+    }
+    Err(last_error)
+}
+
+/// Deserializes the canonical compressed serialization of a VK and prepares it for
+/// [`verify_prepared`](fn.verify_prepared.html), i.e. precomputes the pairing of its
+/// `alpha` and `beta` elements and the line coefficients of its `gamma` and `delta` elements.
+///
+/// Services which verify many proofs against the same key should call this once and reuse
+/// the result, instead of deserializing the VK for each proof.
+pub fn prepare_vk_from_bytes(
+    vk: &[u8],
+) -> Result<PreparedVerifyingKey<CPCurve>, VerificationError> {
+    let vk: VerifyingKey<CPCurve> = deserialize_compressed(vk, Strictness::Strict)?;
+    Ok(prepare_verifying_key(&vk))
+}
+
+/// Same as [`verify`](fn.verify.html), but takes a VK which has already been prepared
+/// for the pairing checks. Use this when verifying multiple proofs against the same VK.
+pub fn verify_prepared(
+    pvk: &PreparedVerifyingKey<CPCurve>,
+    first_epoch: &EpochBlock,
+    last_epoch: &EpochBlock,
+    proof: &Proof<CPCurve>,
+) -> Result<(), VerificationError> {
+    verify_prepared_with_config(
+        pvk,
+        first_epoch,
+        last_epoch,
+        proof,
+        &CircuitConfig::default(),
+        &VerifyOptions::default(),
+    )
+}
+
+/// Same as [`verify`](fn.verify.html), but for a circuit with the provided configuration,
+/// e.g. one set up with [`trusted_setup_with_config`](fn.trusted_setup_with_config.html) or
+/// the version of a proof envelope, and running the checks of the provided options before
+/// the pairing check.
+///
+/// The configuration determines the public inputs, so it must be the one recorded in the
+/// setup's parameters. Otherwise the proof is rejected, with `PublicInputMismatch` if the
+/// configurations result in a different number of public inputs and with
+/// `VerificationFailed` if not.
+pub fn verify_with_config(
+    vk: &VerifyingKey<CPCurve>,
+    first_epoch: &EpochBlock,
+    last_epoch: &EpochBlock,
+    proof: &Proof<CPCurve>,
+    config: &CircuitConfig,
+    options: &VerifyOptions,
+) -> Result<(), VerificationError> {
+    verify_prepared_with_config(
+        &prepare_verifying_key(vk),
+        first_epoch,
+        last_epoch,
+        proof,
+        config,
+        options,
+    )
+}
+
+/// Same as [`verify_with_config`](fn.verify_with_config.html), but takes the epochs'
+/// commitments instead of the epochs, e.g. for light clients which only keep the 32 byte
+/// commitment of the latest epoch they trust instead of its pubkeys.
+///
+/// `first_commitment` must be the first epoch's
+/// [`EpochBlock::commitment`](struct.EpochBlock.html#method.commitment) and
+/// `last_commitment` the last epoch's
+/// [`EpochBlock::apk_commitment`](struct.EpochBlock.html#method.apk_commitment). For
+/// consistent commitments, the proof is accepted if and only if `verify_with_config` accepts
+/// it for the epochs.
+///
+/// **This does not verify from the aggregated public keys.** The circuit does not commit to
+/// the epochs' aggregated public keys but hashes each epoch's full encoding, pubkeys
+/// included, so a `verify_with_apk` taking the aggregated public keys and the indices
+/// cannot produce the public inputs, and the commitments are the least a light client has
+/// to keep instead of the pubkeys. Only the last epoch's commitment includes its aggregated
+/// public key.
+///
+/// The epochs' indices are only committed to by the hashes, so unlike `verify_with_config`
+/// this cannot check their ordering or run any `VerifyOptions` before the pairing check.
+/// The commitments do not carry the fields of the last epoch which a configuration may
+/// expose either, so proofs of such circuits are rejected with `PublicInputMismatch`.
+pub fn verify_with_commitments(
+    vk: &VerifyingKey<CPCurve>,
+    first_commitment: &[u8; 32],
+    last_commitment: &[u8; 32],
+    proof: &Proof<CPCurve>,
+    config: &CircuitConfig,
+) -> Result<(), VerificationError> {
+    let pvk = prepare_verifying_key(vk);
+    // the same hash as `hash_first_last_epoch_block` computes from the epochs
+    let hash = match config.version {
+        CircuitVersion::V1 => [
+            bytes_to_bits(first_commitment, 256),
+            bytes_to_bits(last_commitment, 256),
+        ]
+        .concat(),
+    };
+    let public_inputs = pack_with_width::<CPField>(&hash, config.packing.bits());
+    check_inputs_len(&pvk, &public_inputs)?;
+    if verify_proof(&pvk, proof, &public_inputs)? {
+        Ok(())
+    } else {
+        Err(VerificationError::VerificationFailed)
+    }
+}
+
+/// Same as [`verify_prepared`](fn.verify_prepared.html), but for a circuit with the provided
+/// configuration and options, like [`verify_with_config`](fn.verify_with_config.html)
+pub fn verify_prepared_with_config(
+    pvk: &PreparedVerifyingKey<CPCurve>,
+    first_epoch: &EpochBlock,
+    last_epoch: &EpochBlock,
+    proof: &Proof<CPCurve>,
+    config: &CircuitConfig,
+    options: &VerifyOptions,
+) -> Result<(), VerificationError> {
+    info!(
+        "Verifying proof of circuit version {}",
+        config.version as u32
+    );
+    check_ordering(first_epoch, last_epoch)?;
+    let padded;
+    let (first_epoch, last_epoch) = match options.num_validators {
+        Some(num_validators) => {
+            padded = (
+                first_epoch.padded(num_validators)?,
+                last_epoch.padded(num_validators)?,
+            );
+            (&padded.0, &padded.1)
+        }
+        None => (first_epoch, last_epoch),
+    };
+    check_options(options, first_epoch, last_epoch)?;
+    let public_inputs = public_inputs_with_config(first_epoch, last_epoch, config)?;
+    check_inputs_len(pvk, &public_inputs)?;
     // verifies the BLS proof by using the First/Last epoch as public inputs over CP
-    if verify_proof(&prepare_verifying_key(vk), proof, &public_inputs)? {
+    #[cfg(not(feature = "parallel"))]
+    let valid = verify_proof(pvk, proof, &public_inputs)?;
+    #[cfg(feature = "parallel")]
+    let valid = verify_proof_parallel(pvk, proof, &public_inputs)?;
+    if valid {
         Ok(())
     } else {
         Err(VerificationError::VerificationFailed)
     }
 }
+
+/// Same check as `groth16::verify_proof`, but the Miller loops of the 3 pairings run
+/// concurrently and are multiplied together before a single final exponentiation
+#[cfg(feature = "parallel")]
+fn verify_proof_parallel(
+    pvk: &PreparedVerifyingKey<CPCurve>,
+    proof: &Proof<CPCurve>,
+    public_inputs: &[CPField],
+) -> Result<bool, SynthesisError> {
+    use algebra::{AffineCurve, PairingEngine, PrimeField, ProjectiveCurve};
+
+    let mut g_ic = pvk.vk.gamma_abc_g1[0].into_projective();
+    for (x, b) in public_inputs.iter().zip(pvk.vk.gamma_abc_g1.iter().skip(1)) {
+        g_ic += &b.mul(x.into_repr());
+    }
+    let pairs = [
+        (proof.a.into(), proof.b.into()),
+        (g_ic.into_affine().into(), pvk.gamma_g2_neg_pc.clone()),
+        (proof.c.into(), pvk.delta_g2_neg_pc.clone()),
+    ];
+    let qap = parallel_miller_loop(&pairs);
+    let test = CPCurve::final_exponentiation(&qap).ok_or(SynthesisError::UnexpectedIdentity)?;
+
+    Ok(test == pvk.alpha_g1_beta_g2)
+}
+
+/// Computes the product of the Miller loops of each pair on the rayon thread pool
+#[cfg(feature = "parallel")]
+fn parallel_miller_loop(
+    pairs: &[(
+        <CPCurve as PairingEngine>::G1Prepared,
+        <CPCurve as PairingEngine>::G2Prepared,
+    )],
+) -> <CPCurve as PairingEngine>::Fqk {
+    use algebra::One;
+    use rayon::prelude::*;
+
+    pairs
+        .par_iter()
+        .map(|pair| CPCurve::miller_loop(core::iter::once(pair)))
+        .reduce(<CPCurve as PairingEngine>::Fqk::one, |a, b| a * &b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::{PackingWidth, PublicFields};
+    use crate::testing::random_epoch_block;
+    use algebra::{PairingEngine, ProjectiveCurve, UniformRand};
+
+    /// An epoch with 4 fresh validators, at most one of which may be absent
+    fn epoch(index: u16) -> EpochBlock {
+        random_epoch_block(&mut rand::thread_rng(), 4, index, 1).0
+    }
+
+    fn rand_vk() -> VerifyingKey<CPCurve> {
+        rand_vk_with_inputs(2)
+    }
+
+    fn rand_vk_with_inputs(num_inputs: usize) -> VerifyingKey<CPCurve> {
+        let rng = &mut rand::thread_rng();
+        let mut g1 = || <CPCurve as PairingEngine>::G1Projective::rand(rng).into_affine();
+        let alpha_g1 = g1();
+        let gamma_abc_g1 = (0..=num_inputs).map(|_| g1()).collect();
+        let mut g2 = || <CPCurve as PairingEngine>::G2Projective::rand(rng).into_affine();
+        VerifyingKey {
+            alpha_g1,
+            beta_g2: g2(),
+            gamma_g2: g2(),
+            delta_g2: g2(),
+            gamma_abc_g1,
+        }
+    }
+
+    /// Builds a VK with known gamma and delta and a valid proof for the inputs, i.e.
+    /// e(alpha, beta) * e(inputs, -gamma) * e(c, -delta) = e(alpha, beta)
+    fn rand_vk_with_proof(inputs: &[CPField]) -> (VerifyingKey<CPCurve>, Proof<CPCurve>) {
+        let (vk, gamma, delta) = rand_vk_with_trapdoor();
+        let proof = forge_proof(&vk, gamma, delta, inputs);
+        (vk, proof)
+    }
+
+    /// Builds a VK along with its gamma and delta, which allow forging proofs for any inputs
+    fn rand_vk_with_trapdoor() -> (VerifyingKey<CPCurve>, CPField, CPField) {
+        use algebra::{AffineCurve, PrimeField};
+
+        let rng = &mut rand::thread_rng();
+        let mut vk = rand_vk();
+        let gamma = CPField::rand(rng);
+        let delta = CPField::rand(rng);
+        let g2 = <CPCurve as PairingEngine>::G2Projective::rand(rng).into_affine();
+        vk.gamma_g2 = g2.mul(gamma.into_repr()).into_affine();
+        vk.delta_g2 = g2.mul(delta.into_repr()).into_affine();
+        (vk, gamma, delta)
+    }
+
+    fn forge_proof(
+        vk: &VerifyingKey<CPCurve>,
+        gamma: CPField,
+        delta: CPField,
+        inputs: &[CPField],
+    ) -> Proof<CPCurve> {
+        use algebra::{AffineCurve, Field, PrimeField};
+
+        let mut g_ic = vk.gamma_abc_g1[0].into_projective();
+        for (x, b) in inputs.iter().zip(vk.gamma_abc_g1.iter().skip(1)) {
+            g_ic += &b.mul(x.into_repr());
+        }
+        let c_scalar = -(gamma * &delta.inverse().unwrap());
+        Proof {
+            a: vk.alpha_g1,
+            b: vk.beta_g2,
+            c: g_ic.into_affine().mul(c_scalar.into_repr()).into_affine(),
+        }
+    }
+
+    #[test]
+    fn verifies_against_any_vk() {
+        let (first, last) = (epoch(0), epoch(2));
+        let (vk, proof) = rand_vk_with_proof(&public_inputs(&first, &last).unwrap());
+        let serialize = |vk: &VerifyingKey<CPCurve>| {
+            let mut bytes = vec![];
+            vk.serialize(&mut bytes).unwrap();
+            bytes
+        };
+        let vk = serialize(&vk);
+        let others = (0..3).map(|_| serialize(&rand_vk())).collect::<Vec<_>>();
+        let mut proof_bytes = vec![];
+        proof.serialize(&mut proof_bytes).unwrap();
+
+        let vks = [&others[0][..], &others[1][..], &vk[..], &others[2][..]];
+        assert_eq!(verify_any_vk(&vks, &first, &last, &proof_bytes).unwrap(), 2);
+        assert_eq!(
+            verify_any_vk(&vks[2..], &first, &last, &proof_bytes).unwrap(),
+            0
+        );
+        // a malformed candidate does not prevent the others from being tried
+        let vks = [&vk[..1], &vk[..]];
+        assert_eq!(verify_any_vk(&vks, &first, &last, &proof_bytes).unwrap(), 1);
+
+        let vks = [&others[0][..], &others[1][..]];
+        assert!(matches!(
+            verify_any_vk(&vks, &first, &last, &proof_bytes).unwrap_err(),
+            VerificationError::VerificationFailed
+        ));
+        assert!(matches!(
+            verify_any_vk(&[], &first, &last, &proof_bytes).unwrap_err(),
+            VerificationError::VerificationFailed
+        ));
+        // the proof is not valid for other epochs under any key
+        let vks = [&others[0][..], &vk[..]];
+        verify_any_vk(&vks, &first, &epoch(3), &proof_bytes).unwrap_err();
+    }
+
+    #[test]
+    fn checks_max_gap() {
+        let (vk, gamma, delta) = rand_vk_with_trapdoor();
+        let prove = |first: &EpochBlock, last: &EpochBlock| {
+            forge_proof(&vk, gamma, delta, &public_inputs(first, last).unwrap())
+        };
+        let options = VerifyOptions {
+            max_gap: Some(4),
+            ..Default::default()
+        };
+        let verify_with_options = |first, last, proof, options| {
+            verify_with_config(&vk, first, last, proof, &CircuitConfig::default(), options)
+        };
+
+        // at the boundary
+        let (first, last) = (epoch(10), epoch(14));
+        let proof = prove(&first, &last);
+        verify_with_options(&first, &last, &proof, &options).unwrap();
+
+        // one epoch more is rejected even though the proof is valid
+        let last = epoch(15);
+        let proof = prove(&first, &last);
+        verify(&vk, &first, &last, &proof).unwrap();
+        let err = verify_with_options(&first, &last, &proof, &options).unwrap_err();
+        assert!(matches!(
+            err,
+            VerificationError::EpochGapTooLarge { max: 4, got: 5 }
+        ));
+
+        let err = verify_with_options(&last, &first, &proof, &options).unwrap_err();
+        assert!(matches!(err, VerificationError::EpochOrderingError { .. }));
+
+        // the window is checked along with the gap, and must not exceed it
+        let (first, last) = (epoch(10), epoch(13));
+        let proof = prove(&first, &last);
+        let window = |num_epochs| VerifyOptions {
+            num_epochs: Some(num_epochs),
+            ..options
+        };
+        verify_with_options(&first, &last, &proof, &window(3)).unwrap();
+        assert!(matches!(
+            verify_with_options(&first, &last, &proof, &window(4)).unwrap_err(),
+            VerificationError::EpochCountMismatch {
+                expected: 4,
+                got: 3
+            }
+        ));
+        assert!(matches!(
+            verify_with_options(&first, &last, &proof, &window(5)).unwrap_err(),
+            VerificationError::EpochGapTooLarge { max: 4, got: 5 }
+        ));
+    }
+
+    #[test]
+    fn checks_expected_apk_commitment() {
+        let (first, last, other) = (epoch(0), epoch(2), epoch(2));
+        let (vk, gamma, delta) = rand_vk_with_trapdoor();
+        let proof = forge_proof(&vk, gamma, delta, &public_inputs(&first, &last).unwrap());
+        let other_proof = forge_proof(&vk, gamma, delta, &public_inputs(&first, &other).unwrap());
+        let commitment = last.apk_commitment().unwrap();
+        let verify_with_options = |last: &EpochBlock, proof: &Proof<CPCurve>, options| {
+            verify_with_config(
+                &vk,
+                &first,
+                last,
+                proof,
+                &CircuitConfig::default(),
+                &options,
+            )
+        };
+
+        let expecting = |num_epochs| VerifyOptions {
+            num_epochs,
+            expected_last_apk_commitment: Some(commitment),
+            ..Default::default()
+        };
+        verify_with_options(&last, &proof, VerifyOptions::default()).unwrap();
+        verify_with_options(&last, &proof, expecting(None)).unwrap();
+        verify_with_options(&last, &proof, expecting(Some(2))).unwrap();
+
+        // the other proof is valid, but its last epoch is rejected before the pairing check
+        verify_with_options(&other, &other_proof, VerifyOptions::default()).unwrap();
+        assert!(matches!(
+            verify_with_options(&other, &other_proof, expecting(None)).unwrap_err(),
+            VerificationError::ApkCommitmentMismatch
+        ));
+        // the window is checked first
+        assert!(matches!(
+            verify_with_options(&other, &other_proof, expecting(Some(3))).unwrap_err(),
+            VerificationError::EpochCountMismatch { .. }
+        ));
+
+        // without an expected commitment, the proof for another last epoch fails the pairing
+        // check
+        assert!(matches!(
+            verify_with_options(&last, &other_proof, VerifyOptions::default()).unwrap_err(),
+            VerificationError::VerificationFailed
+        ));
+    }
+
+    #[test]
+    fn epoch_math_does_not_overflow() {
+        let rng = &mut rand::thread_rng();
+        let mut epoch = |index| random_epoch_block(rng, 4, index, u32::MAX).0;
+        let (min, max) = (epoch(0), epoch(u16::MAX));
+
+        // the largest gap is not truncated
+        check_gap(usize::MAX, &min, &max).unwrap();
+        assert!(matches!(
+            check_gap(0, &min, &max).unwrap_err(),
+            VerificationError::EpochGapTooLarge { max: 0, got: 65535 }
+        ));
+        assert!(matches!(
+            check_window(usize::MAX, &min, &max).unwrap_err(),
+            VerificationError::EpochCountMismatch {
+                expected: usize::MAX,
+                got: 65535
+            }
+        ));
+        check_window(u16::MAX as usize, &min, &max).unwrap();
+
+        // reversed epochs are rejected instead of wrapping around
+        for err in vec![
+            check_gap(usize::MAX, &max, &min).unwrap_err(),
+            check_window(1, &max, &min).unwrap_err(),
+        ] {
+            assert!(matches!(
+                err,
+                VerificationError::EpochOrderingError {
+                    first: 65535,
+                    last: 0
+                }
+            ));
+        }
+
+        // any bitmap meets the quorum of an epoch which allows `u32::MAX` non signers
+        assert_eq!(max.count_signers(&[false; 4]).unwrap(), 0);
+    }
+
+    #[test]
+    fn verifies_chain() {
+        let epochs = (0..4).map(epoch).collect::<Vec<_>>();
+        let (vk, gamma, delta) = rand_vk_with_trapdoor();
+        let prove = |first: &EpochBlock, last: &EpochBlock| {
+            let proof = forge_proof(&vk, gamma, delta, &public_inputs(first, last).unwrap());
+            let mut bytes = vec![];
+            proof.serialize(&mut bytes).unwrap();
+            bytes
+        };
+        let mut proofs = epochs
+            .windows(2)
+            .map(|pair| prove(&pair[0], &pair[1]))
+            .collect::<Vec<_>>();
+        verify_chain(&vk, &epochs, &proofs).unwrap();
+        verify_chain(&vk, &epochs[..1], &[]).unwrap();
+
+        // a proof for other epochs in the middle of the chain
+        proofs[1] = prove(&epochs[0], &epochs[2]);
+        assert_eq!(verify_chain(&vk, &epochs, &proofs).unwrap_err(), vec![1]);
+        proofs[1] = prove(&epochs[1], &epochs[2]);
+
+        // undecodable, missing and extra proofs
+        let mut truncated = proofs.clone();
+        truncated[2].pop();
+        assert_eq!(verify_chain(&vk, &epochs, &truncated).unwrap_err(), vec![2]);
+        assert_eq!(
+            verify_chain(&vk, &epochs, &proofs[..1]).unwrap_err(),
+            vec![1, 2]
+        );
+        assert_eq!(
+            verify_chain(&vk, &epochs[..3], &proofs).unwrap_err(),
+            vec![2]
+        );
+
+        // each epoch must come after the previous one, so the swapped epochs break the
+        // link into and out of them, along with the one between them
+        let mut swapped = epochs.clone();
+        swapped.swap(1, 2);
+        assert_eq!(
+            verify_chain(&vk, &swapped, &proofs).unwrap_err(),
+            vec![0, 1, 2]
+        );
+    }
+
+    #[test]
+    fn checks_vk_input_count() {
+        let (first, last) = (epoch(0), epoch(2));
+        let (mut vk, proof) = rand_vk_with_proof(&public_inputs(&first, &last).unwrap());
+        verify(&vk, &first, &last, &proof).unwrap();
+
+        // the epochs of a circuit with another packing have more public inputs
+        let config = CircuitConfig {
+            packing: PackingWidth::new(128).unwrap(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            verify_with_config(
+                &vk,
+                &first,
+                &last,
+                &proof,
+                &config,
+                &VerifyOptions::default()
+            )
+            .unwrap_err(),
+            VerificationError::PublicInputMismatch {
+                expected: 2,
+                got: 4
+            }
+        ));
+
+        // a VK for a circuit with more public inputs
+        vk.gamma_abc_g1.push(vk.gamma_abc_g1[1]);
+        assert!(matches!(
+            verify(&vk, &first, &last, &proof).unwrap_err(),
+            VerificationError::PublicInputMismatch {
+                expected: 3,
+                got: 2
+            }
+        ));
+    }
+
+    #[test]
+    fn verifies_with_public_fields() {
+        let (first, last) = (epoch(0), epoch(2));
+        let exposed = CircuitConfig {
+            public_fields: PublicFields {
+                maximum_non_signers: true,
+            },
+            ..Default::default()
+        };
+        let inputs = public_inputs_with_config(&first, &last, &exposed).unwrap();
+        assert_eq!(inputs.len(), 3);
+        assert_eq!(inputs[..2], public_inputs(&first, &last).unwrap()[..]);
+        assert_eq!(inputs[2], CPField::from(1u64));
+
+        let (vk, proof) = rand_vk_with_proof(&inputs);
+        verify_with_config(
+            &vk,
+            &first,
+            &last,
+            &proof,
+            &exposed,
+            &VerifyOptions::default(),
+        )
+        .unwrap();
+        // the default configuration does not expose the field
+        assert!(matches!(
+            verify(&vk, &first, &last, &proof).unwrap_err(),
+            VerificationError::PublicInputMismatch {
+                expected: 3,
+                got: 2
+            }
+        ));
+
+        // and the proofs of the default configuration cannot be verified with the field
+        let (vk, proof) = rand_vk_with_proof(&public_inputs(&first, &last).unwrap());
+        verify_with_config(
+            &vk,
+            &first,
+            &last,
+            &proof,
+            &CircuitConfig::default(),
+            &VerifyOptions::default(),
+        )
+        .unwrap();
+        assert!(matches!(
+            verify_with_config(
+                &vk,
+                &first,
+                &last,
+                &proof,
+                &exposed,
+                &VerifyOptions::default()
+            )
+            .unwrap_err(),
+            VerificationError::PublicInputMismatch {
+                expected: 2,
+                got: 3
+            }
+        ));
+    }
+
+    #[test]
+    fn versioned_public_inputs() {
+        let (first, last) = (epoch(0), epoch(2));
+
+        let config = CircuitConfig {
+            version: CircuitVersion::try_from(1).unwrap(),
+            ..Default::default()
+        };
+        let inputs = public_inputs_with_config(&first, &last, &config).unwrap();
+        assert_eq!(inputs, public_inputs(&first, &last).unwrap());
+
+        let (vk, proof) = rand_vk_with_proof(&inputs);
+        verify_with_config(
+            &vk,
+            &first,
+            &last,
+            &proof,
+            &config,
+            &VerifyOptions::default(),
+        )
+        .unwrap();
+        for version in &[0, 2, 3] {
+            assert!(matches!(
+                CircuitVersion::try_from(*version).unwrap_err(),
+                VerificationError::UnsupportedCircuitVersion(v) if v == *version
+            ));
+        }
+    }
+
+    #[test]
+    fn public_inputs_bind_epoch_indices() {
+        let (first, last) = (epoch(0), epoch(2));
+        let at = |block: &EpochBlock, index| EpochBlock {
+            index,
+            ..block.clone()
+        };
+
+        let inputs = public_inputs(&first, &last).unwrap();
+        // the same validator sets at shifted indices
+        let shifted = public_inputs(&at(&first, 1), &at(&last, 3)).unwrap();
+        assert_ne!(inputs, shifted);
+        let shifted_last = public_inputs(&first, &at(&last, 3)).unwrap();
+        assert_ne!(inputs, shifted_last);
+        // a different maximum number of non signers
+        let other = EpochBlock {
+            maximum_non_signers: 2,
+            ..first.clone()
+        };
+        assert_ne!(inputs, public_inputs(&other, &last).unwrap());
+    }
+
+    #[test]
+    fn rejects_proofs_for_other_epochs() {
+        let (first, middle, last) = (epoch(0), epoch(1), epoch(2));
+        let inputs = public_inputs(&first, &last).unwrap();
+        let (vk, proof) = rand_vk_with_proof(&inputs);
+        verify(&vk, &first, &last, &proof).unwrap();
+        let pvk = prepare_verifying_key(&vk);
+        assert!(verify_proof(&pvk, &proof, &inputs).unwrap());
+
+        // the inputs of another pair of epochs do not match the proof
+        let other_inputs = public_inputs(&first, &middle).unwrap();
+        assert_ne!(inputs, other_inputs);
+        assert!(!verify_proof(&pvk, &proof, &other_inputs).unwrap());
+        assert!(matches!(
+            verify(&vk, &first, &middle, &proof).unwrap_err(),
+            VerificationError::VerificationFailed
+        ));
+
+        // the proof for (0, 2) cannot be replayed for (1, 3) with the same validator sets,
+        // even if the window has the expected length
+        let shift = |block: &EpochBlock| EpochBlock {
+            index: block.index + 1,
+            ..block.clone()
+        };
+        let (shifted_first, shifted_last) = (shift(&first), shift(&last));
+        for err in vec![
+            verify(&vk, &shifted_first, &shifted_last, &proof).unwrap_err(),
+            verify_window(&vk, 2, &shifted_first, &shifted_last, &proof).unwrap_err(),
+        ] {
+            assert!(matches!(err, VerificationError::VerificationFailed));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn batch_verification_finds_invalid_proofs() {
+        let (vk, gamma, delta) = rand_vk_with_trapdoor();
+        let data = (0..3)
+            .map(|i| {
+                let (first, last) = (epoch(4 * i), epoch(4 * i + 2));
+                let proof = forge_proof(&vk, gamma, delta, &public_inputs(&first, &last).unwrap());
+                (proof, first, last)
+            })
+            .collect::<Vec<_>>();
+
+        let batch = data
+            .iter()
+            .map(|(proof, first, last)| (proof, first, last))
+            .collect::<Vec<_>>();
+        verify_many(&vk, &batch).unwrap();
+        verify_many(&vk, &[]).unwrap();
+
+        // the 2nd proof is checked against the epochs of the 3rd one
+        let mut batch = batch;
+        batch[1].1 = &data[2].1;
+        batch[1].2 = &data[2].2;
+        let err = verify_many(&vk, &batch).unwrap_err();
+        assert!(
+            matches!(err, VerificationError::BatchVerificationFailed(ref failed) if failed == &[1])
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn parallel_verifier_matches_sequential() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<PreparedVerifyingKey<CPCurve>>();
+
+        let epochs = (0..4).map(epoch).collect::<Vec<_>>();
+        let (vk, gamma, delta) = rand_vk_with_trapdoor();
+        let prove = |first: &EpochBlock, last: &EpochBlock| {
+            forge_proof(&vk, gamma, delta, &public_inputs(first, last).unwrap())
+        };
+        let valid = prove(&epochs[0], &epochs[1]);
+        let other = prove(&epochs[1], &epochs[3]);
+        let mut tampered = valid.clone();
+        tampered.c = vk.gamma_abc_g1[0];
+
+        let items = [
+            (&valid, &epochs[0], &epochs[1]),
+            (&valid, &epochs[0], &epochs[2]),
+            (&other, &epochs[1], &epochs[3]),
+            (&tampered, &epochs[0], &epochs[1]),
+            // out of order epochs
+            (&other, &epochs[3], &epochs[1]),
+        ];
+        let pvk = prepare_verifying_key(&vk);
+        let sequential = items
+            .iter()
+            .map(|(proof, first, last)| verify_prepared(&pvk, first, last, proof).is_ok())
+            .collect::<Vec<_>>();
+        assert_eq!(sequential, vec![true, false, true, false, false]);
+        assert_eq!(verify_parallel(&vk, &items), sequential);
+        assert!(verify_parallel(&vk, &[]).is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn parallel_miller_loop_matches_serial() {
+        let rng = &mut rand::thread_rng();
+        let pairs: Vec<(
+            <CPCurve as PairingEngine>::G1Prepared,
+            <CPCurve as PairingEngine>::G2Prepared,
+        )> = (0..3)
+            .map(|_| {
+                let g1 = <CPCurve as PairingEngine>::G1Projective::rand(rng).into_affine();
+                let g2 = <CPCurve as PairingEngine>::G2Projective::rand(rng).into_affine();
+                (g1.into(), g2.into())
+            })
+            .collect();
+        assert_eq!(
+            parallel_miller_loop(&pairs),
+            CPCurve::miller_loop(pairs.iter())
+        );
+
+        let inputs = [CPField::rand(rng), CPField::rand(rng)];
+        let (vk, mut proof) = rand_vk_with_proof(&inputs);
+        let pvk = prepare_verifying_key(&vk);
+        assert!(verify_proof(&pvk, &proof, &inputs).unwrap());
+        assert!(verify_proof_parallel(&pvk, &proof, &inputs).unwrap());
+
+        proof.c = vk.gamma_abc_g1[0];
+        assert!(!verify_proof(&pvk, &proof, &inputs).unwrap());
+        assert!(!verify_proof_parallel(&pvk, &proof, &inputs).unwrap());
+    }
+
+    #[test]
+    fn canonicalizes_vk() {
+        let vk = rand_vk();
+        let mut compressed = vec![];
+        vk.serialize(&mut compressed).unwrap();
+        let mut uncompressed = vec![];
+        vk.serialize_uncompressed(&mut uncompressed).unwrap();
+
+        assert_eq!(canonicalize_vk(&compressed).unwrap(), compressed);
+        assert_eq!(canonicalize_vk(&uncompressed).unwrap(), compressed);
+
+        // trailing data
+        let mut trailing = compressed.clone();
+        trailing.push(0);
+        assert!(canonicalize_vk(&trailing).is_err());
+
+        // set the infinity flag on the first element
+        let mut flagged = compressed.clone();
+        let g1_size = vk.alpha_g1.serialized_size();
+        flagged[g1_size - 1] |= 1 << 6;
+        assert!(canonicalize_vk(&flagged).is_err());
+
+        assert!(canonicalize_vk(&compressed[..compressed.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn fingerprints_vk() {
+        let vk = rand_vk();
+        let mut compressed = vec![];
+        vk.serialize(&mut compressed).unwrap();
+        let mut uncompressed = vec![];
+        vk.serialize_uncompressed(&mut uncompressed).unwrap();
+
+        let fingerprint = vk_fingerprint(&compressed).unwrap();
+        assert_eq!(vk_fingerprint(&uncompressed).unwrap(), fingerprint);
+        assert_eq!(fingerprint, *blake2s_simd::blake2s(&compressed).as_array());
+
+        let mut other = vec![];
+        rand_vk().serialize(&mut other).unwrap();
+        assert_ne!(vk_fingerprint(&other).unwrap(), fingerprint);
+        // invalid encodings do not have a fingerprint
+        let mut trailing = compressed.clone();
+        trailing.push(0);
+        assert!(vk_fingerprint(&trailing).is_err());
+    }
+
+    #[test]
+    fn compares_vks() {
+        let vk = rand_vk();
+        let mut compressed = vec![];
+        vk.serialize(&mut compressed).unwrap();
+        let mut uncompressed = vec![];
+        vk.serialize_uncompressed(&mut uncompressed).unwrap();
+
+        assert!(vk_equal(&compressed, &compressed).unwrap());
+        assert!(vk_equal(&compressed, &uncompressed).unwrap());
+        assert!(vk_equal(&uncompressed, &compressed).unwrap());
+
+        // a different key
+        let mut other = vec![];
+        rand_vk().serialize(&mut other).unwrap();
+        assert!(!vk_equal(&compressed, &other).unwrap());
+        // a key with one element changed
+        let mut changed = vk.clone();
+        changed.gamma_abc_g1.swap(1, 2);
+        let mut changed_bytes = vec![];
+        changed.serialize_uncompressed(&mut changed_bytes).unwrap();
+        assert!(!vk_equal(&compressed, &changed_bytes).unwrap());
+
+        assert!(vk_equal(&compressed, &compressed[..compressed.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn verifies_envelope() {
+        use crate::encoding::encode_proof_envelope;
+
+        let (first, last) = (epoch(0), epoch(2));
+        let (vk, proof) = rand_vk_with_proof(&public_inputs(&first, &last).unwrap());
+        let mut vk_bytes = vec![];
+        vk.serialize(&mut vk_bytes).unwrap();
+        let mut proof_bytes = vec![];
+        proof.serialize(&mut proof_bytes).unwrap();
+
+        let envelope = |version: u8, curve: u8| encode_proof_envelope(&proof_bytes, version, curve);
+        let current = CircuitVersion::CURRENT as u8;
+        verify_envelope(
+            &vk_bytes,
+            &first,
+            &last,
+            &envelope(current, PROOF_ENVELOPE_CURVE_SW6),
+        )
+        .unwrap();
+        for version in &[0, 2] {
+            let err = verify_envelope(
+                &vk_bytes,
+                &first,
+                &last,
+                &envelope(*version, PROOF_ENVELOPE_CURVE_SW6),
+            )
+            .unwrap_err();
+            assert!(matches!(
+                err,
+                VerificationError::UnsupportedCircuitVersion(v) if v == u32::from(*version)
+            ));
+        }
+        // other curves and bare proofs
+        for bytes in &[envelope(current, 0), proof_bytes.clone()] {
+            let err = verify_envelope(&vk_bytes, &first, &last, bytes).unwrap_err();
+            assert!(matches!(
+                err,
+                VerificationError::EpochEncodingError(EncodingError::InvalidProofEnvelope)
+            ));
+        }
+    }
+
+    #[test]
+    fn strictness() {
+        let vk = rand_vk();
+        let mut bytes = vec![];
+        vk.serialize(&mut bytes).unwrap();
+        let proof = Proof::<CPCurve>::default();
+        let mut proof_bytes = vec![];
+        proof.serialize(&mut proof_bytes).unwrap();
+        let epoch = |index| EpochBlock::new(index, 0, vec![]);
+
+        // a non canonical VK byte after the key
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        let err = verify_serialized(&trailing, &epoch(0), &epoch(2), &proof_bytes).unwrap_err();
+        assert!(matches!(
+            err,
+            VerificationError::EpochEncodingError(EncodingError::NonCanonical)
+        ));
+        // the lenient mode ignores it and gets to check the proof
+        let err = verify_serialized_with_strictness(
+            &trailing,
+            &epoch(0),
+            &epoch(2),
+            &proof_bytes,
+            Strictness::Lenient,
+        )
+        .unwrap_err();
+        assert!(!matches!(err, VerificationError::EpochEncodingError(_)));
+        // which is the same as for the canonical VK
+        assert_eq!(
+            deserialize_compressed::<VerifyingKey<CPCurve>>(&trailing, Strictness::Lenient)
+                .unwrap(),
+            deserialize_compressed(&bytes, Strictness::Strict).unwrap()
+        );
+
+        // the uncompressed encoding of the VK and of the proof
+        let mut uncompressed = vec![];
+        vk.serialize_uncompressed(&mut uncompressed).unwrap();
+        let mut uncompressed_proof = vec![];
+        proof
+            .serialize_uncompressed(&mut uncompressed_proof)
+            .unwrap();
+        assert!(
+            deserialize_compressed::<VerifyingKey<CPCurve>>(&uncompressed, Strictness::Strict)
+                .is_err()
+        );
+        assert!(
+            deserialize_compressed::<Proof<CPCurve>>(&uncompressed_proof, Strictness::Strict)
+                .is_err()
+        );
+        // is only accepted in lenient mode, where it decodes to the same elements
+        assert_eq!(
+            deserialize_compressed::<VerifyingKey<CPCurve>>(&uncompressed, Strictness::Lenient)
+                .unwrap(),
+            vk
+        );
+        assert_eq!(
+            deserialize_compressed::<Proof<CPCurve>>(&uncompressed_proof, Strictness::Lenient)
+                .unwrap(),
+            proof
+        );
+        let err = verify_serialized_with_strictness(
+            &uncompressed,
+            &epoch(0),
+            &epoch(2),
+            &uncompressed_proof,
+            Strictness::Lenient,
+        )
+        .unwrap_err();
+        assert!(!matches!(err, VerificationError::EpochEncodingError(_)));
+
+        // the infinity flag set on the first element is never accepted
+        let mut flagged = bytes.clone();
+        flagged[vk.alpha_g1.serialized_size() - 1] |= 1 << 6;
+        for strictness in &[Strictness::Strict, Strictness::Lenient] {
+            assert!(
+                deserialize_compressed::<VerifyingKey<CPCurve>>(&flagged, *strictness).is_err()
+            );
+        }
+        assert_eq!(Strictness::default(), Strictness::Strict);
+    }
+
+    #[test]
+    fn links_adjacent_windows() {
+        let proof = Proof::default();
+        let epoch = |index| EpochBlock::new(index, 0, vec![]);
+
+        let linked =
+            link_proofs(&proof, &epoch(0), &epoch(2), &proof, &epoch(2), &epoch(5)).unwrap();
+        assert_eq!(linked.first_epoch, epoch(0));
+        assert_eq!(linked.boundary_epoch, epoch(2));
+        assert_eq!(linked.last_epoch, epoch(5));
+
+        // the second window starts after the first one ends
+        let err =
+            link_proofs(&proof, &epoch(0), &epoch(2), &proof, &epoch(3), &epoch(5)).unwrap_err();
+        assert!(matches!(
+            err,
+            VerificationError::EpochBoundaryMismatch { end: 2, start: 3 }
+        ));
+        // same index, but a different epoch
+        let other = EpochBlock::new(2, 1, vec![]);
+        let err = link_proofs(&proof, &epoch(0), &epoch(2), &proof, &other, &epoch(5)).unwrap_err();
+        assert!(matches!(
+            err,
+            VerificationError::EpochBoundaryMismatch { end: 2, start: 2 }
+        ));
+        // the windows must be ordered
+        let err =
+            link_proofs(&proof, &epoch(3), &epoch(2), &proof, &epoch(2), &epoch(5)).unwrap_err();
+        assert!(matches!(
+            err,
+            VerificationError::EpochOrderingError { first: 3, last: 2 }
+        ));
+
+        let mut linked = linked;
+        linked.last_epoch = epoch(1);
+        let vk = VerifyingKey::default();
+        let err = verify_linked(&vk, &vk, &linked).unwrap_err();
+        assert!(matches!(
+            err,
+            VerificationError::EpochOrderingError { first: 2, last: 1 }
+        ));
+    }
+
+    #[test]
+    fn serialized_sizes() {
+        let vk = rand_vk();
+        let mut bytes = vec![];
+        vk.serialize(&mut bytes).unwrap();
+        assert_eq!(vk_size(&CircuitConfig::default()), bytes.len());
+        let exposed = CircuitConfig {
+            public_fields: PublicFields {
+                maximum_non_signers: true,
+            },
+            ..Default::default()
+        };
+        let mut bytes = vec![];
+        rand_vk_with_inputs(exposed.num_public_inputs())
+            .serialize(&mut bytes)
+            .unwrap();
+        assert_eq!(vk_size(&exposed), bytes.len());
+
+        let mut bytes = vec![];
+        Proof::<CPCurve>::default().serialize(&mut bytes).unwrap();
+        assert_eq!(proof_size(), bytes.len());
+    }
+
+    #[test]
+    fn prepares_serialized_vk() {
+        let vk = rand_vk();
+        let mut bytes = vec![];
+        vk.serialize(&mut bytes).unwrap();
+        let pvk = prepare_vk_from_bytes(&bytes).unwrap();
+        assert_eq!(pvk.vk, vk);
+        assert_eq!(
+            pvk.alpha_g1_beta_g2,
+            prepare_verifying_key(&vk).alpha_g1_beta_g2
+        );
+
+        bytes.push(0);
+        assert!(matches!(
+            prepare_vk_from_bytes(&bytes).unwrap_err(),
+            VerificationError::EpochEncodingError(EncodingError::NonCanonical)
+        ));
+    }
+
+    #[test]
+    fn garbage_is_a_deserialization_error() {
+        let epoch = EpochBlock::new(0, 0, vec![]);
+        let err = verify_serialized(&[1, 2, 3], &epoch, &epoch, &[4, 5, 6]).unwrap_err();
+        assert!(matches!(err, VerificationError::DeserializationError(_)));
+    }
+
+    #[test]
+    fn rejects_misordered_epochs() {
+        let vk = VerifyingKey::default();
+        let proof = Proof::default();
+        let epoch = |index| EpochBlock::new(index, 0, vec![]);
+
+        // equal
+        let err = verify(&vk, &epoch(3), &epoch(3), &proof).unwrap_err();
+        assert!(matches!(
+            err,
+            VerificationError::EpochOrderingError { first: 3, last: 3 }
+        ));
+        // reversed
+        let err = verify(&vk, &epoch(5), &epoch(3), &proof).unwrap_err();
+        assert!(matches!(
+            err,
+            VerificationError::EpochOrderingError { first: 5, last: 3 }
+        ));
+        let err = verify_window(&vk, 2, &epoch(5), &epoch(3), &proof).unwrap_err();
+        assert!(matches!(
+            err,
+            VerificationError::EpochOrderingError { first: 5, last: 3 }
+        ));
+        let err = verify_many(&vk, &[(&proof, &epoch(5), &epoch(3))]).unwrap_err();
+        assert!(matches!(
+            err,
+            VerificationError::EpochOrderingError { first: 5, last: 3 }
+        ));
+        // wrong gap
+        let err = verify_window(&vk, 2, &epoch(3), &epoch(4), &proof).unwrap_err();
+        assert!(matches!(
+            err,
+            VerificationError::EpochCountMismatch {
+                expected: 2,
+                got: 1
+            }
+        ));
+    }
+
+    #[test]
+    fn rejects_mismatched_window() {
+        let first = EpochBlock::new(3, 0, vec![]);
+        let last = EpochBlock::new(5, 0, vec![]);
+        let err = verify_window(
+            &VerifyingKey::default(),
+            3,
+            &first,
+            &last,
+            &Proof::default(),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            VerificationError::EpochCountMismatch {
+                expected: 3,
+                got: 2
+            }
+        ));
+    }
+}
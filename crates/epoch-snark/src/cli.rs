@@ -0,0 +1,207 @@
+//! Loads the VK, the proof and the first and last epochs from files and verifies the proof.
+//!
+//! The VK and the proof files are read as hex if their extension is `.hex`, and as raw bytes
+//! otherwise. Both are expected to be compressed serializations. The epochs are JSON files of
+//! the form:
+//!
+//! ```json
+//! {
+//!     "index": 2,
+//!     "maximum_non_signers": 1,
+//!     "pubkeys": ["<hex of a compressed pubkey>", "..."]
+//! }
+//! ```
+use crate::{verify_serialized, EpochBlock, VerificationError};
+use algebra::CanonicalDeserialize;
+use bls_crypto::PublicKey;
+use serde::Deserialize;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+/// Error raised while loading the verification data from files. A proof which is
+/// rejected by the verifier is not an error.
+pub enum CliError {
+    #[error("could not read {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("invalid hex in {path}: {source}")]
+    Hex {
+        path: PathBuf,
+        source: hex::FromHexError,
+    },
+    #[error("invalid epoch JSON in {path}: {source}")]
+    Json {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    #[error("invalid pubkey {index} in {path}")]
+    InvalidPubkey { path: PathBuf, index: usize },
+    #[error("could not verify the proof: {0}")]
+    Verification(VerificationError),
+}
+
+/// The JSON schema of an epoch file
+#[derive(Deserialize)]
+struct EpochFile {
+    index: u16,
+    maximum_non_signers: u32,
+    pubkeys: Vec<String>,
+}
+
+/// Loads the data from the provided paths and verifies the proof. Returns `Ok(false)` if
+/// the proof was rejected, and an error if any of the files could not be read or parsed.
+pub fn verify_from_paths<P: AsRef<Path>>(
+    vk_path: P,
+    proof_path: P,
+    first_epoch_path: P,
+    last_epoch_path: P,
+) -> Result<bool, CliError> {
+    let vk = read_bytes(vk_path.as_ref())?;
+    let proof = read_bytes(proof_path.as_ref())?;
+    let first_epoch = read_epoch(first_epoch_path.as_ref())?;
+    let last_epoch = read_epoch(last_epoch_path.as_ref())?;
+
+    match verify_serialized(&vk, &first_epoch, &last_epoch, &proof) {
+        Ok(()) => Ok(true),
+        Err(VerificationError::VerificationFailed) => Ok(false),
+        Err(e) => Err(CliError::Verification(e)),
+    }
+}
+
+/// Reads the file as hex if its extension is `.hex`, or as raw bytes otherwise
+pub fn read_bytes(path: &Path) -> Result<Vec<u8>, CliError> {
+    let is_hex = path.extension().map_or(false, |ext| ext == "hex");
+    if is_hex {
+        let contents = read_to_string(path)?;
+        decode_hex(path, &contents)
+    } else {
+        fs::read(path).map_err(|source| CliError::Io {
+            path: path.to_owned(),
+            source,
+        })
+    }
+}
+
+/// Reads an epoch block from a JSON file
+pub fn read_epoch(path: &Path) -> Result<EpochBlock, CliError> {
+    let contents = read_to_string(path)?;
+    let epoch: EpochFile = serde_json::from_str(&contents).map_err(|source| CliError::Json {
+        path: path.to_owned(),
+        source,
+    })?;
+    let pubkeys = epoch
+        .pubkeys
+        .iter()
+        .enumerate()
+        .map(|(index, pubkey)| {
+            let bytes = decode_hex(path, pubkey)?;
+            PublicKey::deserialize(&mut &bytes[..]).map_err(|_| CliError::InvalidPubkey {
+                path: path.to_owned(),
+                index,
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(EpochBlock::new(
+        epoch.index,
+        epoch.maximum_non_signers,
+        pubkeys,
+    ))
+}
+
+fn read_to_string(path: &Path) -> Result<String, CliError> {
+    fs::read_to_string(path).map_err(|source| CliError::Io {
+        path: path.to_owned(),
+        source,
+    })
+}
+
+fn decode_hex(path: &Path, contents: &str) -> Result<Vec<u8>, CliError> {
+    let contents = contents.trim();
+    let contents = contents.trim_start_matches("0x");
+    hex::decode(contents).map_err(|source| CliError::Hex {
+        path: path.to_owned(),
+        source,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use algebra::CanonicalSerialize;
+    use algebra::{bls12_377::G2Projective, ProjectiveCurve, UniformRand};
+    use std::io::Write;
+
+    fn write_file(dir: &Path, name: &str, contents: &[u8]) -> PathBuf {
+        let path = dir.join(name);
+        fs::File::create(&path)
+            .unwrap()
+            .write_all(contents)
+            .unwrap();
+        path
+    }
+
+    #[test]
+    fn reads_epoch_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let rng = &mut rand::thread_rng();
+        let pubkey = PublicKey::from(G2Projective::rand(rng).into_affine().into_projective());
+        let mut bytes = vec![];
+        pubkey.serialize(&mut bytes).unwrap();
+
+        let json = format!(
+            r#"{{"index": 2, "maximum_non_signers": 1, "pubkeys": ["0x{}"]}}"#,
+            hex::encode(&bytes)
+        );
+        let path = write_file(dir.path(), "epoch.json", json.as_bytes());
+        let epoch = read_epoch(&path).unwrap();
+        assert_eq!(epoch, EpochBlock::new(2, 1, vec![pubkey]));
+
+        let path = write_file(dir.path(), "bad.json", b"{\"index\": 2}");
+        assert!(matches!(read_epoch(&path), Err(CliError::Json { .. })));
+
+        let json = r#"{"index": 2, "maximum_non_signers": 1, "pubkeys": ["ffff"]}"#;
+        let path = write_file(dir.path(), "bad_key.json", json.as_bytes());
+        assert!(matches!(
+            read_epoch(&path),
+            Err(CliError::InvalidPubkey { index: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn reads_hex_or_raw_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        let hex_path = write_file(dir.path(), "vk.hex", b"0x0102ff\n");
+        assert_eq!(read_bytes(&hex_path).unwrap(), vec![1, 2, 255]);
+        let raw_path = write_file(dir.path(), "vk.bin", &[1, 2, 255]);
+        assert_eq!(read_bytes(&raw_path).unwrap(), vec![1, 2, 255]);
+
+        let bad_hex = write_file(dir.path(), "proof.hex", b"zz");
+        assert!(matches!(read_bytes(&bad_hex), Err(CliError::Hex { .. })));
+        assert!(matches!(
+            read_bytes(&dir.path().join("missing.bin")),
+            Err(CliError::Io { .. })
+        ));
+    }
+
+    #[test]
+    fn garbage_is_not_a_rejection() {
+        let dir = tempfile::tempdir().unwrap();
+        let vk = write_file(dir.path(), "vk.bin", &[1, 2, 3]);
+        let proof = write_file(dir.path(), "proof.bin", &[1, 2, 3]);
+        let json = br#"{"index": 0, "maximum_non_signers": 0, "pubkeys": []}"#;
+        let epoch = write_file(dir.path(), "epoch.json", json);
+        assert!(matches!(
+            verify_from_paths(&vk, &proof, &epoch, &epoch),
+            Err(CliError::Verification(
+                VerificationError::DeserializationError(_)
+            ))
+        ));
+    }
+}
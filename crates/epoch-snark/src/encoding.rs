@@ -1,20 +1,283 @@
+use crate::Vec;
 use algebra::{
     bls12_377::{Fq, FqParameters},
-    FpParameters, PrimeField, ProjectiveCurve, ToBytes,
+    io, sw6, BigInteger, CanonicalDeserialize, CanonicalSerialize, FpParameters, PrimeField,
+    ProjectiveCurve, ToBytes,
 };
 use bls_crypto::PublicKey;
 use bls_gadgets::utils::bytes_to_bits;
-use byteorder::{LittleEndian, WriteBytesExt};
-use thiserror::Error;
+use core::fmt;
 
 use algebra::serialize::SerializationError;
-#[derive(Debug, Error)]
+#[derive(Debug)]
 /// Union type for data serialization errors
 pub enum EncodingError {
-    #[error("Zexe Error: {0}")]
-    ZexeSerialization(#[from] SerializationError),
-    #[error("I/O Error: {0}")]
-    IoError(#[from] std::io::Error),
+    ZexeSerialization(SerializationError),
+    IoError(io::Error),
+    NonCanonical,
+    /// An epoch has more validators than the circuit supports
+    TooManyValidators {
+        got: usize,
+        max: usize,
+    },
+    /// An epoch has no validators, e.g. because it was passed over the FFI with a
+    /// `pubkeys_num` of 0
+    EmptyValidatorSet,
+    /// The packing width is 0 or larger than the capacity of the public inputs
+    InvalidPackingWidth {
+        bits: u32,
+        max: u32,
+    },
+    /// The bytes do not start with the header of a proof envelope
+    InvalidProofEnvelope,
+    /// A serialized circuit configuration has an unsupported version, exposes unknown fields
+    /// or has an unknown CRH
+    InvalidCircuitConfig,
+    /// A serialized epoch block is shorter or longer than its number of pubkeys requires
+    InvalidEpochBlockLength {
+        expected: u64,
+        got: usize,
+    },
+}
+
+impl fmt::Display for EncodingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncodingError::ZexeSerialization(e) => write!(f, "Zexe Error: {}", e),
+            EncodingError::IoError(e) => write!(f, "I/O Error: {}", e),
+            EncodingError::NonCanonical => write!(f, "Non-canonical encoding"),
+            EncodingError::TooManyValidators { got, max } => write!(
+                f,
+                "Too many validators: got {}, the circuit supports at most {}",
+                got, max
+            ),
+            EncodingError::EmptyValidatorSet => write!(f, "Empty validator set"),
+            EncodingError::InvalidPackingWidth { bits, max } => write!(
+                f,
+                "Invalid packing width: {} bits, must be between 1 and {}",
+                bits, max
+            ),
+            EncodingError::InvalidProofEnvelope => write!(f, "Invalid proof envelope header"),
+            EncodingError::InvalidCircuitConfig => write!(f, "Invalid circuit configuration"),
+            EncodingError::InvalidEpochBlockLength { expected, got } => write!(
+                f,
+                "Invalid epoch block length: got {} bytes, expected {}",
+                got, expected
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EncodingError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EncodingError::ZexeSerialization(e) => Some(e),
+            EncodingError::IoError(e) => Some(e),
+            EncodingError::NonCanonical
+            | EncodingError::TooManyValidators { .. }
+            | EncodingError::EmptyValidatorSet
+            | EncodingError::InvalidPackingWidth { .. }
+            | EncodingError::InvalidProofEnvelope
+            | EncodingError::InvalidCircuitConfig
+            | EncodingError::InvalidEpochBlockLength { .. } => None,
+        }
+    }
+}
+
+impl From<SerializationError> for EncodingError {
+    fn from(e: SerializationError) -> Self {
+        EncodingError::ZexeSerialization(e)
+    }
+}
+
+impl From<io::Error> for EncodingError {
+    fn from(e: io::Error) -> Self {
+        EncodingError::IoError(e)
+    }
+}
+
+/// How strictly serialized elements are checked when deserializing them
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Strictness {
+    /// Only the canonical compressed encoding of the element is accepted, i.e. the input
+    /// must serialize back to exactly the same bytes. Unreduced coordinates, unexpected
+    /// flags and trailing data are rejected.
+    Strict,
+    /// Any input which deserializes to a valid element is accepted, whether it is the
+    /// compressed or the uncompressed serialization of the element, and trailing data is
+    /// ignored, so that the element can be normalized by re-serializing it. Only meant for
+    /// legacy blobs.
+    Lenient,
+}
+
+impl Default for Strictness {
+    fn default() -> Self {
+        Strictness::Strict
+    }
+}
+
+/// Deserializes a compressed element, rejecting non-canonical encodings in strict mode
+/// with `EncodingError::NonCanonical`. In lenient mode, the uncompressed serialization
+/// of the element is accepted as well.
+pub fn deserialize_compressed<T: CanonicalSerialize + CanonicalDeserialize>(
+    bytes: &[u8],
+    strictness: Strictness,
+) -> Result<T, EncodingError> {
+    match strictness {
+        Strictness::Strict => {
+            let element = T::deserialize(&mut &bytes[..])?;
+            let mut canonical = Vec::with_capacity(bytes.len());
+            element.serialize(&mut canonical)?;
+            if canonical != bytes {
+                return Err(EncodingError::NonCanonical);
+            }
+            Ok(element)
+        }
+        Strictness::Lenient => Ok(deserialize_any(bytes, false)?.0),
+    }
+}
+
+/// Deserializes an element from either its uncompressed or its compressed serialization, and
+/// returns it along with whether it was compressed. If `exact`, the serialization must span
+/// all of `bytes` and `EncodingError::NonCanonical` is returned otherwise, else trailing data
+/// is ignored.
+///
+/// The uncompressed encoding is tried first, since the x coordinate at the start of an
+/// uncompressed point may also be read as a compressed point, with the wrong sign.
+pub(crate) fn deserialize_any<T: CanonicalDeserialize>(
+    bytes: &[u8],
+    exact: bool,
+) -> Result<(T, bool), EncodingError> {
+    let mut reader = bytes;
+    if let Ok(element) = T::deserialize_uncompressed(&mut reader) {
+        if !exact || reader.is_empty() {
+            return Ok((element, false));
+        }
+    }
+
+    let mut reader = bytes;
+    let element = T::deserialize(&mut reader)?;
+    if exact && !reader.is_empty() {
+        return Err(EncodingError::NonCanonical);
+    }
+    Ok((element, true))
+}
+
+/// The number of bits of the first and last epochs' hash which the epochs circuit packs
+/// into each of its public inputs. The prover and the verifier must use the same width, since
+/// it determines the public inputs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PackingWidth(u32);
+
+impl PackingWidth {
+    /// The width used by the Celo network, i.e. the capacity of an SW6 scalar
+    pub const DEFAULT: PackingWidth = PackingWidth(<sw6::FrParameters as FpParameters>::CAPACITY);
+
+    /// Fails with `EncodingError::InvalidPackingWidth` if `bits` is 0 or does not fit in
+    /// an SW6 scalar
+    pub fn new(bits: u32) -> Result<Self, EncodingError> {
+        if bits == 0 || bits > Self::DEFAULT.0 {
+            return Err(EncodingError::InvalidPackingWidth {
+                bits,
+                max: Self::DEFAULT.0,
+            });
+        }
+        Ok(PackingWidth(bits))
+    }
+
+    /// The number of bits per public input
+    pub fn bits(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl Default for PackingWidth {
+    fn default() -> Self {
+        PackingWidth::DEFAULT
+    }
+}
+
+/// The fields of the last epoch which the epochs circuit exposes as public inputs, after the
+/// packed hash of the first and last epochs. All other fields, and the signers' bitmaps, are
+/// private witnesses, which are only committed to by the hash.
+///
+/// The default exposes no field, which is the configuration of the Celo network. The prover
+/// and the verifier must use the same configuration, since it determines the public inputs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PublicFields {
+    /// Exposes the last epoch's maximum number of non signers
+    pub maximum_non_signers: bool,
+}
+
+/// The magic bytes at the start of a proof envelope
+pub const PROOF_ENVELOPE_MAGIC: [u8; 4] = *b"CSNK";
+
+/// The identifier of the SW6 curve in a proof envelope, which is the curve of the epochs
+/// proofs
+pub const PROOF_ENVELOPE_CURVE_SW6: u8 = 1;
+
+/// Length of the header of a proof envelope, i.e. of the magic bytes followed by the version
+/// and curve bytes
+const PROOF_ENVELOPE_HEADER_BYTES: usize = PROOF_ENVELOPE_MAGIC.len() + 2;
+
+/// A serialized proof along with the metadata needed to interpret it, as decoded by
+/// [`decode_proof_envelope`](fn.decode_proof_envelope.html)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProofEnvelope<'a> {
+    /// The version of the circuit which the proof was generated with
+    pub version: u8,
+    /// The curve of the proof's elements, e.g. `PROOF_ENVELOPE_CURVE_SW6`
+    pub curve: u8,
+    /// The serialized proof
+    pub proof: &'a [u8],
+}
+
+/// Wraps the serialized proof in an envelope, i.e. prepends `PROOF_ENVELOPE_MAGIC` followed
+/// by the version and the curve bytes, so that consumers can tell how to interpret it
+pub fn encode_proof_envelope(proof: &[u8], version: u8, curve: u8) -> Vec<u8> {
+    let mut envelope = Vec::with_capacity(PROOF_ENVELOPE_HEADER_BYTES + proof.len());
+    envelope.extend_from_slice(&PROOF_ENVELOPE_MAGIC);
+    envelope.push(version);
+    envelope.push(curve);
+    envelope.extend_from_slice(proof);
+    envelope
+}
+
+/// Splits an envelope created with [`encode_proof_envelope`](fn.encode_proof_envelope.html)
+/// into its metadata and the serialized proof. Fails with
+/// `EncodingError::InvalidProofEnvelope` if the bytes do not start with the magic bytes.
+///
+/// The version and the curve are not checked, since only the consumer knows which ones it
+/// supports.
+pub fn decode_proof_envelope(envelope: &[u8]) -> Result<ProofEnvelope<'_>, EncodingError> {
+    if envelope.len() < PROOF_ENVELOPE_HEADER_BYTES
+        || envelope[..PROOF_ENVELOPE_MAGIC.len()] != PROOF_ENVELOPE_MAGIC
+    {
+        return Err(EncodingError::InvalidProofEnvelope);
+    }
+    Ok(ProofEnvelope {
+        version: envelope[PROOF_ENVELOPE_MAGIC.len()],
+        curve: envelope[PROOF_ENVELOPE_MAGIC.len() + 1],
+        proof: &envelope[PROOF_ENVELOPE_HEADER_BYTES..],
+    })
+}
+
+/// Packs the bits to field elements of `P::CAPACITY` bits each
+pub(crate) fn pack<F: PrimeField, P: FpParameters>(values: &[bool]) -> Vec<F> {
+    pack_with_width(values, P::CAPACITY as usize)
+}
+
+/// Packs the bits to field elements of `width` bits each, which must not be larger than
+/// the field's capacity
+pub(crate) fn pack_with_width<F: PrimeField>(values: &[bool], width: usize) -> Vec<F> {
+    values
+        .chunks(width)
+        .map(|c| {
+            let b = F::BigInt::from_bits(c);
+            F::from_repr(b)
+        })
+        .collect::<Vec<_>>()
 }
 
 /// The function assumes that the public key is not the point in infinity, which is true for
@@ -46,11 +309,10 @@ pub fn encode_public_key(public_key: &PublicKey) -> Result<Vec<bool>, EncodingEr
 
 /// LE Encodes a U16 to **bits**
 pub(crate) fn encode_u16(num: u16) -> Result<Vec<bool>, EncodingError> {
-    let mut bytes = vec![];
-    bytes.write_u16::<LittleEndian>(num)?;
+    let bytes = num.to_le_bytes();
     let bits = bytes
-        .into_iter()
-        .map(|x| (0..8).map(move |i| (((x as u16) & u16::pow(2, i)) >> i) == 1))
+        .iter()
+        .map(|x| (0..8).map(move |i| (((*x as u16) & u16::pow(2, i)) >> i) == 1))
         .flatten()
         .collect::<Vec<_>>();
     Ok(bits)
@@ -58,11 +320,10 @@ pub(crate) fn encode_u16(num: u16) -> Result<Vec<bool>, EncodingError> {
 
 /// LE Encodes a U32 to **bits**
 pub(crate) fn encode_u32(num: u32) -> Result<Vec<bool>, EncodingError> {
-    let mut bytes = vec![];
-    bytes.write_u32::<LittleEndian>(num)?;
+    let bytes = num.to_le_bytes();
     let bits = bytes
-        .into_iter()
-        .map(|x| (0..8).map(move |i| (((x as u32) & u32::pow(2, i)) >> i) == 1))
+        .iter()
+        .map(|x| (0..8).map(move |i| (((*x as u32) & u32::pow(2, i)) >> i) == 1))
         .flatten()
         .collect::<Vec<_>>();
     Ok(bits)
@@ -71,12 +332,80 @@ pub(crate) fn encode_u32(num: u32) -> Result<Vec<bool>, EncodingError> {
 #[cfg(test)]
 mod test {
     use super::*;
-    use algebra::{bls12_377::FqParameters, FpParameters};
+    use algebra::{
+        bls12_377::{FqParameters, G1Affine, G1Projective},
+        FpParameters, ProjectiveCurve, UniformRand,
+    };
     use bls_gadgets::utils::bits_to_bytes;
     use byteorder::{LittleEndian, WriteBytesExt};
     use rand::{Rng, SeedableRng};
     use rand_xorshift::XorShiftRng;
 
+    #[test]
+    fn proof_envelope_roundtrip() {
+        let proof = [7u8; 10];
+        let envelope = encode_proof_envelope(&proof, 1, PROOF_ENVELOPE_CURVE_SW6);
+        assert_eq!(&envelope[..6], b"CSNK\x01\x01");
+        assert_eq!(
+            decode_proof_envelope(&envelope).unwrap(),
+            ProofEnvelope {
+                version: 1,
+                curve: PROOF_ENVELOPE_CURVE_SW6,
+                proof: &proof,
+            }
+        );
+        // an empty proof still has a header
+        let empty = encode_proof_envelope(&[], 1, 0);
+        assert!(decode_proof_envelope(&empty).unwrap().proof.is_empty());
+    }
+
+    #[test]
+    fn rejects_proof_envelope_without_magic() {
+        let mut envelope = encode_proof_envelope(&[7u8; 10], 1, PROOF_ENVELOPE_CURVE_SW6);
+        envelope[0] ^= 1;
+        assert!(matches!(
+            decode_proof_envelope(&envelope).unwrap_err(),
+            EncodingError::InvalidProofEnvelope
+        ));
+        // bare proofs and truncated headers
+        for bytes in &[&[7u8; 10][..], &b"CSNK\x01"[..], &[][..]] {
+            assert!(matches!(
+                decode_proof_envelope(bytes).unwrap_err(),
+                EncodingError::InvalidProofEnvelope
+            ));
+        }
+    }
+
+    #[test]
+    fn deserializes_either_encoding() {
+        let rng = &mut rand::thread_rng();
+        let point = G1Projective::rand(rng).into_affine();
+        let mut compressed = vec![];
+        point.serialize(&mut compressed).unwrap();
+        let mut uncompressed = vec![];
+        point.serialize_uncompressed(&mut uncompressed).unwrap();
+
+        assert_eq!(
+            deserialize_any::<G1Affine>(&compressed, true).unwrap(),
+            (point, true)
+        );
+        assert_eq!(
+            deserialize_any::<G1Affine>(&uncompressed, true).unwrap(),
+            (point, false)
+        );
+
+        // trailing data is only accepted if the serialization does not have to be exact
+        uncompressed.push(0);
+        assert!(matches!(
+            deserialize_any::<G1Affine>(&uncompressed, true).unwrap_err(),
+            EncodingError::NonCanonical
+        ));
+        assert_eq!(
+            deserialize_any::<G1Affine>(&uncompressed, false).unwrap(),
+            (point, false)
+        );
+    }
+
     #[test]
     fn test_bytes_to_bits() {
         let mut rng = XorShiftRng::from_seed([
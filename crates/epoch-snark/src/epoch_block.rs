@@ -1,11 +1,44 @@
-use super::encoding::{encode_public_key, encode_u16, encode_u32, EncodingError};
-use algebra::bls12_377::G1Projective;
-use blake2s_simd::Params;
+use super::encoding::{
+    deserialize_compressed, encode_public_key, encode_u16, encode_u32, EncodingError, Strictness,
+};
+use crate::{String, ToOwned, Vec};
+use algebra::SerializationError;
+use algebra::{
+    bls12_377::{G1Projective, Parameters},
+    curves::bls12::Bls12Parameters,
+    CanonicalSerialize, ProjectiveCurve,
+};
+use blake2s_simd::{Params, State};
 use bls_crypto::{
-    hash_to_curve::{try_and_increment::COMPOSITE_HASH_TO_G1, HashToCurve},
-    PublicKey, Signature, OUT_DOMAIN, SIG_DOMAIN,
+    hash_to_curve::{
+        try_and_increment::{SnarkCrh, TryAndIncrement},
+        HashToCurve,
+    },
+    hashers::DirectHasher,
+    Domain, PublicKey, Signature, OUT_DOMAIN,
 };
 use bls_gadgets::utils::{bits_to_bytes, bytes_to_bits};
+use core::{convert::TryFrom, fmt};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "std")]
+use std::io::{self, Read, Write};
+
+/// The maximum number of validators per epoch which the circuit supports, since the bitmap
+/// gadget counts the non signers of an epoch in a `u8`
+pub const MAX_VALIDATORS: usize = 255;
+
+/// Number of bytes of each compressed pubkey shown when formatting an `EpochBlock`
+const PUBKEY_PREFIX_BYTES: usize = 4;
+
+/// The domain in which the padding public key is hashed to G2
+const PADDING_DOMAIN: &[u8] = b"ULforpad";
+
+/// Length of a compressed public key
+const COMPRESSED_PUBKEY_BYTES: usize = 96;
+
+/// Length of the header of a serialized block, i.e. of its index, its maximum number of non
+/// signers and its number of pubkeys
+pub const EPOCH_BLOCK_HEADER_BYTES: usize = 2 + 4 + 8;
 
 /// A header as parsed after being fetched from the Celo Blockchain
 /// It contains information about the new epoch, as well as an aggregated
@@ -22,14 +55,44 @@ pub struct EpochTransition {
     pub bitmap: Vec<bool>,
 }
 
+impl EpochTransition {
+    /// Returns the transition with its block [`padded`](struct.EpochBlock.html#method.padded)
+    /// to `num_validators` validators, and its bitmap extended with unset entries to as many,
+    /// i.e. for the previous epoch padded the same way. The aggregate signature must be over
+    /// the padded block.
+    ///
+    /// Fails with `EncodingError::TooManyValidators` if the block or the bitmap are longer than
+    /// `num_validators`.
+    pub fn padded(&self, num_validators: usize) -> Result<EpochTransition, EncodingError> {
+        if self.bitmap.len() > num_validators {
+            return Err(EncodingError::TooManyValidators {
+                got: self.bitmap.len(),
+                max: num_validators,
+            });
+        }
+        let mut bitmap = self.bitmap.clone();
+        bitmap.resize(num_validators, false);
+        Ok(EpochTransition {
+            block: self.block.padded(num_validators)?,
+            aggregate_signature: self.aggregate_signature.clone(),
+            bitmap,
+        })
+    }
+}
+
 /// Metadata about the next epoch
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct EpochBlock {
     /// The block number
     pub index: u16,
-    /// The maximum allowed number of signers that may be absent
+    /// The maximum allowed number of signers that may be absent (inclusive)
     pub maximum_non_signers: u32,
-    /// The public keys of the new validators
+    /// The public keys of the new validators, in the order of the validator set.
+    ///
+    /// The order is part of the block's encoding and hashes, and must not be normalized
+    /// (e.g. sorted), since the bitmaps signed over the next epoch refer to the validators by
+    /// their position. Only the aggregated public key does not depend on it.
+    #[serde(with = "compressed_pubkeys")]
     pub new_public_keys: Vec<PublicKey>,
 }
 
@@ -43,15 +106,151 @@ impl EpochBlock {
         }
     }
 
+    /// Returns the public keys of the new validators, in the order of the validator set
+    pub fn pubkeys(&self) -> &[PublicKey] {
+        &self.new_public_keys
+    }
+
+    /// Returns the compressed serializations of the new validators' public keys, in the
+    /// order of the validator set
+    pub fn compressed_pubkeys(&self) -> Result<Vec<Vec<u8>>, EncodingError> {
+        self.new_public_keys
+            .iter()
+            .map(|pubkey| {
+                let mut bytes = vec![];
+                pubkey.serialize(&mut bytes)?;
+                Ok(bytes)
+            })
+            .collect()
+    }
+
+    /// Returns the index of the epoch `window` epochs after this one, or `None` if it would
+    /// overflow the `u16` index.
+    pub fn next_index(&self, window: usize) -> Option<u16> {
+        u16::try_from(window)
+            .ok()
+            .and_then(|window| self.index.checked_add(window))
+    }
+
+    /// Returns `true` if `other` is the epoch `window` epochs after this one, e.g. the next
+    /// epoch for a window of 1
+    pub fn is_consecutive_with(&self, other: &EpochBlock, window: usize) -> bool {
+        self.next_index(window) == Some(other.index)
+    }
+
+    /// Returns the block padded to `num_validators` validators, i.e. with the
+    /// [`padding_public_key`](fn.padding_public_key.html) appended until it has
+    /// `num_validators` public keys, and its maximum number of non signers increased by the
+    /// number of appended keys, so that its quorum stays the same.
+    ///
+    /// This is how the prover and the verifier fit epochs with fewer validators into a
+    /// circuit for `num_validators` validators, so the previous epoch's validators must sign
+    /// the padded block. No one can sign for the padding keys, so their entries in the next
+    /// bitmap are unset. Fails with `EncodingError::TooManyValidators` if the block has more
+    /// than `num_validators` public keys.
+    pub fn padded(&self, num_validators: usize) -> Result<EpochBlock, EncodingError> {
+        let num_pubkeys = self.new_public_keys.len();
+        if num_pubkeys > num_validators {
+            return Err(EncodingError::TooManyValidators {
+                got: num_pubkeys,
+                max: num_validators,
+            });
+        }
+        let mut padded = self.clone();
+        let padding = num_validators - num_pubkeys;
+        if padding > 0 {
+            padded
+                .new_public_keys
+                .resize(num_validators, padding_public_key());
+            padded.maximum_non_signers = self.maximum_non_signers.saturating_add(padding as u32);
+        }
+        Ok(padded)
+    }
+
+    /// Checks that enough of this epoch's validators signed, i.e. that at most
+    /// `maximum_non_signers` entries of the bitmap are unset. The bitmap must have an entry
+    /// for each of the epoch's public keys.
+    ///
+    /// The bound is inclusive: a bitmap with exactly `maximum_non_signers` unset entries meets
+    /// the quorum, and one with `maximum_non_signers + 1` does not.
+    ///
+    /// Since an epoch's validators sign the next epoch's block, this is the check which the
+    /// circuit performs on the next transition's bitmap, and both accept the same bitmaps.
+    pub fn check_quorum(&self, signer_bitmap: &[bool]) -> Result<(), EpochBlockError> {
+        self.count_signers(signer_bitmap).map(|_| ())
+    }
+
+    /// Same as [`check_quorum`](#method.check_quorum), but returns the number of set entries
+    /// of the bitmap, i.e. the number of this epoch's validators which signed
+    pub fn count_signers(&self, signer_bitmap: &[bool]) -> Result<u32, EpochBlockError> {
+        let num_validators = self.new_public_keys.len();
+        if signer_bitmap.len() != num_validators {
+            return Err(EpochBlockError::BitmapLengthMismatch(
+                signer_bitmap.len(),
+                num_validators,
+            ));
+        }
+        let signers = signer_bitmap.iter().filter(|signed| **signed).count();
+        let required = self.min_signers() as usize;
+        if signers < required {
+            return Err(EpochBlockError::NotEnoughSigners { signers, required });
+        }
+        Ok(signers as u32)
+    }
+
+    /// Returns the quorum of the epoch, i.e. the number of its validators which must sign for
+    /// a bitmap to pass [`check_quorum`](#method.check_quorum)
+    pub fn min_signers(&self) -> u32 {
+        (self.new_public_keys.len() as u32).saturating_sub(self.maximum_non_signers)
+    }
+
+    /// Checks that the bitmap satisfies the quorum like [`check_quorum`](#method.check_quorum),
+    /// and that `aggregate_pubkey` is the sum of the public keys whose bitmap entry is set,
+    /// i.e. that it is the key which the signers' aggregate signature verifies against.
+    pub fn check_signers(
+        &self,
+        signer_bitmap: &[bool],
+        aggregate_pubkey: &PublicKey,
+    ) -> Result<(), EpochBlockError> {
+        self.check_quorum(signer_bitmap)?;
+        let signers = self
+            .new_public_keys
+            .iter()
+            .zip(signer_bitmap)
+            .filter(|(_, signed)| **signed)
+            .map(|(pubkey, _)| pubkey);
+        if PublicKey::aggregate(signers) != *aggregate_pubkey {
+            return Err(EpochBlockError::AggregatePubkeyMismatch);
+        }
+        Ok(())
+    }
+
     /// Encodes the block to bytes and then proceeds to hash it to BLS12-377's G1
     /// group using `SIG_DOMAIN` as a domain separator
     pub fn hash_to_g1(&self) -> Result<G1Projective, EncodingError> {
+        self.hash_to_g1_with_domain(Domain::default())
+    }
+
+    /// Same as [`hash_to_g1`](#method.hash_to_g1), but uses the provided domain separator,
+    /// which must match the one the proving parameters were generated with
+    pub fn hash_to_g1_with_domain(&self, domain: Domain) -> Result<G1Projective, EncodingError> {
         let input = self.encode_to_bytes()?;
-        let expected_hash: G1Projective =
-            COMPOSITE_HASH_TO_G1.hash(SIG_DOMAIN, &input, &[]).unwrap();
+        let expected_hash: G1Projective = SnarkCrh::CURRENT
+            .hash(domain.as_bytes(), &input, &[])
+            .unwrap();
         Ok(expected_hash)
     }
 
+    /// Returns the compressed serialization of the G1 message which the validators of the
+    /// previous epoch sign for this block, i.e. the output of [`hash_to_g1`](#method.hash_to_g1).
+    ///
+    /// This is the same point which the circuit computes from the block's bits.
+    pub fn hash_to_message(&self) -> Result<Vec<u8>, EncodingError> {
+        let mut bytes = vec![];
+        self.hash_to_g1()?.into_affine().serialize(&mut bytes)?;
+        Ok(bytes)
+    }
+
     /// Encodes the block to bytes and then hashes it with Blake2
     pub fn blake2(&self) -> Result<Vec<bool>, EncodingError> {
         Ok(hash_to_bits(&self.encode_to_bytes()?))
@@ -62,7 +261,36 @@ impl EpochBlock {
         Ok(hash_to_bits(&self.encode_to_bytes_with_aggregated_pk()?))
     }
 
-    /// Encodes the block to LE bits
+    /// Returns the commitment to the block, i.e. the [`blake2`](#method.blake2) bits packed to
+    /// LE bytes.
+    ///
+    /// For the first epoch of a proof, these are the bits which make up the first half of the
+    /// hash that the verifier packs into the public inputs.
+    pub fn commitment(&self) -> Result<[u8; 32], EncodingError> {
+        let mut commitment = [0u8; 32];
+        commitment.copy_from_slice(&bits_to_bytes(&self.blake2()?));
+        Ok(commitment)
+    }
+
+    /// Returns the commitment to the block and its aggregated public key, i.e. the
+    /// [`blake2_with_aggregated_pk`](#method.blake2_with_aggregated_pk) bits packed to LE
+    /// bytes.
+    ///
+    /// For the last epoch of a proof, these are the bits which the circuit computes from the
+    /// epoch and the sum of its pubkeys, and which make up the second half of the hash that
+    /// the verifier packs into the public inputs.
+    pub fn apk_commitment(&self) -> Result<[u8; 32], EncodingError> {
+        let mut commitment = [0u8; 32];
+        commitment.copy_from_slice(&bits_to_bytes(&self.blake2_with_aggregated_pk()?));
+        Ok(commitment)
+    }
+
+    /// Encodes the block to LE bits: the 16 bits of the index, then the 32 bits of the
+    /// maximum number of non signers, each least significant bit first, followed by the
+    /// encoded public keys.
+    ///
+    /// The endianness is fixed by the circuit, which decomposes both integers to LE bits
+    /// before hashing them, so it is not configurable.
     pub fn encode_to_bits(&self) -> Result<Vec<bool>, EncodingError> {
         let mut epoch_bits = vec![];
         epoch_bits.extend_from_slice(&encode_u16(self.index)?);
@@ -81,7 +309,13 @@ impl EpochBlock {
         Ok(epoch_bits)
     }
 
-    /// Encodes the block to LE bytes
+    /// Encodes the block to LE bytes, i.e. packs the reversed
+    /// [`encode_to_bits`](#method.encode_to_bits) to bytes, least significant bit first.
+    ///
+    /// These are the bytes which get hashed to G1 and signed, so signers in other languages
+    /// must reproduce the packing exactly. Because the whole bitstring is reversed, the index
+    /// and maximum number of non signers end up in the last bytes, bit reversed, and are only
+    /// byte aligned if the length of the encoded public keys is a multiple of 8 bits.
     pub fn encode_to_bytes(&self) -> Result<Vec<u8>, EncodingError> {
         Ok(bits_to_bytes(&self.encode_to_bits()?))
     }
@@ -90,6 +324,394 @@ impl EpochBlock {
     pub fn encode_to_bytes_with_aggregated_pk(&self) -> Result<Vec<u8>, EncodingError> {
         Ok(bits_to_bytes(&self.encode_to_bits_with_aggregated_pk()?))
     }
+
+    /// Serializes the block as its LE index (2 bytes), its LE maximum number of non signers
+    /// (4 bytes) and its LE number of pubkeys (8 bytes), followed by the compressed pubkeys.
+    ///
+    /// Unlike [`encode_to_bytes`](#method.encode_to_bytes), this is not what gets signed but
+    /// the format in which blocks are stored and passed over the FFI. Fails with
+    /// `EncodingError::TooManyValidators` if the block has more than `MAX_VALIDATORS` pubkeys.
+    pub fn serialize_to_bytes(&self) -> Result<Vec<u8>, EncodingError> {
+        let num_pubkeys = self.new_public_keys.len();
+        if num_pubkeys > MAX_VALIDATORS {
+            return Err(EncodingError::TooManyValidators {
+                got: num_pubkeys,
+                max: MAX_VALIDATORS,
+            });
+        }
+        let mut bytes =
+            Vec::with_capacity(EPOCH_BLOCK_HEADER_BYTES + num_pubkeys * COMPRESSED_PUBKEY_BYTES);
+        bytes.extend_from_slice(&self.index.to_le_bytes());
+        bytes.extend_from_slice(&self.maximum_non_signers.to_le_bytes());
+        bytes.extend_from_slice(&(num_pubkeys as u64).to_le_bytes());
+        for pubkey in &self.new_public_keys {
+            pubkey.serialize(&mut bytes)?;
+        }
+        Ok(bytes)
+    }
+
+    /// Deserializes a block serialized with [`serialize_to_bytes`](#method.serialize_to_bytes),
+    /// checking that its pubkeys are canonically encoded and in the prime order subgroup.
+    ///
+    /// Fails with `EncodingError::InvalidEpochBlockLength` if the bytes do not span exactly
+    /// the number of pubkeys in the header, and with `EncodingError::TooManyValidators` if
+    /// there are more than `MAX_VALIDATORS`, in both cases without reading the pubkeys.
+    pub fn deserialize_from_bytes(bytes: &[u8]) -> Result<EpochBlock, EncodingError> {
+        if bytes.len() < EPOCH_BLOCK_HEADER_BYTES {
+            return Err(EncodingError::InvalidEpochBlockLength {
+                expected: EPOCH_BLOCK_HEADER_BYTES as u64,
+                got: bytes.len(),
+            });
+        }
+        let (header, pubkeys) = bytes.split_at(EPOCH_BLOCK_HEADER_BYTES);
+        let mut index = [0u8; 2];
+        index.copy_from_slice(&header[..2]);
+        let mut maximum_non_signers = [0u8; 4];
+        maximum_non_signers.copy_from_slice(&header[2..6]);
+        let mut num_pubkeys = [0u8; 8];
+        num_pubkeys.copy_from_slice(&header[6..]);
+
+        // the count is checked against the input before anything is allocated for it
+        let num_pubkeys = u64::from_le_bytes(num_pubkeys);
+        let expected = num_pubkeys
+            .saturating_mul(COMPRESSED_PUBKEY_BYTES as u64)
+            .saturating_add(EPOCH_BLOCK_HEADER_BYTES as u64);
+        if expected != bytes.len() as u64 {
+            return Err(EncodingError::InvalidEpochBlockLength {
+                expected,
+                got: bytes.len(),
+            });
+        }
+        let num_pubkeys = num_pubkeys as usize;
+        if num_pubkeys > MAX_VALIDATORS {
+            return Err(EncodingError::TooManyValidators {
+                got: num_pubkeys,
+                max: MAX_VALIDATORS,
+            });
+        }
+        let new_public_keys = pubkeys
+            .chunks(COMPRESSED_PUBKEY_BYTES)
+            .map(|bytes| {
+                let pubkey: PublicKey = deserialize_compressed(bytes, Strictness::Strict)?;
+                if !pubkey.is_in_correct_subgroup() {
+                    return Err(SerializationError::InvalidData.into());
+                }
+                Ok(pubkey)
+            })
+            .collect::<Result<Vec<_>, EncodingError>>()?;
+
+        Ok(EpochBlock::new(
+            u16::from_le_bytes(index),
+            u32::from_le_bytes(maximum_non_signers),
+            new_public_keys,
+        ))
+    }
+}
+
+/// Returns the public key which epochs are [`padded`](struct.EpochBlock.html#method.padded)
+/// with, i.e. the hash of the empty message to G2 in the `ULforpad` domain. Its discrete
+/// logarithm is unknown, so no one can sign in place of a padding validator.
+pub fn padding_public_key() -> PublicKey {
+    let hasher =
+        TryAndIncrement::<_, <Parameters as Bls12Parameters>::G2Parameters>::new(&DirectHasher);
+    let point = hasher
+        .hash(PADDING_DOMAIN, &[], &[])
+        .expect("the padding key's hash is known to be on the curve");
+    PublicKey::from(point)
+}
+
+#[cfg(feature = "std")]
+impl EpochBlock {
+    /// Writes the block prefixed with its length as a LE `u32`, so that a sequence of blocks
+    /// can be stored in a stream and read back with [`read_framed`](#method.read_framed).
+    ///
+    /// The block itself is serialized with [`serialize_to_bytes`](#method.serialize_to_bytes).
+    pub fn write_framed<W: Write>(&self, w: &mut W) -> Result<(), EncodingError> {
+        let frame = self.serialize_to_bytes()?;
+        w.write_all(&(frame.len() as u32).to_le_bytes())?;
+        w.write_all(&frame)?;
+        Ok(())
+    }
+
+    /// Reads a block written with [`write_framed`](#method.write_framed), which is
+    /// deserialized with [`deserialize_from_bytes`](#method.deserialize_from_bytes).
+    ///
+    /// Returns `Ok(None)` if the stream ended cleanly before the next block, and fails with an
+    /// `UnexpectedEof` I/O error if it ended in the middle of one.
+    pub fn read_framed<R: Read>(r: &mut R) -> Result<Option<EpochBlock>, EncodingError> {
+        let mut len = [0u8; 4];
+        let mut read = 0;
+        while read < len.len() {
+            match r.read(&mut len[read..]) {
+                Ok(0) if read == 0 => return Ok(None),
+                Ok(0) => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+                Ok(n) => read += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        // the frame's length is checked before allocating it
+        let len = u32::from_le_bytes(len) as usize;
+        let max_len = EPOCH_BLOCK_HEADER_BYTES + MAX_VALIDATORS * COMPRESSED_PUBKEY_BYTES;
+        if len > max_len {
+            return Err(EncodingError::InvalidEpochBlockLength {
+                expected: max_len as u64,
+                got: len,
+            });
+        }
+        let mut frame = vec![0u8; len];
+        r.read_exact(&mut frame)?;
+
+        Ok(Some(EpochBlock::deserialize_from_bytes(&frame)?))
+    }
+}
+
+/// Prints the pubkeys as short hex prefixes of their compressed serialization
+/// instead of the full points
+impl fmt::Debug for EpochBlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EpochBlock")
+            .field("index", &self.index)
+            .field("maximum_non_signers", &self.maximum_non_signers)
+            .field("num_pubkeys", &self.new_public_keys.len())
+            .field("pubkeys", &PubkeyPrefixes(&self.new_public_keys))
+            .finish()
+    }
+}
+
+impl fmt::Display for EpochBlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "epoch {} ({} pubkeys, at most {} non signers)",
+            self.index,
+            self.new_public_keys.len(),
+            self.maximum_non_signers
+        )
+    }
+}
+
+struct PubkeyPrefixes<'a>(&'a [PublicKey]);
+
+impl<'a> fmt::Debug for PubkeyPrefixes<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut list = f.debug_list();
+        for pubkey in self.0 {
+            let mut bytes = vec![];
+            let prefix = match pubkey.serialize(&mut bytes) {
+                Ok(()) => {
+                    let hex = bytes
+                        .iter()
+                        .take(PUBKEY_PREFIX_BYTES)
+                        .map(|b| format!("{:02x}", b))
+                        .collect::<String>();
+                    format!("{}..", hex)
+                }
+                Err(_) => "<invalid>".to_owned(),
+            };
+            // print the prefix without quotes
+            list.entry(&format_args!("{}", prefix));
+        }
+        list.finish()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+/// Error raised while building an `EpochBlock`
+pub enum EpochBlockError {
+    MissingIndex,
+    MissingMaximumNonSigners,
+    EmptyPublicKeys,
+    TooManyNonSigners(u32, usize),
+    BitmapLengthMismatch(usize, usize),
+    NotEnoughSigners { signers: usize, required: usize },
+    NonConsecutiveIndex { expected: u16, got: u16 },
+    AggregatePubkeyMismatch,
+    NonSignerOutOfRange { index: usize, total: usize },
+    DuplicateNonSigner(usize),
+}
+
+impl fmt::Display for EpochBlockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EpochBlockError::MissingIndex => write!(f, "the epoch index was not set"),
+            EpochBlockError::MissingMaximumNonSigners => {
+                write!(f, "the maximum number of non signers was not set")
+            }
+            EpochBlockError::EmptyPublicKeys => {
+                write!(f, "the epoch must have at least one public key")
+            }
+            EpochBlockError::TooManyNonSigners(max, num) => write!(
+                f,
+                "maximum non signers ({}) must be less than the number of public keys ({})",
+                max, num
+            ),
+            EpochBlockError::BitmapLengthMismatch(len, num) => write!(
+                f,
+                "the bitmap has {} entries, but the epoch has {} public keys",
+                len, num
+            ),
+            EpochBlockError::NotEnoughSigners { signers, required } => write!(
+                f,
+                "only {} validators signed, at least {} are required",
+                signers, required
+            ),
+            EpochBlockError::NonConsecutiveIndex { expected, got } => {
+                write!(f, "expected the epoch with index {}, got {}", expected, got)
+            }
+            EpochBlockError::AggregatePubkeyMismatch => write!(
+                f,
+                "the aggregate public key does not match the signers of the bitmap"
+            ),
+            EpochBlockError::NonSignerOutOfRange { index, total } => write!(
+                f,
+                "non signer index {} is out of range for {} validators",
+                index, total
+            ),
+            EpochBlockError::DuplicateNonSigner(index) => {
+                write!(f, "non signer index {} is listed more than once", index)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EpochBlockError {}
+
+/// Builder for `EpochBlock`s which validates the block's fields
+#[derive(Clone, Debug, Default)]
+pub struct EpochBlockBuilder {
+    index: Option<u16>,
+    maximum_non_signers: Option<u32>,
+    pubkeys: Vec<PublicKey>,
+}
+
+impl EpochBlockBuilder {
+    /// Creates an empty builder
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the epoch's index
+    pub fn index(mut self, index: u16) -> Self {
+        self.index = Some(index);
+        self
+    }
+
+    /// Sets the maximum allowed number of signers that may be absent
+    pub fn maximum_non_signers(mut self, maximum_non_signers: u32) -> Self {
+        self.maximum_non_signers = Some(maximum_non_signers);
+        self
+    }
+
+    /// Appends a public key to the epoch's validators
+    pub fn add_pubkey(mut self, pubkey: PublicKey) -> Self {
+        self.pubkeys.push(pubkey);
+        self
+    }
+
+    /// Appends the public keys to the epoch's validators
+    pub fn pubkeys(mut self, pubkeys: impl IntoIterator<Item = PublicKey>) -> Self {
+        self.pubkeys.extend(pubkeys);
+        self
+    }
+
+    /// Validates the provided fields and returns the epoch block. The maximum number of non
+    /// signers must be smaller than the number of pubkeys, and may be 0 if all validators
+    /// must sign.
+    pub fn build(self) -> Result<EpochBlock, EpochBlockError> {
+        let index = self.index.ok_or(EpochBlockError::MissingIndex)?;
+        let maximum_non_signers = self
+            .maximum_non_signers
+            .ok_or(EpochBlockError::MissingMaximumNonSigners)?;
+        if self.pubkeys.is_empty() {
+            return Err(EpochBlockError::EmptyPublicKeys);
+        }
+        if maximum_non_signers as usize >= self.pubkeys.len() {
+            return Err(EpochBlockError::TooManyNonSigners(
+                maximum_non_signers,
+                self.pubkeys.len(),
+            ));
+        }
+
+        Ok(EpochBlock::new(index, maximum_non_signers, self.pubkeys))
+    }
+}
+
+/// Builds the signer bitmap of an epoch with `total` validators from the indices of the
+/// validators which did not sign, rejecting indices which are out of range or listed twice
+pub fn signers_from_nonsigner_indices(
+    total: usize,
+    nonsigners: &[usize],
+) -> Result<Vec<bool>, EpochBlockError> {
+    let mut bitmap = vec![true; total];
+    for &index in nonsigners {
+        if index >= total {
+            return Err(EpochBlockError::NonSignerOutOfRange { index, total });
+        }
+        if !bitmap[index] {
+            return Err(EpochBlockError::DuplicateNonSigner(index));
+        }
+        bitmap[index] = false;
+    }
+    Ok(bitmap)
+}
+
+/// Iterator over the consecutive windows of a chain of epoch blocks, created by
+/// [`EpochTransitionIter::new`](struct.EpochTransitionIter.html#method.new)
+///
+/// Each item is the `(first, last)` pair of epochs of a window of `num_epochs` transitions,
+/// and the last epoch of a window is the first epoch of the next one. Blocks after the last
+/// full window are not yielded.
+///
+/// The iterator stops after the first block whose index does not follow the previous one's.
+#[derive(Clone, Debug)]
+pub struct EpochTransitionIter<'a> {
+    blocks: &'a [EpochBlock],
+    num_epochs: usize,
+    position: usize,
+    failed: bool,
+}
+
+impl<'a> EpochTransitionIter<'a> {
+    /// Iterates over the windows of `num_epochs` transitions of the blocks, which must be
+    /// in order. A window of 0 epochs yields nothing.
+    pub fn new(blocks: &'a [EpochBlock], num_epochs: usize) -> Self {
+        Self {
+            blocks,
+            num_epochs,
+            position: 0,
+            failed: false,
+        }
+    }
+}
+
+impl<'a> Iterator for EpochTransitionIter<'a> {
+    type Item = Result<(&'a EpochBlock, &'a EpochBlock), EpochBlockError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.num_epochs == 0 {
+            return None;
+        }
+        // a window ending past `usize::MAX` does not fit in the blocks either
+        let end = self.position.checked_add(self.num_epochs)?;
+        if end >= self.blocks.len() {
+            return None;
+        }
+        let window = &self.blocks[self.position..=end];
+        for pair in window.windows(2) {
+            if !pair[0].is_consecutive_with(&pair[1], 1) {
+                self.failed = true;
+                return Some(Err(EpochBlockError::NonConsecutiveIndex {
+                    expected: pair[0].index.saturating_add(1),
+                    got: pair[1].index,
+                }));
+            }
+        }
+        self.position = end;
+        Some(Ok((&window[0], &window[self.num_epochs])))
+    }
 }
 
 /// Serializes the first and last epoch to bytes, hashes them with Blake2 personalized to
@@ -105,15 +727,811 @@ pub fn hash_first_last_epoch_block(
 
 /// Blake2 hash of the input personalized to `OUT_DOMAIN`
 pub fn hash_to_bits(bytes: &[u8]) -> Vec<bool> {
-    let hash = Params::new()
+    let mut state = blake2_state();
+    state.update(&bytes);
+    digest_to_bits(&state)
+}
+
+/// Blake2 state personalized to `OUT_DOMAIN`
+fn blake2_state() -> State {
+    Params::new()
         .hash_length(32)
         .personal(OUT_DOMAIN)
         .to_state()
-        .update(&bytes)
-        .finalize()
-        .as_ref()
-        .to_vec();
+}
+
+/// Finalizes the state to the LE bits of the hash
+fn digest_to_bits(state: &State) -> Vec<bool> {
+    let hash = state.finalize().as_ref().to_vec();
     let mut bits = bytes_to_bits(&hash, 256);
     bits.reverse();
     bits
 }
+
+/// Number of bytes passed to the Blake2 state at once by an `EpochBlockHasher`
+const HASHER_CHUNK_BYTES: usize = 1024;
+
+/// Computes the encoding and the Blake2 hashes of an epoch block from its pubkeys given in
+/// chunks, without holding the pubkeys or the unpacked bits of their encoding in memory.
+///
+/// Its outputs are the same as the ones of the corresponding `EpochBlock` methods, for any
+/// chunking of the pubkeys. The encoding is packed to bytes in the reverse order of its bits,
+/// so the hashes can only be computed once all pubkeys were added, and the hasher keeps the
+/// packed encoding, of 95 bytes per pubkey.
+#[derive(Clone, Debug)]
+pub struct EpochBlockHasher {
+    /// The encoding's bits, packed LE
+    packed: Vec<u8>,
+    num_bits: usize,
+    aggregated_pk: PublicKey,
+}
+
+impl EpochBlockHasher {
+    /// Starts encoding a block with the provided index and maximum number of non signers
+    pub fn new(index: u16, maximum_non_signers: u32) -> Result<Self, EncodingError> {
+        let mut hasher = Self {
+            packed: Vec::new(),
+            num_bits: 0,
+            aggregated_pk: PublicKey::aggregate(&[] as &[PublicKey]),
+        };
+        hasher.push_bits(&encode_u16(index)?);
+        hasher.push_bits(&encode_u32(maximum_non_signers)?);
+        Ok(hasher)
+    }
+
+    /// Appends the pubkeys to the block's pubkeys
+    pub fn update(&mut self, pubkeys: &[PublicKey]) -> Result<(), EncodingError> {
+        for pubkey in pubkeys {
+            self.push_bits(&encode_public_key(pubkey)?);
+        }
+        self.aggregated_pk =
+            PublicKey::aggregate(core::iter::once(&self.aggregated_pk).chain(pubkeys));
+        Ok(())
+    }
+
+    /// Same as [`EpochBlock::encode_to_bytes`](struct.EpochBlock.html#method.encode_to_bytes)
+    pub fn encode_to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.num_bits / 8 + 1);
+        pack_reversed(self.reversed_bits(), |chunk| bytes.extend_from_slice(chunk));
+        bytes
+    }
+
+    /// Same as [`EpochBlock::encode_to_bytes_with_aggregated_pk`](struct.EpochBlock.html#method.encode_to_bytes_with_aggregated_pk)
+    pub fn encode_to_bytes_with_aggregated_pk(&self) -> Result<Vec<u8>, EncodingError> {
+        let aggregated_pk = encode_public_key(&self.aggregated_pk)?;
+        let mut bytes = Vec::with_capacity((self.num_bits + aggregated_pk.len()) / 8 + 1);
+        pack_reversed(
+            aggregated_pk.into_iter().rev().chain(self.reversed_bits()),
+            |chunk| bytes.extend_from_slice(chunk),
+        );
+        Ok(bytes)
+    }
+
+    /// Same as [`EpochBlock::blake2`](struct.EpochBlock.html#method.blake2)
+    pub fn blake2(&self) -> Vec<bool> {
+        let mut state = blake2_state();
+        pack_reversed(self.reversed_bits(), |chunk| {
+            state.update(chunk);
+        });
+        digest_to_bits(&state)
+    }
+
+    /// Same as [`EpochBlock::blake2_with_aggregated_pk`](struct.EpochBlock.html#method.blake2_with_aggregated_pk)
+    pub fn blake2_with_aggregated_pk(&self) -> Result<Vec<bool>, EncodingError> {
+        let aggregated_pk = encode_public_key(&self.aggregated_pk)?;
+        let mut state = blake2_state();
+        pack_reversed(
+            aggregated_pk.into_iter().rev().chain(self.reversed_bits()),
+            |chunk| {
+                state.update(chunk);
+            },
+        );
+        Ok(digest_to_bits(&state))
+    }
+
+    fn push_bits(&mut self, bits: &[bool]) {
+        for bit in bits {
+            if self.num_bits % 8 == 0 {
+                self.packed.push(0);
+            }
+            if *bit {
+                self.packed[self.num_bits / 8] |= 1 << (self.num_bits % 8);
+            }
+            self.num_bits += 1;
+        }
+    }
+
+    fn reversed_bits<'a>(&'a self) -> impl Iterator<Item = bool> + 'a {
+        (0..self.num_bits)
+            .rev()
+            .map(move |i| self.packed[i / 8] & (1 << (i % 8)) != 0)
+    }
+}
+
+/// Packs the already reversed bits to bytes like `bits_to_bytes` does, passing the bytes to
+/// `sink` in chunks of at most `HASHER_CHUNK_BYTES`
+fn pack_reversed(bits: impl Iterator<Item = bool>, mut sink: impl FnMut(&[u8])) {
+    let mut chunk = Vec::with_capacity(HASHER_CHUNK_BYTES);
+    let (mut byte, mut position) = (0u8, 0);
+    for bit in bits {
+        byte |= (bit as u8) << position;
+        position += 1;
+        if position == 8 {
+            chunk.push(byte);
+            byte = 0;
+            position = 0;
+            if chunk.len() == HASHER_CHUNK_BYTES {
+                sink(&chunk);
+                chunk.clear();
+            }
+        }
+    }
+    if position > 0 {
+        chunk.push(byte);
+    }
+    sink(&chunk);
+}
+
+/// Serde helpers for (de)serializing public keys as compressed points
+mod compressed_pubkeys {
+    use crate::Vec;
+    use algebra::{CanonicalDeserialize, CanonicalSerialize};
+    use bls_crypto::PublicKey;
+    use serde::{de::Error as _, ser::Error as _, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        pubkeys: &[PublicKey],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut encoded = Vec::with_capacity(pubkeys.len());
+        for pubkey in pubkeys {
+            let mut bytes = vec![];
+            pubkey.serialize(&mut bytes).map_err(S::Error::custom)?;
+            encoded.push(bytes);
+        }
+        serializer.collect_seq(encoded)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<PublicKey>, D::Error> {
+        let encoded = <Vec<Vec<u8>> as Deserialize>::deserialize(deserializer)?;
+        encoded
+            .iter()
+            .map(|bytes| {
+                let pubkey = <PublicKey as CanonicalDeserialize>::deserialize(&mut &bytes[..])
+                    .map_err(D::Error::custom)?;
+
+                // reject trailing data and non-canonical encodings
+                let mut canonical = vec![];
+                pubkey.serialize(&mut canonical).map_err(D::Error::custom)?;
+                if &canonical != bytes {
+                    return Err(D::Error::custom("non-canonical public key encoding"));
+                }
+
+                if !pubkey.is_in_correct_subgroup() {
+                    return Err(D::Error::custom(
+                        "public key is not in the prime order subgroup",
+                    ));
+                }
+
+                Ok(pubkey)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use algebra::{
+        bls12_377::{G2Affine, G2Projective},
+        AffineCurve, CanonicalDeserialize, ProjectiveCurve, UniformRand,
+    };
+    use rand::{Rng, RngCore};
+
+    fn rand_block() -> EpochBlock {
+        let rng = &mut rand::thread_rng();
+        let mut points = (0..5).map(|_| G2Projective::rand(rng)).collect::<Vec<_>>();
+        // normalize the points so that they compare equal to the deserialized ones
+        G2Projective::batch_normalization(&mut points);
+        EpochBlock::new(3, 1, points.into_iter().map(PublicKey::from).collect())
+    }
+
+    #[test]
+    fn header_encoding_vector() {
+        let block = EpochBlock::new(0x0102, 0x0304_0506, vec![]);
+
+        // both integers are encoded LE, least significant bit first
+        let bits = block.encode_to_bits().unwrap();
+        assert_eq!(bits.len(), 48);
+        let expected_bits = [0x02u8, 0x01, 0x06, 0x05, 0x04, 0x03]
+            .iter()
+            .flat_map(|byte| (0..8).map(move |i| (byte >> i) & 1 == 1))
+            .collect::<Vec<_>>();
+        assert_eq!(bits, expected_bits);
+
+        // the hashed bytes pack the reversed bits
+        assert_eq!(
+            block.encode_to_bytes().unwrap(),
+            vec![0xc0, 0x20, 0xa0, 0x60, 0x80, 0x40]
+        );
+    }
+
+    #[test]
+    fn extracts_pubkeys() {
+        let block = rand_block();
+        let pubkeys = block.new_public_keys.clone();
+        let rebuilt = EpochBlockBuilder::new()
+            .index(block.index)
+            .maximum_non_signers(block.maximum_non_signers)
+            .pubkeys(block.pubkeys().iter().cloned())
+            .build()
+            .unwrap();
+        assert_eq!(rebuilt.pubkeys(), &pubkeys[..]);
+
+        let compressed = block.compressed_pubkeys().unwrap();
+        assert_eq!(compressed.len(), pubkeys.len());
+        for (bytes, pubkey) in compressed.iter().zip(&pubkeys) {
+            let decoded =
+                <PublicKey as CanonicalDeserialize>::deserialize(&mut &bytes[..]).unwrap();
+            assert_eq!(&decoded, pubkey);
+        }
+    }
+
+    #[test]
+    fn framed_roundtrip() {
+        let blocks = (0..3)
+            .map(|index| EpochBlock {
+                index,
+                ..rand_block()
+            })
+            .collect::<Vec<_>>();
+        let mut stream = vec![];
+        for block in &blocks {
+            block.write_framed(&mut stream).unwrap();
+        }
+
+        let mut reader = &stream[..];
+        let mut decoded = vec![];
+        while let Some(block) = EpochBlock::read_framed(&mut reader).unwrap() {
+            decoded.push(block);
+        }
+        assert_eq!(decoded, blocks);
+        // the end of the stream is reported again
+        assert!(EpochBlock::read_framed(&mut reader).unwrap().is_none());
+
+        // a stream which ends in the middle of a block
+        for len in &[stream.len() - 1, stream.len() - 100, 2] {
+            let mut reader = &stream[..*len];
+            let err = loop {
+                match EpochBlock::read_framed(&mut reader) {
+                    Ok(Some(_)) => {}
+                    Ok(None) => panic!("truncated stream ended cleanly"),
+                    Err(err) => break err,
+                }
+            };
+            match err {
+                EncodingError::IoError(e) => {
+                    assert_eq!(e.kind(), std::io::ErrorKind::UnexpectedEof)
+                }
+                err => panic!("unexpected error: {}", err),
+            }
+        }
+
+        // a frame whose length does not fit the pubkeys
+        let mut corrupted = stream.clone();
+        corrupted[0] -= 1;
+        EpochBlock::read_framed(&mut &corrupted[..]).unwrap_err();
+    }
+
+    #[test]
+    fn serialization_roundtrip() {
+        let block = EpochBlock::new(3, 2, rand_block().new_public_keys);
+        let serialized = block.serialize_to_bytes().unwrap();
+        assert_eq!(
+            serialized.len(),
+            EPOCH_BLOCK_HEADER_BYTES + block.new_public_keys.len() * COMPRESSED_PUBKEY_BYTES
+        );
+        assert_eq!(
+            EpochBlock::deserialize_from_bytes(&serialized).unwrap(),
+            block
+        );
+        // empty blocks roundtrip
+        let empty = EpochBlock::new(1, 0, vec![]);
+        let decoded = EpochBlock::deserialize_from_bytes(&empty.serialize_to_bytes().unwrap());
+        assert_eq!(decoded.unwrap(), empty);
+
+        // truncated, padded, or with a count which does not match the pubkeys
+        let length_error = |bytes: &[u8]| match EpochBlock::deserialize_from_bytes(bytes) {
+            Err(EncodingError::InvalidEpochBlockLength { expected, got }) => (expected, got),
+            res => panic!("unexpected result: {:?}", res),
+        };
+        let len = serialized.len();
+        assert_eq!(length_error(&serialized[..len - 1]), (len as u64, len - 1));
+        assert_eq!(
+            length_error(&serialized[..2]),
+            (EPOCH_BLOCK_HEADER_BYTES as u64, 2)
+        );
+        let mut padded = serialized.clone();
+        padded.push(0);
+        assert_eq!(length_error(&padded), (len as u64, len + 1));
+        let mut huge_count = serialized.clone();
+        huge_count[6..EPOCH_BLOCK_HEADER_BYTES].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert_eq!(length_error(&huge_count), (u64::MAX, len));
+
+        // too many pubkeys
+        let pubkeys = vec![block.new_public_keys[0].clone(); MAX_VALIDATORS + 1];
+        let too_many = EpochBlock::new(0, 0, pubkeys);
+        assert!(matches!(
+            too_many.serialize_to_bytes(),
+            Err(EncodingError::TooManyValidators { .. })
+        ));
+
+        // a pubkey which is not a point
+        let mut corrupted = serialized.clone();
+        for byte in &mut corrupted[EPOCH_BLOCK_HEADER_BYTES..][..COMPRESSED_PUBKEY_BYTES] {
+            *byte = 0xff;
+        }
+        EpochBlock::deserialize_from_bytes(&corrupted).unwrap_err();
+    }
+
+    #[test]
+    fn iterates_over_windows() {
+        let chain = (0..5)
+            .map(|index| EpochBlock::new(index, 1, vec![]))
+            .collect::<Vec<_>>();
+        let indices = |num_epochs| {
+            EpochTransitionIter::new(&chain, num_epochs)
+                .map(|window| window.map(|(first, last)| (first.index, last.index)))
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap()
+        };
+        assert_eq!(indices(1), vec![(0, 1), (1, 2), (2, 3), (3, 4)]);
+        assert_eq!(indices(2), vec![(0, 2), (2, 4)]);
+        // the last epoch is not part of a full window
+        assert_eq!(indices(3), vec![(0, 3)]);
+        assert_eq!(indices(4), vec![(0, 4)]);
+        assert!(indices(5).is_empty());
+        assert!(indices(0).is_empty());
+        assert!(indices(usize::MAX).is_empty());
+        // a window ending past `usize::MAX`, which would overflow
+        let mut overflowing = EpochTransitionIter::new(&chain, usize::MAX);
+        overflowing.position = 1;
+        assert!(overflowing.next().is_none());
+
+        // a gap stops the iteration at the window which contains it
+        let mut gap = chain.clone();
+        gap[3].index = 4;
+        let windows = EpochTransitionIter::new(&gap, 2).collect::<Vec<_>>();
+        assert_eq!(windows.len(), 2);
+        assert!(windows[0].is_ok());
+        assert_eq!(
+            windows[1].as_ref().unwrap_err(),
+            &EpochBlockError::NonConsecutiveIndex {
+                expected: 3,
+                got: 4
+            }
+        );
+        // as does an unordered chain
+        let mut unordered = chain;
+        unordered.swap(0, 1);
+        let mut windows = EpochTransitionIter::new(&unordered, 1);
+        assert_eq!(
+            windows.next().unwrap().unwrap_err(),
+            EpochBlockError::NonConsecutiveIndex {
+                expected: 2,
+                got: 0
+            }
+        );
+        assert!(windows.next().is_none());
+    }
+
+    #[test]
+    fn incremental_hashing_matches_block() {
+        let rng = &mut rand::thread_rng();
+        let pubkeys = (0..10)
+            .map(|_| PublicKey::from(G2Projective::rand(rng)))
+            .collect::<Vec<_>>();
+        let block = EpochBlock::new(7, 3, pubkeys.clone());
+        let encoded = block.encode_to_bytes().unwrap();
+        let encoded_with_aggregated_pk = block.encode_to_bytes_with_aggregated_pk().unwrap();
+        let blake2 = block.blake2().unwrap();
+        let blake2_with_aggregated_pk = block.blake2_with_aggregated_pk().unwrap();
+
+        for _ in 0..10 {
+            let mut hasher = EpochBlockHasher::new(7, 3).unwrap();
+            let mut rest = &pubkeys[..];
+            while !rest.is_empty() {
+                // chunks may be empty
+                let (chunk, tail) = rest.split_at(rng.gen_range(0, rest.len() + 1));
+                hasher.update(chunk).unwrap();
+                rest = tail;
+            }
+            assert_eq!(hasher.encode_to_bytes(), encoded);
+            assert_eq!(
+                hasher.encode_to_bytes_with_aggregated_pk().unwrap(),
+                encoded_with_aggregated_pk
+            );
+            assert_eq!(hasher.blake2(), blake2);
+            assert_eq!(
+                hasher.blake2_with_aggregated_pk().unwrap(),
+                blake2_with_aggregated_pk
+            );
+        }
+
+        // a block without pubkeys
+        let empty = EpochBlock::new(1, 0, vec![]);
+        let hasher = EpochBlockHasher::new(1, 0).unwrap();
+        assert_eq!(hasher.encode_to_bytes(), empty.encode_to_bytes().unwrap());
+        assert_eq!(hasher.blake2(), empty.blake2().unwrap());
+    }
+
+    #[test]
+    fn pubkey_order() {
+        let block = rand_block();
+        let mut permuted = block.clone();
+        permuted.new_public_keys.reverse();
+
+        // the aggregated public key is the same for any order
+        let pubkey_bits = encode_public_key(&block.new_public_keys[0]).unwrap().len();
+        let aggregated = |block: &EpochBlock| {
+            let bits = block.encode_to_bits_with_aggregated_pk().unwrap();
+            bits[bits.len() - pubkey_bits..].to_vec()
+        };
+        assert_eq!(aggregated(&block), aggregated(&permuted));
+        // but the committed validator set is not
+        assert_ne!(block.blake2().unwrap(), permuted.blake2().unwrap());
+        assert_ne!(
+            block.blake2_with_aggregated_pk().unwrap(),
+            permuted.blake2_with_aggregated_pk().unwrap()
+        );
+    }
+
+    #[test]
+    fn serde_bincode_roundtrip() {
+        let block = rand_block();
+        let encoded = bincode::serialize(&block).unwrap();
+        let decoded: EpochBlock = bincode::deserialize(&encoded).unwrap();
+        assert_eq!(decoded, block);
+    }
+
+    #[test]
+    fn serde_json_roundtrip() {
+        let block = rand_block();
+        let encoded = serde_json::to_string(&block).unwrap();
+        let decoded: EpochBlock = serde_json::from_str(&encoded).unwrap();
+        assert_eq!(decoded, block);
+    }
+
+    #[test]
+    fn serde_rejects_invalid_points() {
+        let mut block = rand_block();
+        let mut encoded = serde_json::to_value(&block).unwrap();
+        encoded["new_public_keys"][0] = serde_json::json!(vec![0xffu8; 96]);
+        assert!(serde_json::from_value::<EpochBlock>(encoded).is_err());
+
+        // a point on the curve but outside of the prime order subgroup
+        let rng = &mut rand::thread_rng();
+        let point = loop {
+            let mut bytes = [0u8; 96];
+            rng.fill_bytes(&mut bytes);
+            if let Some(p) = G2Affine::from_random_bytes(&bytes) {
+                break p;
+            }
+        };
+        block.new_public_keys[0] = PublicKey::from(point.into_projective());
+        let encoded = serde_json::to_string(&block).unwrap();
+        assert!(serde_json::from_str::<EpochBlock>(&encoded).is_err());
+    }
+
+    #[test]
+    fn apk_commitment_is_hashed_in_public_inputs() {
+        let first = rand_block();
+        let last = rand_block();
+        let commitment = last.apk_commitment().unwrap();
+
+        let hash = hash_first_last_epoch_block(&first, &last).unwrap();
+        assert_eq!(
+            bytes_to_bits(&first.commitment().unwrap(), 256),
+            &hash[..256]
+        );
+        assert_eq!(bytes_to_bits(&commitment, 256), &hash[256..]);
+        // it commits to the pubkeys
+        let mut other = last.clone();
+        other.new_public_keys.swap(0, 1);
+        assert_ne!(other.apk_commitment().unwrap(), commitment);
+    }
+
+    #[test]
+    fn bitmap_from_nonsigners() {
+        assert_eq!(
+            signers_from_nonsigner_indices(4, &[3, 1]).unwrap(),
+            vec![true, false, true, false]
+        );
+        assert_eq!(
+            signers_from_nonsigner_indices(2, &[]).unwrap(),
+            vec![true; 2]
+        );
+        assert_eq!(
+            signers_from_nonsigner_indices(4, &[1, 4]).unwrap_err(),
+            EpochBlockError::NonSignerOutOfRange { index: 4, total: 4 }
+        );
+        assert_eq!(
+            signers_from_nonsigner_indices(4, &[2, 0, 2]).unwrap_err(),
+            EpochBlockError::DuplicateNonSigner(2)
+        );
+    }
+
+    #[test]
+    fn formats_summary() {
+        let block = rand_block();
+        let mut first = vec![];
+        block.new_public_keys[0].serialize(&mut first).unwrap();
+
+        let debug = format!("{:?}", block);
+        assert!(debug.starts_with("EpochBlock { index: 3, maximum_non_signers: 1, num_pubkeys: 5"));
+        assert!(debug.contains(&format!(
+            "{:02x}{:02x}{:02x}{:02x}..",
+            first[0], first[1], first[2], first[3]
+        )));
+        // the full points are not printed
+        assert!(debug.len() < 200);
+        assert_eq!(
+            block.to_string(),
+            "epoch 3 (5 pubkeys, at most 1 non signers)"
+        );
+
+        let empty = EpochBlock::new(0, 0, vec![]);
+        assert_eq!(
+            format!("{:?}", empty),
+            "EpochBlock { index: 0, maximum_non_signers: 0, num_pubkeys: 0, pubkeys: [] }"
+        );
+        assert_eq!(
+            empty.to_string(),
+            "epoch 0 (0 pubkeys, at most 0 non signers)"
+        );
+    }
+
+    #[test]
+    fn next_index() {
+        let block = EpochBlock::new(7, 1, vec![]);
+        assert_eq!(block.next_index(0), Some(7));
+        assert_eq!(block.next_index(1), Some(8));
+        assert_eq!(block.next_index(10), Some(17));
+        assert!(block.is_consecutive_with(&EpochBlock::new(8, 1, vec![]), 1));
+        assert!(block.is_consecutive_with(&EpochBlock::new(17, 1, vec![]), 10));
+        assert!(!block.is_consecutive_with(&EpochBlock::new(9, 1, vec![]), 1));
+        assert!(!block.is_consecutive_with(&EpochBlock::new(6, 1, vec![]), 1));
+
+        // the index does not wrap around
+        let last = EpochBlock::new(u16::MAX - 1, 1, vec![]);
+        assert_eq!(last.next_index(1), Some(u16::MAX));
+        assert_eq!(last.next_index(2), None);
+        assert_eq!(last.next_index(usize::MAX), None);
+        assert!(!last.is_consecutive_with(&EpochBlock::new(0, 1, vec![]), 2));
+    }
+
+    #[test]
+    fn quorum() {
+        let block = EpochBlock::new(1, 2, rand_block().new_public_keys);
+        assert_eq!(block.new_public_keys.len(), 5);
+
+        block.check_quorum(&[true; 5]).unwrap();
+        block
+            .check_quorum(&[true, false, true, false, true])
+            .unwrap();
+        assert_eq!(
+            block
+                .check_quorum(&[false, false, true, false, true])
+                .unwrap_err(),
+            EpochBlockError::NotEnoughSigners {
+                signers: 2,
+                required: 3
+            }
+        );
+        assert_eq!(
+            block.check_quorum(&[true; 4]).unwrap_err(),
+            EpochBlockError::BitmapLengthMismatch(4, 5)
+        );
+
+        assert_eq!(block.min_signers(), 3);
+        assert_eq!(block.count_signers(&[true; 5]).unwrap(), 5);
+        assert_eq!(
+            block
+                .count_signers(&[true, false, true, false, true])
+                .unwrap(),
+            3
+        );
+        block
+            .count_signers(&[false, false, true, false, true])
+            .unwrap_err();
+    }
+
+    #[test]
+    fn quorum_boundary() {
+        let block = EpochBlock::new(1, 2, rand_block().new_public_keys);
+        // exactly `maximum_non_signers` non signers
+        assert_eq!(
+            block
+                .count_signers(&[false, true, false, true, true])
+                .unwrap(),
+            3
+        );
+        // one more
+        assert_eq!(
+            block
+                .check_quorum(&[false, true, false, true, false])
+                .unwrap_err(),
+            EpochBlockError::NotEnoughSigners {
+                signers: 2,
+                required: 3
+            }
+        );
+    }
+
+    #[test]
+    fn pads_to_validator_count() {
+        let block = EpochBlock::new(3, 1, rand_block().new_public_keys[..4].to_vec());
+        let padded = block.padded(7).unwrap();
+        assert_eq!(padded.index, block.index);
+        assert_eq!(&padded.new_public_keys[..4], block.pubkeys());
+        assert_eq!(
+            &padded.new_public_keys[4..],
+            &vec![padding_public_key(); 3][..]
+        );
+        assert_eq!(padded.maximum_non_signers, 4);
+        // the padding keys do not change the quorum
+        assert_eq!(padded.min_signers(), block.min_signers());
+        padded
+            .check_quorum(&[true, false, true, true, false, false, false])
+            .unwrap();
+        padded
+            .check_quorum(&[true, false, false, true, false, false, false])
+            .unwrap_err();
+
+        assert_eq!(block.padded(4).unwrap(), block);
+        assert!(matches!(
+            block.padded(3).unwrap_err(),
+            EncodingError::TooManyValidators { got: 4, max: 3 }
+        ));
+
+        let transition = EpochTransition {
+            block: block.clone(),
+            aggregate_signature: Signature::from(G1Projective::rand(&mut rand::thread_rng())),
+            bitmap: vec![true, false, true],
+        };
+        let padded = transition.padded(5).unwrap();
+        assert_eq!(padded.block, block.padded(5).unwrap());
+        assert_eq!(padded.aggregate_signature, transition.aggregate_signature);
+        assert_eq!(padded.bitmap, [true, false, true, false, false]);
+        assert!(transition.padded(2).is_err());
+    }
+
+    #[test]
+    fn quorum_without_non_signers() {
+        let block = EpochBlock::new(1, 0, rand_block().new_public_keys);
+
+        assert_eq!(block.count_signers(&[true; 5]).unwrap(), 5);
+        assert_eq!(
+            block
+                .check_quorum(&[true, true, false, true, true])
+                .unwrap_err(),
+            EpochBlockError::NotEnoughSigners {
+                signers: 4,
+                required: 5
+            }
+        );
+
+        // a single validator which must sign
+        let block = EpochBlock::new(1, 0, block.new_public_keys[..1].to_vec());
+        block.check_quorum(&[true]).unwrap();
+        block.check_quorum(&[false]).unwrap_err();
+    }
+
+    #[test]
+    fn signers() {
+        let block = EpochBlock::new(1, 2, rand_block().new_public_keys);
+        let pubkeys = &block.new_public_keys;
+
+        // all signers
+        let all = PublicKey::aggregate(pubkeys);
+        block.check_signers(&[true; 5], &all).unwrap();
+
+        // minimal quorum
+        let bitmap = [false, true, true, false, true];
+        let quorum = PublicKey::aggregate([&pubkeys[1], &pubkeys[2], &pubkeys[4]].iter().copied());
+        block.check_signers(&bitmap, &quorum).unwrap();
+
+        // the key of a different subset
+        assert_eq!(
+            block
+                .check_signers(&[true, true, true, false, false], &quorum)
+                .unwrap_err(),
+            EpochBlockError::AggregatePubkeyMismatch
+        );
+        assert_eq!(
+            block.check_signers(&bitmap, &all).unwrap_err(),
+            EpochBlockError::AggregatePubkeyMismatch
+        );
+        // below the quorum
+        assert_eq!(
+            block
+                .check_signers(&[false, true, false, false, true], &quorum)
+                .unwrap_err(),
+            EpochBlockError::NotEnoughSigners {
+                signers: 2,
+                required: 3
+            }
+        );
+    }
+
+    #[test]
+    fn builder_ok() {
+        let block = rand_block();
+        let (first, rest) = block.new_public_keys.split_first().unwrap();
+        let built = EpochBlockBuilder::new()
+            .index(block.index)
+            .maximum_non_signers(block.maximum_non_signers)
+            .add_pubkey(first.clone())
+            .pubkeys(rest.to_vec())
+            .build()
+            .unwrap();
+        assert_eq!(built, block);
+    }
+
+    #[test]
+    fn builder_rejects_invalid_blocks() {
+        let pubkeys = rand_block().new_public_keys;
+        let builder = EpochBlockBuilder::new().index(1).maximum_non_signers(1);
+
+        assert_eq!(
+            EpochBlockBuilder::new()
+                .maximum_non_signers(1)
+                .pubkeys(pubkeys.clone())
+                .build()
+                .unwrap_err(),
+            EpochBlockError::MissingIndex
+        );
+        assert_eq!(
+            EpochBlockBuilder::new()
+                .index(1)
+                .pubkeys(pubkeys.clone())
+                .build()
+                .unwrap_err(),
+            EpochBlockError::MissingMaximumNonSigners
+        );
+        assert_eq!(
+            builder.clone().build().unwrap_err(),
+            EpochBlockError::EmptyPublicKeys
+        );
+        assert_eq!(
+            builder
+                .maximum_non_signers(5)
+                .pubkeys(pubkeys)
+                .build()
+                .unwrap_err(),
+            EpochBlockError::TooManyNonSigners(5, 5)
+        );
+    }
+
+    #[test]
+    fn builder_accepts_no_non_signers() {
+        let pubkeys = rand_block().new_public_keys;
+        let builder = EpochBlockBuilder::new().index(1).maximum_non_signers(0);
+
+        let block = builder.clone().pubkeys(pubkeys.clone()).build().unwrap();
+        assert_eq!(block.maximum_non_signers, 0);
+        builder.add_pubkey(pubkeys[0].clone()).build().unwrap();
+        // one validator allows no non signers at all
+        assert_eq!(
+            EpochBlockBuilder::new()
+                .index(1)
+                .maximum_non_signers(1)
+                .add_pubkey(pubkeys[0].clone())
+                .build()
+                .unwrap_err(),
+            EpochBlockError::TooManyNonSigners(1, 1)
+        );
+    }
+}
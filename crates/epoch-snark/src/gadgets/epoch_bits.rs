@@ -23,7 +23,10 @@ use crypto_primitives::{
 use r1cs_std::fields::fp::FpGadget;
 type FrGadget = FpGadget<Fr>;
 
-use crate::gadgets::{HashToBits, HashToBitsHelper, MultipackGadget};
+use crate::{
+    encoding::PackingWidth,
+    gadgets::{HashToBits, HashToBitsHelper, MultipackGadget},
+};
 use bls_crypto::OUT_DOMAIN;
 
 /// Contains the first and last epoch's bits, along with auxiliary CRH and XOF bits
@@ -37,6 +40,8 @@ pub struct EpochBits {
     pub crh_bits: Vec<Boolean>,
     /// The XOF bits for all intermediate state transitions
     pub xof_bits: Vec<Boolean>,
+    /// The number of bits of the first and last epoch's hash in each public input
+    pub packing: PackingWidth,
 }
 
 impl EpochBits {
@@ -101,7 +106,7 @@ impl EpochBits {
         let packed = MultipackGadget::pack(
             cs.ns(|| "pack output hash"),
             &xof_bits,
-            FrParameters::CAPACITY as usize,
+            self.packing.bits(),
             true,
         )?;
 
@@ -162,14 +167,45 @@ mod tests {
     use bls_gadgets::utils::bytes_to_bits;
     use rand::RngCore;
 
-    use crate::epoch_block::hash_to_bits;
-    use crate::gadgets::pack;
+    use crate::encoding::{pack, pack_with_width};
+    use crate::epoch_block::{hash_to_bits, EpochBlock};
+    use crate::testing::random_epoch_block;
     use r1cs_std::test_constraint_system::TestConstraintSystem;
 
     fn to_bool(iter: &[bool]) -> Vec<Boolean> {
         iter.iter().map(|b| Boolean::constant(*b)).collect()
     }
 
+    /// An epoch with 4 fresh validators, at most one of which may be absent
+    fn epoch(index: u16) -> EpochBlock {
+        random_epoch_block(&mut rand::thread_rng(), 4, index, 1).0
+    }
+
+    #[test]
+    fn apk_commitment_matches_circuit() {
+        let first = epoch(0);
+        let last = epoch(2);
+
+        let bits = EpochBits {
+            crh_bits: vec![],
+            xof_bits: vec![],
+            first_epoch_bits: to_bool(&first.encode_to_bits().unwrap()),
+            last_epoch_bits: to_bool(&last.encode_to_bits_with_aggregated_pk().unwrap()),
+            packing: PackingWidth::default(),
+        };
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        let packed = bits.verify_edges(&mut cs).unwrap();
+        assert!(cs.is_satisfied());
+        let inner = packed
+            .into_iter()
+            .map(|i| i.get_value().unwrap())
+            .collect::<Vec<_>>();
+
+        let commitment = bytes_to_bits(&last.apk_commitment().unwrap(), 256);
+        let expected = [first.blake2().unwrap(), commitment].concat();
+        assert_eq!(inner, pack::<Fr, FrParameters>(&expected));
+    }
+
     #[test]
     fn correct_blake2_hash() {
         let rng = &mut rand::thread_rng();
@@ -192,6 +228,7 @@ mod tests {
             xof_bits: vec![],
             first_epoch_bits: to_bool(&first_epoch_bits),
             last_epoch_bits: to_bool(&last_epoch_bits),
+            packing: PackingWidth::default(),
         };
 
         let mut cs = TestConstraintSystem::<Fr>::new();
@@ -207,4 +244,35 @@ mod tests {
         let public_inputs = pack::<Fr, FrParameters>(&both_blake_bits);
         assert_eq!(inner, public_inputs);
     }
+
+    #[test]
+    fn packs_with_custom_width() {
+        let rng = &mut rand::thread_rng();
+        let mut bytes = vec![0; 32];
+        rng.fill_bytes(&mut bytes);
+        let epoch_bits = bytes_to_bits(&bytes, 256);
+        let blake_bits = [hash_to_bits(&bytes), hash_to_bits(&bytes)].concat();
+
+        let packing = PackingWidth::new(128).unwrap();
+        let bits = EpochBits {
+            crh_bits: vec![],
+            xof_bits: vec![],
+            first_epoch_bits: to_bool(&epoch_bits),
+            last_epoch_bits: to_bool(&epoch_bits),
+            packing,
+        };
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        let packed = bits.verify_edges(&mut cs).unwrap();
+        assert!(cs.is_satisfied());
+        // the 512 bits of the hash are packed in 4 inputs instead of 2
+        assert_eq!(packed.len(), 4);
+        let inner = packed
+            .into_iter()
+            .map(|i| i.get_value().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(inner, pack_with_width::<Fr>(&blake_bits, packing.bits()));
+
+        assert!(PackingWidth::new(0).is_err());
+        assert!(PackingWidth::new(FrParameters::CAPACITY + 1).is_err());
+    }
 }
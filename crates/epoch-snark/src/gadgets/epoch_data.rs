@@ -12,9 +12,11 @@ use r1cs_std::{
     Assignment,
 };
 
-use bls_crypto::{hash_to_curve::try_and_increment::COMPOSITE_HASH_TO_G1, SIG_DOMAIN};
+use bls_crypto::{
+    hash_to_curve::try_and_increment::SnarkCrh, hashers::composite::CRHParameters, Domain,
+};
 
-use super::{fr_to_bits, g2_to_bits, to_fr};
+use super::{fr_to_bits, g2_to_bits, to_fr, HASH_TO_G1_NAMESPACE};
 use tracing::{span, trace, Level};
 
 type FrGadget = FpGadget<Fr>;
@@ -31,6 +33,9 @@ pub struct EpochData<E: PairingEngine> {
     pub index: Option<u16>,
     /// The public keys at the epoch
     pub public_keys: Vec<Option<E::G2Projective>>,
+    /// The try-and-increment counter of the epoch's hash to G1. It gets searched for
+    /// during synthesis if not provided
+    pub hash_counter: Option<u8>,
 }
 
 /// [`EpochData`] is constrained to a `ConstrainedEpochData` via [`EpochData.constrain`]
@@ -61,6 +66,7 @@ impl<E: PairingEngine> EpochData<E> {
             index: None,
             maximum_non_signers: maximum_non_signers as u32,
             public_keys: vec![None; num_validators],
+            hash_counter: None,
         }
     }
 }
@@ -68,11 +74,14 @@ impl<E: PairingEngine> EpochData<E> {
 impl EpochData<Bls12_377> {
     /// Ensures that the epoch's index is equal to `previous_index + 1`. Enforces that
     /// the epoch's G1 hash is correctly calculated, and also provides auxiliary data for
-    /// verifying the CRH->XOF hash outside of SW6.
+    /// verifying the CRH->XOF hash outside of SW6. The epoch is hashed in the provided domain,
+    /// with the CRH parameters if provided (otherwise they get generated).
     pub fn constrain<CS: ConstraintSystem<Fr>>(
         &self,
         cs: &mut CS,
         previous_index: &FrGadget,
+        domain: Domain,
+        crh_parameters: Option<&CRHParameters>,
         generate_constraints_for_hash: bool,
     ) -> Result<ConstrainedEpochData, SynthesisError> {
         let span = span!(Level::TRACE, "EpochData");
@@ -82,8 +91,11 @@ impl EpochData<Bls12_377> {
 
         // Hash to G1
         let (message_hash, crh_bits, xof_bits) = Self::hash_bits_to_g1(
-            &mut cs.ns(|| "hash epoch to g1 bits"),
+            &mut cs.ns(|| HASH_TO_G1_NAMESPACE),
             &bits,
+            domain,
+            self.hash_counter,
+            crh_parameters,
             generate_constraints_for_hash,
         )?;
 
@@ -146,11 +158,15 @@ impl EpochData<Bls12_377> {
         Ok(())
     }
 
-    /// Packs the provided bits in U8s, and calculates the hash and the counter
-    /// Also returns the auxiliary CRH and XOF bits for potential compression from consumers
+    /// Packs the provided bits in U8s, and calculates the hash and the counter (unless it
+    /// is provided). Also returns the auxiliary CRH and XOF bits for potential compression
+    /// from consumers
     fn hash_bits_to_g1<CS: ConstraintSystem<Fr>>(
         cs: &mut CS,
         epoch_bits: &[Boolean],
+        domain: Domain,
+        counter: Option<u8>,
+        crh_parameters: Option<&CRHParameters>,
         generate_constraints_for_hash: bool,
     ) -> Result<(G1Gadget, Vec<Boolean>, Vec<Boolean>), SynthesisError> {
         trace!("hashing epoch to g1");
@@ -175,6 +191,8 @@ impl EpochData<Bls12_377> {
         // Get the inner values
         let counter = if is_setup {
             0
+        } else if let Some(counter) = counter {
+            counter as usize
         } else {
             // find the counter value for the hash
             let input_bytes = input_bytes_var
@@ -182,17 +200,19 @@ impl EpochData<Bls12_377> {
                 .map(|b| b.get_value().get())
                 .collect::<Result<Vec<_>, _>>()?;
 
-            let (_, counter) = COMPOSITE_HASH_TO_G1
-                .hash_with_attempt(SIG_DOMAIN, &input_bytes, &[])
+            let (_, counter) = SnarkCrh::CURRENT
+                .hash_with_attempt(domain.as_bytes(), &input_bytes, &[])
                 .map_err(|_| SynthesisError::Unsatisfiable)?;
             counter
         };
 
         let counter_var = UInt8::alloc(&mut cs.ns(|| "alloc counter"), || Ok(counter as u8))?;
-        HashToGroupGadget::<Parameters>::enforce_hash_to_group(
+        HashToGroupGadget::<Parameters>::enforce_hash_to_group_with_parameters(
             &mut cs.ns(|| "hash to group"),
             counter_var,
             &input_bytes_var,
+            domain,
+            crh_parameters,
             generate_constraints_for_hash,
         )
     }
@@ -220,6 +240,7 @@ mod tests {
             index: Some(index),
             maximum_non_signers: 12,
             public_keys: pubkeys,
+            hash_counter: None,
         }
     }
 
@@ -229,7 +250,13 @@ mod tests {
         let mut cs = TestConstraintSystem::<Fr>::new();
         let index = to_fr(&mut cs.ns(|| "index"), Some(9u32)).unwrap();
         epoch
-            .constrain(&mut cs.ns(|| "constraint"), &index, false)
+            .constrain(
+                &mut cs.ns(|| "constraint"),
+                &index,
+                Domain::default(),
+                None,
+                false,
+            )
             .unwrap();
         assert!(cs.is_satisfied());
     }
@@ -246,15 +273,57 @@ mod tests {
         let epoch_bytes = EpochBlock::new(epoch.index.unwrap(), epoch.maximum_non_signers, pubkeys)
             .encode_to_bytes()
             .unwrap();
-        let (hash, _) = COMPOSITE_HASH_TO_G1
-            .hash_with_attempt(SIG_DOMAIN, &epoch_bytes, &[])
+        for domain in &[Domain::default(), Domain::new(b"testnet").unwrap()] {
+            let (hash, _) = SnarkCrh::CURRENT
+                .hash_with_attempt(domain.as_bytes(), &epoch_bytes, &[])
+                .unwrap();
+
+            // compare it with the one calculated in the circuit from its bytes
+            let mut cs = TestConstraintSystem::<Fr>::new();
+            let bits = epoch.to_bits(&mut cs.ns(|| "epoch2bits")).unwrap().0;
+            let ret = EpochData::hash_bits_to_g1(
+                &mut cs.ns(|| "hash epoch bits"),
+                &bits,
+                *domain,
+                None,
+                None,
+                false,
+            )
+            .unwrap();
+            assert!(cs.is_satisfied());
+            assert_eq!(ret.0.get_value().unwrap(), hash);
+        }
+    }
+
+    #[test]
+    fn hash_epoch_to_g1_with_counter() {
+        let epoch = test_epoch(10);
+        let pubkeys = epoch
+            .public_keys
+            .iter()
+            .map(|pk| PublicKey::from(pk.unwrap()))
+            .collect();
+        let epoch_bytes = EpochBlock::new(epoch.index.unwrap(), epoch.maximum_non_signers, pubkeys)
+            .encode_to_bytes()
+            .unwrap();
+        let domain = Domain::default();
+        let (hash, counter) = SnarkCrh::CURRENT
+            .hash_with_attempt(domain.as_bytes(), &epoch_bytes, &[])
             .unwrap();
 
-        // compare it with the one calculated in the circuit from its bytes
+        // the provided counter is used instead of searching for it
         let mut cs = TestConstraintSystem::<Fr>::new();
         let bits = epoch.to_bits(&mut cs.ns(|| "epoch2bits")).unwrap().0;
-        let ret =
-            EpochData::hash_bits_to_g1(&mut cs.ns(|| "hash epoch bits"), &bits, false).unwrap();
+        let ret = EpochData::hash_bits_to_g1(
+            &mut cs.ns(|| "hash epoch bits"),
+            &bits,
+            domain,
+            Some(counter as u8),
+            None,
+            false,
+        )
+        .unwrap();
+        assert!(cs.is_satisfied());
         assert_eq!(ret.0.get_value().unwrap(), hash);
     }
 
@@ -17,9 +17,17 @@ use r1cs_core::{ConstraintSynthesizer, ConstraintSystem, SynthesisError};
 use algebra::PairingEngine;
 use groth16::{Proof, VerifyingKey};
 
-use crate::gadgets::{g2_to_bits, single_update::SingleUpdate, EpochBits, EpochData};
+use crate::{
+    api::CircuitConfig,
+    gadgets::{
+        g2_to_bits, single_update::SingleUpdate, EpochBits, EpochData, BLS_VERIFICATION_NAMESPACE,
+        PUBLIC_INPUTS_NAMESPACE,
+    },
+};
 
+use bls_crypto::{hashers::composite::CRHParameters, Domain};
 use bls_gadgets::BlsVerifyGadget;
+use std::sync::Arc;
 type BlsGadget = BlsVerifyGadget<Bls12_377, Fr, PairingGadget>;
 type FrGadget = FpGadget<Fr>;
 
@@ -39,6 +47,14 @@ pub struct ValidatorSetUpdate<E: PairingEngine> {
     /// constrain the inner CRH->XOF hashes in SW6 and instead it will be verified
     /// via the helper's proof which is in BLS12-377.
     pub hash_helper: Option<HashToBitsHelper<E>>,
+    /// The domain in which the validators signed the epochs
+    pub domain: Domain,
+    /// The CRH parameters for hashing the epochs to G1. Generating them is expensive, so
+    /// they should be provided when proving. If `None`, they are generated for each epoch.
+    pub crh_parameters: Option<Arc<CRHParameters>>,
+    /// The packing width and the exposed fields of the public inputs. The verifier must
+    /// derive the public inputs with the same configuration.
+    pub config: CircuitConfig,
 }
 
 #[derive(Clone)]
@@ -57,6 +73,7 @@ impl<E: PairingEngine> ValidatorSetUpdate<E> {
         num_epochs: usize,
         maximum_non_signers: usize,
         vk: Option<VerifyingKey<E>>,
+        domain: Domain,
     ) -> Self {
         let empty_update = SingleUpdate::empty(num_validators, maximum_non_signers);
         let hash_helper = vk.map(|vk| HashToBitsHelper {
@@ -70,8 +87,17 @@ impl<E: PairingEngine> ValidatorSetUpdate<E> {
             epochs: vec![empty_update; num_epochs],
             aggregated_signature: None,
             hash_helper,
+            domain,
+            crh_parameters: None,
+            config: CircuitConfig::default(),
         }
     }
+
+    /// Sets the configuration of the public inputs
+    pub fn with_config(mut self, config: CircuitConfig) -> Self {
+        self.config = config;
+        self
+    }
 }
 
 impl ConstraintSynthesizer<Fr> for ValidatorSetUpdate<Bls12_377> {
@@ -84,8 +110,15 @@ impl ConstraintSynthesizer<Fr> for ValidatorSetUpdate<Bls12_377> {
         let span = span!(Level::TRACE, "ValidatorSetUpdate");
         let _enter = span.enter();
         info!("generating constraints");
-        let epoch_bits = self.enforce(&mut cs.ns(|| "check signature"))?;
-        epoch_bits.verify(&mut cs.ns(|| "compress public inputs"), self.hash_helper)?;
+        let (epoch_bits, last_max_non_signers) = self.enforce(&mut cs.ns(|| "check signature"))?;
+        epoch_bits.verify(&mut cs.ns(|| PUBLIC_INPUTS_NAMESPACE), self.hash_helper)?;
+        if self.config.public_fields.maximum_non_signers {
+            let input = FrGadget::alloc_input(cs.ns(|| "last maximum non signers"), || {
+                last_max_non_signers.get_value().get()
+            })?;
+            last_max_non_signers
+                .enforce_equal(cs.ns(|| "expose last maximum non signers"), &input)?;
+        }
 
         info!("constraints generated");
 
@@ -94,7 +127,12 @@ impl ConstraintSynthesizer<Fr> for ValidatorSetUpdate<Bls12_377> {
 }
 
 impl ValidatorSetUpdate<Bls12_377> {
-    fn enforce<CS: ConstraintSystem<Fr>>(&self, cs: &mut CS) -> Result<EpochBits, SynthesisError> {
+    /// Returns the bits of the first and last epochs, and the last epoch's maximum number of
+    /// non signers
+    fn enforce<CS: ConstraintSystem<Fr>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<(EpochBits, FrGadget), SynthesisError> {
         let span = span!(Level::TRACE, "ValidatorSetUpdate_enforce");
         let _enter = span.enter();
 
@@ -112,6 +150,7 @@ impl ValidatorSetUpdate<Bls12_377> {
             xof_bits,
             prepared_aggregated_public_keys,
             prepared_message_hashes,
+            last_max_non_signers,
         ) = self.verify_intermediate_epochs(
             &mut cs.ns(|| "verify epochs"),
             first_epoch_index,
@@ -127,12 +166,14 @@ impl ValidatorSetUpdate<Bls12_377> {
             &prepared_message_hashes,
         )?;
 
-        Ok(EpochBits {
+        let epoch_bits = EpochBits {
             first_epoch_bits,
             last_epoch_bits,
             crh_bits,
             xof_bits,
-        })
+            packing: self.config.packing,
+        };
+        Ok((epoch_bits, last_max_non_signers))
     }
 
     /// Ensure that all epochs's bitmaps have been correctly computed
@@ -152,6 +193,7 @@ impl ValidatorSetUpdate<Bls12_377> {
             Vec<Boolean>,
             Vec<G2PreparedGadget>,
             Vec<G1PreparedGadget>,
+            FrGadget,
         ),
         SynthesisError,
     > {
@@ -175,6 +217,8 @@ impl ValidatorSetUpdate<Bls12_377> {
                 &previous_epoch_index,
                 &previous_max_non_signers,
                 self.num_validators,
+                self.domain,
+                self.crh_parameters.as_deref(),
                 self.hash_helper.is_none(), // generate constraints in SW6 if no helper was provided
             )?;
 
@@ -211,6 +255,7 @@ impl ValidatorSetUpdate<Bls12_377> {
             all_xof_bits,
             prepared_aggregated_public_keys,
             prepared_message_hashes,
+            previous_max_non_signers,
         ))
     }
 
@@ -225,7 +270,7 @@ impl ValidatorSetUpdate<Bls12_377> {
             self.aggregated_signature.get()
         })?;
         BlsGadget::batch_verify_prepared(
-            cs.ns(|| "batch verify BLS"),
+            cs.ns(|| BLS_VERIFICATION_NAMESPACE),
             &pubkeys,
             &messages,
             &aggregated_signature,
@@ -239,7 +284,9 @@ impl ValidatorSetUpdate<Bls12_377> {
 mod tests {
     use super::*;
 
-    use crate::gadgets::single_update::test_helpers::generate_single_update;
+    use crate::{
+        encoding::PublicFields, gadgets::single_update::test_helpers::generate_single_update,
+    };
     use algebra::bls12_377::G1Projective;
     use bls_crypto::test_helpers::{keygen_batch, keygen_mul, sign_batch, sum};
     use r1cs_std::test_constraint_system::TestConstraintSystem;
@@ -318,11 +365,27 @@ mod tests {
                 num_validators,
                 aggregated_signature: Some(aggregated_signature),
                 hash_helper: None,
+                domain: Domain::default(),
+                crh_parameters: None,
+                config: CircuitConfig::default(),
             };
 
             let mut cs = TestConstraintSystem::<Fr>::new();
             valset.enforce(&mut cs).unwrap();
             assert!(cs.is_satisfied());
+
+            // the maximum number of non signers can be exposed
+            let mut cs = TestConstraintSystem::<Fr>::new();
+            valset
+                .with_config(CircuitConfig {
+                    public_fields: PublicFields {
+                        maximum_non_signers: true,
+                    },
+                    ..Default::default()
+                })
+                .generate_constraints(&mut cs)
+                .unwrap();
+            assert!(cs.is_satisfied());
         }
     }
 }
@@ -5,7 +5,7 @@ use algebra::{
 use r1cs_core::{ConstraintSynthesizer, ConstraintSystem, SynthesisError};
 use tracing::{debug, info, span, trace, Level};
 
-use bls_crypto::SIG_DOMAIN;
+use bls_crypto::{hash_to_curve::try_and_increment::SnarkCrh, Domain};
 use bls_gadgets::hash_to_bits;
 
 use super::{constrain_bool, MultipackGadget};
@@ -20,14 +20,17 @@ use super::{constrain_bool, MultipackGadget};
 /// are correctly calculated from the CRH in the SNARK
 pub struct HashToBits {
     pub message_bits: Vec<Vec<Option<bool>>>,
+    /// The domain used as the XOF's personalization
+    pub domain: Domain,
 }
 
 impl HashToBits {
-    /// Initializes an empty vector of bits. This is called when running the trusted setup
-    pub fn empty<P: FpParameters>(num_epochs: usize) -> Self {
-        let modulus_bit_rounded = (((P::MODULUS_BITS + 7) / 8) * 8) as usize;
+    /// Initializes an empty vector of bits, one for each bit of the output of the CRH which
+    /// the SNARK uses. This is called when running the trusted setup
+    pub fn empty(num_epochs: usize, domain: Domain) -> Self {
         HashToBits {
-            message_bits: vec![vec![None; modulus_bit_rounded]; num_epochs],
+            message_bits: vec![vec![None; SnarkCrh::CURRENT.output_bits()]; num_epochs],
+            domain,
         }
     }
 }
@@ -41,8 +44,7 @@ impl ConstraintSynthesizer<Fr> for HashToBits {
         let span = span!(Level::TRACE, "HashToBits");
         info!("generating constraints");
         let _enter = span.enter();
-        let mut personalization = [0; 8];
-        personalization.copy_from_slice(SIG_DOMAIN);
+        let personalization = self.domain.personalization();
 
         let mut all_bits = vec![];
         let mut xof_bits = vec![];
@@ -84,9 +86,12 @@ impl ConstraintSynthesizer<Fr> for HashToBits {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::gadgets::pack;
-    use algebra::{sw6::FrParameters as SW6FrParameters, Bls12_377};
-    use bls_crypto::hashers::{DirectHasher, Hasher};
+    use crate::encoding::pack;
+    use algebra::Bls12_377;
+    use bls_crypto::{
+        hashers::{DirectHasher, Hasher},
+        SIG_DOMAIN,
+    };
     use bls_gadgets::utils::{bits_to_bytes, bytes_to_bits};
     use groth16::{
         create_random_proof, generate_random_parameters, prepare_verifying_key, verify_proof,
@@ -109,21 +114,21 @@ mod tests {
         let rng = &mut rand::thread_rng();
         // generate an empty circuit for 3 epochs
         let num_epochs = 3;
-        // Trusted Setup -- USES THE SW6FrParameters!
+        // Trusted Setup
         let params = {
-            let empty = HashToBits::empty::<SW6FrParameters>(num_epochs);
+            let empty = HashToBits::empty(num_epochs, Domain::default());
             generate_random_parameters::<Bls12_377, _, _>(empty, rng).unwrap()
         };
 
         // Prover generates the input and the proof
-        // Each message must be 384 bits.
+        // Each message must be as long as the CRH's output.
         let (proof, input) = {
             let mut message_bits = Vec::new();
             for _ in 0..num_epochs {
                 // say we have some input
                 let mut input = vec![0; 64];
                 rng.fill_bytes(&mut input);
-                let bits = bytes_to_bits(&input, 384)
+                let bits = bytes_to_bits(&input, SnarkCrh::CURRENT.output_bits())
                     .iter()
                     .map(|b| Some(*b))
                     .collect::<Vec<_>>();
@@ -133,6 +138,7 @@ mod tests {
             // generate the proof
             let circuit = HashToBits {
                 message_bits: message_bits.clone(),
+                domain: Domain::default(),
             };
             let proof = create_random_proof(circuit, &params, rng).unwrap();
 
@@ -1,6 +1,17 @@
 mod epoch_data;
 pub use epoch_data::EpochData;
 
+// The namespaces of the gadgets whose constraints `constraint_breakdown` counts separately
+
+/// Sums the pubkeys of an epoch's signers and prepares them for the pairing
+pub(crate) const AGGREGATION_NAMESPACE: &str = "verify signature partial";
+/// The pairing checks of the aggregated signature against all epochs
+pub(crate) const BLS_VERIFICATION_NAMESPACE: &str = "batch verify BLS";
+/// The CRH->XOF hash of an epoch and the try-and-increment to G1
+pub(crate) const HASH_TO_G1_NAMESPACE: &str = "hash epoch to g1 bits";
+/// The Blake2 hashes of the first and last epoch and their packing to the public inputs
+pub(crate) const PUBLIC_INPUTS_NAMESPACE: &str = "compress public inputs";
+
 mod hash_to_bits;
 pub use hash_to_bits::HashToBits;
 
@@ -17,7 +28,7 @@ mod epochs;
 pub use epochs::{HashToBitsHelper, ValidatorSetUpdate};
 
 // some helpers
-use algebra::{bls12_377::Parameters, sw6::Fr, BigInteger, Field, FpParameters, PrimeField};
+use algebra::{bls12_377::Parameters, sw6::Fr, Field};
 use r1cs_std::prelude::*;
 use r1cs_std::{bls12_377::G2Gadget, fields::fp::FpGadget, Assignment};
 
@@ -31,9 +42,7 @@ pub mod test_helpers {
     use super::*;
     use crate::epoch_block::EpochBlock;
     use algebra::{bls12_377::G1Projective, Bls12_377};
-    use bls_crypto::{
-        hash_to_curve::try_and_increment::COMPOSITE_HASH_TO_G1, PublicKey, SIG_DOMAIN,
-    };
+    use bls_crypto::{hash_to_curve::try_and_increment::SnarkCrh, PublicKey, SIG_DOMAIN};
 
     pub fn to_option_iter<T: Copy>(it: &[T]) -> Vec<Option<T>> {
         it.iter().map(|t| Some(*t)).collect()
@@ -49,7 +58,7 @@ pub mod test_helpers {
         let epoch_bytes = EpochBlock::new(epoch.index.unwrap(), epoch.maximum_non_signers, pubkeys)
             .encode_to_bytes()
             .unwrap();
-        let (hash, _) = COMPOSITE_HASH_TO_G1
+        let (hash, _) = SnarkCrh::CURRENT
             .hash_with_attempt(SIG_DOMAIN, &epoch_bytes, &[])
             .unwrap();
 
@@ -57,16 +66,6 @@ pub mod test_helpers {
     }
 }
 
-pub(super) fn pack<F: PrimeField, P: FpParameters>(values: &[bool]) -> Vec<F> {
-    values
-        .chunks(P::CAPACITY as usize)
-        .map(|c| {
-            let b = F::BigInt::from_bits(c);
-            F::from_repr(b)
-        })
-        .collect::<Vec<_>>()
-}
-
 fn to_fr<T: Into<u64>, CS: ConstraintSystem<Fr>>(
     cs: &mut CS,
     num: Option<T>,
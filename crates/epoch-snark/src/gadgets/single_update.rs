@@ -6,7 +6,8 @@ use r1cs_std::{
     fields::fp::FpGadget,
 };
 
-use super::{constrain_bool, EpochData};
+use super::{constrain_bool, EpochData, AGGREGATION_NAMESPACE};
+use bls_crypto::{hashers::composite::CRHParameters, Domain};
 use bls_gadgets::BlsVerifyGadget;
 use tracing::{span, Level};
 
@@ -60,11 +61,12 @@ pub struct ConstrainedEpoch {
 
 impl SingleUpdate<Bls12_377> {
     /// Ensures that enough validators are present on the bitmap and generates
-    /// the epoch's G1 Hash and Aggregated Public Key
+    /// the epoch's G1 Hash (in the provided domain) and Aggregated Public Key
     ///
     /// # Panics
     ///
     /// - If `num_validators != self.epoch_data.public_keys.len()`
+    #[allow(clippy::too_many_arguments)]
     pub fn constrain<CS: ConstraintSystem<Fr>>(
         &self,
         cs: &mut CS,
@@ -72,6 +74,8 @@ impl SingleUpdate<Bls12_377> {
         previous_epoch_index: &FrGadget,
         previous_max_non_signers: &FrGadget,
         num_validators: u32,
+        domain: Domain,
+        crh_parameters: Option<&CRHParameters>,
         generate_constraints_for_hash: bool,
     ) -> Result<ConstrainedEpoch, SynthesisError> {
         let span = span!(Level::TRACE, "SingleUpdate");
@@ -83,6 +87,8 @@ impl SingleUpdate<Bls12_377> {
         let epoch_data = self.epoch_data.constrain(
             &mut cs.ns(|| "constrain"),
             previous_epoch_index,
+            domain,
+            crh_parameters,
             generate_constraints_for_hash,
         )?;
 
@@ -93,7 +99,7 @@ impl SingleUpdate<Bls12_377> {
         // previous epoch and prepare the message hash and the aggregate pk
         let (prepared_message_hash, prepared_aggregated_public_key) =
             BlsGadget::enforce_bitmap_and_prepare(
-                cs.ns(|| "verify signature partial"),
+                cs.ns(|| AGGREGATION_NAMESPACE),
                 previous_pubkeys,
                 &signed_bitmap,
                 &epoch_data.message_hash,
@@ -128,6 +134,7 @@ pub mod test_helpers {
             index: Some(index),
             maximum_non_signers,
             public_keys: to_option_iter(public_keys),
+            hash_counter: None,
         };
 
         SingleUpdate::<E> {
@@ -167,7 +174,49 @@ mod tests {
         single_update_enforce(&mut cs, 5, 5, 4, 5, 1, &[true, true, false, true, false]);
         assert!(!cs.is_satisfied());
         let not_satisfied = cs.which_is_unsatisfied();
-        assert_eq!(not_satisfied.unwrap(), "constrain epoch 2/verify signature partial/enforce maximum number of occurrences/enforce smaller than/enforce smaller than/enforce smaller than");
+        assert_eq!(not_satisfied.unwrap(), "constrain epoch 2/verify signature partial/enforce bitmap/enforce maximum number of occurrences/enforce smaller than/enforce smaller than/enforce smaller than");
+    }
+
+    #[test]
+    fn all_signers_required_without_non_signers() {
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        single_update_enforce(&mut cs, 5, 5, 1, 2, 0, &[true; 5]);
+        assert!(cs.is_satisfied());
+
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        single_update_enforce(&mut cs, 5, 5, 1, 2, 0, &[true, true, true, true, false]);
+        assert!(!cs.is_satisfied());
+    }
+
+    #[test]
+    fn threshold_matches_check_quorum() {
+        use crate::epoch_block::EpochBlock;
+        use bls_crypto::PublicKey;
+
+        let pubkeys = pubkeys::<Bls12_377>(5)
+            .into_iter()
+            .map(PublicKey::from)
+            .collect::<Vec<_>>();
+        for maximum_non_signers in 0..5 {
+            let block = EpochBlock::new(1, maximum_non_signers, pubkeys.clone());
+            // up to the boundary and one past it
+            for non_signers in 0..=(maximum_non_signers as usize + 1) {
+                let bitmap = (0..5).map(|i| i >= non_signers).collect::<Vec<_>>();
+                let mut cs = TestConstraintSystem::<Fr>::new();
+                single_update_enforce(&mut cs, 5, 5, 1, 2, maximum_non_signers, &bitmap);
+                assert_eq!(
+                    cs.is_satisfied(),
+                    block.check_quorum(&bitmap).is_ok(),
+                    "{} non signers, maximum {}",
+                    non_signers,
+                    maximum_non_signers
+                );
+                assert_eq!(
+                    cs.is_satisfied(),
+                    non_signers <= maximum_non_signers as usize
+                );
+            }
+        }
     }
 
     #[test]
@@ -211,6 +260,8 @@ mod tests {
                 &prev_index,
                 &prev_max_non_signers,
                 prev_n_validators as u32,
+                Domain::default(),
+                None,
                 false,
             )
             .unwrap()
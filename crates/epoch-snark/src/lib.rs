@@ -18,17 +18,56 @@
 //! blocks.
 //!
 //! A presentation of this mechanism can be found [here](https://www.youtube.com/watch?v=2e0XpWgFKLg).
+//!
+//! ## `no_std`
+//!
+//! With the default `std` feature disabled the crate only depends on `core` and `alloc`,
+//! and only exposes the verifier and the `EpochBlock` encoding. The file helpers require
+//! `std`, and proving, the parameter setup and the circuit require the `prover` feature.
+//!
+//! ## Verifying without the prover
+//!
+//! Light clients which link the standard library but never prove can build the crate with
+//! `default-features = false, features = ["std", "compat"]`, which leaves out the `prover`
+//! feature along with the dependencies which are only used for proving.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(not(feature = "std"))]
+#[macro_use]
+extern crate alloc;
+
+#[cfg(not(feature = "std"))]
+use alloc::{borrow::ToOwned, string::String, vec::Vec};
+#[cfg(feature = "std")]
+use std::{borrow::ToOwned, string::String, vec::Vec};
 
 /// High level methods for generating public parameters & producing and verifying SNARK proofs for
 /// a headerchain of blocks
 mod api;
 pub use api::*;
 
+/// Helpers for verifying proofs stored in files
+#[cfg(feature = "std")]
+pub mod cli;
+
 mod encoding;
-pub use encoding::EncodingError;
+pub use encoding::{
+    decode_proof_envelope, deserialize_compressed, encode_proof_envelope, EncodingError,
+    PackingWidth, ProofEnvelope, PublicFields, Strictness, PROOF_ENVELOPE_CURVE_SW6,
+    PROOF_ENVELOPE_MAGIC,
+};
 
 mod epoch_block;
-pub use epoch_block::{EpochBlock, EpochTransition};
+pub use epoch_block::{
+    padding_public_key, signers_from_nonsigner_indices, EpochBlock, EpochBlockBuilder,
+    EpochBlockError, EpochBlockHasher, EpochTransition, EpochTransitionIter,
+    EPOCH_BLOCK_HEADER_BYTES, MAX_VALIDATORS,
+};
+
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 
+#[cfg(feature = "prover")]
 mod gadgets;
+#[cfg(feature = "prover")]
 pub use gadgets::ValidatorSetUpdate;
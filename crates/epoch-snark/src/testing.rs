@@ -0,0 +1,53 @@
+//! Helpers for generating epochs in tests, without needing hardcoded keys
+use crate::{epoch_block::EpochBlock, Vec};
+use bls_crypto::PrivateKey;
+use rand::Rng;
+
+/// Generates an epoch block with `num_validators` fresh validators, and returns it along
+/// with the validators' private keys (in the order of the block's public keys), so that
+/// tests can sign the next epoch with them
+pub fn random_epoch_block<R: Rng>(
+    rng: &mut R,
+    num_validators: usize,
+    index: u16,
+    maximum_non_signers: u32,
+) -> (EpochBlock, Vec<PrivateKey>) {
+    let private_keys = (0..num_validators)
+        .map(|_| PrivateKey::generate(rng))
+        .collect::<Vec<_>>();
+    let public_keys = private_keys.iter().map(PrivateKey::to_public).collect();
+    (
+        EpochBlock::new(index, maximum_non_signers, public_keys),
+        private_keys,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bls_crypto::{hash_to_curve::try_and_increment::SnarkCrh, PublicKey, Signature};
+
+    #[test]
+    fn keys_sign_for_block() {
+        let rng = &mut rand::thread_rng();
+        let (block, private_keys) = random_epoch_block(rng, 4, 7, 1);
+        assert_eq!(block.index, 7);
+        assert_eq!(block.maximum_non_signers, 1);
+        assert_eq!(private_keys.len(), 4);
+        for (private_key, public_key) in private_keys.iter().zip(block.pubkeys()) {
+            assert_eq!(&private_key.to_public(), public_key);
+        }
+
+        // the validators' aggregate signature verifies against their aggregate key
+        let (next, _) = random_epoch_block(rng, 4, 8, 1);
+        let message = next.encode_to_bytes().unwrap();
+        let signature = Signature::aggregate(
+            private_keys
+                .iter()
+                .map(|key| key.sign(&message, &[], &SnarkCrh::CURRENT).unwrap()),
+        );
+        PublicKey::aggregate(block.pubkeys())
+            .verify(&message, &[], &signature, &SnarkCrh::CURRENT)
+            .unwrap();
+    }
+}
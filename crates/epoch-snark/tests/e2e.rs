@@ -1,37 +1,556 @@
-use epoch_snark::{prove, trusted_setup, verify};
+use algebra::CanonicalSerialize;
+
+use epoch_snark::{
+    link_proofs, prove, prove_deterministic, prove_with_context, prove_with_metrics,
+    prove_with_options, prove_with_threads, prove_with_witness, trusted_setup,
+    trusted_setup_with_config, verify, verify_accumulated, verify_linked, verify_serialized,
+    verify_window, verify_with_config, CircuitConfig, EncodingError, EpochProofAccumulator,
+    EpochProver, EpochTransition, PackingWidth, ProveOptions, ProverContext, PublicFields,
+    VerificationError, VerifyOptions, Witness,
+};
 
 mod fixtures;
-use fixtures::generate_test_data;
+use bls_crypto::{hash_to_curve::try_and_increment::SnarkCrh, Domain, Signature};
+use epoch_snark::testing::random_epoch_block;
+use fixtures::{
+    generate_test_data, setup, setup_with_config, FAULTS, NUM_TRANSITIONS, NUM_VALIDATORS,
+};
 
 #[test]
 #[ignore] // This test makes CI run out of memory and takes too long. It works though!
 fn prover_verifier_groth16() {
+    // Trusted setup, and the state to be proven (first epoch + `NUM_TRANSITIONS` transitions).
+    // Note: This is all data which should be fetched via the Celo blockchain
+    let (params, first_epoch, transitions, last_epoch) = setup(NUM_TRANSITIONS);
+
+    // Prover generates the proof given the params
+    let proof = prove(&params, NUM_VALIDATORS as u32, &first_epoch, &transitions).unwrap();
+
+    // Verifier checks the proof
+    let res = verify(&params.epochs.vk, &first_epoch, &last_epoch, &proof);
+    assert!(res.is_ok());
+}
+
+#[test]
+#[ignore] // Same as above, runs 2 setups and the prover twice
+fn proves_different_windows() {
+    let rng = &mut rand::thread_rng();
+
+    let mut proofs = vec![];
+    for &num_transitions in &[3, 6] {
+        let params = trusted_setup(NUM_VALIDATORS, num_transitions, FAULTS, rng, true).unwrap();
+        let (first_epoch, transitions, last_epoch) =
+            generate_test_data(NUM_VALIDATORS, FAULTS, num_transitions);
+        let proof = prove(&params, NUM_VALIDATORS as u32, &first_epoch, &transitions).unwrap();
+        verify_window(
+            &params.epochs.vk,
+            num_transitions,
+            &first_epoch,
+            &last_epoch,
+            &proof,
+        )
+        .unwrap();
+        proofs.push((params.epochs.vk, first_epoch, last_epoch, proof));
+    }
+
+    // each proof only verifies against the VK for its window
+    let (vk_3, first_3, last_3, proof_3) = &proofs[0];
+    let (vk_6, first_6, last_6, proof_6) = &proofs[1];
+    let err = verify_window(vk_6, 6, first_3, last_3, proof_3).unwrap_err();
+    assert!(matches!(
+        err,
+        VerificationError::EpochCountMismatch {
+            expected: 6,
+            got: 3
+        }
+    ));
+    let err = verify(vk_6, first_3, last_3, proof_3).unwrap_err();
+    assert!(matches!(err, VerificationError::VerificationFailed));
+    let err = verify(vk_3, first_6, last_6, proof_6).unwrap_err();
+    assert!(matches!(err, VerificationError::VerificationFailed));
+}
+
+#[test]
+#[ignore] // Same as above, runs the prover twice
+fn proof_does_not_depend_on_thread_count() {
+    let (params, first_epoch, transitions, last_epoch) = setup(NUM_TRANSITIONS);
+
+    let serial = prove_with_threads(
+        &params,
+        NUM_VALIDATORS as u32,
+        &first_epoch,
+        &transitions,
+        Some(1),
+    )
+    .unwrap();
+    let parallel = prove_with_threads(
+        &params,
+        NUM_VALIDATORS as u32,
+        &first_epoch,
+        &transitions,
+        Some(4),
+    )
+    .unwrap();
+    assert_eq!(serial, parallel);
+    // without a thread count, the prover runs on the global pool
+    let global = prove_with_threads(
+        &params,
+        NUM_VALIDATORS as u32,
+        &first_epoch,
+        &transitions,
+        None,
+    )
+    .unwrap();
+    assert_eq!(global, serial);
+    assert!(verify(&params.epochs.vk, &first_epoch, &last_epoch, &parallel).is_ok());
+}
+
+#[test]
+#[ignore] // Same as above
+fn accumulates_transitions() {
+    let (params, first_epoch, transitions, last_epoch) = setup(NUM_TRANSITIONS);
+
+    let mut accumulator = EpochProofAccumulator::new(NUM_VALIDATORS as u32, first_epoch);
+    for transition in transitions {
+        accumulator.push(transition).unwrap();
+    }
+    let accumulated = accumulator.finalize(&params).unwrap();
+    assert_eq!(accumulated.last_epoch, last_epoch);
+    verify_accumulated(&params.epochs.vk, &accumulated).unwrap();
+}
+
+#[test]
+#[ignore] // Same as above
+fn reports_prover_metrics() {
+    let (params, first_epoch, transitions, last_epoch) = setup(NUM_TRANSITIONS);
+
+    let (proof, metrics) =
+        prove_with_metrics(&params, NUM_VALIDATORS as u32, &first_epoch, &transitions).unwrap();
+    assert!(metrics.num_constraints > 0);
+    assert!(metrics.num_variables > 0);
+    assert!(metrics.witness_generation.as_nanos() > 0);
+    assert!(metrics.hash_to_bits.as_nanos() > 0);
+    assert!(metrics.proving.as_nanos() > 0);
+
+    let mut vk = vec![];
+    params.epochs.vk.serialize(&mut vk).unwrap();
+    verify_serialized(&vk, &first_epoch, &last_epoch, &proof).unwrap();
+}
+
+#[test]
+#[ignore] // Same as above, runs 2 setups
+fn proves_in_custom_domain() {
+    let domain = Domain::new(b"testnet").unwrap();
+
+    let (params, first_epoch, transitions, last_epoch) =
+        setup_with_config(NUM_TRANSITIONS, domain, CircuitConfig::default());
+    let proof = prove(&params, NUM_VALIDATORS as u32, &first_epoch, &transitions).unwrap();
+    verify(&params.epochs.vk, &first_epoch, &last_epoch, &proof).unwrap();
+
+    // the default domain's keys cannot verify proofs for the epochs signed in the custom domain
+    let (default_params, first_epoch_default, transitions_default, last_epoch_default) =
+        setup(NUM_TRANSITIONS);
+    let err = verify(&default_params.epochs.vk, &first_epoch, &last_epoch, &proof).unwrap_err();
+    assert!(matches!(err, VerificationError::VerificationFailed));
+
+    // and epochs signed in the default domain cannot be proven with the custom domain's keys
+    let (first_epoch, transitions, last_epoch) =
+        (first_epoch_default, transitions_default, last_epoch_default);
+    let proof = prove(&params, NUM_VALIDATORS as u32, &first_epoch, &transitions).unwrap();
+    let err = verify(&params.epochs.vk, &first_epoch, &last_epoch, &proof).unwrap_err();
+    assert!(matches!(err, VerificationError::VerificationFailed));
+}
+
+#[test]
+#[ignore] // Same as above, runs the prover 3 times
+fn context_does_not_change_proof() {
+    let (params, first_epoch, transitions, last_epoch) = setup(NUM_TRANSITIONS);
+
+    let proof = prove(&params, NUM_VALIDATORS as u32, &first_epoch, &transitions).unwrap();
+    let context = ProverContext::new().unwrap();
+    for _ in 0..2 {
+        let with_context = prove_with_context(
+            &context,
+            &params,
+            NUM_VALIDATORS as u32,
+            &first_epoch,
+            &transitions,
+        )
+        .unwrap();
+        assert_eq!(with_context, proof);
+    }
+
+    // nor does combining it with a witness
+    let mut witness = Witness::new();
+    let options = ProveOptions {
+        context: Some(&context),
+        witness: Some(&mut witness),
+        ..Default::default()
+    };
+    let combined = prove_with_options(
+        &params,
+        NUM_VALIDATORS as u32,
+        &first_epoch,
+        &transitions,
+        options,
+    )
+    .unwrap();
+    assert_eq!(combined, proof);
+    assert_eq!(witness.len(), NUM_TRANSITIONS + 1);
+    verify(&params.epochs.vk, &first_epoch, &last_epoch, &proof).unwrap();
+}
+
+#[test]
+#[ignore] // Same as above, runs the prover 5 times
+fn witness_does_not_change_proof() {
+    let (params, first_epoch, transitions, last_epoch) = setup(NUM_TRANSITIONS + 1);
+
+    // two overlapping windows share the witness of their common epochs
+    let mut witness = Witness::new();
+    let windows = [
+        (&first_epoch, &transitions[..NUM_TRANSITIONS]),
+        (&transitions[0].block, &transitions[1..]),
+    ];
+    for (initial_epoch, window) in windows.iter() {
+        let proof = prove_with_witness(
+            &params,
+            NUM_VALIDATORS as u32,
+            initial_epoch,
+            window,
+            &mut witness,
+        )
+        .unwrap();
+        let expected = prove(&params, NUM_VALIDATORS as u32, initial_epoch, window).unwrap();
+        assert_eq!(proof, expected);
+
+        let last = &window[NUM_TRANSITIONS - 1].block;
+        verify(&params.epochs.vk, initial_epoch, last, &proof).unwrap();
+    }
+    assert_eq!(witness.len(), NUM_TRANSITIONS + 2);
+
+    // removed epochs get hashed again
+    assert!(witness.remove(&last_epoch, params.domain));
+    let (initial_epoch, window) = windows[1];
+    let proof = prove_with_witness(
+        &params,
+        NUM_VALIDATORS as u32,
+        initial_epoch,
+        window,
+        &mut witness,
+    )
+    .unwrap();
+    verify(&params.epochs.vk, initial_epoch, &last_epoch, &proof).unwrap();
+}
+
+#[test]
+#[ignore] // Same as above, runs the prover twice
+fn links_adjacent_proofs() {
+    let (params, first_epoch, transitions, last_epoch) = setup(2 * NUM_TRANSITIONS);
+    let (first, second) = transitions.split_at(NUM_TRANSITIONS);
+    let boundary_epoch = &first[NUM_TRANSITIONS - 1].block;
+
+    let first_proof = prove(&params, NUM_VALIDATORS as u32, &first_epoch, first).unwrap();
+    let second_proof = prove(&params, NUM_VALIDATORS as u32, boundary_epoch, second).unwrap();
+    let linked = link_proofs(
+        &first_proof,
+        &first_epoch,
+        boundary_epoch,
+        &second_proof,
+        boundary_epoch,
+        &last_epoch,
+    )
+    .unwrap();
+    verify_linked(&params.epochs.vk, &params.epochs.vk, &linked).unwrap();
+
+    // the proofs are only valid for their own windows
+    let mut swapped = linked.clone();
+    swapped.first = second_proof;
+    swapped.second = first_proof;
+    let err = verify_linked(&params.epochs.vk, &params.epochs.vk, &swapped).unwrap_err();
+    assert!(matches!(err, VerificationError::VerificationFailed));
+
+    // the second window must start where the first one ends
+    let err = link_proofs(
+        &linked.first,
+        &first_epoch,
+        boundary_epoch,
+        &linked.second,
+        &first[0].block,
+        &last_epoch,
+    )
+    .unwrap_err();
+    assert!(matches!(
+        err,
+        VerificationError::EpochBoundaryMismatch { end: 2, start: 1 }
+    ));
+}
+
+#[test]
+#[ignore] // Same as above
+fn epoch_prover_roundtrip() {
+    let rng = &mut rand::thread_rng();
+    let prover = EpochProver::setup(
+        NUM_VALIDATORS,
+        NUM_TRANSITIONS,
+        FAULTS,
+        rng,
+        true,
+        Domain::default(),
+    )
+    .unwrap();
+    let (first_epoch, transitions, last_epoch) =
+        generate_test_data(NUM_VALIDATORS, FAULTS, NUM_TRANSITIONS);
+
+    let proof = prover.prove(&first_epoch, &transitions).unwrap();
+    prover.verify(&proof, &first_epoch, &last_epoch).unwrap();
+    // the free functions accept the same proof
+    verify(
+        &prover.parameters().epochs.vk,
+        &first_epoch,
+        &last_epoch,
+        &proof,
+    )
+    .unwrap();
+}
+
+#[test]
+#[ignore] // Same as above
+fn proves_with_custom_packing() {
+    let config = CircuitConfig {
+        packing: PackingWidth::new(128).unwrap(),
+        ..Default::default()
+    };
+
+    let (params, first_epoch, transitions, last_epoch) =
+        setup_with_config(NUM_TRANSITIONS, Domain::default(), config);
+    assert_eq!(params.config, config);
+    let proof = prove(&params, NUM_VALIDATORS as u32, &first_epoch, &transitions).unwrap();
+    verify_with_config(
+        &params.epochs.vk,
+        &first_epoch,
+        &last_epoch,
+        &proof,
+        &config,
+        &VerifyOptions::default(),
+    )
+    .unwrap();
+
+    // the default width results in 2 public inputs instead of 4
+    let err = verify(&params.epochs.vk, &first_epoch, &last_epoch, &proof).unwrap_err();
+    assert!(matches!(
+        err,
+        VerificationError::PublicInputMismatch {
+            expected: 4,
+            got: 2
+        }
+    ));
+    // a width with the same number of inputs yields different inputs
+    let other = CircuitConfig {
+        packing: PackingWidth::new(129).unwrap(),
+        ..config
+    };
+    let err = verify_with_config(
+        &params.epochs.vk,
+        &first_epoch,
+        &last_epoch,
+        &proof,
+        &other,
+        &VerifyOptions::default(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, VerificationError::VerificationFailed));
+}
+
+#[test]
+#[ignore] // Same as above
+fn proves_padded_validator_sets() {
     let rng = &mut rand::thread_rng();
-    let num_transitions = 2;
+    let num_validators = 10;
     let faults = 1;
-    let num_validators = 3 * faults + 1;
 
-    let hashes_in_bls12_377 = true;
+    // a chain of epochs with 4, 7 and 10 validators, for a circuit with 10 validators
+    let params = trusted_setup(num_validators, 2, faults, rng, true).unwrap();
+    let epochs = [4, 7, 10]
+        .iter()
+        .enumerate()
+        .map(|(index, size)| random_epoch_block(rng, *size, index as u16, faults as u32))
+        .collect::<Vec<_>>();
+    // all but the first `faults` validators of each epoch sign the next padded epoch
+    let transitions = epochs
+        .windows(2)
+        .map(|pair| {
+            let (previous, signers) = &pair[0];
+            let block = pair[1].0.clone();
+            let message = block
+                .padded(num_validators)
+                .unwrap()
+                .encode_to_bytes()
+                .unwrap();
+            let bitmap = (0..previous.new_public_keys.len())
+                .map(|i| i >= faults)
+                .collect::<Vec<_>>();
+            let aggregate_signature = Signature::aggregate(
+                signers
+                    .iter()
+                    .zip(&bitmap)
+                    .filter(|(_, signed)| **signed)
+                    .map(|(key, _)| key.sign(&message, &[], &SnarkCrh::CURRENT).unwrap()),
+            );
+            EpochTransition {
+                block,
+                aggregate_signature,
+                bitmap,
+            }
+        })
+        .collect::<Vec<_>>();
+    let (first_epoch, last_epoch) = (&epochs[0].0, &epochs[2].0);
+    let proof = prove(&params, num_validators as u32, first_epoch, &transitions).unwrap();
+
+    let verify_padded = |num_validators| {
+        let options = VerifyOptions {
+            num_validators: Some(num_validators),
+            ..Default::default()
+        };
+        verify_with_config(
+            &params.epochs.vk,
+            first_epoch,
+            last_epoch,
+            &proof,
+            &CircuitConfig::default(),
+            &options,
+        )
+    };
+    verify_padded(num_validators).unwrap();
+    // the proof is for the padded first epoch
+    let err = verify(&params.epochs.vk, first_epoch, last_epoch, &proof).unwrap_err();
+    assert!(matches!(err, VerificationError::VerificationFailed));
+    // epochs with more validators than the circuit's are rejected
+    let err = verify_padded(7).unwrap_err();
+    assert!(matches!(
+        err,
+        VerificationError::EpochEncodingError(EncodingError::TooManyValidators { got: 10, max: 7 })
+    ));
+    let mut transitions = transitions;
+    transitions[1].block = random_epoch_block(rng, 11, 2, faults as u32).0;
+    assert!(prove(&params, num_validators as u32, first_epoch, &transitions).is_err());
+}
+
+#[test]
+#[ignore] // Same as above
+fn proves_with_public_fields() {
+    // the bitmaps and the maximum number of non signers are witnesses by default
+    let (params, first_epoch, transitions, last_epoch) = setup(NUM_TRANSITIONS);
+    assert_eq!(params.config, CircuitConfig::default());
+    let proof = prove(&params, NUM_VALIDATORS as u32, &first_epoch, &transitions).unwrap();
+    verify(&params.epochs.vk, &first_epoch, &last_epoch, &proof).unwrap();
+
+    let exposed = CircuitConfig {
+        public_fields: PublicFields {
+            maximum_non_signers: true,
+        },
+        ..Default::default()
+    };
+    let err = verify_with_config(
+        &params.epochs.vk,
+        &first_epoch,
+        &last_epoch,
+        &proof,
+        &exposed,
+        &VerifyOptions::default(),
+    )
+    .unwrap_err();
+    assert!(matches!(
+        err,
+        VerificationError::PublicInputMismatch {
+            expected: 2,
+            got: 3
+        }
+    ));
 
-    // Trusted setup
-    let params = trusted_setup(
-        num_validators,
-        num_transitions,
-        faults,
+    let rng = &mut rand::thread_rng();
+    let params = trusted_setup_with_config(
+        NUM_VALIDATORS,
+        NUM_TRANSITIONS,
+        FAULTS,
         rng,
-        hashes_in_bls12_377,
+        true,
+        Domain::default(),
+        exposed,
+    )
+    .unwrap();
+    assert_eq!(params.config, exposed);
+    let proof = prove(&params, NUM_VALIDATORS as u32, &first_epoch, &transitions).unwrap();
+    verify_with_config(
+        &params.epochs.vk,
+        &first_epoch,
+        &last_epoch,
+        &proof,
+        &exposed,
+        &VerifyOptions::default(),
     )
     .unwrap();
+    let err = verify(&params.epochs.vk, &first_epoch, &last_epoch, &proof).unwrap_err();
+    assert!(matches!(
+        err,
+        VerificationError::PublicInputMismatch {
+            expected: 3,
+            got: 2
+        }
+    ));
+    // the exposed field must match the last epoch's
+    let mut other = last_epoch.clone();
+    other.maximum_non_signers += 1;
+    assert!(verify_with_config(
+        &params.epochs.vk,
+        &first_epoch,
+        &other,
+        &proof,
+        &exposed,
+        &VerifyOptions::default()
+    )
+    .is_err());
+}
 
-    // Create the state to be proven (first epoch + `num_transitions` transitions.
-    // Note: This is all data which should be fetched via the Celo blockchain
-    let (first_epoch, transitions, last_epoch) =
-        generate_test_data(num_validators, faults, num_transitions);
+#[cfg(feature = "tokio")]
+#[tokio::test(threaded_scheduler)]
+#[ignore] // Same as above
+async fn proves_async() {
+    let (params, first_epoch, transitions, last_epoch) = setup(NUM_TRANSITIONS);
+    let params = std::sync::Arc::new(params);
+    let proof = epoch_snark::prove_async(
+        params.clone(),
+        NUM_VALIDATORS as u32,
+        first_epoch.clone(),
+        transitions,
+    )
+    .await
+    .unwrap();
 
-    // Prover generates the proof given the params
-    let proof = prove(&params, num_validators as u32, &first_epoch, &transitions).unwrap();
+    let mut vk = vec![];
+    params.epochs.vk.serialize(&mut vk).unwrap();
+    verify_serialized(&vk, &first_epoch, &last_epoch, &proof).unwrap();
+}
 
-    // Verifier checks the proof
-    let res = verify(&params.epochs.vk, &first_epoch, &last_epoch, &proof);
-    assert!(res.is_ok());
+#[test]
+#[ignore] // Same as above, runs the prover 3 times
+fn deterministic_proofs() {
+    let (params, first_epoch, transitions, last_epoch) = setup(NUM_TRANSITIONS);
+    let prove = |seed| {
+        let proof = prove_deterministic(
+            &params,
+            NUM_VALIDATORS as u32,
+            &first_epoch,
+            &transitions,
+            seed,
+        )
+        .unwrap();
+        verify(&params.epochs.vk, &first_epoch, &last_epoch, &proof).unwrap();
+        let mut bytes = vec![];
+        proof.serialize(&mut bytes).unwrap();
+        bytes
+    };
+
+    let proof = prove([1; 32]);
+    assert_eq!(proof, prove([1; 32]));
+    assert_ne!(proof, prove([2; 32]));
 }
@@ -1,11 +1,52 @@
-use algebra::{
-    bls12_377::{Bls12_377, G1Projective},
-    ProjectiveCurve, Zero,
+// Not all the fixtures are used by the examples which include this file
+#![allow(dead_code)]
+
+use algebra::{bls12_377::Bls12_377, sw6::SW6};
+use bls_crypto::{hash_to_curve::try_and_increment::SnarkCrh, Domain, Signature};
+use epoch_snark::{
+    testing::random_epoch_block, trusted_setup_with_config, CircuitConfig, EpochBlock,
+    EpochTransition, Parameters,
 };
 
-use bls_crypto::test_helpers::{keygen_batch, keygen_mul};
-use bls_crypto::{PublicKey, Signature};
-use epoch_snark::{EpochBlock, EpochTransition};
+// The window, the number of faults and the number of validators of the setup which the
+// tests prove with
+pub const NUM_TRANSITIONS: usize = 2;
+pub const FAULTS: usize = 1;
+pub const NUM_VALIDATORS: usize = 3 * FAULTS + 1;
+
+// The parameters of a setup, along with the first epoch, the transitions and the last epoch
+// to prove with them
+pub type Setup = (
+    Parameters<SW6, Bls12_377>,
+    EpochBlock,
+    Vec<EpochTransition>,
+    EpochBlock,
+);
+
+// Runs the trusted setup for `NUM_TRANSITIONS` transitions of `NUM_VALIDATORS` validators, and
+// returns its parameters along with `num_epochs` state transitions to prove with them
+pub fn setup(num_epochs: usize) -> Setup {
+    setup_with_config(num_epochs, Domain::default(), CircuitConfig::default())
+}
+
+// Same as `setup`, but for a circuit with the provided configuration, and with the
+// transitions signed in the provided domain
+pub fn setup_with_config(num_epochs: usize, domain: Domain, config: CircuitConfig) -> Setup {
+    let rng = &mut rand::thread_rng();
+    let params = trusted_setup_with_config(
+        NUM_VALIDATORS,
+        NUM_TRANSITIONS,
+        FAULTS,
+        rng,
+        true,
+        domain,
+        config,
+    )
+    .unwrap();
+    let (first_epoch, transitions, last_epoch) =
+        generate_test_data_with_domain(NUM_VALIDATORS, FAULTS, num_epochs, domain);
+    (params, first_epoch, transitions, last_epoch)
+}
 
 // Returns the initial epoch and a list of signed `num_epochs` state transitions
 pub fn generate_test_data(
@@ -13,77 +54,60 @@ pub fn generate_test_data(
     faults: usize,
     num_epochs: usize,
 ) -> (EpochBlock, Vec<EpochTransition>, EpochBlock) {
-    let bitmaps = generate_bitmaps(num_epochs, num_validators, faults);
-    let initial_validator_set = keygen_mul::<Bls12_377>(num_validators as usize);
+    generate_test_data_with_domain(num_validators, faults, num_epochs, Domain::default())
+}
+
+// Same as `generate_test_data`, but the transitions are signed in the provided domain
+pub fn generate_test_data_with_domain(
+    num_validators: usize,
+    faults: usize,
+    num_epochs: usize,
+    domain: Domain,
+) -> (EpochBlock, Vec<EpochTransition>, EpochBlock) {
+    let rng = &mut rand::thread_rng();
+    let bitmap = generate_bitmap(num_validators, faults);
     // Generate the initial epoch. This was proven to be correct either via
     // the previous epoch proof, or it's the genesis block
-    let initial_pubkeys = initial_validator_set
-        .1
-        .iter()
-        .map(|pk| PublicKey::from(*pk))
-        .collect::<Vec<_>>();
-    let first_epoch = generate_block(0, faults, &initial_pubkeys);
-
-    // Generate keys for the validators of each epoch
-    let validators = keygen_batch::<Bls12_377>(num_epochs, num_validators as usize);
-    // generate the block for i+1th epoch
-    let pubkeys = validators
-        .1
-        .iter()
-        .map(|epoch_keys| epoch_keys.iter().map(|pk| PublicKey::from(*pk)).collect())
-        .collect::<Vec<Vec<_>>>();
+    let (first_epoch, mut signers) = random_epoch_block(rng, num_validators, 0, faults as u32);
 
-    // Signers will be from the 1st to the last-1 epoch
-    let mut signers = vec![initial_validator_set.0];
-    signers.extend_from_slice(&validators.0[..validators.0.len() - 1]);
     // sign each state transition
     let mut transitions = vec![];
-    for (i, signers_epoch) in signers.iter().enumerate() {
-        let block: EpochBlock = generate_block(i + 1, faults, &pubkeys[i]);
-        let hash = block.hash_to_g1().unwrap();
+    for i in 0..num_epochs {
+        // generate the block for i+1th epoch
+        let (block, validators) =
+            random_epoch_block(rng, num_validators, i as u16 + 1, faults as u32);
 
         // A subset of the i-th validator set, signs on the i+1th epoch's G1 hash
-        let bitmap_epoch = &bitmaps[i];
-        let asig = {
-            let mut asig = G1Projective::zero();
-            for (j, sk) in signers_epoch.iter().enumerate() {
-                if bitmap_epoch[j] {
-                    asig += hash.mul(*sk)
-                }
-            }
-            asig
-        };
-        let asig = Signature::from(asig);
+        let message = block.encode_to_bytes().unwrap();
+        let asig = Signature::aggregate(
+            signers
+                .iter()
+                .zip(&bitmap)
+                .filter(|(_, signed)| **signed)
+                .map(|(sk, _)| {
+                    sk.sign_with_domain(domain, &message, &[], &SnarkCrh::CURRENT)
+                        .unwrap()
+                }),
+        );
 
         let transition = EpochTransition {
             block,
             aggregate_signature: asig,
-            bitmap: bitmap_epoch.to_vec(),
+            bitmap: bitmap.clone(),
         };
         transitions.push(transition);
+        signers = validators;
     }
     let last_epoch = transitions[transitions.len() - 1].block.clone();
 
     (first_epoch, transitions, last_epoch)
 }
 
-fn generate_block(index: usize, non_signers: usize, pubkeys: &[PublicKey]) -> EpochBlock {
-    EpochBlock {
-        index: index as u16,
-        maximum_non_signers: non_signers as u32,
-        new_public_keys: pubkeys.to_vec(),
-    }
-}
-
-// generates `num_epochs` bitmaps with `num_validators - faults` 1 bits set and `faults` 0 bits set
-fn generate_bitmaps(num_epochs: usize, num_validators: usize, faults: usize) -> Vec<Vec<bool>> {
-    let mut ret = Vec::new();
-    for _ in 0..num_epochs {
-        let mut bitmap = vec![true; num_validators];
-        for b in bitmap.iter_mut().take(faults) {
-            *b = false;
-        }
-        ret.push(bitmap)
+// generates a bitmap with `num_validators - faults` 1 bits set and `faults` 0 bits set
+fn generate_bitmap(num_validators: usize, faults: usize) -> Vec<bool> {
+    let mut bitmap = vec![true; num_validators];
+    for b in bitmap.iter_mut().take(faults) {
+        *b = false;
     }
-    ret
+    bitmap
 }